@@ -0,0 +1,40 @@
+// Compares day 20's mixers on the real input, for both parts' mixing.
+use aoc2022::days::day20::{IndexedMixer, Mixer, ScanMixer, TreapMixer};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::fs;
+
+const DECRYPTION_KEY: i64 = 811589153;
+
+fn read_numbers() -> Vec<i64> {
+    let input = fs::read_to_string("input/20.txt").expect("failed to read input/20.txt");
+    input
+        .lines()
+        .map(|line| line.parse().expect("invalid number"))
+        .collect()
+}
+
+fn bench_mixers(c: &mut Criterion) {
+    let numbers = read_numbers();
+    let decrypted = numbers
+        .iter()
+        .map(|n| n * DECRYPTION_KEY)
+        .collect::<Vec<_>>();
+    let mut group = c.benchmark_group("day20");
+    // Part B mixes ten times over, so each sample takes a while.
+    group.sample_size(10);
+    for (part, numbers, rounds) in [("A", &numbers, 1), ("B", &decrypted, 10)] {
+        group.bench_with_input(BenchmarkId::new("indexed", part), numbers, |b, numbers| {
+            b.iter(|| IndexedMixer::mix(black_box(numbers), rounds))
+        });
+        group.bench_with_input(BenchmarkId::new("treap", part), numbers, |b, numbers| {
+            b.iter(|| TreapMixer::mix(black_box(numbers), rounds))
+        });
+        group.bench_with_input(BenchmarkId::new("scan", part), numbers, |b, numbers| {
+            b.iter(|| ScanMixer::mix(black_box(numbers), rounds))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_mixers);
+criterion_main!(benches);
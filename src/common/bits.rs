@@ -0,0 +1,39 @@
+use num::PrimInt;
+
+// The bits set in every mask, or no bits if there are no masks.
+pub fn multi_intersection<T: PrimInt>(masks: impl IntoIterator<Item = T>) -> T {
+    masks
+        .into_iter()
+        .reduce(|intersection, mask| intersection & mask)
+        .unwrap_or_else(T::zero)
+}
+
+// The positions of the bits set in a mask, from lowest to highest.
+pub fn ones<T: PrimInt>(mut mask: T) -> impl Iterator<Item = u32> {
+    std::iter::from_fn(move || {
+        (!mask.is_zero()).then(|| {
+            let bit = mask.trailing_zeros();
+            mask = mask & (mask - T::one());
+            bit
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersects_masks() {
+        assert_eq!(multi_intersection([0b1110u8, 0b0111, 0b0110]), 0b0110);
+        assert_eq!(multi_intersection([u64::MAX]), u64::MAX);
+        assert_eq!(multi_intersection::<u64>([]), 0);
+    }
+
+    #[test]
+    fn finds_set_bits() {
+        assert_eq!(ones(0b1010_0001u8).collect::<Vec<_>>(), [0, 5, 7]);
+        assert_eq!(ones(1u64 << 63).collect::<Vec<_>>(), [63]);
+        assert_eq!(ones(0i32).count(), 0);
+    }
+}
@@ -1,100 +1,354 @@
-use std::str;
+use std::io::{BufRead, BufReader, Cursor, Read};
 
-pub struct NewlineBlocksIterator<'a> {
-    newlines: u32,
-    next_group_begin_index: usize,
-    next_index: usize,
-    bytes: &'a [u8],
+use super::{AocResult, IntoAocResult};
+
+enum Separator<'a> {
+    // A run of at least this many line breaks, with nothing but whitespace
+    // between them.
+    LineBreaks(usize),
+    // Exact text, except that a "\n" also matches "\r\n".
+    Text(&'a str),
 }
 
-impl<'a> NewlineBlocksIterator<'a> {
-    fn new(input: &'a str, newlines: u32) -> Self {
-        Self {
-            newlines,
-            next_group_begin_index: 0,
-            next_index: 0,
-            bytes: input.as_bytes(),
+impl Separator<'_> {
+    // The start and end of the first separator in a string.
+    fn find(&self, s: &str) -> Option<(usize, usize)> {
+        match self {
+            Self::LineBreaks(count) => find_line_breaks(s, *count),
+            Self::Text(sep) => find_text(s, sep),
         }
     }
 }
 
-impl<'a> NewlineBlocksIterator<'a> {
-    fn find_next_newline(&mut self) -> Option<usize> {
-        for i in self.next_index..self.bytes.len() {
-            if self.bytes[i] == b'\n' {
-                self.next_index = i + 1;
-                return if i > self.next_group_begin_index && self.bytes[i - 1] == b'\r' {
-                    Some(i - 1)
-                } else {
-                    Some(i)
-                };
+// The blank lines after the last line with content, which separate blocks once
+// there are enough line breaks in them.
+#[derive(Default)]
+struct Gap {
+    // Where the gap starts and how many line breaks are in it.
+    current: Option<(usize, usize)>,
+}
+
+impl Gap {
+    // Adds the next line, which starts at the given offset, returning the start
+    // and end of the gap if the line ends a separator.
+    fn push(&mut self, line: &str, offset: usize, count: usize) -> Option<(usize, usize)> {
+        if line.trim().is_empty() {
+            if let Some((_, breaks)) = &mut self.current {
+                if line.ends_with('\n') {
+                    *breaks += 1;
+                }
             }
+            return None;
         }
-        self.next_index = self.bytes.len();
-        None
+        let separator = self
+            .current
+            .filter(|(_, breaks)| *breaks >= count)
+            .map(|(start, _)| (start, offset));
+        // The next gap starts at the line break that ends this line.
+        self.current = line.ends_with('\n').then_some((offset + line.len() - 1, 1));
+        separator
     }
+}
 
-    fn get_next_byte(&mut self) -> Option<u8> {
-        if self.next_index >= self.bytes.len() {
-            None
-        } else {
-            let byte = self.bytes[self.next_index];
-            self.next_index += 1;
-            Some(byte)
+fn find_line_breaks(s: &str, count: usize) -> Option<(usize, usize)> {
+    let mut gap = Gap::default();
+    let mut offset = 0;
+    for line in s.split_inclusive('\n') {
+        if let Some(separator) = gap.push(line, offset, count) {
+            return Some(separator);
         }
+        offset += line.len();
     }
+    None
+}
 
-    fn try_finish_group(&mut self) -> bool {
-        for _ in 1..self.newlines {
-            match self.get_next_byte() {
-                None => return false,
-                Some(b'\n') => (),
-                Some(b'\r') => match self.get_next_byte() {
-                    None => return false,
-                    Some(b'\n') => (),
-                    _ => return false,
-                },
-                _ => return false,
-            }
+// The length of the separator if it starts the string.
+fn match_text(s: &[u8], sep: &[u8]) -> Option<usize> {
+    let mut i = 0;
+    for &byte in sep {
+        if byte == b'\n' && s.get(i) == Some(&b'\r') {
+            i += 1;
         }
-        true
+        if s.get(i) != Some(&byte) {
+            return None;
+        }
+        i += 1;
+    }
+    Some(i)
+}
+
+fn find_text(s: &str, sep: &str) -> Option<(usize, usize)> {
+    (0..s.len()).find_map(|start| {
+        match_text(&s.as_bytes()[start..], sep.as_bytes()).map(|len| (start, start + len))
+    })
+}
+
+// Removes blank lines from either side of a block, along with the line break
+// that ends its last line. Whitespace at the start of the first line is kept,
+// since it can be part of a drawing.
+fn trim_blank_lines(block: &str) -> &str {
+    let content_end = block.trim_end().len();
+    if content_end == 0 {
+        return "";
+    }
+    let end = block[content_end..]
+        .find(['\r', '\n'])
+        .map_or(block.len(), |i| content_end + i);
+    let block = &block[..end];
+    let leading = block.len() - block.trim_start().len();
+    match block[..leading].rfind('\n') {
+        Some(i) => &block[i + 1..],
+        None => block,
     }
 }
 
-impl<'a> Iterator for NewlineBlocksIterator<'a> {
+// Splits a string into blocks of lines, for inputs with several sections.
+//
+// Blocks work the same with LF and CRLF line endings. Blank lines around each
+// block are removed, and separators at the end of the input do not make an
+// empty block after them.
+pub struct BlocksIterator<'a> {
+    rest: Option<&'a str>,
+    separator: Separator<'a>,
+}
+
+impl<'a> Iterator for BlocksIterator<'a> {
     type Item = &'a str;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.next_index >= self.bytes.len() {
-            None
-        } else if self.newlines == 0 {
-            let next_byte = unsafe {
-                str::from_utf8_unchecked(&self.bytes[self.next_index..self.next_index + 1])
-            };
-            self.next_index += 1;
-            Some(next_byte)
-        } else {
-            while let Some(index) = self.find_next_newline() {
-                if self.try_finish_group() {
-                    let result = &self.bytes[self.next_group_begin_index..index];
-                    self.next_group_begin_index = self.next_index;
-                    return unsafe { Some(str::from_utf8_unchecked(result)) };
-                }
+        let rest = self.rest?;
+        match self.separator.find(rest) {
+            Some((start, end)) => {
+                self.rest = Some(&rest[end..]);
+                Some(trim_blank_lines(&rest[..start]))
+            }
+            None => {
+                self.rest = None;
+                let block = trim_blank_lines(rest);
+                (!block.trim().is_empty()).then_some(block)
             }
-            let last_group =
-                unsafe { str::from_utf8_unchecked(&self.bytes[self.next_group_begin_index..]) };
-            self.next_index = self.bytes.len();
-            Some(last_group)
         }
     }
 }
 
 pub trait NewlineBlocks {
-    fn newline_blocks<'a>(&'a self, newlines: u32) -> NewlineBlocksIterator<'a>;
+    // Blocks separated by at least `newlines` line breaks in a row, so
+    // `newline_blocks(2)` splits on blank lines.
+    fn newline_blocks(&self, newlines: usize) -> BlocksIterator<'_>;
+
+    // Blocks separated by the given text.
+    fn split_blocks<'a>(&'a self, sep: &'a str) -> BlocksIterator<'a>;
+}
+
+impl NewlineBlocks for str {
+    fn newline_blocks(&self, newlines: usize) -> BlocksIterator<'_> {
+        assert!(
+            newlines > 0,
+            "blocks must be separated by at least one newline"
+        );
+        BlocksIterator {
+            rest: Some(self),
+            separator: Separator::LineBreaks(newlines),
+        }
+    }
+
+    fn split_blocks<'a>(&'a self, sep: &'a str) -> BlocksIterator<'a> {
+        assert!(!sep.is_empty(), "blocks cannot be separated by empty text");
+        BlocksIterator {
+            rest: Some(self),
+            separator: Separator::Text(sep),
+        }
+    }
 }
 
-impl NewlineBlocks for &str {
-    fn newline_blocks<'a>(&'a self, newlines: u32) -> NewlineBlocksIterator<'a> {
-        NewlineBlocksIterator::new(self, newlines)
+// Blocks separated by blank lines, like `newline_blocks`, read one line at a
+// time and owned, for input that is not all in memory as a `str`.
+pub struct ReadBlocksIterator<R> {
+    reader: R,
+    newlines: usize,
+    // The text read since the last separator.
+    block: String,
+    line: String,
+    gap: Gap,
+    done: bool,
+}
+
+impl<R: BufRead> ReadBlocksIterator<R> {
+    pub fn new(reader: R, newlines: usize) -> Self {
+        assert!(
+            newlines > 0,
+            "blocks must be separated by at least one newline"
+        );
+        Self {
+            reader,
+            newlines,
+            block: String::new(),
+            line: String::new(),
+            gap: Gap::default(),
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for ReadBlocksIterator<R> {
+    type Item = AocResult<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err).into_aoc_result_msg("failed to read block"));
+                }
+                Ok(0) => {
+                    self.done = true;
+                    let block = trim_blank_lines(&self.block);
+                    return (!block.trim().is_empty()).then(|| Ok(block.to_owned()));
+                }
+                Ok(_) => (),
+            }
+            let separator = self.gap.push(&self.line, self.block.len(), self.newlines);
+            if let Some((start, _)) = separator {
+                let block = trim_blank_lines(&self.block[..start]).to_owned();
+                self.block.clear();
+                self.block.push_str(&self.line);
+                self.gap = Gap::default();
+                self.gap.push(&self.line, 0, self.newlines);
+                return Some(Ok(block));
+            }
+            self.block.push_str(&self.line);
+        }
+        None
+    }
+}
+
+// Owned input that can be split into owned blocks.
+pub trait IntoNewlineBlocks {
+    type Reader: BufRead;
+    fn into_newline_blocks(self, newlines: usize) -> ReadBlocksIterator<Self::Reader>;
+}
+
+impl IntoNewlineBlocks for String {
+    type Reader = Cursor<String>;
+    fn into_newline_blocks(self, newlines: usize) -> ReadBlocksIterator<Self::Reader> {
+        ReadBlocksIterator::new(Cursor::new(self), newlines)
+    }
+}
+
+impl<R: Read> IntoNewlineBlocks for BufReader<R> {
+    type Reader = Self;
+    fn into_newline_blocks(self, newlines: usize) -> ReadBlocksIterator<Self::Reader> {
+        ReadBlocksIterator::new(self, newlines)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn blocks(input: &str) -> Vec<&str> {
+        input.newline_blocks(2).collect()
+    }
+
+    #[test]
+    fn splits_on_blank_lines() {
+        assert_eq!(blocks("1\n2\n\n3\n\n4"), ["1\n2", "3", "4"]);
+        assert_eq!(blocks("1\n2"), ["1\n2"]);
+        assert_eq!(blocks(""), [] as [&str; 0]);
+        assert_eq!(blocks("\n\n\n"), [] as [&str; 0]);
+    }
+
+    #[test]
+    fn handles_crlf_and_mixed_line_endings() {
+        assert_eq!(blocks("1\r\n2\r\n\r\n3\r\n"), ["1\r\n2", "3"]);
+        assert_eq!(blocks("1\r\n\n2\n\r\n3\r\n\r\n"), ["1", "2", "3"]);
+        assert_eq!(blocks("1\r\n2\r\n"), ["1\r\n2"]);
+    }
+
+    #[test]
+    fn normalizes_extra_and_trailing_blank_lines() {
+        assert_eq!(blocks("\n\n1\n\n\n\n2\n\n\n"), ["1", "2"]);
+        assert_eq!(blocks("1\n  \n2\n\t\n"), ["1", "2"]);
+        assert_eq!(blocks("1   \n\n2  "), ["1   ", "2  "]);
+    }
+
+    #[test]
+    fn keeps_indentation() {
+        let input = "    [D]\n[N] [C]\n 1   2 \n\nmove 1 from 2 to 1\n";
+        assert_eq!(
+            blocks(input),
+            ["    [D]\n[N] [C]\n 1   2 ", "move 1 from 2 to 1"]
+        );
+    }
+
+    #[test]
+    fn splits_on_more_than_one_blank_line() {
+        let input = "1\n\n2\n\n\n3\r\n\r\n\r\n4";
+        assert_eq!(
+            input.newline_blocks(3).collect::<Vec<_>>(),
+            ["1\n\n2", "3", "4"]
+        );
+        assert_eq!(
+            "1\n2\r\n\n3".newline_blocks(1).collect::<Vec<_>>(),
+            ["1", "2", "3"]
+        );
+    }
+
+    #[test]
+    fn splits_on_text() {
+        let split = |input: &'static str, sep| input.split_blocks(sep).collect::<Vec<_>>();
+        assert_eq!(split("a\n---\nb\n---\n", "---"), ["a", "b"]);
+        assert_eq!(split("a\r\n---\r\nb", "\n---\n"), ["a", "b"]);
+        assert_eq!(split("a;;b;;;;c", ";;"), ["a", "b", "", "c"]);
+        assert_eq!(split("a\n\nb", "\n\n"), ["a", "b"]);
+        assert_eq!(split("a\r\n\r\nb\r\n", "\n\n"), ["a", "b"]);
+        assert_eq!(split("héllo→wörld→", "→"), ["héllo", "wörld"]);
+    }
+
+    #[test]
+    fn reads_owned_blocks() {
+        let input = "    [D]\r\n 1 \r\n\r\n\r\nmove 1\nmove 2\n\n".to_owned();
+        let blocks = input
+            .into_newline_blocks(2)
+            .collect::<AocResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(blocks, ["    [D]\r\n 1 ", "move 1\nmove 2"]);
+
+        let reader = BufReader::new("1\n\n2\n".as_bytes());
+        let blocks = reader
+            .into_newline_blocks(2)
+            .collect::<AocResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(blocks, ["1", "2"]);
+    }
+
+    #[test]
+    fn reports_read_errors() {
+        let mut blocks = ReadBlocksIterator::new(&[b'1', b'\n', 0xff][..], 2);
+        assert!(blocks.next().unwrap().is_err());
+        assert!(blocks.next().is_none());
+    }
+
+    proptest! {
+        #[test]
+        fn reading_matches_borrowing(
+            lines in prop::collection::vec(prop::sample::select(vec!["a", "b c", "  ", ""]), 0..12),
+            endings in prop::collection::vec(prop::sample::select(vec!["\n", "\r\n"]), 12),
+            newlines in 1..4usize,
+        ) {
+            let input = lines
+                .iter()
+                .zip(&endings)
+                .map(|(line, ending)| format!("{line}{ending}"))
+                .collect::<String>();
+            let borrowed = input.newline_blocks(newlines).collect::<Vec<_>>();
+            let owned = input
+                .clone()
+                .into_newline_blocks(newlines)
+                .collect::<AocResult<Vec<_>>>()?;
+            prop_assert_eq!(owned, borrowed);
+        }
     }
 }
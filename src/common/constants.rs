@@ -0,0 +1,57 @@
+use super::DayArgs;
+
+// Which input a puzzle's constants are for. Puzzles often use smaller numbers
+// for the example in their text than for the real input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Example,
+    Real,
+}
+
+// Constants from a puzzle's text, with one set for the example and one for the
+// real input. Days query them through their `DayArgs` instead of hard-coding
+// the real ones.
+pub trait Constants: Sized {
+    const EXAMPLE: Self;
+    const REAL: Self;
+
+    fn for_profile(profile: Profile) -> Self {
+        match profile {
+            Profile::Example => Self::EXAMPLE,
+            Profile::Real => Self::REAL,
+        }
+    }
+}
+
+impl DayArgs {
+    // The constants for the input being solved, if it is known which input it
+    // is. The program always knows, but solvers called through the library may
+    // not, so days that can tell the example apart from the real input fall
+    // back on guessing.
+    pub fn constants<C: Constants>(&self) -> Option<C> {
+        self.profile().map(C::for_profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Rounds(u64);
+
+    impl Constants for Rounds {
+        const EXAMPLE: Self = Self(10);
+        const REAL: Self = Self(1000);
+    }
+
+    #[test]
+    fn picks_constants_for_profile() {
+        let day_args = DayArgs::default();
+        assert_eq!(day_args.constants::<Rounds>(), None);
+        let day_args = day_args.with_profile(Profile::Example);
+        assert_eq!(day_args.constants(), Some(Rounds(10)));
+        let day_args = day_args.with_profile(Profile::Real);
+        assert_eq!(day_args.constants(), Some(Rounds(1000)));
+    }
+}
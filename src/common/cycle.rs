@@ -0,0 +1,139 @@
+use num::PrimInt;
+use std::{collections::HashMap, hash::Hash};
+
+// Where a sequence of states starts repeating itself: the state at step
+// `start + length` is the same as the one at step `start`, so every state from
+// `start` on repeats every `length` steps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CycleInfo {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl CycleInfo {
+    // The step at which the cycle was found, where the first repeated state
+    // appears again.
+    pub fn end(&self) -> usize {
+        self.start + self.length
+    }
+
+    // The value at step `n`, given the value at every step up to and including
+    // `end()`, for values that change by the same amount every cycle, like the
+    // height of a tower.
+    pub fn value_at<T: PrimInt>(&self, values: &[T], n: usize) -> T {
+        if n < values.len() {
+            return values[n];
+        }
+        let offset = n - self.start;
+        let repeats = T::from(offset / self.length).unwrap();
+        let per_cycle = values[self.end()] - values[self.start];
+        values[self.start + offset % self.length] + repeats * per_cycle
+    }
+}
+
+// Finds cycles in states that are produced one step at a time, for simulations
+// that need to do something with each step along the way.
+pub struct CycleDetector<S> {
+    seen: HashMap<S, usize>,
+    steps: usize,
+}
+
+impl<S: Hash + Eq> CycleDetector<S> {
+    pub fn new() -> Self {
+        Self {
+            seen: HashMap::new(),
+            steps: 0,
+        }
+    }
+
+    // Records the state at the next step, returning the cycle if it has been
+    // seen before.
+    pub fn push(&mut self, state: S) -> Option<CycleInfo> {
+        let step = self.steps;
+        self.steps += 1;
+        self.seen.insert(state, step).map(|start| CycleInfo {
+            start,
+            length: step - start,
+        })
+    }
+}
+
+impl<S: Hash + Eq> Default for CycleDetector<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Finds the first state that repeats an earlier one. Never returns for
+// infinite sequences that do not repeat.
+pub fn detect<S: Hash + Eq>(states: impl IntoIterator<Item = S>) -> Option<CycleInfo> {
+    let mut detector = CycleDetector::new();
+    states.into_iter().find_map(|state| detector.push(state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_cycle_after_prefix() {
+        let states = [7, 3, 1, 4, 1, 5, 9, 4, 1, 5, 9];
+        assert_eq!(
+            detect(states),
+            Some(CycleInfo {
+                start: 2,
+                length: 2,
+            })
+        );
+        let states = [7, 3, 4, 1, 5, 9, 4, 1, 5, 9];
+        assert_eq!(
+            detect(states),
+            Some(CycleInfo {
+                start: 2,
+                length: 4,
+            })
+        );
+        assert_eq!(detect([1, 2, 3]), None);
+    }
+
+    #[test]
+    fn finds_cycle_of_periodic_sequence() {
+        // x -> x^2 + 1 mod 1000 starting from 2 enters a cycle eventually.
+        let states = std::iter::successors(Some(2u64), |x| Some((x * x + 1) % 1000));
+        let cycle = detect(states.clone()).unwrap();
+        let states = states
+            .take(cycle.end() + cycle.length * 3)
+            .collect::<Vec<_>>();
+        for step in cycle.start..states.len() - cycle.length {
+            assert_eq!(states[step], states[step + cycle.length]);
+        }
+        assert_ne!(states[cycle.start - 1], states[cycle.end() - 1]);
+    }
+
+    #[test]
+    fn extrapolates_values_that_grow_every_cycle() {
+        // Steps 0 and 1 are a prefix, and then each cycle of 3 steps adds 3, 1,
+        // and 6.
+        let per_step = |step: u64| -> u64 {
+            if step < 2 {
+                step * 100
+            } else {
+                [3, 1, 6][(step as usize - 2) % 3]
+            }
+        };
+        let values = (0..200)
+            .scan(0, |total, step| {
+                let value = *total;
+                *total += per_step(step);
+                Some(value)
+            })
+            .collect::<Vec<_>>();
+        let cycle = CycleInfo {
+            start: 2,
+            length: 3,
+        };
+        for n in 0..values.len() {
+            assert_eq!(cycle.value_at(&values[..=cycle.end()], n), values[n]);
+        }
+    }
+}
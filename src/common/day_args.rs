@@ -0,0 +1,123 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter, Result as DisplayResult},
+    str::FromStr,
+};
+
+use super::{constants::Profile, AocError, AocResult};
+
+// Options for a single day's solvers, given after `--` on the command line, such
+// as `15 A -- --row 10`. Days with constants from the puzzle read them from here,
+// so they can be tuned without recompiling.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DayArgs {
+    values: BTreeMap<String, String>,
+    // Which input is being solved, when it is known, such as with `--example`.
+    profile: Option<Profile>,
+}
+
+impl DayArgs {
+    // Reads options given as `--key value` or `--key=value`.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> AocResult<Self> {
+        let mut day_args = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let option = arg.strip_prefix("--").ok_or_else(|| {
+                AocError::invalid_args(format!("expected a --key for the day, found {arg}"))
+            })?;
+            let (key, value) = match option.split_once('=') {
+                Some((key, value)) => (key.to_owned(), value.to_owned()),
+                None => (
+                    option.to_owned(),
+                    args.next().ok_or_else(|| {
+                        AocError::invalid_args(format!("missing value for --{option}"))
+                    })?,
+                ),
+            };
+            if key.is_empty() {
+                return Err(AocError::invalid_args("day options must have a name"));
+            }
+            if day_args.values.contains_key(&key) {
+                return Err(AocError::invalid_args(format!("--{key} was given twice")));
+            }
+            day_args.values.insert(key, value);
+        }
+        Ok(day_args)
+    }
+
+    pub fn with(mut self, key: &str, value: impl ToString) -> Self {
+        self.values.insert(key.to_owned(), value.to_string());
+        self
+    }
+
+    pub fn with_profile(mut self, profile: Profile) -> Self {
+        self.profile = Some(profile);
+        self
+    }
+
+    pub fn profile(&self) -> Option<Profile> {
+        self.profile
+    }
+
+    // Whether no options were given.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str) -> AocResult<Option<T>> {
+        self.values
+            .get(key)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    AocError::invalid_args(format!("invalid value for --{key}: {value}"))
+                })
+            })
+            .transpose()
+    }
+
+    pub fn get_or<T: FromStr>(&self, key: &str, default: T) -> AocResult<T> {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+}
+
+impl Display for DayArgs {
+    fn fmt(&self, f: &mut Formatter) -> DisplayResult {
+        for (i, (key, value)) in self.values.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "--{key} {value}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> AocResult<DayArgs> {
+        DayArgs::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn reads_keys_and_values() {
+        let args = parse(&["--row", "10", "--bound=20"]).unwrap();
+        assert_eq!(args.get::<i64>("row").unwrap(), Some(10));
+        assert_eq!(args.get_or::<i64>("bound", 4_000_000).unwrap(), 20);
+        assert_eq!(args.get_or::<u64>("rocks", 2022).unwrap(), 2022);
+        assert_eq!(args, DayArgs::default().with("row", 10).with("bound", 20));
+        assert_eq!(args.to_string(), "--bound 20 --row 10");
+        assert!(args.get::<u8>("row").is_ok());
+        assert!(args.get::<bool>("row").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_options() {
+        assert!(parse(&["row", "10"]).is_err());
+        assert!(parse(&["--row"]).is_err());
+        assert!(parse(&["--row", "1", "--row", "2"]).is_err());
+        assert!(parse(&["--=1"]).is_err());
+        assert!(parse(&[]).unwrap().is_empty());
+    }
+}
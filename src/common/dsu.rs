@@ -0,0 +1,135 @@
+// A partition of the integers 0..n into disjoint sets that can be merged, also
+// known as union-find. Finding uses path compression and merging uses union by
+// rank, so both take nearly constant time.
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    size: Vec<usize>,
+    components: usize,
+}
+
+impl DisjointSet {
+    // Starts with every element in a set of its own.
+    pub fn new(len: usize) -> Self {
+        Self {
+            parent: (0..len).collect(),
+            rank: vec![0; len],
+            size: vec![1; len],
+            components: len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    // The representative element of the set containing the element.
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // Point everything on the way straight at the root.
+        let mut current = element;
+        while self.parent[current] != root {
+            current = std::mem::replace(&mut self.parent[current], root);
+        }
+        root
+    }
+
+    // Merges the sets containing the two elements, returning false if they were
+    // already in the same set.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (root, child) = if self.rank[a] >= self.rank[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+        if self.rank[root] == self.rank[child] {
+            self.rank[root] += 1;
+        }
+        self.components -= 1;
+        true
+    }
+
+    pub fn same_set(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    // The number of elements in the set containing the element.
+    pub fn size_of(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+
+    // The number of disjoint sets.
+    pub fn component_count(&self) -> usize {
+        self.components
+    }
+
+    // Every set, each in increasing order, ordered by their smallest elements.
+    pub fn components(&mut self) -> impl Iterator<Item = Vec<usize>> {
+        let mut slots = vec![None; self.len()];
+        let mut components: Vec<Vec<usize>> = Vec::with_capacity(self.components);
+        for element in 0..self.len() {
+            let root = self.find(element);
+            let slot = *slots[root].get_or_insert_with(|| {
+                components.push(Vec::new());
+                components.len() - 1
+            });
+            components[slot].push(element);
+        }
+        components.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_sets() {
+        let mut sets = DisjointSet::new(6);
+        assert_eq!(sets.component_count(), 6);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(4, 1));
+        assert!(sets.union(2, 3));
+        assert!(!sets.union(0, 4));
+        assert!(sets.same_set(0, 4));
+        assert!(!sets.same_set(0, 2));
+        assert_eq!(sets.size_of(1), 3);
+        assert_eq!(sets.size_of(3), 2);
+        assert_eq!(sets.size_of(5), 1);
+        assert_eq!(sets.component_count(), 3);
+        assert_eq!(
+            sets.components().collect::<Vec<_>>(),
+            [vec![0, 1, 4], vec![2, 3], vec![5]]
+        );
+    }
+
+    #[test]
+    fn compresses_long_chains() {
+        let mut sets = DisjointSet::new(1000);
+        for i in 1..1000 {
+            sets.union(i - 1, i);
+        }
+        let root = sets.find(999);
+        for i in 0..1000 {
+            sets.find(i);
+        }
+        assert!(sets.parent.iter().all(|parent| *parent == root));
+        assert_eq!(sets.size_of(0), 1000);
+        assert_eq!(sets.component_count(), 1);
+    }
+}
@@ -1,21 +1,148 @@
-use std::fmt::{Display, Formatter, Result as DisplayResult};
+use super::SolutionPart;
+use std::{
+    error::Error,
+    fmt::{Display, Formatter, Result as DisplayResult},
+    io,
+    time::Duration,
+};
+
+pub type ErrorSource = Box<dyn Error + Send + Sync + 'static>;
 
 #[derive(Debug)]
-pub struct AocError {
-    message: String,
+pub enum AocError {
+    // Puzzle input or a data file is malformed.
+    ParseError {
+        message: String,
+        source: Option<ErrorSource>,
+    },
+    // Reading or writing a file, or talking to another process, failed.
+    IoError {
+        message: String,
+        source: Option<ErrorSource>,
+    },
+    // The input was understood, but the solver could not find an answer.
+    NoSolution(String),
+    // The command line could not be understood.
+    InvalidArgs(String),
+    // A solver did not finish within the time it was given.
+    Timeout(Duration),
+    // A solver failed while solving a specific day and part.
+    SolverFailed {
+        day: u8,
+        part: SolutionPart,
+        source: Box<AocError>,
+    },
 }
 
 impl AocError {
-    pub fn new<S: Into<String>>(message: S) -> AocError {
-        AocError {
+    pub fn parse<S: Into<String>>(message: S) -> AocError {
+        AocError::ParseError {
+            message: message.into(),
+            source: None,
+        }
+    }
+
+    pub fn io<S: Into<String>>(message: S) -> AocError {
+        AocError::IoError {
             message: message.into(),
+            source: None,
+        }
+    }
+
+    pub fn no_solution<S: Into<String>>(message: S) -> AocError {
+        AocError::NoSolution(message.into())
+    }
+
+    pub fn invalid_args<S: Into<String>>(message: S) -> AocError {
+        AocError::InvalidArgs(message.into())
+    }
+
+    pub fn timeout(limit: Duration) -> AocError {
+        AocError::Timeout(limit)
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, AocError::Timeout(_))
+    }
+
+    // Whether the error is in the arguments, even if a solver found it.
+    pub fn is_invalid_args(&self) -> bool {
+        match self {
+            AocError::InvalidArgs(_) => true,
+            AocError::SolverFailed { source, .. } => source.is_invalid_args(),
+            _ => false,
+        }
+    }
+
+    // Wraps an underlying error, which is classified as an I/O error if it is
+    // one and as a parse error otherwise.
+    pub fn from_source<S, E>(message: S, source: E) -> AocError
+    where
+        S: Into<String>,
+        E: Error + Send + Sync + 'static,
+    {
+        let message = message.into();
+        let source: ErrorSource = Box::new(source);
+        if source.is::<io::Error>() {
+            AocError::IoError {
+                message,
+                source: Some(source),
+            }
+        } else {
+            AocError::ParseError {
+                message,
+                source: Some(source),
+            }
+        }
+    }
+
+    // Attaches the day and part being solved, unless the error already has it.
+    pub fn in_solver(self, day: u8, part: SolutionPart) -> AocError {
+        match self {
+            AocError::SolverFailed { .. } => self,
+            _ => AocError::SolverFailed {
+                day,
+                part,
+                source: Box::new(self),
+            },
+        }
+    }
+
+    // The full error message, including the messages of any underlying errors.
+    pub fn message(&self) -> String {
+        match self {
+            AocError::ParseError { message, source } | AocError::IoError { message, source } => {
+                match source {
+                    None => message.clone(),
+                    Some(source) if message.is_empty() => source.to_string(),
+                    Some(source) => format!("{}: {}", message, source),
+                }
+            }
+            AocError::NoSolution(message) => format!("no solution: {}", message),
+            AocError::InvalidArgs(message) => message.clone(),
+            AocError::Timeout(limit) => format!("timed out after {} s", limit.as_secs_f64()),
+            AocError::SolverFailed { day, part, source } => {
+                format!("Day {} Part {} failed: {}", day, part, source.message())
+            }
         }
     }
 }
 
 impl Display for AocError {
     fn fmt(&self, f: &mut Formatter) -> DisplayResult {
-        write!(f, "Error: {}", self.message)
+        write!(f, "Error: {}", self.message())
+    }
+}
+
+impl Error for AocError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AocError::ParseError { source, .. } | AocError::IoError { source, .. } => source
+                .as_ref()
+                .map(|source| source.as_ref() as &(dyn Error + 'static)),
+            AocError::SolverFailed { source, .. } => Some(source.as_ref()),
+            AocError::NoSolution(_) | AocError::InvalidArgs(_) | AocError::Timeout(_) => None,
+        }
     }
 }
 
@@ -26,34 +153,24 @@ pub trait IntoAocResult<T> {
     fn into_aoc_result_msg(self, message: &str) -> AocResult<T>;
 }
 
-impl<T, E: ToString> IntoAocResult<T> for Result<T, E> {
+impl<T, E: Error + Send + Sync + 'static> IntoAocResult<T> for Result<T, E> {
     fn into_aoc_result(self) -> AocResult<T> {
-        match self {
-            Err(err) => Err(AocError::new(err.to_string())),
-            Ok(res) => Ok(res),
-        }
+        self.map_err(|err| AocError::from_source("", err))
     }
 
     fn into_aoc_result_msg(self, message: &str) -> AocResult<T> {
-        match self {
-            Err(err) => Err(AocError::new(format!("{}: {}", message, err.to_string()))),
-            Ok(res) => Ok(res),
-        }
+        self.map_err(|err| AocError::from_source(message, err))
     }
 }
 
+// A missing value almost always means that the input did not have the expected
+// shape, so these are parse errors.
 impl<T> IntoAocResult<T> for Option<T> {
     fn into_aoc_result(self) -> AocResult<T> {
-        match self {
-            None => Err(AocError::new("option contained no value")),
-            Some(res) => Ok(res),
-        }
+        self.ok_or_else(|| AocError::parse("option contained no value"))
     }
 
     fn into_aoc_result_msg(self, message: &str) -> AocResult<T> {
-        match self {
-            None => Err(AocError::new(message)),
-            Some(res) => Ok(res),
-        }
+        self.ok_or_else(|| AocError::parse(message))
     }
 }
@@ -0,0 +1,453 @@
+use itertools::Itertools;
+use num::Signed;
+use std::{
+    ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+use super::{AocError, AocResult, IntoAocResult};
+
+// A point on a 2D grid.
+//
+// The y-axis grows downward, like the rows of puzzle input, so "up" is negative
+// y.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point2<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Self { x, y }
+    }
+}
+
+impl<T: Copy + PartialOrd> Point2<T> {
+    // Checks if the point is inside of the half-open rectangle [min, max).
+    pub fn in_bounds(&self, min: &Self, max: &Self) -> bool {
+        min.x <= self.x && self.x < max.x && min.y <= self.y && self.y < max.y
+    }
+}
+
+impl<T: Signed + Copy + PartialOrd> Point2<T> {
+    pub fn manhattan_distance(&self, other: &Self) -> T {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    // The number of king moves between two points.
+    pub fn chebyshev_distance(&self, other: &Self) -> T {
+        let (dx, dy) = ((self.x - other.x).abs(), (self.y - other.y).abs());
+        if dx > dy {
+            dx
+        } else {
+            dy
+        }
+    }
+
+    // Clamps each component to -1, 0, or 1.
+    pub fn signum(&self) -> Self {
+        Self::new(self.x.signum(), self.y.signum())
+    }
+
+    // Rotates 90 degrees counterclockwise (as seen on screen) around the origin.
+    pub fn rotate_left(&self) -> Self {
+        Self::new(self.y, -self.x)
+    }
+
+    // Rotates 90 degrees clockwise (as seen on screen) around the origin.
+    pub fn rotate_right(&self) -> Self {
+        Self::new(-self.y, self.x)
+    }
+
+    // The four orthogonally adjacent points, in `Direction` order.
+    pub fn neighbors4(&self) -> [Self; 4] {
+        Direction::ALL.map(|dir| *self + dir.delta())
+    }
+
+    // The eight orthogonally and diagonally adjacent points, clockwise from the
+    // right.
+    pub fn neighbors8(&self) -> [Self; 8] {
+        let (zero, one) = (T::zero(), T::one());
+        [
+            Self::new(one, zero),
+            Self::new(one, one),
+            Self::new(zero, one),
+            Self::new(-one, one),
+            Self::new(-one, zero),
+            Self::new(-one, -one),
+            Self::new(zero, -one),
+            Self::new(one, -one),
+        ]
+        .map(|delta| *self + delta)
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point2<T> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T: AddAssign> AddAssign for Point2<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point2<T> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl<T: SubAssign> SubAssign for Point2<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point2<T> {
+    type Output = Self;
+    fn mul(self, rhs: T) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<T: MulAssign + Copy> MulAssign<T> for Point2<T> {
+    fn mul_assign(&mut self, rhs: T) {
+        self.x *= rhs;
+        self.y *= rhs;
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point2<T> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+// One of the four orthogonal directions on a grid.
+//
+// Directions are ordered clockwise starting from the right, so rotating is
+// just modular arithmetic on the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Direction {
+    Right = 0,
+    Down = 1,
+    Left = 2,
+    Up = 3,
+}
+
+impl Direction {
+    pub const COUNT: usize = 4;
+    pub const ALL: [Direction; Self::COUNT] = [Self::Right, Self::Down, Self::Left, Self::Up];
+
+    pub fn from_index(index: usize) -> Self {
+        Self::ALL[index % Self::COUNT]
+    }
+
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        matches!(self, Self::Right | Self::Left)
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        !self.is_horizontal()
+    }
+
+    pub fn rotate_left(&self) -> Self {
+        Self::from_index(self.index() + Self::COUNT - 1)
+    }
+
+    pub fn rotate_right(&self) -> Self {
+        Self::from_index(self.index() + 1)
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self::from_index(self.index() + 2)
+    }
+
+    pub fn delta<T: Signed>(&self) -> Point2<T> {
+        match self {
+            Self::Right => Point2::new(T::one(), T::zero()),
+            Self::Down => Point2::new(T::zero(), T::one()),
+            Self::Left => Point2::new(-T::one(), T::zero()),
+            Self::Up => Point2::new(T::zero(), -T::one()),
+        }
+    }
+}
+
+// A point in 3D space.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T> Point3<T> {
+    pub const fn new(x: T, y: T, z: T) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn from_array([x, y, z]: [T; 3]) -> Self {
+        Self::new(x, y, z)
+    }
+
+    pub fn to_array(self) -> [T; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl<T: Add<Output = T> + Sub<Output = T> + Mul<Output = T> + Copy> Point3<T> {
+    pub fn dot(&self, other: &Self) -> T {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    // Perpendicular to both points, following the right-hand rule.
+    pub fn cross(&self, other: &Self) -> Self {
+        Self::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+}
+
+impl<T: Signed + Copy> Point3<T> {
+    // The unit vectors along each axis, in both directions.
+    pub fn axes() -> [Self; 6] {
+        let (zero, one) = (T::zero(), T::one());
+        [
+            Self::new(one, zero, zero),
+            Self::new(-one, zero, zero),
+            Self::new(zero, one, zero),
+            Self::new(zero, -one, zero),
+            Self::new(zero, zero, one),
+            Self::new(zero, zero, -one),
+        ]
+    }
+
+    // The six points that share a face with this one.
+    pub fn neighbors6(&self) -> [Self; 6] {
+        Self::axes().map(|axis| *self + axis)
+    }
+
+    pub fn manhattan_distance(&self, other: &Self) -> T {
+        (self.x - other.x).abs() + (self.y - other.y).abs() + (self.z - other.z).abs()
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point3<T> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl<T: AddAssign> AddAssign for Point3<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point3<T> {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl<T: SubAssign> SubAssign for Point3<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+        self.z -= rhs.z;
+    }
+}
+
+impl<T: Mul<Output = T> + Copy> Mul<T> for Point3<T> {
+    type Output = Self;
+    fn mul(self, rhs: T) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point3<T> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+// Parses comma-separated coordinates, such as "1,2,3".
+impl<T: FromStr> FromStr for Point3<T> {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        let (x, y, z) = s
+            .split(',')
+            .collect_tuple()
+            .into_aoc_result_msg("invalid point format")?;
+        Ok(Self::new(
+            x.parse().ok().into_aoc_result_msg("invalid x-coordinate")?,
+            y.parse().ok().into_aoc_result_msg("invalid y-coordinate")?,
+            z.parse().ok().into_aoc_result_msg("invalid z-coordinate")?,
+        ))
+    }
+}
+
+// A rotation that maps axes onto axes, as a matrix with a single 1 or -1 in
+// each row. There are 24 of them, one for each way of picking which way is
+// forward and which way is up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rotation {
+    // Each coordinate of a rotated point is the coordinate of the original
+    // point on this axis, negated if the sign is negative.
+    axes: [usize; 3],
+    signs: [i8; 3],
+}
+
+impl Rotation {
+    pub const IDENTITY: Self = Self {
+        axes: [0, 1, 2],
+        signs: [1, 1, 1],
+    };
+
+    // Every rotation, starting with the identity.
+    pub fn all() -> impl Iterator<Item = Self> {
+        [
+            [0, 1, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [0, 2, 1],
+            [2, 1, 0],
+            [1, 0, 2],
+        ]
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, axes)| {
+            // The last three permutations are odd, so they take an odd
+            // number of negated axes to keep from turning into reflections.
+            let odd = i >= 3;
+            (0..8u32)
+                .filter(move |negated| negated.count_ones() % 2 == u32::from(odd))
+                .map(move |negated| Self {
+                    axes,
+                    signs: [0, 1, 2].map(|bit| if negated & (1 << bit) == 0 { 1 } else { -1 }),
+                })
+        })
+    }
+
+    // The rotation taking the x and y axes to the given unit vectors, if they
+    // are perpendicular axes.
+    pub fn from_axes(x: Point3<i64>, y: Point3<i64>) -> Option<Self> {
+        Self::all().find(|rotation| {
+            rotation.apply(Point3::new(1, 0, 0)) == x && rotation.apply(Point3::new(0, 1, 0)) == y
+        })
+    }
+
+    // The rotation as a matrix, which rotates column vectors.
+    pub fn matrix(&self) -> [[i64; 3]; 3] {
+        let mut matrix = [[0; 3]; 3];
+        for (row, (&axis, &sign)) in self.axes.iter().zip(&self.signs).enumerate() {
+            matrix[row][axis] = sign.into();
+        }
+        matrix
+    }
+
+    pub fn apply<T: Neg<Output = T> + Copy>(&self, point: Point3<T>) -> Point3<T> {
+        let coordinates = point.to_array();
+        Point3::from_array([0, 1, 2].map(|row| {
+            let coordinate = coordinates[self.axes[row]];
+            if self.signs[row] < 0 {
+                -coordinate
+            } else {
+                coordinate
+            }
+        }))
+    }
+
+    // This rotation followed by another.
+    pub fn then(&self, other: &Self) -> Self {
+        Self {
+            axes: other.axes.map(|axis| self.axes[axis]),
+            signs: [0, 1, 2].map(|row| other.signs[row] * self.signs[other.axes[row]]),
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        let mut inverse = Self::IDENTITY;
+        for (row, (&axis, &sign)) in self.axes.iter().zip(&self.signs).enumerate() {
+            inverse.axes[axis] = row;
+            inverse.signs[axis] = sign;
+        }
+        inverse
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multiplies_points() {
+        let (x, y) = (Point3::new(1, 0, 0), Point3::new(0, 1, 0));
+        assert_eq!(x.cross(&y), Point3::new(0, 0, 1));
+        assert_eq!(y.cross(&x), Point3::new(0, 0, -1));
+        assert_eq!(Point3::new(1, 2, 3).dot(&Point3::new(4, -5, 6)), 12);
+        assert_eq!(
+            "1,-2,3".parse::<Point3<i64>>().unwrap(),
+            Point3::new(1, -2, 3)
+        );
+        assert!("1,2".parse::<Point3<i64>>().is_err());
+    }
+
+    #[test]
+    fn forms_the_rotation_group() {
+        let rotations = Rotation::all().collect::<Vec<_>>();
+        assert_eq!(rotations.len(), 24);
+        assert_eq!(rotations[0], Rotation::IDENTITY);
+        let (x, y) = (Point3::new(1, 0, 0), Point3::new(0, 1, 0));
+        let mut images = rotations
+            .iter()
+            .map(|rotation| (rotation.apply(x), rotation.apply(y)))
+            .collect::<Vec<_>>();
+        images.sort();
+        images.dedup();
+        assert_eq!(images.len(), 24);
+        for rotation in &rotations {
+            let axes = (rotation.apply(x), rotation.apply(y));
+            assert_eq!(Rotation::from_axes(axes.0, axes.1), Some(*rotation));
+        }
+        assert_eq!(Rotation::from_axes(x, x), None);
+
+        let point = Point3::new(1, 2, 3);
+        for a in &rotations {
+            // Rotations keep the right-hand rule, so they are not reflections.
+            assert_eq!(a.apply(x).cross(&a.apply(y)), a.apply(Point3::new(0, 0, 1)));
+            assert_eq!(a.then(&a.inverse()), Rotation::IDENTITY);
+            let matrix = a.matrix();
+            let row = |i: usize| Point3::from_array(matrix[i]);
+            assert_eq!(
+                a.apply(point),
+                Point3::new(row(0).dot(&point), row(1).dot(&point), row(2).dot(&point))
+            );
+            for b in &rotations {
+                assert_eq!(a.then(b).apply(point), b.apply(a.apply(point)));
+                assert!(rotations.contains(&a.then(b)));
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use std::ops::{Index, IndexMut};
+
+use super::{AocError, AocResult, Direction, Point2};
+
+type Point = Point2<i64>;
+
+// A rectangle of cells, stored row by row, with the origin in the top-left
+// corner like the rows of puzzle input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    // Builds a grid from its rows, which must all be the same length.
+    pub fn from_rows(rows: impl IntoIterator<Item = Vec<T>>) -> AocResult<Self> {
+        let mut rows = rows.into_iter();
+        let (mut cells, mut height) = match rows.next() {
+            None => (Vec::new(), 0),
+            Some(row) => (row, 1),
+        };
+        let width = cells.len();
+        for row in rows {
+            if row.len() != width {
+                return Err(AocError::parse(format!(
+                    "row {} has {} cells, but row 1 has {}",
+                    height + 1,
+                    row.len(),
+                    width
+                )));
+            }
+            cells.extend(row);
+            height += 1;
+        }
+        Ok(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    // Parses a grid with one row per line, turning each character into a cell.
+    pub fn parse(input: &str, mut cell: impl FnMut(char) -> AocResult<T>) -> AocResult<Self> {
+        Self::from_rows(
+            input
+                .lines()
+                .map(|line| line.chars().map(&mut cell).collect::<AocResult<Vec<_>>>())
+                .collect::<AocResult<Vec<_>>>()?,
+        )
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        point.in_bounds(
+            &Point::default(),
+            &Point::new(self.width as i64, self.height as i64),
+        )
+    }
+
+    fn index_of(&self, point: &Point) -> Option<usize> {
+        self.contains(point)
+            .then(|| point.y as usize * self.width + point.x as usize)
+    }
+
+    pub fn get(&self, point: &Point) -> Option<&T> {
+        self.index_of(point).map(|i| &self.cells[i])
+    }
+
+    pub fn get_mut(&mut self, point: &Point) -> Option<&mut T> {
+        self.index_of(point).map(|i| &mut self.cells[i])
+    }
+
+    // Every cell with its position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().enumerate().map(|(i, cell)| {
+            let point = Point::new((i % self.width) as i64, (i / self.width) as i64);
+            (point, cell)
+        })
+    }
+
+    // A grid of the same size with every cell mapped.
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    fn line(
+        &self,
+        start: Point,
+        step: Point,
+        len: usize,
+    ) -> impl DoubleEndedIterator<Item = (Point, &T)> + Clone {
+        (0..len as i64).map(move |i| {
+            let point = start + step * i;
+            (point, &self[point])
+        })
+    }
+
+    // Each row from top to bottom, going left to right. Rows can be reversed to
+    // go right to left.
+    pub fn rows(
+        &self,
+    ) -> impl Iterator<Item = impl DoubleEndedIterator<Item = (Point, &T)> + Clone> {
+        (0..self.height as i64).map(|y| self.line(Point::new(0, y), Point::new(1, 0), self.width))
+    }
+
+    // Each column from left to right, going top to bottom. Columns can be
+    // reversed to go bottom to top.
+    pub fn cols(
+        &self,
+    ) -> impl Iterator<Item = impl DoubleEndedIterator<Item = (Point, &T)> + Clone> {
+        (0..self.width as i64).map(|x| self.line(Point::new(x, 0), Point::new(0, 1), self.height))
+    }
+
+    // The cells seen looking from a point in a direction, starting next to the
+    // point and ending at the edge of the grid.
+    pub fn sightline(&self, point: Point, dir: Direction) -> impl Iterator<Item = &T> {
+        let step = dir.delta();
+        (1..)
+            .map(move |i| self.get(&(point + step * i)))
+            .take_while(Option::is_some)
+            .flatten()
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+    fn index(&self, point: Point) -> &T {
+        self.get(&point)
+            .unwrap_or_else(|| panic!("{point:?} is outside of the grid"))
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        self.get_mut(&point)
+            .unwrap_or_else(|| panic!("{point:?} is outside of the grid"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Grid<u32> {
+        Grid::parse("123\n456", |c| Ok(c.to_digit(10).unwrap())).unwrap()
+    }
+
+    fn values<'a>(line: impl Iterator<Item = (Point, &'a u32)>) -> Vec<u32> {
+        line.map(|(_, cell)| *cell).collect()
+    }
+
+    #[test]
+    fn walks_rows_and_columns() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(
+            grid.rows().map(values).collect::<Vec<_>>(),
+            [vec![1, 2, 3], vec![4, 5, 6]]
+        );
+        assert_eq!(
+            grid.cols().map(|col| values(col.rev())).collect::<Vec<_>>(),
+            [vec![4, 1], vec![5, 2], vec![6, 3]]
+        );
+        assert_eq!(grid[Point::new(2, 1)], 6);
+        assert_eq!(grid.get(&Point::new(3, 0)), None);
+    }
+
+    #[test]
+    fn looks_along_sightlines() {
+        let grid = example();
+        let sightline = |x, y, dir| {
+            grid.sightline(Point::new(x, y), dir)
+                .copied()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sightline(0, 0, Direction::Right), [2, 3]);
+        assert_eq!(sightline(2, 1, Direction::Left), [5, 4]);
+        assert_eq!(sightline(1, 1, Direction::Up), [2]);
+        assert_eq!(sightline(1, 1, Direction::Down), [] as [u32; 0]);
+    }
+
+    #[test]
+    fn rejects_ragged_rows() {
+        let err = Grid::parse("123\n45\n6", Ok).unwrap_err();
+        assert_eq!(err.message(), "row 2 has 2 cells, but row 1 has 3");
+        assert!(Grid::<char>::parse("", Ok).unwrap().is_empty());
+    }
+}
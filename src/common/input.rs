@@ -0,0 +1,103 @@
+use std::{
+    borrow::Cow,
+    fs::{self, File},
+    io::{self, BufRead, BufReader},
+    path::PathBuf,
+    str,
+};
+
+use super::{AocResult, IntoAocResult};
+
+// The input to a solver, which is either already in memory or still in a file.
+// A file is only read as far as the solver asks for it, so solvers that go
+// line by line never hold all of it at once.
+#[derive(Debug, Clone)]
+pub enum Input<'a> {
+    Text(&'a str),
+    File(PathBuf),
+}
+
+impl<'a> Input<'a> {
+    // The whole input, reading the file if needed.
+    pub fn text(&self) -> AocResult<Cow<'a, str>> {
+        match self {
+            Self::Text(text) => Ok(Cow::Borrowed(text)),
+            Self::File(path) => fs::read_to_string(path)
+                .map(Cow::Owned)
+                .into_aoc_result_msg(&format!("failed to read {}", path.display())),
+        }
+    }
+
+    // The input one line at a time, without line endings.
+    pub fn lines(&self) -> AocResult<InputLines<'a>> {
+        match self {
+            Self::Text(text) => Ok(InputLines::Text(text.lines())),
+            Self::File(path) => File::open(path)
+                .map(|file| InputLines::File(BufReader::new(file).lines()))
+                .into_aoc_result_msg(&format!("failed to open {}", path.display())),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Input<'a> {
+    fn from(text: &'a str) -> Self {
+        Self::Text(text)
+    }
+}
+
+pub enum InputLines<'a> {
+    Text(str::Lines<'a>),
+    File(io::Lines<BufReader<File>>),
+}
+
+impl<'a> From<&'a str> for InputLines<'a> {
+    fn from(text: &'a str) -> Self {
+        Self::Text(text.lines())
+    }
+}
+
+impl<'a> Iterator for InputLines<'a> {
+    type Item = AocResult<Cow<'a, str>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Text(lines) => lines.next().map(|line| Ok(Cow::Borrowed(line))),
+            Self::File(lines) => lines.next().map(|line| {
+                line.map(Cow::Owned)
+                    .into_aoc_result_msg("failed to read line")
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_the_same_lines_from_text_and_files() {
+        let text = "1,2\r\n\n3,4\n";
+        let path = std::env::temp_dir().join(format!("aoc-input-test-{}.txt", std::process::id()));
+        fs::write(&path, text).unwrap();
+        let from_file = Input::File(path.clone())
+            .lines()
+            .unwrap()
+            .collect::<AocResult<Vec<_>>>()
+            .unwrap();
+        let from_text = Input::from(text)
+            .lines()
+            .unwrap()
+            .collect::<AocResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(Input::File(path.clone()).text().unwrap(), text);
+        fs::remove_file(path).unwrap();
+        assert_eq!(from_file, ["1,2", "", "3,4"]);
+        assert_eq!(from_text, from_file);
+    }
+
+    #[test]
+    fn fails_to_stream_missing_files() {
+        assert!(Input::File(PathBuf::from("does/not/exist.txt"))
+            .lines()
+            .is_err());
+    }
+}
@@ -1,93 +1,184 @@
-use std::{marker::PhantomData, str::FromStr};
+use std::{fmt::Display, marker::PhantomData};
 
-use num::{Integer, Signed, Unsigned};
+use num::{Num, Signed, Unsigned};
 
-pub struct IntegerParsingIterator<'a, T, const SIGNED: bool> {
+use super::{AocError, AocResult};
+
+// The radix that a prefix like "0x" switches to.
+fn prefix_radix(prefix: u8) -> Option<u32> {
+    match prefix.to_ascii_lowercase() {
+        b'b' => Some(2),
+        b'o' => Some(8),
+        b'x' => Some(16),
+        _ => None,
+    }
+}
+
+// Finds the integers in a string, skipping everything between them.
+//
+// A number written with a "0x", "0o", or "0b" prefix is read in that radix
+// rather than the default one. A "-" right before a number negates it when
+// parsing signed integers; otherwise it is skipped like any other separator,
+// so that ranges like "2-4" can be read as two unsigned numbers.
+//
+// Numbers that do not fit in the type are reported as errors, and parsing
+// carries on after them.
+pub struct IntegerParsingIterator<'a, T> {
     src: &'a str,
     radix: u32,
+    signed: bool,
     i: usize,
     phantom: PhantomData<T>,
 }
 
-impl<'a, T, const SIGNED: bool> IntegerParsingIterator<'a, T, SIGNED>
+impl<'a, T> IntegerParsingIterator<'a, T>
 where
-    T: Integer + FromStr,
+    T: Num,
+    T::FromStrRadixErr: Display,
 {
-    pub fn new(src: &'a str, radix: u32) -> Self {
+    fn new(src: &'a str, radix: u32, signed: bool) -> Self {
         Self {
             src,
             radix,
+            signed,
             i: 0,
             phantom: PhantomData,
         }
     }
 
-    fn get_next(&mut self) -> Option<(bool, T)> {
-        if self.i >= self.src.len() {
-            None
-        } else if let Some(mut next) = self.src[self.i..].find(|c: char| c.is_digit(self.radix)) {
-            next = next + self.i;
-            let negative = SIGNED && next > 0 && &self.src[(next - 1)..next] == "-";
-            let end = match self.src[next..].find(|c: char| !c.is_digit(self.radix)) {
-                Some(end) => next + end,
-                None => self.src.len(),
-            };
-            self.i = end;
-            unsafe {
-                Some((
-                    negative,
-                    self.src[next..end].parse::<T>().unwrap_unchecked(),
-                ))
-            }
-        } else {
-            None
-        }
+    // The end of the run of digits starting at `start`.
+    fn digits_end(&self, start: usize, radix: u32) -> usize {
+        self.src[start..]
+            .find(|c: char| !c.is_digit(radix))
+            .map_or(self.src.len(), |end| start + end)
     }
-}
 
-impl<'a, T> Iterator for IntegerParsingIterator<'a, T, false>
-where
-    T: Integer + Unsigned + FromStr,
-{
-    type Item = T;
-    fn next(&mut self) -> Option<Self::Item> {
-        self.get_next().and_then(|(_, n)| Some(n))
+    // A prefixed number starting at `start`, as its radix and the start of its
+    // digits. Letters that are digits in the default radix, like the "b" in hex,
+    // are read as digits instead.
+    fn prefixed(&self, start: usize) -> Option<(u32, usize)> {
+        let bytes = self.src.as_bytes();
+        if bytes[start] != b'0' {
+            return None;
+        }
+        let prefix = *bytes.get(start + 1)?;
+        if (prefix as char).is_digit(self.radix) {
+            return None;
+        }
+        let radix = prefix_radix(prefix)?;
+        let digits = start + 2;
+        (self.src[digits..].chars().next()?.is_digit(radix)).then_some((radix, digits))
     }
 }
 
-impl<'a, T> Iterator for IntegerParsingIterator<'a, T, true>
+impl<'a, T> Iterator for IntegerParsingIterator<'a, T>
 where
-    T: Integer + Signed + FromStr,
+    T: Num,
+    T::FromStrRadixErr: Display,
 {
-    type Item = T;
+    type Item = AocResult<T>;
     fn next(&mut self) -> Option<Self::Item> {
-        self.get_next()
-            .and_then(|(neg, n)| Some(if neg { n.neg() } else { n }))
+        let start = self.i + self.src[self.i..].find(|c: char| c.is_digit(self.radix))?;
+        let (radix, digits) = self.prefixed(start).unwrap_or((self.radix, start));
+        let end = self.digits_end(digits, radix);
+        self.i = end;
+
+        let negative = self.signed && start > 0 && self.src.as_bytes()[start - 1] == b'-';
+        let number = if negative {
+            format!("-{}", &self.src[digits..end])
+        } else {
+            self.src[digits..end].to_owned()
+        };
+        Some(T::from_str_radix(&number, radix).map_err(|err| {
+            AocError::parse(format!(
+                "invalid integer {}: {}",
+                &self.src[start - negative as usize..end],
+                err
+            ))
+        }))
     }
 }
 
 pub trait ParseIntegers {
-    fn parse_integers<'a, T>(&'a self, radix: u32) -> IntegerParsingIterator<'a, T, false>
+    fn parse_integers<T>(&self, radix: u32) -> IntegerParsingIterator<'_, T>
     where
-        T: Integer + Unsigned + FromStr;
+        T: Num + Unsigned,
+        T::FromStrRadixErr: Display;
 
-    fn parse_signed_integers<'a, T>(&'a self, radix: u32) -> IntegerParsingIterator<'a, T, true>
+    fn parse_signed_integers<T>(&self, radix: u32) -> IntegerParsingIterator<'_, T>
     where
-        T: Integer + Signed + FromStr;
+        T: Num + Signed,
+        T::FromStrRadixErr: Display;
 }
 
-impl ParseIntegers for &str {
-    fn parse_integers<'a, T>(&'a self, radix: u32) -> IntegerParsingIterator<'a, T, false>
+impl ParseIntegers for str {
+    fn parse_integers<T>(&self, radix: u32) -> IntegerParsingIterator<'_, T>
     where
-        T: Integer + Unsigned + FromStr,
+        T: Num + Unsigned,
+        T::FromStrRadixErr: Display,
     {
-        IntegerParsingIterator::new(self, radix)
+        IntegerParsingIterator::new(self, radix, false)
     }
 
-    fn parse_signed_integers<'a, T>(&'a self, radix: u32) -> IntegerParsingIterator<'a, T, true>
+    fn parse_signed_integers<T>(&self, radix: u32) -> IntegerParsingIterator<'_, T>
     where
-        T: Integer + Signed + FromStr,
+        T: Num + Signed,
+        T::FromStrRadixErr: Display,
     {
-        IntegerParsingIterator::new(self, radix)
+        IntegerParsingIterator::new(self, radix, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unsigned(s: &str) -> AocResult<Vec<u8>> {
+        s.parse_integers(10).collect()
+    }
+
+    fn signed(s: &str) -> AocResult<Vec<i8>> {
+        s.parse_signed_integers(10).collect()
+    }
+
+    #[test]
+    fn finds_integers_between_other_text() {
+        assert_eq!(unsigned("Test: divisible by 23").unwrap(), [23]);
+        assert_eq!(unsigned("2-4,6-8").unwrap(), [2, 4, 6, 8]);
+        assert_eq!(signed("x=-2, y=15 2-4").unwrap(), [-2, 15, 2, -4]);
+        assert!(unsigned("no numbers here").unwrap().is_empty());
+        assert!(unsigned("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_overflow() {
+        assert_eq!(signed("-128 127").unwrap(), [-128, 127]);
+        assert_eq!(
+            signed("x=-129").unwrap_err().message(),
+            "invalid integer -129: number too small to fit in target type"
+        );
+        assert_eq!(
+            unsigned("255, 256").unwrap_err().message(),
+            "invalid integer 256: number too large to fit in target type"
+        );
+
+        // The numbers after an error are still found.
+        let mut numbers = "300 7".parse_integers::<u8>(10);
+        assert!(numbers.next().unwrap().is_err());
+        assert_eq!(numbers.next().unwrap().unwrap(), 7);
+        assert!(numbers.next().is_none());
+    }
+
+    #[test]
+    fn reads_radix_prefixes() {
+        assert_eq!(unsigned("0x1f 0o17 0b101 0 0x").unwrap(), [31, 15, 5, 0, 0]);
+        assert_eq!(signed("-0x80 -0b1").unwrap(), [-128, -1]);
+        assert_eq!(
+            "ff 0b11 0o7"
+                .parse_integers::<u16>(16)
+                .collect::<AocResult<Vec<_>>>()
+                .unwrap(),
+            [255, 0xb11, 7]
+        );
     }
 }
@@ -0,0 +1,247 @@
+use num::PrimInt;
+
+// An inclusive range of integers. It is empty if the start is after the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T> Interval<T> {
+    pub const fn new(start: T, end: T) -> Self {
+        Self { start, end }
+    }
+}
+
+impl<T: PrimInt> Interval<T> {
+    // The interval containing only the given value.
+    pub fn point(value: T) -> Self {
+        Self::new(value, value)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start > self.end
+    }
+
+    // The number of integers in the interval.
+    pub fn len(&self) -> T {
+        if self.is_empty() {
+            T::zero()
+        } else {
+            self.end - self.start + T::one()
+        }
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.start <= value && value <= self.end
+    }
+
+    pub fn fully_contains(&self, other: &Self) -> bool {
+        self.start <= other.start && self.end >= other.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    // The integers in both intervals, which may be empty.
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    // Whether the two intervals could be merged into one, which is true if
+    // they overlap or one ends right before the other starts.
+    fn touches(&self, other: &Self) -> bool {
+        self.start <= other.end.saturating_add(T::one())
+            && other.start <= self.end.saturating_add(T::one())
+    }
+}
+
+// A set of integers stored as the fewest intervals that cover it, in
+// increasing order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self {
+            intervals: Vec::new(),
+        }
+    }
+}
+
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.intervals
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let i = self
+            .intervals
+            .partition_point(|interval| interval.end < value);
+        self.intervals
+            .get(i)
+            .is_some_and(|interval| interval.contains(value))
+    }
+
+    // Adds every integer in the interval, merging it with the intervals it
+    // touches.
+    pub fn insert(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        let first = self
+            .intervals
+            .partition_point(|other| other.end.saturating_add(T::one()) < interval.start);
+        let mut merged = interval;
+        let mut last = first;
+        while let Some(other) = self
+            .intervals
+            .get(last)
+            .filter(|other| other.touches(&merged))
+        {
+            merged.start = merged.start.min(other.start);
+            merged.end = merged.end.max(other.end);
+            last += 1;
+        }
+        self.intervals.splice(first..last, [merged]);
+    }
+
+    // Adds every integer in another set.
+    pub fn merge(&mut self, other: &Self) {
+        for interval in &other.intervals {
+            self.insert(*interval);
+        }
+    }
+
+    // Removes every integer in the interval, splitting any interval it falls
+    // in the middle of.
+    pub fn subtract(&mut self, interval: Interval<T>) {
+        if interval.is_empty() {
+            return;
+        }
+        let first = self
+            .intervals
+            .partition_point(|other| other.end < interval.start);
+        let last = first
+            + self.intervals[first..]
+                .iter()
+                .take_while(|other| other.start <= interval.end)
+                .count();
+        let remaining = self.intervals[first..last]
+            .iter()
+            .flat_map(|other| {
+                // Checking first keeps the bounds from overflowing at the edges
+                // of the integer range.
+                let before = (other.start < interval.start)
+                    .then(|| Interval::new(other.start, interval.start - T::one()));
+                let after = (interval.end < other.end)
+                    .then(|| Interval::new(interval.end + T::one(), other.end));
+                before.into_iter().chain(after)
+            })
+            .collect::<Vec<_>>();
+        self.intervals.splice(first..last, remaining);
+    }
+
+    // The number of integers in the set.
+    pub fn total_len(&self) -> T {
+        self.intervals
+            .iter()
+            .fold(T::zero(), |total, interval| total + interval.len())
+    }
+}
+
+impl<T: PrimInt> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut set = Self::new();
+        for interval in iter {
+            set.insert(interval);
+        }
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet<i64> {
+        intervals
+            .iter()
+            .map(|&(start, end)| Interval::new(start, end))
+            .collect()
+    }
+
+    fn pairs(set: &IntervalSet<i64>) -> Vec<(i64, i64)> {
+        set.intervals()
+            .iter()
+            .map(|interval| (interval.start, interval.end))
+            .collect()
+    }
+
+    #[test]
+    fn compares_intervals() {
+        let interval = Interval::new(2, 8);
+        assert!(interval.fully_contains(&Interval::new(3, 7)));
+        assert!(!interval.fully_contains(&Interval::new(6, 9)));
+        assert!(interval.overlaps(&Interval::new(8, 9)));
+        assert!(!interval.overlaps(&Interval::new(9, 9)));
+        assert_eq!(interval.len(), 7);
+        assert_eq!(Interval::new(3, 2).len(), 0);
+        assert_eq!(
+            interval.intersection(&Interval::new(5, 12)),
+            Interval::new(5, 8)
+        );
+        assert!(interval.intersection(&Interval::new(9, 12)).is_empty());
+    }
+
+    #[test]
+    fn merges_overlapping_and_adjacent_intervals() {
+        let mut intervals = set(&[(10, 12), (0, 2), (5, 6), (3, 3)]);
+        assert_eq!(pairs(&intervals), [(0, 3), (5, 6), (10, 12)]);
+        intervals.insert(Interval::new(4, 9));
+        assert_eq!(pairs(&intervals), [(0, 12)]);
+        assert_eq!(intervals.total_len(), 13);
+    }
+
+    #[test]
+    fn subtracts_intervals() {
+        let mut intervals = set(&[(0, 10), (20, 30), (40, 50)]);
+        intervals.subtract(Interval::new(5, 5));
+        intervals.subtract(Interval::new(25, 45));
+        assert_eq!(pairs(&intervals), [(0, 4), (6, 10), (20, 24), (46, 50)]);
+        assert!(!intervals.contains(5));
+        assert!(intervals.contains(46));
+        assert_eq!(intervals.total_len(), 20);
+    }
+
+    #[test]
+    fn merges_sets() {
+        let mut intervals = set(&[(0, 2), (8, 9)]);
+        intervals.merge(&set(&[(3, 4), (6, 7)]));
+        assert_eq!(pairs(&intervals), [(0, 4), (6, 9)]);
+    }
+
+    #[test]
+    fn handles_edges_of_integer_range() {
+        let mut intervals = IntervalSet::new();
+        intervals.insert(Interval::new(u8::MAX - 1, u8::MAX));
+        intervals.insert(Interval::new(0, 1));
+        intervals.insert(Interval::new(2, 3));
+        intervals.subtract(Interval::new(0, 0));
+        assert_eq!(
+            intervals.intervals(),
+            [Interval::new(1, 3), Interval::new(254, 255)]
+        );
+    }
+}
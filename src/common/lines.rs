@@ -0,0 +1,95 @@
+use std::str::FromStr;
+
+use super::{AocError, AocResult};
+
+// Prefixes an error from parsing a line with its line number, counting from 1.
+// The error keeps its kind and source, so a line with no solution is still not
+// a parse error.
+pub fn at_line(line: usize, err: AocError) -> AocError {
+    let prefix = |message: String| match message.is_empty() {
+        true => format!("line {}", line),
+        false => format!("line {}: {}", line, message),
+    };
+    match err {
+        AocError::ParseError { message, source } => AocError::ParseError {
+            message: prefix(message),
+            source,
+        },
+        AocError::IoError { message, source } => AocError::IoError {
+            message: prefix(message),
+            source,
+        },
+        AocError::NoSolution(message) => AocError::NoSolution(prefix(message)),
+        AocError::InvalidArgs(message) => AocError::InvalidArgs(prefix(message)),
+        // Neither says anything about the line itself.
+        AocError::Timeout(_) | AocError::SolverFailed { .. } => err,
+    }
+}
+
+// Parses each line into a record as the lines are read, so that errors say
+// which line they came from. Errors reading the lines themselves are passed
+// through as they are.
+pub fn map_lines<T>(
+    lines: impl IntoIterator<Item = AocResult<impl AsRef<str>>>,
+    mut parse: impl FnMut(&str) -> AocResult<T>,
+) -> impl Iterator<Item = AocResult<T>> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(move |(i, line)| parse(line?.as_ref()).map_err(|err| at_line(i + 1, err)))
+}
+
+// Parses every line into a record.
+pub fn parse_lines<T>(
+    lines: impl IntoIterator<Item = AocResult<impl AsRef<str>>>,
+) -> AocResult<Vec<T>>
+where
+    T: FromStr<Err = AocError>,
+{
+    map_lines(lines, str::parse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{input::InputLines, Point3};
+
+    type Point = Point3<i64>;
+
+    #[test]
+    fn parses_every_line() {
+        let points = parse_lines::<Point>(InputLines::from("1,2,3\n4,5,6")).unwrap();
+        assert_eq!(points, [Point::new(1, 2, 3), Point::new(4, 5, 6)]);
+        assert!(parse_lines::<Point>(InputLines::from(""))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn reports_the_line_that_failed() {
+        let err = parse_lines::<Point>(InputLines::from("1,2,3\n4,5,6\n7,8")).unwrap_err();
+        assert_eq!(err.message(), "line 3: invalid point format");
+    }
+
+    #[test]
+    fn keeps_the_kind_of_error() {
+        let err = at_line(2, AocError::no_solution("no marker"));
+        assert!(matches!(err, AocError::NoSolution(_)));
+        assert_eq!(err.message(), "no solution: line 2: no marker");
+        let err = at_line(4, AocError::invalid_args("unknown option"));
+        assert!(err.is_invalid_args());
+        assert_eq!(err.message(), "line 4: unknown option");
+        let err = at_line(1, AocError::from_source("", "x".parse::<u8>().unwrap_err()));
+        assert!(std::error::Error::source(&err).is_some());
+        assert_eq!(err.message(), "line 1: invalid digit found in string");
+    }
+
+    #[test]
+    fn passes_read_errors_through() {
+        let lines = [Ok("1"), Err(AocError::io("failed to read line"))];
+        let err = map_lines(lines, |line| Ok(line.len()))
+            .collect::<AocResult<Vec<_>>>()
+            .unwrap_err();
+        assert_eq!(err.message(), "failed to read line");
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    fmt::Arguments,
+    str::FromStr,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use super::{AocError, AocResult};
+
+pub const LOG_ENV_VAR: &str = "AOC_LOG";
+
+// How much the solvers say about what they are doing, on stderr so that it
+// never mixes with answers.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u8)]
+pub enum Level {
+    Off,
+    // Statistics about each search or simulation once it is done.
+    Debug,
+    // Progress while it runs.
+    Trace,
+}
+
+impl FromStr for Level {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        match s {
+            "off" => Ok(Self::Off),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            _ => Err(AocError::invalid_args(format!(
+                "{LOG_ENV_VAR} must be off, debug, or trace"
+            ))),
+        }
+    }
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Off as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    level != Level::Off && LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+// Sets the level from the environment, if it is set there.
+pub fn init_from_env() -> AocResult<()> {
+    if let Ok(level) = std::env::var(LOG_ENV_VAR) {
+        set_level(level.parse()?);
+    }
+    Ok(())
+}
+
+// Writes a message from the module it was logged in, named by the last part of
+// its path, such as "day16".
+pub fn write(level: Level, module_path: &str, message: Arguments) {
+    let module = module_path.rsplit("::").next().unwrap_or(module_path);
+    let level = match level {
+        Level::Off => return,
+        Level::Debug => "debug",
+        Level::Trace => "trace",
+    };
+    eprintln!("[{module} {level}] {message}");
+}
+
+// Logs statistics, when running with `AOC_LOG=debug` or more.
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::common::log::enabled($crate::common::log::Level::Debug) {
+            $crate::common::log::write(
+                $crate::common::log::Level::Debug,
+                module_path!(),
+                format_args!($($arg)*),
+            );
+        }
+    };
+}
+
+// Logs progress, when running with `--trace` or `AOC_LOG=trace`.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        if $crate::common::log::enabled($crate::common::log::Level::Trace) {
+            $crate::common::log::write(
+                $crate::common::log::Level::Trace,
+                module_path!(),
+                format_args!($($arg)*),
+            );
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_levels() {
+        assert_eq!("trace".parse::<Level>().unwrap(), Level::Trace);
+        assert_eq!("off".parse::<Level>().unwrap(), Level::Off);
+        assert!("verbose".parse::<Level>().is_err());
+        assert!(Level::Off < Level::Debug && Level::Debug < Level::Trace);
+    }
+}
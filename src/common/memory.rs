@@ -0,0 +1,115 @@
+// Heap usage tracking for solvers.
+//
+// With the `memory-stats` feature, the program installs a global allocator that
+// counts live heap bytes and remembers the highest count. Without it, nothing is
+// counted and every measurement is `None`. The counts are process-wide, so
+// measurements taken while solvers run in parallel include each other's usage.
+
+#[cfg(feature = "memory-stats")]
+mod counting {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    pub static CURRENT: AtomicUsize = AtomicUsize::new(0);
+    pub static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+    pub struct CountingAllocator;
+
+    impl CountingAllocator {
+        fn add(size: usize) {
+            let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+
+        fn remove(size: usize) {
+            CURRENT.fetch_sub(size, Ordering::Relaxed);
+        }
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc(layout);
+            if !ptr.is_null() {
+                Self::add(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = System.alloc_zeroed(layout);
+            if !ptr.is_null() {
+                Self::add(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout);
+            Self::remove(layout.size());
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new_ptr = System.realloc(ptr, layout, new_size);
+            if !new_ptr.is_null() {
+                Self::remove(layout.size());
+                Self::add(new_size);
+            }
+            new_ptr
+        }
+    }
+}
+
+#[cfg(feature = "memory-stats")]
+pub use counting::CountingAllocator;
+
+// Measures the highest heap usage above the usage at the time it was started.
+pub struct PeakMemory {
+    #[cfg_attr(not(feature = "memory-stats"), allow(dead_code))]
+    baseline: usize,
+}
+
+impl PeakMemory {
+    #[cfg(feature = "memory-stats")]
+    pub fn start() -> Self {
+        use std::sync::atomic::Ordering;
+        let baseline = counting::CURRENT.load(Ordering::Relaxed);
+        counting::PEAK.store(baseline, Ordering::Relaxed);
+        Self { baseline }
+    }
+
+    #[cfg(not(feature = "memory-stats"))]
+    pub fn start() -> Self {
+        Self { baseline: 0 }
+    }
+
+    #[cfg(feature = "memory-stats")]
+    pub fn finish(self) -> Option<usize> {
+        use std::sync::atomic::Ordering;
+        Some(
+            counting::PEAK
+                .load(Ordering::Relaxed)
+                .saturating_sub(self.baseline),
+        )
+    }
+
+    #[cfg(not(feature = "memory-stats"))]
+    pub fn finish(self) -> Option<usize> {
+        None
+    }
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
@@ -1,9 +1,46 @@
+pub mod bits;
 mod blocks;
+pub mod constants;
+pub mod cycle;
+mod day_args;
+pub mod dsu;
 mod error;
+mod geometry;
+pub mod grid;
+pub mod input;
 mod integers;
+pub mod interval;
+pub mod lines;
+#[macro_use]
+pub mod log;
+mod memory;
+pub mod ocr;
+mod order_list;
+mod parallel;
+pub mod parse;
+mod part;
+pub mod progress;
+pub mod render;
+pub mod search;
+pub mod snafu;
 mod solver;
+pub mod tetris;
+pub mod timing;
+pub mod viz;
+pub mod vm;
+pub mod voxel;
+mod window;
 
-pub use blocks::{NewlineBlocks, NewlineBlocksIterator};
+pub use blocks::{IntoNewlineBlocks, NewlineBlocks, ReadBlocksIterator};
+pub use day_args::DayArgs;
 pub use error::{AocError, AocResult, IntoAocResult};
-pub use integers::{IntegerParsingIterator, ParseIntegers};
-pub use solver::{AocSolution, IntSolverFn, Solver, StringSolverFn};
+pub use geometry::{Direction, Point2, Point3, Rotation};
+pub use integers::ParseIntegers;
+#[cfg(feature = "memory-stats")]
+pub use memory::CountingAllocator;
+pub use memory::{format_bytes, PeakMemory};
+pub use order_list::OrderList;
+pub use parallel::{parallel_map, with_timeout};
+pub use part::SolutionPart;
+pub use solver::{AocSolution, Solver};
+pub use window::find_distinct_window;
@@ -0,0 +1,106 @@
+use super::{AocError, AocResult};
+
+// The width and height of a letter drawn by a puzzle, in pixels.
+pub const LETTER_WIDTH: usize = 4;
+pub const LETTER_HEIGHT: usize = 6;
+
+// Letters are drawn next to each other with one column of space between them.
+const LETTER_SPACING: usize = LETTER_WIDTH + 1;
+
+// Every capital letter that puzzles are known to draw, row by row.
+const GLYPHS: [(char, [&str; LETTER_HEIGHT]); 17] = [
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+// Reads the letters drawn on a grid of pixels, stored row by row.
+pub fn read_letters(pixels: &[bool], width: usize) -> AocResult<String> {
+    if width == 0 || pixels.len() != width * LETTER_HEIGHT {
+        return Err(AocError::parse(format!(
+            "letters must be drawn on a grid {LETTER_HEIGHT} pixels tall"
+        )));
+    }
+    (0..width.div_ceil(LETTER_SPACING))
+        .map(|letter| {
+            let left = letter * LETTER_SPACING;
+            let lit = |x: usize, y: usize| x < width && pixels[y * width + x];
+            GLYPHS
+                .iter()
+                .find(|(_, glyph)| {
+                    glyph.iter().enumerate().all(|(y, row)| {
+                        row.chars()
+                            .enumerate()
+                            .all(|(x, c)| lit(left + x, y) == (c == '#'))
+                    })
+                })
+                .map(|(c, _)| *c)
+                .ok_or_else(|| AocError::parse(format!("unrecognized letter at column {left}")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Draws rows of `#` and `.` as pixels, returning them with their width.
+    fn pixels(rows: [&str; LETTER_HEIGHT]) -> (Vec<bool>, usize) {
+        let pixels = rows
+            .iter()
+            .flat_map(|row| row.chars().map(|c| c == '#'))
+            .collect();
+        (pixels, rows[0].len())
+    }
+
+    #[test]
+    fn reads_known_letters() {
+        // The last letter may be drawn without the space after it.
+        let (pixels, width) = pixels([
+            "#..#.####",
+            "#..#.#...",
+            "####.###.",
+            "#..#.#...",
+            "#..#.#...",
+            "#..#.####",
+        ]);
+        assert_eq!(read_letters(&pixels, width).unwrap(), "HE");
+    }
+
+    #[test]
+    fn rejects_unknown_letters() {
+        let (pixels, width) = pixels([
+            "#..#.#..#",
+            "#..#.##.#",
+            "####.#.##",
+            "#..#.#..#",
+            "#..#.#..#",
+            "#..#.#..#",
+        ]);
+        let err = read_letters(&pixels, width).unwrap_err();
+        assert!(err.to_string().contains("column 5"), "{err}");
+    }
+
+    #[test]
+    fn rejects_ragged_grids() {
+        let (pixels, width) = pixels([".##.", "#..#", "#..#", "####", "#..#", "#..#"]);
+        assert_eq!(read_letters(&pixels, width).unwrap(), "A");
+        assert!(read_letters(&pixels[..pixels.len() - 1], width).is_err());
+        assert!(read_letters(&pixels, width + 1).is_err());
+        assert!(read_letters(&[], 0).is_err());
+    }
+}
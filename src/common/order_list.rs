@@ -0,0 +1,279 @@
+use std::{cmp::Ordering, iter};
+
+// A list that supports finding, removing, and inserting elements by position
+// in O(log n) time.
+//
+// Elements are stored in an implicit treap: a binary tree ordered by position,
+// balanced by random priorities, where each node knows the size of its subtree.
+// Pushing an element returns a handle that stays valid as the element moves
+// around, so an element's current position can always be found by walking up
+// from its node.
+#[derive(Debug, Clone)]
+pub struct OrderList<T> {
+    nodes: Vec<Node<T>>,
+    root: usize,
+    seed: u64,
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    value: T,
+    priority: u64,
+    size: usize,
+    left: usize,
+    right: usize,
+    parent: usize,
+}
+
+const NIL: usize = usize::MAX;
+
+impl<T> Default for OrderList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> OrderList<T> {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            root: NIL,
+            seed: 0x2545_f491_4f6c_dd1d,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.size(self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root == NIL
+    }
+
+    // Appends an element to the end of the list, returning its handle.
+    pub fn push(&mut self, value: T) -> usize {
+        let handle = self.nodes.len();
+        let priority = self.next_priority();
+        self.nodes.push(Node {
+            value,
+            priority,
+            size: 1,
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+        });
+        self.root = self.merge(self.root, handle);
+        handle
+    }
+
+    // The element with the given handle, wherever it currently is.
+    pub fn value(&self, handle: usize) -> &T {
+        &self.nodes[handle].value
+    }
+
+    pub fn value_mut(&mut self, handle: usize) -> &mut T {
+        &mut self.nodes[handle].value
+    }
+
+    // The element at the given position.
+    pub fn get(&self, mut index: usize) -> Option<&T> {
+        let mut node = self.root;
+        while node != NIL {
+            let left_size = self.size(self.nodes[node].left);
+            match index.cmp(&left_size) {
+                Ordering::Less => node = self.nodes[node].left,
+                Ordering::Equal => return Some(&self.nodes[node].value),
+                Ordering::Greater => {
+                    index -= left_size + 1;
+                    node = self.nodes[node].right;
+                }
+            }
+        }
+        None
+    }
+
+    // The current position of the element with the given handle.
+    pub fn index_of(&self, handle: usize) -> usize {
+        let mut index = self.size(self.nodes[handle].left);
+        let mut node = handle;
+        while self.nodes[node].parent != NIL {
+            let parent = self.nodes[node].parent;
+            if self.nodes[parent].right == node {
+                index += self.size(self.nodes[parent].left) + 1;
+            }
+            node = parent;
+        }
+        index
+    }
+
+    // Moves the element with the given handle so that it ends up at the given
+    // position.
+    pub fn move_to(&mut self, handle: usize, index: usize) {
+        let current = self.index_of(handle);
+        let (before, rest) = self.split(self.root, current);
+        let (_, after) = self.split(rest, 1);
+        let remaining = self.merge(before, after);
+
+        let node = &mut self.nodes[handle];
+        (node.left, node.right, node.parent, node.size) = (NIL, NIL, NIL, 1);
+
+        let (before, after) = self.split(remaining, index);
+        let before = self.merge(before, handle);
+        self.root = self.merge(before, after);
+    }
+
+    // Iterates over the elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        let mut stack = Vec::new();
+        let mut node = self.root;
+        iter::from_fn(move || {
+            while node != NIL {
+                stack.push(node);
+                node = self.nodes[node].left;
+            }
+            let next = stack.pop()?;
+            node = self.nodes[next].right;
+            Some(&self.nodes[next].value)
+        })
+    }
+
+    fn next_priority(&mut self) -> u64 {
+        // xorshift64, which is plenty random for balancing.
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 7;
+        self.seed ^= self.seed << 17;
+        self.seed
+    }
+
+    fn size(&self, node: usize) -> usize {
+        if node == NIL {
+            0
+        } else {
+            self.nodes[node].size
+        }
+    }
+
+    // Recalculates the size of a node and points its children back at it.
+    fn update(&mut self, node: usize) {
+        let (left, right) = (self.nodes[node].left, self.nodes[node].right);
+        self.nodes[node].size = self.size(left) + self.size(right) + 1;
+        for child in [left, right] {
+            if child != NIL {
+                self.nodes[child].parent = node;
+            }
+        }
+    }
+
+    // Splits a tree into the first `count` elements and the rest.
+    fn split(&mut self, node: usize, count: usize) -> (usize, usize) {
+        if node == NIL {
+            return (NIL, NIL);
+        }
+        self.nodes[node].parent = NIL;
+        let left_size = self.size(self.nodes[node].left);
+        if count <= left_size {
+            let (left, right) = self.split(self.nodes[node].left, count);
+            self.nodes[node].left = right;
+            self.update(node);
+            (left, node)
+        } else {
+            let (left, right) = self.split(self.nodes[node].right, count - left_size - 1);
+            self.nodes[node].right = left;
+            self.update(node);
+            (node, right)
+        }
+    }
+
+    // Joins two trees, with every element of the first before every element of
+    // the second.
+    fn merge(&mut self, left: usize, right: usize) -> usize {
+        if left == NIL {
+            return right;
+        }
+        if right == NIL {
+            return left;
+        }
+        if self.nodes[left].priority > self.nodes[right].priority {
+            let merged = self.merge(self.nodes[left].right, right);
+            self.nodes[left].right = merged;
+            self.update(left);
+            self.nodes[left].parent = NIL;
+            left
+        } else {
+            let merged = self.merge(left, self.nodes[right].left);
+            self.nodes[right].left = merged;
+            self.update(right);
+            self.nodes[right].parent = NIL;
+            right
+        }
+    }
+}
+
+impl<T> FromIterator<T> for OrderList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = Self::new();
+        for value in iter {
+            list.push(value);
+        }
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(list: &OrderList<char>) -> String {
+        list.iter().collect()
+    }
+
+    #[test]
+    fn pushes_in_order() {
+        let mut list = OrderList::new();
+        assert!(list.is_empty());
+        let handles = "abcde".chars().map(|c| list.push(c)).collect::<Vec<_>>();
+        assert_eq!(list.len(), 5);
+        assert_eq!(contents(&list), "abcde");
+        for (index, &handle) in handles.iter().enumerate() {
+            assert_eq!(list.index_of(handle), index);
+        }
+        assert_eq!(list.get(2), Some(&'c'));
+        assert_eq!(list.get(5), None);
+    }
+
+    #[test]
+    fn moves_elements_to_both_ends() {
+        let mut list = "abcde".chars().collect::<OrderList<_>>();
+        // Handles are the order elements were pushed in.
+        list.move_to(0, 4);
+        assert_eq!(contents(&list), "bcdea");
+        assert_eq!(list.index_of(0), 4);
+        list.move_to(0, 0);
+        assert_eq!(contents(&list), "abcde");
+        list.move_to(4, 0);
+        assert_eq!(contents(&list), "eabcd");
+        list.move_to(2, 3);
+        assert_eq!(contents(&list), "eabcd");
+        list.move_to(1, 3);
+        assert_eq!(contents(&list), "eacbd");
+        assert_eq!(list.len(), 5);
+    }
+
+    #[test]
+    fn handles_follow_moved_elements() {
+        let mut list = (0..100).collect::<OrderList<_>>();
+        let mut expected = (0..100).collect::<Vec<_>>();
+        for handle in 0..100 {
+            let to = (handle * 37 + 11) % 100;
+            list.move_to(handle, to);
+            let from = expected.iter().position(|&n| n == handle).unwrap();
+            let value = expected.remove(from);
+            expected.insert(to, value);
+        }
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), expected);
+        for handle in 0..100 {
+            assert_eq!(*list.value(handle), handle);
+            assert_eq!(list.get(list.index_of(handle)), Some(&handle));
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use rayon::prelude::*;
+use std::{
+    panic,
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+// Maps each item on rayon's thread pool, returning the results in the same
+// order as the items.
+//
+// Idle threads steal work from busy ones, so a few slow items do not hold up
+// the rest.
+pub fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    items.par_iter().map(&f).collect()
+}
+
+// Runs a function on a new thread, returning None if it does not finish within
+// the timeout.
+//
+// Threads cannot be stopped from the outside, so a function that times out
+// keeps running in the background until it returns or the program exits. Long
+// solvers stop early by checking `progress::Tracker::cancelled`.
+pub fn with_timeout<T, F>(timeout: Duration, f: F) -> Option<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let worker = thread::spawn(move || {
+        // The receiver is gone if the caller already gave up.
+        let _ = sender.send(f());
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => Some(result),
+        Err(RecvTimeoutError::Timeout) => None,
+        // The sender is only dropped without sending if the function panicked.
+        Err(RecvTimeoutError::Disconnected) => match worker.join() {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(()) => unreachable!("worker finished without sending a result"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gives_up_after_timeout() {
+        assert_eq!(with_timeout(Duration::from_secs(10), || 7), Some(7));
+        assert_eq!(
+            with_timeout(Duration::from_millis(10), || thread::sleep(
+                Duration::from_secs(10)
+            )),
+            None
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "solver panicked")]
+    fn propagates_panics() {
+        with_timeout(Duration::from_secs(10), || panic!("solver panicked"));
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    fmt::{Display, Formatter, Result as DisplayResult},
+    str::FromStr,
+};
+
+use super::{AocError, AocResult};
+
+// Small parser combinators for puzzle input with nested structure.
+//
+// A parser reads from the start of its input and returns what it parsed along
+// with the rest of the input. Parsers are plain functions or closures, so a
+// recursive grammar is just a function that calls itself through the
+// combinators.
+//
+// Failures do not allocate, since parsers like `either` and `separated_list`
+// expect some of their attempts to fail. They only become an `AocError` once
+// parsing is finished.
+pub type ParseResult<'a, T> = Result<(T, &'a str), ParseFailure<'a>>;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Expected {
+    Tag(&'static str),
+    Integer,
+    End,
+}
+
+impl Display for Expected {
+    fn fmt(&self, f: &mut Formatter) -> DisplayResult {
+        match self {
+            Self::Tag(tag) => write!(f, "\"{tag}\""),
+            Self::Integer => write!(f, "an integer"),
+            Self::End => write!(f, "end of input"),
+        }
+    }
+}
+
+// What a parser expected to find, and the input it found instead.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseFailure<'a> {
+    pub expected: Expected,
+    pub found: &'a str,
+}
+
+impl<'a> ParseFailure<'a> {
+    fn new(expected: Expected, found: &'a str) -> Self {
+        Self { expected, found }
+    }
+}
+
+impl From<ParseFailure<'_>> for AocError {
+    fn from(failure: ParseFailure<'_>) -> Self {
+        const CONTEXT: usize = 16;
+        let input = failure.found;
+        let found = match input.char_indices().nth(CONTEXT) {
+            None if input.is_empty() => "end of input".to_owned(),
+            None => format!("\"{input}\""),
+            Some((end, _)) => format!("\"{}...\"", &input[..end]),
+        };
+        AocError::parse(format!("expected {}, found {found}", failure.expected))
+    }
+}
+
+// Matches an exact string.
+pub fn tag(expected: &'static str) -> impl Fn(&str) -> ParseResult<'_, ()> {
+    move |input| match input.strip_prefix(expected) {
+        Some(rest) => Ok(((), rest)),
+        None => Err(ParseFailure::new(Expected::Tag(expected), input)),
+    }
+}
+
+// Skips any leading whitespace, which always succeeds.
+pub fn whitespace(input: &str) -> ParseResult<'_, ()> {
+    Ok(((), input.trim_start()))
+}
+
+// Parses a run of ASCII digits as an unsigned integer.
+pub fn unsigned<T: FromStr>(input: &str) -> ParseResult<'_, T> {
+    let end = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    match input[..end].parse() {
+        Ok(n) => Ok((n, &input[end..])),
+        Err(_) => Err(ParseFailure::new(Expected::Integer, input)),
+    }
+}
+
+// Parses an integer that may have a leading minus sign.
+pub fn integer<T: FromStr>(input: &str) -> ParseResult<'_, T> {
+    let digits = input.strip_prefix('-').unwrap_or(input);
+    let end = input.len() - digits.len()
+        + digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(digits.len());
+    match input[..end].parse() {
+        Ok(n) => Ok((n, &input[end..])),
+        Err(_) => Err(ParseFailure::new(Expected::Integer, input)),
+    }
+}
+
+// Transforms the result of a parser.
+pub fn map<T, U>(
+    parser: impl Fn(&str) -> ParseResult<'_, T>,
+    f: impl Fn(T) -> U,
+) -> impl Fn(&str) -> ParseResult<'_, U> {
+    move |input| parser(input).map(|(value, rest)| (f(value), rest))
+}
+
+// Tries the first parser, falling back to the second if it fails.
+pub fn either<T>(
+    first: impl Fn(&str) -> ParseResult<'_, T>,
+    second: impl Fn(&str) -> ParseResult<'_, T>,
+) -> impl Fn(&str) -> ParseResult<'_, T> {
+    move |input| first(input).or_else(|_| second(input))
+}
+
+// Parses a value surrounded by an opening and closing parser, keeping only the
+// value.
+pub fn delimited<O, T, C>(
+    open: impl Fn(&str) -> ParseResult<'_, O>,
+    value: impl Fn(&str) -> ParseResult<'_, T>,
+    close: impl Fn(&str) -> ParseResult<'_, C>,
+) -> impl Fn(&str) -> ParseResult<'_, T> {
+    move |input| {
+        let (_, rest) = open(input)?;
+        let (value, rest) = value(rest)?;
+        let (_, rest) = close(rest)?;
+        Ok((value, rest))
+    }
+}
+
+// Parses zero or more items with a separator between each one.
+//
+// The list ends at the first item that fails to parse, so an empty list is
+// allowed, but a separator must always be followed by an item.
+pub fn separated_list<T, S>(
+    item: impl Fn(&str) -> ParseResult<'_, T>,
+    separator: impl Fn(&str) -> ParseResult<'_, S>,
+) -> impl Fn(&str) -> ParseResult<'_, Vec<T>> {
+    move |input| {
+        let mut items = Vec::new();
+        let mut rest = match item(input) {
+            Err(_) => return Ok((items, input)),
+            Ok((first, rest)) => {
+                items.push(first);
+                rest
+            }
+        };
+        while let Ok((_, after_separator)) = separator(rest) {
+            let (next, after_item) = item(after_separator)?;
+            items.push(next);
+            rest = after_item;
+        }
+        Ok((items, rest))
+    }
+}
+
+// Runs a parser over the whole input, failing if anything is left over.
+pub fn parse_all<T>(parser: impl Fn(&str) -> ParseResult<'_, T>, input: &str) -> AocResult<T> {
+    let (value, rest) = parser(input)?;
+    if rest.is_empty() {
+        Ok(value)
+    } else {
+        Err(ParseFailure::new(Expected::End, rest).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(input: &str) -> AocResult<Vec<i32>> {
+        parse_all(
+            delimited(tag("["), separated_list(integer, tag(",")), tag("]")),
+            input,
+        )
+    }
+
+    fn message(result: AocResult<Vec<i32>>) -> String {
+        result.unwrap_err().message()
+    }
+
+    #[test]
+    fn parses_nested_input() {
+        assert_eq!(numbers("[1,-2,30]").unwrap(), [1, -2, 30]);
+        assert!(numbers("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn reports_what_was_expected() {
+        assert_eq!(message(numbers("1,2]")), "expected \"[\", found \"1,2]\"");
+        assert_eq!(
+            message(numbers("[1,2")),
+            "expected \"]\", found end of input"
+        );
+        // A separator must be followed by another item.
+        assert_eq!(message(numbers("[1,]")), "expected an integer, found \"]\"");
+        // A list that fails on its first item is empty, so what follows it is
+        // blamed instead.
+        assert_eq!(message(numbers("[-]")), "expected \"]\", found \"-]\"");
+        assert_eq!(
+            message(numbers("[1] and a long tail")),
+            "expected end of input, found \" and a long tail\""
+        );
+        assert_eq!(
+            message(numbers("[1] and a longer tail")),
+            "expected end of input, found \" and a longer ta...\""
+        );
+    }
+
+    #[test]
+    fn rejects_integers_out_of_range() {
+        assert!(unsigned::<u8>("255").is_ok());
+        let failure = unsigned::<u8>("256,").unwrap_err();
+        assert_eq!(failure.found, "256,");
+        assert!(integer::<i8>("-129").is_err());
+        assert!(unsigned::<u32>("-1").is_err());
+    }
+
+    #[test]
+    fn either_reports_the_last_failure() {
+        let parser = either(map(tag("old"), |_| 0), unsigned::<u32>);
+        assert_eq!(parser("old + 1").unwrap(), (0, " + 1"));
+        assert_eq!(parser("7 + 1").unwrap(), (7, " + 1"));
+        let err = AocError::from(parser("new").unwrap_err());
+        assert_eq!(err.message(), "expected an integer, found \"new\"");
+    }
+}
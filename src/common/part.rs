@@ -0,0 +1,37 @@
+use super::{AocError, AocResult};
+use std::{
+    fmt::{Display, Formatter, Result as DisplayResult},
+    str::FromStr,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SolutionPart {
+    A,
+    B,
+    // Both parts, sharing a single read of the input.
+    Both,
+}
+
+impl FromStr for SolutionPart {
+    type Err = AocError;
+
+    fn from_str(string: &str) -> AocResult<Self> {
+        match string {
+            "A" => Ok(Self::A),
+            "B" => Ok(Self::B),
+            "both" => Ok(Self::Both),
+            _ => Err(AocError::invalid_args("part must be A, B, or both")),
+        }
+    }
+}
+
+impl Display for SolutionPart {
+    fn fmt(&self, f: &mut Formatter) -> DisplayResult {
+        let string = match self {
+            Self::A => "A",
+            Self::B => "B",
+            Self::Both => "both",
+        };
+        write!(f, "{}", string)
+    }
+}
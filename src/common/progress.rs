@@ -0,0 +1,147 @@
+use std::{
+    cell::RefCell,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+// Somewhere for a solver to report how far along it is, such as a progress bar.
+pub trait Progress: Send + Sync {
+    // Called once the solver knows how much work it has to do, if it ever
+    // does, and what to call each step.
+    fn start(&self, total: Option<u64>, unit: &'static str);
+    fn advance(&self, steps: u64);
+}
+
+thread_local! {
+    static CURRENT: RefCell<Tracker> = const { RefCell::new(Tracker::none()) };
+}
+
+// Runs a solver with somewhere to report its progress to, which it can find
+// with `Tracker::current`.
+pub fn with_progress<T>(tracker: &Tracker, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT.with(|current| current.replace(tracker.clone()));
+    let result = f();
+    CURRENT.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+// A solver's handle for reporting progress, which does nothing if no one is
+// watching. It can be cloned into the solver's own worker threads.
+//
+// It also tells a solver when whoever is running it has given up on it, such
+// as after a timeout, so that long loops can stop early instead of running on
+// in the background.
+#[derive(Clone, Default)]
+pub struct Tracker {
+    progress: Option<Arc<dyn Progress>>,
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl Tracker {
+    const fn none() -> Self {
+        Self {
+            progress: None,
+            cancelled: None,
+        }
+    }
+
+    pub fn new(progress: Arc<dyn Progress>) -> Self {
+        Self {
+            progress: Some(progress),
+            cancelled: None,
+        }
+    }
+
+    // The progress being watched for the solver running on this thread.
+    pub fn current() -> Self {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    // Returns a tracker that can be cancelled, along with every clone of it.
+    pub fn cancellable(self) -> Self {
+        Self {
+            cancelled: Some(Arc::default()),
+            ..self
+        }
+    }
+
+    pub fn cancel(&self) {
+        if let Some(cancelled) = &self.cancelled {
+            cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Whether the solver should stop, since no one wants its answer anymore.
+    pub fn cancelled(&self) -> bool {
+        self.cancelled
+            .as_ref()
+            .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+    }
+
+    pub fn start(&self, total: Option<u64>, unit: &'static str) {
+        if let Some(progress) = &self.progress {
+            progress.start(total, unit);
+        }
+    }
+
+    pub fn advance(&self, steps: u64) {
+        if let Some(progress) = &self.progress {
+            progress.advance(steps);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU64;
+
+    #[derive(Default)]
+    struct Counter(AtomicU64);
+
+    impl Progress for Counter {
+        fn start(&self, total: Option<u64>, _: &'static str) {
+            self.0.store(total.unwrap_or(0) * 1000, Ordering::Relaxed);
+        }
+
+        fn advance(&self, steps: u64) {
+            self.0.fetch_add(steps, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn reports_to_the_installed_progress() {
+        let counter = Arc::new(Counter::default());
+        with_progress(&Tracker::new(counter.clone()), || {
+            let tracker = Tracker::current();
+            tracker.start(Some(3), "steps");
+            std::thread::scope(|scope| {
+                scope.spawn(|| tracker.advance(2));
+            });
+            tracker.advance(1);
+        });
+        assert_eq!(counter.0.load(Ordering::Relaxed), 3003);
+
+        // Nothing is watching outside of `with_progress`.
+        Tracker::current().advance(5);
+        assert_eq!(counter.0.load(Ordering::Relaxed), 3003);
+    }
+
+    #[test]
+    fn cancels_every_clone() {
+        let tracker = Tracker::default().cancellable();
+        with_progress(&tracker, || {
+            let current = Tracker::current();
+            assert!(!current.cancelled());
+            tracker.cancel();
+            assert!(current.cancelled());
+        });
+
+        // Trackers that cannot be cancelled never are.
+        let tracker = Tracker::default();
+        tracker.cancel();
+        assert!(!tracker.cancelled());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{fmt::Write, fs, path::Path};
+
+use super::{AocError, AocResult, IntoAocResult};
+use itertools::Itertools;
+
+// How many pixels wide each tile is in a PNG.
+#[cfg(feature = "png")]
+const PNG_TILE_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+// A snapshot of a 2D grid where every tile is a solid square of color, for
+// saving puzzle states as images.
+#[derive(Debug, Clone)]
+pub struct GridImage {
+    width: usize,
+    height: usize,
+    background: Color,
+    tiles: Vec<Color>,
+}
+
+impl GridImage {
+    pub fn new(width: usize, height: usize, background: Color) -> Self {
+        Self {
+            width,
+            height,
+            background,
+            tiles: vec![background; width * height],
+        }
+    }
+
+    // Colors a tile. Tiles outside of the image are ignored.
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            self.tiles[y * self.width + x] = color;
+        }
+    }
+
+    // An SVG with one unit per tile. Runs of the same color in a row are drawn as
+    // a single rectangle to keep the file small.
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"{}\" height=\"{}\" shape-rendering=\"crispEdges\">\n<rect width=\"{w}\" height=\"{h}\" fill=\"{}\"/>\n",
+            8 * self.width,
+            8 * self.height,
+            self.background.hex(),
+            w = self.width,
+            h = self.height,
+        );
+        for (y, row) in self.tiles.chunks(self.width.max(1)).enumerate() {
+            let mut x = 0;
+            for (color, run) in &row.iter().group_by(|color| **color) {
+                let length = run.count();
+                if color != self.background {
+                    // Writing to a string cannot fail.
+                    let _ = writeln!(
+                        svg,
+                        "<rect x=\"{x}\" y=\"{y}\" width=\"{length}\" height=\"1\" fill=\"{}\"/>",
+                        color.hex()
+                    );
+                }
+                x += length;
+            }
+        }
+        svg += "</svg>\n";
+        svg
+    }
+
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> AocResult<Vec<u8>> {
+        let (width, height) = (self.width * PNG_TILE_SIZE, self.height * PNG_TILE_SIZE);
+        let mut pixels = Vec::with_capacity(3 * width * height);
+        for row in self.tiles.chunks(self.width.max(1)) {
+            let line = row
+                .iter()
+                .flat_map(|color| [color.0, color.1, color.2].repeat(PNG_TILE_SIZE))
+                .collect::<Vec<_>>();
+            for _ in 0..PNG_TILE_SIZE {
+                pixels.extend_from_slice(&line);
+            }
+        }
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .into_aoc_result_msg("failed to encode png")?;
+        Ok(png)
+    }
+
+    // Writes the image in the format matching the file extension.
+    pub fn save(&self, path: &str) -> AocResult<()> {
+        let contents = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("svg") => self.to_svg().into_bytes(),
+            #[cfg(feature = "png")]
+            Some("png") => self.to_png()?,
+            #[cfg(not(feature = "png"))]
+            Some("png") => {
+                return Err(AocError::invalid_args(
+                    "rendering png images requires the png feature",
+                ))
+            }
+            _ => {
+                return Err(AocError::invalid_args(format!(
+                    "cannot render {path}: expected a .svg or .png file"
+                )))
+            }
+        };
+        fs::write(path, contents).into_aoc_result_msg(&format!("failed to write {}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_runs_of_color_in_svg() {
+        let red = Color(255, 0, 0);
+        let mut image = GridImage::new(4, 2, Color(0, 0, 0));
+        image.set(1, 0, red);
+        image.set(2, 0, red);
+        image.set(3, 1, red);
+        // Out of bounds, so ignored.
+        image.set(4, 1, red);
+        let rects = image
+            .to_svg()
+            .lines()
+            .filter(|line| line.contains("#ff0000"))
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rects,
+            [
+                "<rect x=\"1\" y=\"0\" width=\"2\" height=\"1\" fill=\"#ff0000\"/>",
+                "<rect x=\"3\" y=\"1\" width=\"1\" height=\"1\" fill=\"#ff0000\"/>",
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_extensions() {
+        assert!(GridImage::new(1, 1, Color(0, 0, 0))
+            .save("out.bmp")
+            .is_err());
+    }
+}
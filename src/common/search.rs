@@ -0,0 +1,424 @@
+use num::Zero;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    hash::Hash,
+    ops::Add,
+};
+
+// A path found by one of the search functions, including both ends.
+#[derive(Debug, Clone)]
+pub struct Path<S, C> {
+    pub cost: C,
+    pub states: Vec<S>,
+}
+
+fn reconstruct_path<S>(parents: &FxHashMap<S, S>, end: S) -> Vec<S>
+where
+    S: Clone + Hash + Eq,
+{
+    let mut states = vec![end];
+    while let Some(parent) = parents.get(states.last().unwrap()) {
+        states.push(parent.clone());
+    }
+    states.reverse();
+    states
+}
+
+fn bfs_internal<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut is_goal: impl FnMut(&S) -> bool,
+    track_path: bool,
+) -> Option<Path<S, usize>>
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+{
+    let mut queue = VecDeque::new();
+    let mut seen = FxHashSet::default();
+    let mut parents = FxHashMap::default();
+    for start in starts {
+        if seen.insert(start.clone()) {
+            queue.push_back((start, 0));
+        }
+    }
+    while let Some((state, steps)) = queue.pop_front() {
+        if is_goal(&state) {
+            let states = if track_path {
+                reconstruct_path(&parents, state)
+            } else {
+                Vec::new()
+            };
+            return Some(Path {
+                cost: steps,
+                states,
+            });
+        }
+        for next in successors(&state) {
+            if seen.insert(next.clone()) {
+                if track_path {
+                    parents.insert(next.clone(), state.clone());
+                }
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    None
+}
+
+// Finds the fewest number of steps from any of the start states to a goal
+// state, where every step has the same cost.
+pub fn bfs<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<usize>
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+{
+    bfs_internal(starts, successors, is_goal, false).map(|path| path.cost)
+}
+
+// Like `bfs`, but also returns the states along the path.
+pub fn bfs_path<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, usize>>
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+{
+    bfs_internal(starts, successors, is_goal, true)
+}
+
+// Finds the fewest number of steps from any state that satisfies `is_start` to
+// any of the end states, by searching backwards from the end states.
+//
+// `predecessors` returns the states that can step to a given state. This is
+// faster than searching forwards when there are many possible starts, since
+// the search stops at the first start reached.
+pub fn bfs_reverse<S, I>(
+    ends: impl IntoIterator<Item = S>,
+    predecessors: impl FnMut(&S) -> I,
+    is_start: impl FnMut(&S) -> bool,
+) -> Option<usize>
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+{
+    bfs_internal(ends, predecessors, is_start, false).map(|path| path.cost)
+}
+
+// Like `bfs_reverse`, but also returns the states along the path, in the order
+// they are stepped through going forwards.
+pub fn bfs_reverse_path<S, I>(
+    ends: impl IntoIterator<Item = S>,
+    predecessors: impl FnMut(&S) -> I,
+    is_start: impl FnMut(&S) -> bool,
+) -> Option<Path<S, usize>>
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+{
+    bfs_internal(ends, predecessors, is_start, true).map(|mut path| {
+        path.states.reverse();
+        path
+    })
+}
+
+// Expands every state in the frontier by one step, returning the next frontier
+// and the shortest total distance through any state the other search has
+// already seen.
+fn expand_layer<S, I>(
+    frontier: Vec<S>,
+    steps: usize,
+    seen: &mut FxHashMap<S, usize>,
+    other_seen: &FxHashMap<S, usize>,
+    mut neighbors: impl FnMut(&S) -> I,
+) -> (Vec<S>, Option<usize>)
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+{
+    let mut next_frontier = Vec::new();
+    let mut shortest = None;
+    for state in frontier {
+        for next in neighbors(&state) {
+            if seen.contains_key(&next) {
+                continue;
+            }
+            if let Some(&other_steps) = other_seen.get(&next) {
+                let total = steps + 1 + other_steps;
+                shortest = Some(shortest.map_or(total, |shortest: usize| shortest.min(total)));
+            }
+            seen.insert(next.clone(), steps + 1);
+            next_frontier.push(next);
+        }
+    }
+    (next_frontier, shortest)
+}
+
+// Finds the fewest number of steps from any of the start states to any of the
+// end states by searching forwards from the starts and backwards from the ends
+// at the same time, one layer at a time from whichever side is smaller.
+pub fn bfs_bidirectional<S, I, J>(
+    starts: impl IntoIterator<Item = S>,
+    ends: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut predecessors: impl FnMut(&S) -> J,
+) -> Option<usize>
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+    J: IntoIterator<Item = S>,
+{
+    let mut forward_seen = FxHashMap::default();
+    let mut forward = Vec::new();
+    for start in starts {
+        if forward_seen.insert(start.clone(), 0).is_none() {
+            forward.push(start);
+        }
+    }
+    let mut backward_seen = FxHashMap::default();
+    let mut backward = Vec::new();
+    for end in ends {
+        if forward_seen.contains_key(&end) {
+            return Some(0);
+        }
+        if backward_seen.insert(end.clone(), 0).is_none() {
+            backward.push(end);
+        }
+    }
+
+    let (mut forward_steps, mut backward_steps) = (0, 0);
+    while !forward.is_empty() && !backward.is_empty() {
+        // The first layer to meet the other search contains the shortest path, but
+        // not necessarily at the first meeting point found.
+        let shortest = if forward.len() <= backward.len() {
+            let (next, shortest) = expand_layer(
+                forward,
+                forward_steps,
+                &mut forward_seen,
+                &backward_seen,
+                &mut successors,
+            );
+            forward = next;
+            forward_steps += 1;
+            shortest
+        } else {
+            let (next, shortest) = expand_layer(
+                backward,
+                backward_steps,
+                &mut backward_seen,
+                &forward_seen,
+                &mut predecessors,
+            );
+            backward = next;
+            backward_steps += 1;
+            shortest
+        };
+        if shortest.is_some() {
+            return shortest;
+        }
+    }
+    None
+}
+
+// Returns every state reachable from the start states, including the start
+// states themselves.
+pub fn flood_fill<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+) -> FxHashSet<S>
+where
+    S: Clone + Hash + Eq,
+    I: IntoIterator<Item = S>,
+{
+    let mut seen = FxHashSet::default();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if seen.insert(start.clone()) {
+            queue.push_back(start);
+        }
+    }
+    while let Some(state) = queue.pop_front() {
+        for next in successors(&state) {
+            if seen.insert(next.clone()) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+fn astar_internal<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut is_goal: impl FnMut(&S) -> bool,
+    track_path: bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Hash + Eq,
+    C: Copy + Ord + Zero + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    // The heap stores indices into `states` so that states do not need to be
+    // ordered themselves.
+    let mut states = Vec::new();
+    let mut heap = BinaryHeap::new();
+    let mut best = FxHashMap::default();
+    let mut parents = FxHashMap::default();
+    for start in starts {
+        if best.insert(start.clone(), C::zero()).is_none() {
+            heap.push(Reverse((heuristic(&start), C::zero(), states.len())));
+            states.push(start);
+        }
+    }
+    while let Some(Reverse((_, cost, index))) = heap.pop() {
+        let state = states[index].clone();
+        if best.get(&state).is_some_and(|&best| cost > best) {
+            // A cheaper path to this state was found after this one was queued.
+            continue;
+        }
+        if is_goal(&state) {
+            let states = if track_path {
+                reconstruct_path(&parents, state)
+            } else {
+                Vec::new()
+            };
+            return Some(Path { cost, states });
+        }
+        for (next, step_cost) in successors(&state) {
+            let next_cost = cost + step_cost;
+            if best.get(&next).is_none_or(|&best| next_cost < best) {
+                best.insert(next.clone(), next_cost);
+                if track_path {
+                    parents.insert(next.clone(), state.clone());
+                }
+                heap.push(Reverse((
+                    next_cost + heuristic(&next),
+                    next_cost,
+                    states.len(),
+                )));
+                states.push(next);
+            }
+        }
+    }
+    None
+}
+
+// Finds the cheapest path cost from any of the start states to a goal state,
+// where successors are paired with the cost of moving to them.
+pub fn dijkstra<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<C>
+where
+    S: Clone + Hash + Eq,
+    C: Copy + Ord + Zero + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar_internal(starts, successors, |_| C::zero(), is_goal, false).map(|path| path.cost)
+}
+
+pub fn dijkstra_path<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Hash + Eq,
+    C: Copy + Ord + Zero + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar_internal(starts, successors, |_| C::zero(), is_goal, true)
+}
+
+// Like `dijkstra`, but guided by a heuristic that must never overestimate the
+// remaining cost to a goal.
+pub fn astar<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    heuristic: impl FnMut(&S) -> C,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<C>
+where
+    S: Clone + Hash + Eq,
+    C: Copy + Ord + Zero + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar_internal(starts, successors, heuristic, is_goal, false).map(|path| path.cost)
+}
+
+pub fn astar_path<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    heuristic: impl FnMut(&S) -> C,
+    is_goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Hash + Eq,
+    C: Copy + Ord + Zero + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar_internal(starts, successors, heuristic, is_goal, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Steps along a line of numbers, where 5 is a wall.
+    fn steps(n: &i32) -> Vec<i32> {
+        [n - 1, n + 1]
+            .into_iter()
+            .filter(|next| (0..10).contains(next) && *next != 5)
+            .collect()
+    }
+
+    #[test]
+    fn finds_nothing_when_unreachable() {
+        assert_eq!(bfs([0], steps, |&n| n == 9), None);
+        assert!(bfs_path([0], steps, |&n| n == 9).is_none());
+        assert_eq!(bfs_reverse([9], steps, |&n| n == 0), None);
+        assert_eq!(bfs_bidirectional([0], [9], steps, steps), None);
+        let weighted = |n: &i32| steps(n).into_iter().map(|next| (next, 1));
+        assert_eq!(dijkstra([0], weighted, |&n| n == 9), None::<u32>);
+        assert_eq!(astar([0], weighted, |&n| 9 - n as u32, |&n| n == 9), None);
+        assert_eq!(flood_fill([0], steps).len(), 5);
+    }
+
+    #[test]
+    fn finds_nothing_without_starts() {
+        assert_eq!(bfs([], steps, |_| true), None);
+        assert_eq!(bfs_bidirectional([], [0], steps, steps), None);
+        assert!(flood_fill([], steps).is_empty());
+    }
+
+    #[test]
+    fn starts_can_be_goals() {
+        let path = bfs_path([3], steps, |&n| n == 3).unwrap();
+        assert_eq!((path.cost, path.states), (0, vec![3]));
+        assert_eq!(bfs_bidirectional([2, 3], [3], steps, steps), Some(0));
+        assert_eq!(bfs([7, 2], steps, |&n| n == 0), Some(2));
+    }
+
+    #[test]
+    fn prefers_cheaper_paths_found_later() {
+        // Going straight from 0 to 2 is queued first but costs more than going
+        // through 1.
+        let successors = |n: &i32| match n {
+            0 => vec![(2, 10), (1, 1)],
+            1 => vec![(2, 1)],
+            _ => vec![],
+        };
+        let path = dijkstra_path([0], successors, |&n| n == 2).unwrap();
+        assert_eq!((path.cost, path.states), (2, vec![0, 1, 2]));
+    }
+}
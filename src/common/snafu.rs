@@ -0,0 +1,248 @@
+use std::{
+    fmt::{self, Display},
+    iter::Sum,
+    ops::{Add, Neg},
+    str::FromStr,
+};
+
+use super::{AocError, AocResult};
+use itertools::{EitherOrBoth, Itertools};
+
+// Symbols for the digits -1, -2, -3 and -4. Positive digits are written as
+// themselves, so bases up to 9 can be shown.
+const NEGATIVE_DIGITS: [char; 4] = ['-', '=', '~', '!'];
+
+// A number in a balanced base, where every digit is between -B/2 and B/2
+// rather than between 0 and B - 1. B must be odd so the digits are symmetric,
+// which also means negative numbers need no sign: they just lead with a
+// negative digit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BalancedRadix<const B: u32> {
+    // Least significant first, with no leading zeros, so zero has no digits.
+    digits: Vec<i64>,
+}
+
+// Special Numeral-Analogue Fuel Units, which are balanced base 5.
+pub type Snafu = BalancedRadix<5>;
+
+impl<const B: u32> BalancedRadix<B> {
+    const HALF: i64 = {
+        assert!(
+            B % 2 == 1 && B >= 3 && B <= 9,
+            "base must be odd and in 3..=9"
+        );
+        B as i64 / 2
+    };
+
+    fn from_digits(mut digits: Vec<i64>) -> Self {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Self { digits }
+    }
+
+    // The digits, most significant first.
+    pub fn digits(&self) -> impl Iterator<Item = i64> + '_ {
+        self.digits.iter().rev().copied()
+    }
+
+    // The value in base 10, or an error if it does not fit in an i64. The
+    // running total can briefly leave the i64 range before a digit of the
+    // opposite sign brings it back, so it is kept in an i128.
+    pub fn value(&self) -> AocResult<i64> {
+        self.digits()
+            .try_fold(0i128, |value, digit| {
+                value
+                    .checked_mul(B as i128)
+                    .and_then(|value| value.checked_add(digit as i128))
+            })
+            .and_then(|value| i64::try_from(value).ok())
+            .ok_or_else(|| AocError::parse("balanced number does not fit in an i64"))
+    }
+
+    fn digit_symbol(digit: i64) -> char {
+        match digit {
+            0.. => char::from_digit(digit as u32, 10).unwrap(),
+            _ => NEGATIVE_DIGITS[(-digit - 1) as usize],
+        }
+    }
+
+    fn symbol_digit(symbol: char) -> Option<i64> {
+        let digit = match symbol {
+            '0'..='9' => symbol.to_digit(10)? as i64,
+            _ => -(NEGATIVE_DIGITS.iter().position(|&c| c == symbol)? as i64 + 1),
+        };
+        (-Self::HALF..=Self::HALF).contains(&digit).then_some(digit)
+    }
+}
+
+impl<const B: u32> From<i64> for BalancedRadix<B> {
+    fn from(mut num: i64) -> Self {
+        let base = B as i64;
+        let mut digits = Vec::new();
+        while num != 0 {
+            let mut digit = num.rem_euclid(base);
+            if digit > Self::HALF {
+                digit -= base;
+            }
+            digits.push(digit);
+            // Dividing first keeps the subtraction from overflowing at the
+            // edges of the i64 range.
+            num = num.div_euclid(base) + (num.rem_euclid(base) - digit) / base;
+        }
+        Self::from_digits(digits)
+    }
+}
+
+impl<const B: u32> FromStr for BalancedRadix<B> {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        if s.is_empty() {
+            return Err(AocError::parse("empty balanced number"));
+        }
+        Ok(Self::from_digits(
+            s.chars()
+                .rev()
+                .map(|c| {
+                    Self::symbol_digit(c).ok_or_else(|| {
+                        AocError::parse(format!("invalid digit in balanced base {B} number: {c}"))
+                    })
+                })
+                .collect::<AocResult<_>>()?,
+        ))
+    }
+}
+
+impl<const B: u32> Display for BalancedRadix<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            return f.write_str("0");
+        }
+        self.digits()
+            .try_for_each(|digit| write!(f, "{}", Self::digit_symbol(digit)))
+    }
+}
+
+impl<const B: u32> Add for BalancedRadix<B> {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self::Output {
+        let base = B as i64;
+        let mut result = Vec::new();
+        let mut carry = 0;
+        for pair in self.digits.iter().zip_longest(rhs.digits.iter()) {
+            let (a, b) = match pair {
+                EitherOrBoth::Both(&a, &b) => (a, b),
+                EitherOrBoth::Left(&a) => (a, 0),
+                EitherOrBoth::Right(&b) => (0, b),
+            };
+            let mut sum = a + b + carry;
+            if sum > Self::HALF {
+                sum -= base;
+                carry = 1;
+            } else if sum < -Self::HALF {
+                sum += base;
+                carry = -1;
+            } else {
+                carry = 0;
+            }
+            result.push(sum);
+        }
+        result.push(carry);
+        Self::from_digits(result)
+    }
+}
+
+impl<const B: u32> Neg for BalancedRadix<B> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self {
+            digits: self.digits.into_iter().map(|digit| -digit).collect(),
+        }
+    }
+}
+
+impl<const B: u32> Sum for BalancedRadix<B> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+pub fn snafu_to_base_10(digits: &str) -> AocResult<i64> {
+    digits.parse::<Snafu>()?.value()
+}
+
+pub fn base_10_to_snafu(num: i64) -> String {
+    Snafu::from(num).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn converts_puzzle_examples() {
+        for (snafu, value) in [
+            ("0", 0),
+            ("1=", 3),
+            ("1-0---0", 12345),
+            ("1121-1110-1=0", 314159265),
+            ("2=-01", 976),
+            ("-", -1),
+            ("-2", -3),
+        ] {
+            assert_eq!(snafu_to_base_10(snafu).unwrap(), value);
+            assert_eq!(base_10_to_snafu(value), snafu);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_digits() {
+        assert!("".parse::<Snafu>().is_err());
+        assert!("13".parse::<Snafu>().is_err());
+        assert!("1=".parse::<BalancedRadix<3>>().is_err());
+    }
+
+    #[test]
+    fn reports_overflow() {
+        assert!(Snafu::from(i64::MAX).value().is_ok());
+        assert!(Snafu::from(i64::MIN).value().is_ok());
+        assert!((Snafu::from(i64::MAX) + Snafu::from(1)).value().is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn round_trips_through_base_10(value: i64) {
+            prop_assert_eq!(snafu_to_base_10(&base_10_to_snafu(value))?, value);
+        }
+
+        #[test]
+        fn round_trips_through_strings(value: i64) {
+            let ternary = BalancedRadix::<3>::from(value);
+            prop_assert_eq!(ternary.to_string().parse::<BalancedRadix<3>>()?, ternary);
+            let septenary = BalancedRadix::<7>::from(value);
+            prop_assert_eq!(septenary.to_string().parse::<BalancedRadix<7>>()?, septenary);
+        }
+
+        #[test]
+        fn adds_like_integers(a in -(1i64 << 60)..(1 << 60), b in -(1i64 << 60)..(1 << 60)) {
+            prop_assert_eq!((Snafu::from(a) + Snafu::from(b)).value()?, a + b);
+            prop_assert_eq!((BalancedRadix::<9>::from(a) + BalancedRadix::<9>::from(b)).value()?, a + b);
+        }
+
+        #[test]
+        fn addition_is_associative(a: i64, b: i64, c: i64) {
+            let (a, b, c) = (Snafu::from(a), Snafu::from(b), Snafu::from(c));
+            prop_assert_eq!(
+                (a.clone() + b.clone()) + c.clone(),
+                a + (b + c)
+            );
+        }
+
+        #[test]
+        fn negation_cancels(value: i64) {
+            let snafu = Snafu::from(value);
+            prop_assert_eq!(snafu.clone() + -snafu, Snafu::default());
+        }
+    }
+}
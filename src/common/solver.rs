@@ -1,36 +1,294 @@
-use std::fmt::{Display, Formatter, Result as DisplayResult};
+use std::{
+    fmt::{Display, Formatter, Result as DisplayResult},
+    iter,
+};
 
-use crate::common::AocResult;
+use crate::common::{
+    input::{Input, InputLines},
+    progress::{self, Tracker},
+    AocError, AocResult, DayArgs,
+};
+use num::BigInt;
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub enum AocSolution {
     Int(u64),
+    SInt(i64),
+    BigInt(BigInt),
     Str(String),
 }
 
+impl AocSolution {
+    // The numeric value of the solution, regardless of which integer type the
+    // solver returned.
+    pub fn as_integer(&self) -> Option<BigInt> {
+        match self {
+            Self::Int(n) => Some(BigInt::from(*n)),
+            Self::SInt(n) => Some(BigInt::from(*n)),
+            Self::BigInt(n) => Some(n.clone()),
+            Self::Str(_) => None,
+        }
+    }
+}
+
 impl Display for AocSolution {
     fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
         match self {
             Self::Int(n) => write!(f, "{}", n),
+            Self::SInt(n) => write!(f, "{}", n),
+            Self::BigInt(n) => write!(f, "{}", n),
             Self::Str(s) => write!(f, "{}", s),
         }
     }
 }
 
-pub type IntSolverFn = fn(&str) -> AocResult<u64>;
-pub type StringSolverFn = fn(&str) -> AocResult<String>;
+// Integer solutions are equal if their values are equal, even if they were
+// returned as different integer types.
+impl PartialEq for AocSolution {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Str(a), Self::Str(b)) => a == b,
+            (Self::Str(_), _) | (_, Self::Str(_)) => false,
+            _ => self.as_integer() == other.as_integer(),
+        }
+    }
+}
 
-#[derive(Clone)]
-pub enum Solver {
-    Int(IntSolverFn),
-    Str(StringSolverFn),
+impl Eq for AocSolution {}
+
+// A solver function, which takes either the whole input at once or a stream of
+// its lines. Solvers with constants that can be tuned also take the day's
+// options.
+pub enum Solve<T> {
+    Text(fn(&str) -> AocResult<T>),
+    Lines(fn(InputLines) -> AocResult<T>),
+    WithArgs(fn(&str, &DayArgs) -> AocResult<T>),
 }
 
-impl Solver {
-    pub fn run(&self, input: &str) -> AocResult<AocSolution> {
+impl<T> Solve<T> {
+    fn call(&self, input: &Input, day_args: &DayArgs) -> AocResult<T> {
+        match self {
+            Self::Text(solve) => solve(&input.text()?),
+            Self::Lines(solve) => solve(input.lines()?),
+            Self::WithArgs(solve) => solve(&input.text()?, day_args),
+        }
+    }
+
+    fn takes_args(&self) -> bool {
+        matches!(self, Self::WithArgs(_))
+    }
+}
+
+// Function pointers are always copyable, even when the answer type is not.
+impl<T> Clone for Solve<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Solve<T> {}
+
+// A single implementation of a solver, wrapped by the type of answer it returns.
+#[derive(Clone, Copy)]
+pub enum SolverFn {
+    Int(Solve<u64>),
+    SInt(Solve<i64>),
+    BigInt(Solve<BigInt>),
+    Str(Solve<String>),
+}
+
+// An answer type that a solver function can return, which decides the
+// `SolverFn` variant that wraps the function.
+pub trait SolverOutput: Sized {
+    fn solver(solve: Solve<Self>) -> SolverFn;
+}
+
+impl SolverOutput for u64 {
+    fn solver(solve: Solve<u64>) -> SolverFn {
+        SolverFn::Int(solve)
+    }
+}
+
+impl SolverOutput for i64 {
+    fn solver(solve: Solve<i64>) -> SolverFn {
+        SolverFn::SInt(solve)
+    }
+}
+
+impl SolverOutput for BigInt {
+    fn solver(solve: Solve<BigInt>) -> SolverFn {
+        SolverFn::BigInt(solve)
+    }
+}
+
+impl SolverOutput for String {
+    fn solver(solve: Solve<String>) -> SolverFn {
+        SolverFn::Str(solve)
+    }
+}
+
+impl SolverFn {
+    pub fn new<T: SolverOutput>(solve: fn(&str) -> AocResult<T>) -> Self {
+        T::solver(Solve::Text(solve))
+    }
+
+    pub fn streaming<T: SolverOutput>(solve: fn(InputLines) -> AocResult<T>) -> Self {
+        T::solver(Solve::Lines(solve))
+    }
+
+    pub fn with_args<T: SolverOutput>(solve: fn(&str, &DayArgs) -> AocResult<T>) -> Self {
+        T::solver(Solve::WithArgs(solve))
+    }
+
+    // The name of the answer type returned by the solver.
+    pub fn output_type(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "Int",
+            Self::SInt(_) => "SInt",
+            Self::BigInt(_) => "BigInt",
+            Self::Str(_) => "Str",
+        }
+    }
+
+    // Whether the solver reads its input line by line.
+    pub fn streams_lines(&self) -> bool {
+        matches!(
+            self,
+            Self::Int(Solve::Lines(_))
+                | Self::SInt(Solve::Lines(_))
+                | Self::BigInt(Solve::Lines(_))
+                | Self::Str(Solve::Lines(_))
+        )
+    }
+
+    // Whether the solver reads options from the command line.
+    pub fn takes_args(&self) -> bool {
+        match self {
+            Self::Int(solve) => solve.takes_args(),
+            Self::SInt(solve) => solve.takes_args(),
+            Self::BigInt(solve) => solve.takes_args(),
+            Self::Str(solve) => solve.takes_args(),
+        }
+    }
+
+    pub fn run(&self, input: &Input, day_args: &DayArgs) -> AocResult<AocSolution> {
         Ok(match self {
-            Self::Int(solver) => AocSolution::Int(solver(&input)?),
-            Self::Str(solver) => AocSolution::Str(solver(&input)?),
+            Self::Int(solver) => AocSolution::Int(solver.call(input, day_args)?),
+            Self::SInt(solver) => AocSolution::SInt(solver.call(input, day_args)?),
+            Self::BigInt(solver) => AocSolution::BigInt(solver.call(input, day_args)?),
+            Self::Str(solver) => AocSolution::Str(solver.call(input, day_args)?),
         })
     }
 }
+
+// The solver for one part of a day, along with any alternate implementations
+// that should find the same answer, which are only run when checking.
+#[derive(Clone)]
+pub struct Solver {
+    primary: SolverFn,
+    alternates: Vec<SolverFn>,
+}
+
+impl Solver {
+    pub fn new<T: SolverOutput>(solve: fn(&str) -> AocResult<T>) -> Self {
+        Self {
+            primary: SolverFn::new(solve),
+            alternates: Vec::new(),
+        }
+    }
+
+    // A solver that streams its input line by line.
+    pub fn streaming<T: SolverOutput>(solve: fn(InputLines) -> AocResult<T>) -> Self {
+        Self {
+            primary: SolverFn::streaming(solve),
+            alternates: Vec::new(),
+        }
+    }
+
+    // A solver that takes the day's options.
+    pub fn with_args<T: SolverOutput>(solve: fn(&str, &DayArgs) -> AocResult<T>) -> Self {
+        Self {
+            primary: SolverFn::with_args(solve),
+            alternates: Vec::new(),
+        }
+    }
+
+    pub fn with_alternate<T: SolverOutput>(mut self, solve: fn(&str) -> AocResult<T>) -> Self {
+        self.alternates.push(SolverFn::new(solve));
+        self
+    }
+
+    pub fn with_alternate_streaming<T: SolverOutput>(
+        mut self,
+        solve: fn(InputLines) -> AocResult<T>,
+    ) -> Self {
+        self.alternates.push(SolverFn::streaming(solve));
+        self
+    }
+
+    pub fn with_alternate_args<T: SolverOutput>(
+        mut self,
+        solve: fn(&str, &DayArgs) -> AocResult<T>,
+    ) -> Self {
+        self.alternates.push(SolverFn::with_args(solve));
+        self
+    }
+
+    // The name of the answer type returned by the primary implementation.
+    pub fn output_type(&self) -> &'static str {
+        self.primary.output_type()
+    }
+
+    pub fn streams_lines(&self) -> bool {
+        self.primary.streams_lines()
+    }
+
+    pub fn takes_args(&self) -> bool {
+        self.primary.takes_args()
+    }
+
+    pub fn alternates(&self) -> usize {
+        self.alternates.len()
+    }
+
+    // The primary implementation followed by every alternate.
+    pub fn implementations(&self) -> impl Iterator<Item = &SolverFn> {
+        iter::once(&self.primary).chain(&self.alternates)
+    }
+
+    pub fn run(&self, input: &Input, day_args: &DayArgs) -> AocResult<AocSolution> {
+        self.primary.run(input, day_args)
+    }
+
+    // Runs the primary implementation, letting it report its progress.
+    pub fn run_with_progress(
+        &self,
+        input: &Input,
+        day_args: &DayArgs,
+        tracker: &Tracker,
+    ) -> AocResult<AocSolution> {
+        progress::with_progress(tracker, || self.primary.run(input, day_args))
+    }
+
+    // Runs every alternate implementation, failing if any of them finds a
+    // different answer than the one found by the primary implementation.
+    pub fn check_alternates(
+        &self,
+        input: &Input,
+        day_args: &DayArgs,
+        expected: &AocSolution,
+    ) -> AocResult<()> {
+        for (i, alternate) in self.alternates.iter().enumerate() {
+            let solution = alternate.run(input, day_args)?;
+            if solution != *expected {
+                return Err(AocError::no_solution(format!(
+                    "alternate implementation {} found {}, but the primary implementation found {}",
+                    i + 1,
+                    solution,
+                    expected
+                )));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,877 @@
+use std::{hash::Hash, str::FromStr};
+
+use super::{
+    cycle::{self, CycleDetector},
+    progress::Tracker,
+    AocError, AocResult, IntoAocResult, NewlineBlocks, Point2,
+};
+
+// The chamber grows upward, so y increases with height.
+pub type Point = Point2<i64>;
+
+// The width of the chamber in the puzzle.
+pub const DEFAULT_WIDTH: usize = 7;
+
+// The widest chamber supported, since each row is stored as a 64-bit word.
+pub const MAX_WIDTH: usize = 64;
+
+// Deeper than any rock a real puzzle drops can slide.
+const SURFACE_PROFILE_ROWS: usize = 64;
+
+// New rocks appear this many columns from the left wall...
+const SPAWN_OFFSET_X: i64 = 2;
+// ...and this many rows above the highest rock or the floor.
+const SPAWN_OFFSET_Y: i64 = 3;
+
+// A rock shape, as points relative to its bottom-left corner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rock {
+    pub points: Vec<Point>,
+}
+
+impl Rock {
+    pub fn new(points: &[Point]) -> Self {
+        Self {
+            points: Vec::from(points),
+        }
+    }
+
+    // The five rocks from the puzzle, in the order they fall.
+    pub fn default_set() -> Vec<Rock> {
+        Vec::from([
+            Rock::new(&[
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(2, 0),
+                Point::new(3, 0),
+            ]),
+            Rock::new(&[
+                Point::new(1, 0),
+                Point::new(0, 1),
+                Point::new(1, 1),
+                Point::new(2, 1),
+                Point::new(1, 2),
+            ]),
+            Rock::new(&[
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(2, 0),
+                Point::new(2, 1),
+                Point::new(2, 2),
+            ]),
+            Rock::new(&[
+                Point::new(0, 0),
+                Point::new(0, 1),
+                Point::new(0, 2),
+                Point::new(0, 3),
+            ]),
+            Rock::new(&[
+                Point::new(0, 0),
+                Point::new(1, 0),
+                Point::new(0, 1),
+                Point::new(1, 1),
+            ]),
+        ])
+    }
+
+    // The number of columns the rock spans.
+    pub fn width(&self) -> i64 {
+        self.points
+            .iter()
+            .map(|point| point.x + 1)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn drift(&mut self, delta: &Point) {
+        for point in &mut self.points {
+            *point += *delta;
+        }
+    }
+}
+
+// Parses a rock drawn the way the puzzle draws them, with `#` for rock and `.`
+// for empty space.
+impl FromStr for Rock {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        let lines = s.lines().collect::<Vec<_>>();
+        let mut points = Vec::new();
+        for (row, line) in lines.iter().enumerate() {
+            // The last line is the bottom of the rock.
+            let y = (lines.len() - row - 1) as i64;
+            for (x, c) in line.char_indices() {
+                match c {
+                    '#' => points.push(Point::new(x as i64, y)),
+                    '.' => (),
+                    _ => return Err(AocError::parse(format!("invalid rock character: {c}"))),
+                }
+            }
+        }
+        if points.is_empty() {
+            return Err(AocError::parse("rock has no points"));
+        }
+
+        // Move the rock so that it touches the bottom and left edges.
+        let min_x = points.iter().map(|point| point.x).min().into_aoc_result()?;
+        let min_y = points.iter().map(|point| point.y).min().into_aoc_result()?;
+        let mut rock = Self { points };
+        rock.drift(&Point::new(-min_x, -min_y));
+        Ok(rock)
+    }
+}
+
+// Parses a set of rocks separated by blank lines.
+pub fn parse_rocks(input: &str) -> AocResult<Vec<Rock>> {
+    input.trim().newline_blocks(2).map(Rock::from_str).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jet {
+    Left,
+    Right,
+}
+
+impl Jet {
+    pub fn delta(&self) -> Point {
+        match self {
+            Self::Left => Point::new(-1, 0),
+            Self::Right => Point::new(1, 0),
+        }
+    }
+}
+
+pub fn parse_jet_pattern(input: &str) -> AocResult<Vec<Jet>> {
+    input
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '<' => Ok(Jet::Left),
+            '>' => Ok(Jet::Right),
+            _ => Err(AocError::parse(format!("unexpected character: {c}"))),
+        })
+        .collect()
+}
+
+// Where the chamber gets its jets of hot gas from.
+//
+// The state must capture everything that determines the jets to come, since
+// cycle detection assumes that two equal states produce the same jets forever.
+pub trait JetSource {
+    type State: Clone + Eq + Hash;
+    fn next_jet(&mut self) -> Jet;
+    fn state(&self) -> Self::State;
+}
+
+// Where the chamber gets its rocks from, with the same requirement on its state
+// as `JetSource`.
+pub trait RockSource {
+    type State: Clone + Eq + Hash;
+    // Every rock that can be produced.
+    fn rocks(&self) -> &[Rock];
+    // The index of the next rock in `rocks`.
+    fn next_rock(&mut self) -> usize;
+    fn state(&self) -> Self::State;
+}
+
+// Repeats the same sequence forever, like the jet pattern and the rocks in the
+// puzzle, or a fixed script in tests.
+#[derive(Debug, Clone)]
+pub struct Repeating<T> {
+    items: Vec<T>,
+    index: usize,
+}
+
+impl<T> Repeating<T> {
+    pub fn new(items: Vec<T>) -> AocResult<Self> {
+        if items.is_empty() {
+            return Err(AocError::parse("cannot repeat an empty sequence"));
+        }
+        Ok(Self { items, index: 0 })
+    }
+
+    fn advance(&mut self) -> usize {
+        let index = self.index;
+        self.index = (self.index + 1) % self.items.len();
+        index
+    }
+}
+
+impl JetSource for Repeating<Jet> {
+    type State = usize;
+
+    fn next_jet(&mut self) -> Jet {
+        let index = self.advance();
+        self.items[index]
+    }
+
+    fn state(&self) -> usize {
+        self.index
+    }
+}
+
+impl RockSource for Repeating<Rock> {
+    type State = usize;
+
+    fn rocks(&self) -> &[Rock] {
+        &self.items
+    }
+
+    fn next_rock(&mut self) -> usize {
+        self.advance()
+    }
+
+    fn state(&self) -> usize {
+        self.index
+    }
+}
+
+// A small xorshift random number generator, so that chambers can be fuzzed
+// reproducibly from a seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SeededRng(u64);
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift gets stuck at zero.
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // A number in `0..bound`.
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    // A random jet pattern of the given length, to be repeated.
+    pub fn jet_pattern(&mut self, len: usize) -> Vec<Jet> {
+        (0..len)
+            .map(|_| {
+                if self.next_u64() & 1 == 0 {
+                    Jet::Left
+                } else {
+                    Jet::Right
+                }
+            })
+            .collect()
+    }
+}
+
+// Jets chosen at random. The generator never repeats a state in practice, so a
+// chamber using it is always simulated in full.
+#[derive(Debug, Clone)]
+pub struct RandomJets(SeededRng);
+
+impl RandomJets {
+    pub fn new(seed: u64) -> Self {
+        Self(SeededRng::new(seed))
+    }
+}
+
+impl JetSource for RandomJets {
+    type State = SeededRng;
+
+    fn next_jet(&mut self) -> Jet {
+        if self.0.next_u64() & 1 == 0 {
+            Jet::Left
+        } else {
+            Jet::Right
+        }
+    }
+
+    fn state(&self) -> SeededRng {
+        self.0
+    }
+}
+
+// Rocks chosen at random from a set.
+#[derive(Debug, Clone)]
+pub struct RandomRocks {
+    rocks: Vec<Rock>,
+    rng: SeededRng,
+}
+
+impl RandomRocks {
+    pub fn new(rocks: Vec<Rock>, seed: u64) -> AocResult<Self> {
+        if rocks.is_empty() {
+            return Err(AocError::parse("no rocks to choose from"));
+        }
+        Ok(Self {
+            rocks,
+            rng: SeededRng::new(seed),
+        })
+    }
+}
+
+impl RockSource for RandomRocks {
+    type State = SeededRng;
+
+    fn rocks(&self) -> &[Rock] {
+        &self.rocks
+    }
+
+    fn next_rock(&mut self) -> usize {
+        self.rng.below(self.rocks.len())
+    }
+
+    fn state(&self) -> SeededRng {
+        self.rng
+    }
+}
+
+// How the shape of the top of the tower is summarized when looking for a cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CycleKey {
+    // The height of each column relative to the lowest one. Cheap, but rocks can
+    // slide sideways under overhangs, so two towers with the same heights can
+    // play out differently.
+    ColumnHeights,
+    // Every empty cell that a falling rock could reach, which is exact.
+    #[default]
+    SurfaceProfile,
+}
+
+impl FromStr for CycleKey {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        match s {
+            "column-heights" => Ok(Self::ColumnHeights),
+            "surface-profile" => Ok(Self::SurfaceProfile),
+            _ => Err(AocError::invalid_args(format!(
+                "cycle key must be column-heights or surface-profile, not {s}"
+            ))),
+        }
+    }
+}
+
+// The shape of the top of the tower, as summarized by a `CycleKey`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Shape {
+    ColumnHeights(Vec<usize>),
+    SurfaceProfile(Vec<u64>),
+}
+
+// A rock that has come to rest in the chamber.
+#[derive(Debug, Clone)]
+pub struct RockPlaced {
+    // The number of rocks placed before this one.
+    pub number: usize,
+    // The index of the rock in the rock set.
+    pub rock: usize,
+    // Where the rock came to rest.
+    pub points: Vec<Point>,
+    // The height of the tower once the rock came to rest.
+    pub height: usize,
+}
+
+// A chamber that rocks fall into, pushed side to side by jets of hot gas.
+#[derive(Debug, Clone)]
+pub struct Chamber<J = Repeating<Jet>, R = Repeating<Rock>> {
+    width: usize,
+    // Each row is stored as a word, where the lowest `width` bits represent if a
+    // rock is present in each column.
+    map: Vec<u64>,
+    // The height of the tower in each column, which is enough to tell where
+    // future rocks can fall.
+    height_in_column: Vec<usize>,
+    jets: J,
+    rocks: R,
+    rocks_placed: usize,
+    cycle_key: CycleKey,
+    // How deep the surface profile may go before the column heights are used
+    // instead.
+    surface_profile_rows: usize,
+}
+
+impl Chamber {
+    // A chamber that repeats the jet pattern and rocks, like the puzzle.
+    pub fn new(width: usize, jet_pattern: Vec<Jet>, rocks: Vec<Rock>) -> AocResult<Self> {
+        let jets =
+            Repeating::new(jet_pattern).map_err(|_| AocError::parse("jet pattern is empty"))?;
+        let rocks = Repeating::new(rocks).map_err(|_| AocError::parse("no rocks to drop"))?;
+        Self::with_sources(width, jets, rocks)
+    }
+}
+
+impl<J: JetSource + Clone, R: RockSource + Clone> Chamber<J, R> {
+    pub fn with_sources(width: usize, jets: J, rocks: R) -> AocResult<Self> {
+        if width == 0 || width > MAX_WIDTH {
+            return Err(AocError::invalid_args(format!(
+                "chamber width must be between 1 and {MAX_WIDTH}"
+            )));
+        }
+        if let Some(rock) = rocks
+            .rocks()
+            .iter()
+            .position(|rock| SPAWN_OFFSET_X + rock.width() > width as i64)
+        {
+            return Err(AocError::parse(format!(
+                "rock {rock} does not fit in a chamber of width {width}"
+            )));
+        }
+        Ok(Self {
+            width,
+            map: Vec::new(),
+            height_in_column: vec![0; width],
+            jets,
+            rocks,
+            rocks_placed: 0,
+            cycle_key: CycleKey::default(),
+            surface_profile_rows: SURFACE_PROFILE_ROWS,
+        })
+    }
+
+    pub fn with_cycle_key(mut self, cycle_key: CycleKey) -> Self {
+        self.cycle_key = cycle_key;
+        self
+    }
+
+    pub fn height(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn rock_at(&self, point: &Point) -> bool {
+        point.x < 0
+            || point.x >= self.width as i64
+            || point.y < 0
+            || self.map.get(point.y as usize).unwrap_or(&0) & (1 << point.x) != 0
+    }
+
+    fn set_rock_at(&mut self, point: &Point) {
+        let y = point.y as usize;
+        if y >= self.map.len() {
+            self.map.resize(y + 1, 0);
+        }
+        let height_in_column = &mut self.height_in_column[point.x as usize];
+        if y + 1 > *height_in_column {
+            *height_in_column = y + 1;
+        }
+        self.map[y] |= 1 << point.x
+    }
+
+    // Drops the next rock until it comes to rest.
+    pub fn drop_rock(&mut self) -> RockPlaced {
+        let rock = self.rocks.next_rock();
+        let mut current_rock = self.rocks.rocks()[rock].clone();
+        current_rock.drift(&Point::new(
+            SPAWN_OFFSET_X,
+            self.height() as i64 + SPAWN_OFFSET_Y,
+        ));
+
+        // Rocks are pushed by a jet and then fall one unit, until they cannot fall.
+        let can_move = |chamber: &Self, rock: &Rock, delta: Point| {
+            rock.points
+                .iter()
+                .all(|point| !chamber.rock_at(&(*point + delta)))
+        };
+        loop {
+            let push = self.jets.next_jet().delta();
+            if can_move(self, &current_rock, push) {
+                current_rock.drift(&push);
+            }
+            let fall = Point::new(0, -1);
+            if !can_move(self, &current_rock, fall) {
+                break;
+            }
+            current_rock.drift(&fall);
+        }
+
+        for point in &current_rock.points {
+            self.set_rock_at(point);
+        }
+        let placed = RockPlaced {
+            number: self.rocks_placed,
+            rock,
+            points: current_rock.points,
+            height: self.height(),
+        };
+        self.rocks_placed += 1;
+        placed
+    }
+
+    // Drops the given number of rocks, calling back after each one comes to rest.
+    pub fn drop_rocks(&mut self, num_rocks: usize, mut on_rock: impl FnMut(&RockPlaced)) {
+        for _ in 0..num_rocks {
+            let placed = self.drop_rock();
+            on_rock(&placed);
+        }
+    }
+
+    // Every empty cell that a falling rock could reach from above the tower, as
+    // one word per row from the top down, stopping at the first row with none.
+    // Rocks only ever move sideways or down, so a row can be flooded from the
+    // reachable cells above it. Returns `None` if cells are still reachable
+    // `rows` rows down, short of the floor.
+    pub fn surface_profile(&self, rows: usize) -> Option<Vec<u64>> {
+        let full = u64::MAX >> (MAX_WIDTH - self.width);
+        let mut profile = Vec::new();
+        let mut reachable = full;
+        for y in (0..self.height()).rev() {
+            let empty = !self.map[y] & full;
+            reachable &= empty;
+            loop {
+                let spread = (reachable | reachable << 1 | reachable >> 1) & empty;
+                if spread == reachable {
+                    break;
+                }
+                reachable = spread;
+            }
+            if reachable == 0 {
+                break;
+            }
+            if profile.len() == rows {
+                return None;
+            }
+            profile.push(reachable);
+        }
+        Some(profile)
+    }
+
+    // The height in each column, relative to the lowest height.
+    fn relative_column_heights(&self) -> Vec<usize> {
+        let lowest_column_height = self.height_in_column.iter().min().copied().unwrap_or(0);
+        self.height_in_column
+            .iter()
+            .map(|height| height - lowest_column_height)
+            .collect()
+    }
+
+    // Everything that determines how the rest of the rocks will fall: the state
+    // of the jet source, the state of the rock source, and the shape of the top
+    // of the tower.
+    //
+    // Rocks cannot reach anything below the surface profile, so it is all that
+    // matters of the shape. The height in each column, relative to the lowest
+    // height, usually matters just as much, since the next rock cannot go below
+    // any of them without sliding under an overhang. It stands in for a surface
+    // too deep to profile, so that a cycle can still be found.
+    fn state(&self) -> (J::State, R::State, Shape) {
+        let shape = match self.cycle_key {
+            CycleKey::ColumnHeights => Shape::ColumnHeights(self.relative_column_heights()),
+            CycleKey::SurfaceProfile => match self.surface_profile(self.surface_profile_rows) {
+                Some(profile) => Shape::SurfaceProfile(profile),
+                None => Shape::ColumnHeights(self.relative_column_heights()),
+            },
+        };
+        (self.jets.state(), self.rocks.state(), shape)
+    }
+
+    // The number of rocks dropped before the chamber returns to a state it has
+    // been in before, after which the tower repeats itself forever. Never returns
+    // for sources that do not repeat, like random jets.
+    pub fn rocks_until_cycle(&self) -> usize {
+        let mut chamber = self.clone();
+        let states = std::iter::repeat_with(|| {
+            let state = chamber.state();
+            chamber.drop_rock();
+            state
+        });
+        self.rocks_placed + cycle::detect(states).unwrap().end()
+    }
+
+    // Draws the top rows of the chamber the way the puzzle does, with the given
+    // points drawn as `@` like a falling rock.
+    pub fn render_top(&self, rows: usize, falling: &[Point]) -> String {
+        let top = self.height().max(
+            falling
+                .iter()
+                .map(|point| point.y as usize + 1)
+                .max()
+                .unwrap_or(0),
+        );
+        let mut frame = String::new();
+        for y in (top.saturating_sub(rows)..top).rev() {
+            frame.push('|');
+            for x in 0..self.width {
+                let point = Point::new(x as i64, y as i64);
+                frame.push(if falling.contains(&point) {
+                    '@'
+                } else if self.rock_at(&point) {
+                    '#'
+                } else {
+                    '.'
+                });
+            }
+            frame += "|\n";
+        }
+        if top <= rows {
+            frame += &format!("+{}+\n", "-".repeat(self.width));
+        }
+        frame
+    }
+
+    // The height of the tower after dropping the given number of rocks into this
+    // chamber, leaving the chamber itself untouched.
+    //
+    // With cycle detection, the simulation stops as soon as the chamber returns to a
+    // state it has been in before, and the rest of the height is extrapolated from
+    // the cycle. A cancelled solver stops dropping rocks early.
+    pub fn height_after(&self, num_rocks: usize, look_for_cycle: bool) -> usize {
+        let mut chamber = self.clone();
+        let progress = Tracker::current();
+        progress.start(Some(num_rocks as u64), "rocks");
+        if !look_for_cycle {
+            for _ in 0..num_rocks {
+                if progress.cancelled() {
+                    break;
+                }
+                chamber.drop_rock();
+                progress.advance(1);
+            }
+            return chamber.height();
+        }
+
+        // Keep track of the height at each rock placed, to extrapolate the rest of
+        // the rocks from once a cycle is found.
+        let mut cycles = CycleDetector::new();
+        let mut height_at_rocks_placed = Vec::new();
+        for rock in 0..num_rocks {
+            if progress.cancelled() {
+                break;
+            }
+            height_at_rocks_placed.push(chamber.height());
+            if let Some(cycle) = cycles.push(chamber.state()) {
+                // The rest of the rocks are accounted for by repeating the cycle.
+                progress.advance((num_rocks - rock) as u64);
+                return cycle.value_at(&height_at_rocks_placed, num_rocks);
+            }
+            chamber.drop_rock();
+            progress.advance(1);
+        }
+
+        chamber.height()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_JETS: &str = ">>><<><>><<<>><>>><<<>>><<<><<<>><>><<>>";
+
+    fn example_chamber() -> Chamber {
+        Chamber::new(
+            DEFAULT_WIDTH,
+            parse_jet_pattern(EXAMPLE_JETS).unwrap(),
+            Rock::default_set(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn cycle_detection_matches_simulation() {
+        let chamber = example_chamber();
+        for num_rocks in [0, 1, 10, 100, 2022] {
+            assert_eq!(
+                chamber.height_after(num_rocks, true),
+                chamber.height_after(num_rocks, false)
+            );
+        }
+        assert_eq!(chamber.height_after(2022, true), 3068);
+        assert_eq!(chamber.height_after(1_000_000_000_000, true), 1514285714288);
+    }
+
+    #[test]
+    fn parses_puzzle_rocks() {
+        let sorted = |rocks: Vec<Rock>| {
+            rocks
+                .into_iter()
+                .map(|mut rock| {
+                    rock.points.sort();
+                    rock
+                })
+                .collect::<Vec<_>>()
+        };
+        let rocks = parse_rocks("####\n\n.#.\n###\n.#.\n\n..#\n..#\n###\n\n#\n#\n#\n#\n\n##\n##");
+        assert_eq!(sorted(rocks.unwrap()), sorted(Rock::default_set()));
+    }
+
+    #[test]
+    fn reports_each_rock() {
+        let mut chamber = example_chamber();
+        let mut heights = Vec::new();
+        chamber.drop_rocks(3, |placed| heights.push((placed.number, placed.height)));
+        assert_eq!(heights, [(0, 1), (1, 4), (2, 6)]);
+    }
+
+    #[test]
+    fn renders_top_of_tower() {
+        let mut chamber = example_chamber();
+        chamber.drop_rocks(2, |_| ());
+        assert_eq!(
+            chamber.render_top(5, &[Point::new(2, 4)]),
+            "|..@....|\n|...#...|\n|..###..|\n|...#...|\n|..####.|\n+-------+\n"
+        );
+    }
+
+    // Heights after every number of rocks up to the given count, found by dropping
+    // the rocks one at a time.
+    fn brute_force_heights<J, R>(chamber: &Chamber<J, R>, num_rocks: usize) -> Vec<usize>
+    where
+        J: JetSource + Clone,
+        R: RockSource + Clone,
+    {
+        let mut chamber = chamber.clone();
+        let mut heights = vec![chamber.height()];
+        chamber.drop_rocks(num_rocks, |placed| heights.push(placed.height));
+        heights
+    }
+
+    #[test]
+    fn cycle_detection_matches_brute_force_for_random_patterns() {
+        let mut rng = SeededRng::new(2022);
+        for _ in 0..40 {
+            let len = 1 + rng.below(30);
+            let jets = rng.jet_pattern(len);
+            let mut rocks = Rock::default_set();
+            rocks.truncate(1 + rng.below(rocks.len()));
+            let chamber = Chamber::new(DEFAULT_WIDTH, jets, rocks).unwrap();
+            let heights = brute_force_heights(&chamber, 300);
+            for num_rocks in (0..heights.len()).step_by(13) {
+                assert_eq!(chamber.height_after(num_rocks, true), heights[num_rocks]);
+            }
+        }
+    }
+
+    #[test]
+    fn follows_a_fixed_script() {
+        // Every rock is pushed all the way right, so flat rocks stack on the
+        // right wall.
+        let flat = Rock::from_str("###").unwrap();
+        let chamber = Chamber::with_sources(
+            DEFAULT_WIDTH,
+            Repeating::new(vec![Jet::Right]).unwrap(),
+            Repeating::new(vec![flat]).unwrap(),
+        )
+        .unwrap();
+        let mut placed = Vec::new();
+        chamber
+            .clone()
+            .drop_rocks(3, |rock| placed.push(rock.points.clone()));
+        for (y, points) in placed.iter().enumerate() {
+            let y = y as i64;
+            assert_eq!(
+                points,
+                &[Point::new(4, y), Point::new(5, y), Point::new(6, y)]
+            );
+        }
+        assert_eq!(chamber.height_after(1000, true), 1000);
+    }
+
+    #[test]
+    fn random_sources_are_reproducible() {
+        let chamber = |seed| {
+            Chamber::with_sources(
+                DEFAULT_WIDTH,
+                RandomJets::new(seed),
+                RandomRocks::new(Rock::default_set(), seed).unwrap(),
+            )
+            .unwrap()
+        };
+        let heights = brute_force_heights(&chamber(7), 200);
+        assert_eq!(heights, brute_force_heights(&chamber(7), 200));
+        assert_ne!(heights, brute_force_heights(&chamber(8), 200));
+        for num_rocks in [0, 1, 50, 200] {
+            assert_eq!(chamber(7).height_after(num_rocks, true), heights[num_rocks]);
+        }
+    }
+
+    #[test]
+    fn rejects_rocks_wider_than_chamber() {
+        let jets = parse_jet_pattern(EXAMPLE_JETS).unwrap();
+        assert!(Chamber::new(5, jets.clone(), Rock::default_set()).is_err());
+        assert!(Chamber::new(6, jets, Rock::default_set()).is_ok());
+    }
+
+    // A rock of random cells in a 3x3 box, which can have holes and overhangs
+    // that the puzzle's rocks do not.
+    fn random_rock(rng: &mut SeededRng) -> Rock {
+        loop {
+            let drawing = (0..3)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| if rng.below(2) == 0 { '#' } else { '.' })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            if let Ok(rock) = Rock::from_str(&drawing) {
+                return rock;
+            }
+        }
+    }
+
+    #[test]
+    fn surface_profile_cycles_match_brute_force_for_random_rocks() {
+        let mut rng = SeededRng::new(17);
+        for _ in 0..40 {
+            let len = 1 + rng.below(20);
+            let jets = rng.jet_pattern(len);
+            let rocks = (0..1 + rng.below(3))
+                .map(|_| random_rock(&mut rng))
+                .collect();
+            let chamber = Chamber::new(DEFAULT_WIDTH, jets, rocks).unwrap();
+            let heights = brute_force_heights(&chamber, 300);
+            for num_rocks in (0..heights.len()).step_by(11) {
+                assert_eq!(chamber.height_after(num_rocks, true), heights[num_rocks]);
+            }
+        }
+    }
+
+    #[test]
+    fn column_heights_miss_overhangs() {
+        let diagonal = Rock::from_str("#.\n.#").unwrap();
+        let chamber = Chamber::new(
+            DEFAULT_WIDTH,
+            parse_jet_pattern(">>>>>>><<<><<<><<>>").unwrap(),
+            vec![diagonal],
+        )
+        .unwrap();
+        let heights = brute_force_heights(&chamber, 400);
+        let column_heights = chamber.clone().with_cycle_key(CycleKey::ColumnHeights);
+        assert_ne!(column_heights.height_after(400, true), heights[400]);
+        assert_eq!(chamber.height_after(400, true), heights[400]);
+    }
+
+    #[test]
+    fn floods_surface_from_the_top() {
+        let mut chamber = example_chamber();
+        assert_eq!(chamber.surface_profile(10), Some(vec![]));
+        // The plus rests on the flat rock, leaving pockets under its arms that
+        // can be reached from the side.
+        chamber.drop_rocks(2, |_| ());
+        assert_eq!(
+            chamber.surface_profile(10),
+            Some(vec![0b1110111, 0b1100011, 0b1110111, 0b1000011])
+        );
+        assert_eq!(chamber.surface_profile(2), None);
+        // A profile exactly as deep as allowed is still complete.
+        chamber.drop_rocks(18, |_| ());
+        let profile = chamber.surface_profile(usize::MAX).unwrap();
+        assert!(chamber.height() > profile.len());
+        assert_eq!(
+            chamber.surface_profile(profile.len()),
+            Some(profile.clone())
+        );
+        assert_eq!(chamber.surface_profile(profile.len() - 1), None);
+    }
+
+    #[test]
+    fn falls_back_to_column_heights_for_deep_surfaces() {
+        let mut chamber = example_chamber();
+        chamber.surface_profile_rows = 1;
+        for num_rocks in [0, 10, 100, 2022] {
+            assert_eq!(
+                chamber.height_after(num_rocks, true),
+                chamber.height_after(num_rocks, false)
+            );
+        }
+        assert_eq!(chamber.height_after(1_000_000_000_000, true), 1514285714288);
+    }
+}
@@ -0,0 +1,28 @@
+use std::{cell::Cell, time::Duration};
+
+thread_local! {
+    static PARSE_TIME: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+// Runs the part of a solver that parses its input, recording how long it took
+// so that parsing can be reported separately from solving.
+//
+// WebAssembly has no clock to read, so nothing is recorded there.
+pub fn parse_phase<T>(parse: impl FnOnce() -> T) -> T {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let now = std::time::Instant::now();
+        let parsed = parse();
+        let elapsed = now.elapsed();
+        PARSE_TIME.with(|time| time.set(Some(time.get().unwrap_or_default() + elapsed)));
+        parsed
+    }
+    #[cfg(target_arch = "wasm32")]
+    parse()
+}
+
+// Returns the total time spent in `parse_phase` on this thread since the last
+// call, if it was called at all.
+pub fn take_parse_time() -> Option<Duration> {
+    PARSE_TIME.with(|time| time.take())
+}
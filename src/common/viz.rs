@@ -0,0 +1,89 @@
+use std::{
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use super::{AocResult, IntoAocResult, Point2};
+
+// Moves the cursor to the top-left corner and clears the terminal.
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+// A puzzle that can be shown as an animation in the terminal, one frame of
+// ASCII art at a time.
+pub trait Visualize {
+    fn frames(&self) -> impl Iterator<Item = String>;
+}
+
+// Draws every frame of a visualization over the previous one, waiting between
+// frames. Only every `every`th frame is drawn, along with the last one, so long
+// animations can be sped up past what the terminal can keep up with.
+pub fn play(viz: &impl Visualize, delay: Duration, every: usize) -> AocResult<()> {
+    let mut stdout = io::stdout().lock();
+    let mut frames = viz.frames().enumerate().peekable();
+    while let Some((i, frame)) = frames.next() {
+        if i % every.max(1) != 0 && frames.peek().is_some() {
+            continue;
+        }
+        write!(stdout, "{CLEAR_SCREEN}{frame}")
+            .and_then(|_| stdout.flush())
+            .into_aoc_result_msg("failed to draw frame")?;
+        thread::sleep(delay);
+    }
+    Ok(())
+}
+
+// The smallest rectangle containing every point, as its top-left and
+// bottom-right corners.
+pub fn bounds(points: impl IntoIterator<Item = Point2<i64>>) -> Option<(Point2<i64>, Point2<i64>)> {
+    points.into_iter().fold(None, |bounds, point| {
+        let (min, max) = bounds.unwrap_or((point, point));
+        Some((
+            Point2::new(min.x.min(point.x), min.y.min(point.y)),
+            Point2::new(max.x.max(point.x), max.y.max(point.y)),
+        ))
+    })
+}
+
+// Draws every tile between two corners, inclusive, one row per line.
+pub fn render_grid(
+    min: Point2<i64>,
+    max: Point2<i64>,
+    mut tile: impl FnMut(Point2<i64>) -> char,
+) -> String {
+    let mut frame = String::new();
+    for y in min.y..=max.y {
+        frame.extend((min.x..=max.x).map(|x| tile(Point2::new(x, y))));
+        frame.push('\n');
+    }
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_points_within_bounds() {
+        let points = [Point2::new(2, -1), Point2::new(0, 1), Point2::new(1, 0)];
+        let (min, max) = bounds(points).unwrap();
+        assert_eq!((min, max), (Point2::new(0, -1), Point2::new(2, 1)));
+        let frame = render_grid(
+            min,
+            max,
+            |point| {
+                if points.contains(&point) {
+                    '#'
+                } else {
+                    '.'
+                }
+            },
+        );
+        assert_eq!(frame, "..#\n.#.\n#..\n");
+    }
+
+    #[test]
+    fn has_no_bounds_without_points() {
+        assert_eq!(bounds([]), None);
+    }
+}
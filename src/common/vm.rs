@@ -0,0 +1,295 @@
+use super::{AocError, AocResult, IntoAocResult};
+use std::{
+    ops::{Index, IndexMut},
+    str::FromStr,
+};
+
+// A register, named by a lowercase letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Register(u8);
+
+impl Register {
+    pub const COUNT: usize = 26;
+    pub const X: Register = Register(b'x' - b'a');
+
+    pub fn name(&self) -> char {
+        (b'a' + self.0) as char
+    }
+}
+
+impl TryFrom<char> for Register {
+    type Error = AocError;
+    fn try_from(c: char) -> AocResult<Self> {
+        match c {
+            'a'..='z' => Ok(Self(c as u8 - b'a')),
+            _ => Err(AocError::parse(format!("invalid register: {c}"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registers([i64; Register::COUNT]);
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self([0; Register::COUNT])
+    }
+}
+
+impl Index<Register> for Registers {
+    type Output = i64;
+    fn index(&self, register: Register) -> &i64 {
+        &self.0[register.0 as usize]
+    }
+}
+
+impl IndexMut<Register> for Registers {
+    fn index_mut(&mut self, register: Register) -> &mut i64 {
+        &mut self.0[register.0 as usize]
+    }
+}
+
+// An instruction, which takes effect at the end of its last cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // `add<register> value`, taking two cycles.
+    Add(Register, i64),
+    // `jmp offset`, relative to the jump itself, taking one cycle.
+    Jmp(i64),
+    // `noop` for one cycle, or `nop n` for any number of cycles.
+    Nop(u64),
+}
+
+impl Instruction {
+    pub fn cycles(&self) -> u64 {
+        match self {
+            Self::Add(..) => 2,
+            Self::Jmp(_) => 1,
+            Self::Nop(cycles) => *cycles,
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        let operand = |operand: &str| {
+            operand
+                .parse::<i64>()
+                .into_aoc_result_msg(&format!("invalid operand: {operand}"))
+        };
+        let mut words = s.split(' ');
+        let instruction = match (words.next(), words.next()) {
+            (Some("noop"), None) => Self::Nop(1),
+            (Some("nop"), Some(cycles)) => match cycles.parse() {
+                Ok(cycles) if cycles > 0 => Self::Nop(cycles),
+                _ => return Err(AocError::parse(format!("invalid cycles for nop: {cycles}"))),
+            },
+            (Some("jmp"), Some(offset)) => Self::Jmp(operand(offset)?),
+            (Some(opcode), Some(value)) if opcode.len() == 4 && opcode.starts_with("add") => {
+                let register = opcode.chars().last().unwrap_or_default();
+                Self::Add(Register::try_from(register)?, operand(value)?)
+            }
+            _ => return Err(AocError::parse(format!("unknown instruction: {s}"))),
+        };
+        match words.next() {
+            None => Ok(instruction),
+            Some(_) => Err(AocError::parse(format!("unknown instruction: {s}"))),
+        }
+    }
+}
+
+pub fn parse_program(input: &str) -> AocResult<Vec<Instruction>> {
+    input.lines().map(Instruction::from_str).collect()
+}
+
+// Something connected to the machine that watches its registers, like a
+// screen. Closures taking the same arguments can be used as well.
+pub trait Peripheral {
+    // Called during every cycle, numbered from 1, before any instruction that
+    // finishes in it takes effect.
+    fn during_cycle(&mut self, cycle: u64, registers: &Registers);
+}
+
+impl<F: FnMut(u64, &Registers)> Peripheral for F {
+    fn during_cycle(&mut self, cycle: u64, registers: &Registers) {
+        self(cycle, registers)
+    }
+}
+
+// Runs a program one cycle at a time, halting once it runs past either end of
+// the program.
+pub struct Vm {
+    program: Vec<Instruction>,
+    pc: Option<usize>,
+    registers: Registers,
+    // The cycles left for the instruction at the program counter, once it has
+    // started.
+    executing: Option<u64>,
+    cycle: u64,
+}
+
+impl Vm {
+    pub fn new(program: Vec<Instruction>) -> Self {
+        Self {
+            program,
+            pc: Some(0),
+            registers: Registers::default(),
+            executing: None,
+            cycle: 0,
+        }
+    }
+
+    pub fn with_register(mut self, register: Register, value: i64) -> Self {
+        self.registers[register] = value;
+        self
+    }
+
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    // How many cycles have passed.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    fn current(&self) -> Option<Instruction> {
+        self.program.get(self.pc?).copied()
+    }
+
+    pub fn halted(&self) -> bool {
+        self.current().is_none()
+    }
+
+    fn finish(&mut self, instruction: Instruction) {
+        let pc = self.pc.unwrap_or_default();
+        self.pc = match instruction {
+            Instruction::Add(register, value) => {
+                self.registers[register] += value;
+                Some(pc + 1)
+            }
+            Instruction::Jmp(offset) => pc.checked_add_signed(offset as isize),
+            Instruction::Nop(_) => Some(pc + 1),
+        };
+        self.executing = None;
+    }
+
+    // Runs a single cycle, returning false without running it if the program
+    // has halted.
+    pub fn step(&mut self, peripheral: &mut impl Peripheral) -> bool {
+        let instruction = match self.current() {
+            None => return false,
+            Some(instruction) => instruction,
+        };
+        let remaining = self.executing.unwrap_or(instruction.cycles()) - 1;
+        self.cycle += 1;
+        peripheral.during_cycle(self.cycle, &self.registers);
+        match remaining {
+            0 => self.finish(instruction),
+            _ => self.executing = Some(remaining),
+        }
+        true
+    }
+
+    // Runs until the program halts.
+    pub fn run(&mut self, peripheral: &mut impl Peripheral) {
+        while self.step(peripheral) {}
+    }
+
+    // Runs until the program halts or the cycles run out, whichever is first,
+    // so a program that loops forever still stops.
+    pub fn run_at_most(&mut self, cycles: u64, peripheral: &mut impl Peripheral) {
+        for _ in 0..cycles {
+            if !self.step(peripheral) {
+                break;
+            }
+        }
+    }
+
+    // Runs for a number of cycles, which keep passing with the registers left
+    // alone once the program halts.
+    pub fn run_for(&mut self, cycles: u64, peripheral: &mut impl Peripheral) {
+        for _ in 0..cycles {
+            if !self.step(peripheral) {
+                self.cycle += 1;
+                peripheral.during_cycle(self.cycle, &self.registers);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace(program: &str, cycles: Option<u64>) -> Vec<(i64, i64)> {
+        let mut vm = Vm::new(parse_program(program).unwrap()).with_register(Register::X, 1);
+        let mut values = Vec::new();
+        let mut record = |cycle: u64, registers: &Registers| {
+            assert_eq!(cycle, values.len() as u64 + 1);
+            let y = Register::try_from('y').unwrap();
+            values.push((registers[Register::X], registers[y]));
+        };
+        match cycles {
+            None => vm.run(&mut record),
+            Some(cycles) => vm.run_for(cycles, &mut record),
+        }
+        values
+    }
+
+    #[test]
+    fn runs_puzzle_instructions() {
+        let xs = trace("noop\naddx 3\naddx -5", None)
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        assert_eq!(xs, [1, 1, 1, 4, 4]);
+        // Cycles keep passing after the program halts, with x at its final value.
+        let xs = trace("noop\naddx 3\naddx -5", Some(7))
+            .into_iter()
+            .map(|(x, _)| x)
+            .collect::<Vec<_>>();
+        assert_eq!(xs, [1, 1, 1, 4, 4, -1, -1]);
+    }
+
+    #[test]
+    fn runs_extended_instructions() {
+        // Jumps over the backward jump, then past the end of the program.
+        let program = "addy 2\nnop 3\njmp 2\njmp -3\naddx 10\njmp 5";
+        assert_eq!(
+            trace(program, None),
+            [
+                (1, 0),
+                (1, 0),
+                (1, 2),
+                (1, 2),
+                (1, 2),
+                (1, 2),
+                (1, 2),
+                (1, 2),
+                (11, 2)
+            ]
+        );
+        let mut vm = Vm::new(parse_program("jmp -1").unwrap());
+        vm.run(&mut |_, _: &Registers| ());
+        assert!(vm.halted());
+        assert_eq!(vm.cycle(), 1);
+        let mut vm = Vm::new(parse_program("jmp 0").unwrap());
+        vm.run_at_most(5, &mut |_, _: &Registers| ());
+        assert!(!vm.halted());
+        assert_eq!(vm.cycle(), 5);
+    }
+
+    #[test]
+    fn rejects_invalid_instructions() {
+        for instruction in [
+            "addx", "add 1", "addX 1", "addxy 1", "nop 0", "jmp x", "noop 1",
+        ] {
+            assert!(
+                Instruction::from_str(instruction).is_err(),
+                "{instruction} was parsed"
+            );
+        }
+    }
+}
@@ -0,0 +1,505 @@
+use std::{fs, path::Path, str::FromStr};
+
+use super::{dsu::DisjointSet, search, AocError, AocResult, IntoAocResult, Point3};
+use itertools::Itertools;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+type Point = Point3<i64>;
+
+// An axis-aligned box of points, inclusive on both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl BoundingBox {
+    pub fn contains(&self, point: &Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+
+    // Returns a box that extends the given distance further out on every side.
+    pub fn expand(&self, distance: i64) -> Self {
+        let offset = Point::new(distance, distance, distance);
+        Self {
+            min: self.min - offset,
+            max: self.max + offset,
+        }
+    }
+
+    pub fn volume(&self) -> u64 {
+        let size = self.max - self.min + Point::new(1, 1, 1);
+        (size.x * size.y * size.z) as u64
+    }
+}
+
+// A set of unit cubes on a 3D grid.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VoxelSet {
+    voxels: FxHashSet<Point>,
+}
+
+// Measurements of a single group of connected voxels, like one droplet of
+// several.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComponentStats {
+    // The smallest box containing the component.
+    pub bounds: BoundingBox,
+    // The number of voxels in the component.
+    pub volume: u64,
+    // The number of the component's faces that do not touch another voxel.
+    pub surface_area: u64,
+    // The number of the component's faces that can be reached from outside of
+    // every component, so a component inside of another's cavity has none.
+    pub external_surface_area: u64,
+    // The separate pockets of empty space the component encloses, and how many
+    // points they hold in total, as if it were the only component.
+    pub cavities: usize,
+    pub cavity_volume: u64,
+}
+
+impl VoxelSet {
+    pub fn len(&self) -> usize {
+        self.voxels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        self.voxels.contains(point)
+    }
+
+    // The smallest box containing every voxel, if there are any.
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let extent = |axis: fn(&Point) -> i64| self.voxels.iter().map(axis).minmax().into_option();
+        let (min_x, max_x) = extent(|voxel| voxel.x)?;
+        let (min_y, max_y) = extent(|voxel| voxel.y)?;
+        let (min_z, max_z) = extent(|voxel| voxel.z)?;
+        Some(BoundingBox {
+            min: Point::new(min_x, min_y, min_z),
+            max: Point::new(max_x, max_y, max_z),
+        })
+    }
+
+    // Every voxel face that touches an open point, as the voxel and the point on
+    // the other side of the face.
+    fn faces_facing<'a>(
+        &'a self,
+        open: &'a impl Fn(&Point) -> bool,
+    ) -> impl Iterator<Item = (Point, Point)> + 'a {
+        self.voxels.iter().flat_map(move |voxel| {
+            voxel
+                .neighbors6()
+                .into_iter()
+                .filter(move |point| open(point))
+                .map(move |point| (*voxel, point))
+        })
+    }
+
+    // The number of voxel faces that do not touch another voxel.
+    pub fn surface_area(&self) -> u64 {
+        self.faces_facing(&|point| !self.voxels.contains(point))
+            .count() as u64
+    }
+
+    // A mesh of every voxel face that does not touch another voxel.
+    pub fn surface_mesh(&self) -> Mesh {
+        self.faces_facing(&|point| !self.voxels.contains(point))
+            .collect()
+    }
+
+    // Returns every empty point reachable from outside of the voxels, within a box
+    // extending 1 unit out from them.
+    fn exterior(&self) -> Option<(BoundingBox, FxHashSet<Point>)> {
+        let bounds = self.bounding_box()?.expand(1);
+        let filled = search::flood_fill([bounds.min], |point| {
+            point
+                .neighbors6()
+                .into_iter()
+                .filter(|next| !self.voxels.contains(next) && bounds.contains(next))
+                .collect::<Vec<_>>()
+        });
+        Some((bounds, filled))
+    }
+
+    // The number of voxel faces that can be reached from outside, which excludes
+    // faces touching enclosed pockets of empty space.
+    pub fn external_surface_area(&self) -> u64 {
+        let Some((_, exterior)) = self.exterior() else {
+            return 0;
+        };
+        self.faces_facing(&|point| exterior.contains(point)).count() as u64
+    }
+
+    // A mesh of every voxel face that can be reached from outside.
+    pub fn external_surface_mesh(&self) -> Mesh {
+        let Some((_, exterior)) = self.exterior() else {
+            return Mesh::default();
+        };
+        self.faces_facing(&|point| exterior.contains(point))
+            .collect()
+    }
+
+    // The number of empty points enclosed by the voxels.
+    pub fn interior_volume(&self) -> u64 {
+        match self.exterior() {
+            None => 0,
+            Some((bounds, exterior)) => {
+                bounds.volume() - exterior.len() as u64 - self.voxels.len() as u64
+            }
+        }
+    }
+
+    // Every empty point enclosed by the voxels.
+    pub fn interior(&self) -> VoxelSet {
+        let Some((bounds, exterior)) = self.exterior() else {
+            return VoxelSet::default();
+        };
+        let (min, max) = (bounds.min, bounds.max);
+        (min.x..=max.x)
+            .cartesian_product(min.y..=max.y)
+            .cartesian_product(min.z..=max.z)
+            .map(|((x, y), z)| Point::new(x, y, z))
+            .filter(|point| !self.voxels.contains(point) && !exterior.contains(point))
+            .collect()
+    }
+
+    // Splits the voxels into groups connected by shared faces.
+    pub fn components(&self) -> Vec<VoxelSet> {
+        let (voxels, mut sets) = self.connect();
+        sets.components()
+            .map(|component| VoxelSet {
+                voxels: component.into_iter().map(|i| voxels[i]).collect(),
+            })
+            .collect()
+    }
+
+    // Measures every group of voxels connected by shared faces, in order of
+    // their bounding boxes. Each of the voxels' faces is counted by exactly one
+    // component, so the areas sum to those of the whole set.
+    pub fn component_stats(&self) -> Vec<ComponentStats> {
+        let Some((_, exterior)) = self.exterior() else {
+            return Vec::new();
+        };
+        self.components()
+            .into_iter()
+            .map(|component| {
+                let interior = component.interior();
+                ComponentStats {
+                    bounds: component.bounding_box().unwrap(),
+                    volume: component.len() as u64,
+                    surface_area: component.surface_area(),
+                    external_surface_area: component
+                        .faces_facing(&|point| exterior.contains(point))
+                        .count() as u64,
+                    cavities: interior.components().len(),
+                    cavity_volume: interior.len() as u64,
+                }
+            })
+            .sorted_by_key(|stats| (stats.bounds.min, stats.bounds.max))
+            .collect()
+    }
+
+    // The number of groups of voxels connected by shared faces, counted without
+    // building the groups.
+    pub fn component_count(&self) -> usize {
+        self.connect().1.component_count()
+    }
+
+    // Merges every pair of voxels that share a face, as sets of indices into
+    // the returned voxels.
+    fn connect(&self) -> (Vec<Point>, DisjointSet) {
+        let voxels = self.voxels.iter().copied().collect::<Vec<_>>();
+        let indices = voxels
+            .iter()
+            .enumerate()
+            .map(|(i, voxel)| (*voxel, i))
+            .collect::<FxHashMap<_, _>>();
+        let mut sets = DisjointSet::new(voxels.len());
+        for (i, voxel) in voxels.iter().enumerate() {
+            // Only looking forward along each axis still visits every shared face.
+            for delta in [
+                Point::new(1, 0, 0),
+                Point::new(0, 1, 0),
+                Point::new(0, 0, 1),
+            ] {
+                if let Some(&j) = indices.get(&(*voxel + delta)) {
+                    sets.union(i, j);
+                }
+            }
+        }
+        (voxels, sets)
+    }
+}
+
+// The outside faces of a set of voxels, as squares of four corners in
+// counterclockwise order when seen from outside.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mesh {
+    quads: Vec<([Point; 4], Point)>,
+}
+
+impl Mesh {
+    pub fn len(&self) -> usize {
+        self.quads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+
+    // The corners of the face of a unit cube pointing in the given direction.
+    fn quad(voxel: Point, normal: Point) -> [Point; 4] {
+        // Two edges of the face, chosen so that going from the first to the second
+        // turns counterclockwise around the normal.
+        let (u, w) = match (normal.x, normal.y, normal.z) {
+            (1, _, _) => (Point::new(0, 1, 0), Point::new(0, 0, 1)),
+            (-1, _, _) => (Point::new(0, 0, 1), Point::new(0, 1, 0)),
+            (_, 1, _) => (Point::new(0, 0, 1), Point::new(1, 0, 0)),
+            (_, -1, _) => (Point::new(1, 0, 0), Point::new(0, 0, 1)),
+            (_, _, 1) => (Point::new(1, 0, 0), Point::new(0, 1, 0)),
+            _ => (Point::new(0, 1, 0), Point::new(1, 0, 0)),
+        };
+        // Faces pointing in a positive direction are on the far side of the cube.
+        let corner = Point::new(
+            voxel.x + normal.x.max(0),
+            voxel.y + normal.y.max(0),
+            voxel.z + normal.z.max(0),
+        );
+        [corner, corner + u, corner + u + w, corner + w]
+    }
+
+    // A Wavefront OBJ file, sharing vertices between faces.
+    pub fn to_obj(&self) -> String {
+        let mut vertices = FxHashMap::default();
+        let mut obj = String::new();
+        let mut faces = String::new();
+        for (quad, _) in &self.quads {
+            faces += "f";
+            for corner in quad {
+                let next = vertices.len() + 1;
+                let index = *vertices.entry(*corner).or_insert_with(|| {
+                    obj += &format!("v {} {} {}\n", corner.x, corner.y, corner.z);
+                    next
+                });
+                faces += &format!(" {index}");
+            }
+            faces += "\n";
+        }
+        obj + &faces
+    }
+
+    // An ASCII STL file, with each face split into two triangles.
+    pub fn to_stl(&self) -> String {
+        let mut stl = String::from("solid voxels\n");
+        for ([a, b, c, d], normal) in &self.quads {
+            for triangle in [[a, b, c], [a, c, d]] {
+                stl += &format!(
+                    "facet normal {} {} {}\nouter loop\n",
+                    normal.x, normal.y, normal.z
+                );
+                for vertex in triangle {
+                    stl += &format!("vertex {} {} {}\n", vertex.x, vertex.y, vertex.z);
+                }
+                stl += "endloop\nendfacet\n";
+            }
+        }
+        stl += "endsolid voxels\n";
+        stl
+    }
+
+    // Writes the mesh in the format matching the file extension.
+    pub fn save(&self, path: &str) -> AocResult<()> {
+        let contents = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("obj") => self.to_obj(),
+            Some("stl") => self.to_stl(),
+            _ => {
+                return Err(AocError::invalid_args(format!(
+                    "cannot save mesh to {path}: expected a .obj or .stl file"
+                )))
+            }
+        };
+        fs::write(path, contents).into_aoc_result_msg(&format!("failed to write {}", path))
+    }
+}
+
+// Collects faces given as a voxel and the point on the other side of the face.
+impl FromIterator<(Point, Point)> for Mesh {
+    fn from_iter<I: IntoIterator<Item = (Point, Point)>>(iter: I) -> Self {
+        Self {
+            quads: iter
+                .into_iter()
+                .map(|(voxel, outside)| {
+                    let normal = outside - voxel;
+                    (Self::quad(voxel, normal), normal)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl FromIterator<Point> for VoxelSet {
+    fn from_iter<I: IntoIterator<Item = Point>>(iter: I) -> Self {
+        Self {
+            voxels: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl FromStr for VoxelSet {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        s.lines().map(Point::from_str).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> VoxelSet {
+        include_str!("../../examples/18.txt").parse().unwrap()
+    }
+
+    #[test]
+    fn measures_surface_area() {
+        let small: VoxelSet = "1,1,1\n2,1,1".parse().unwrap();
+        assert_eq!(small.surface_area(), 10);
+        assert_eq!(small.external_surface_area(), 10);
+        assert_eq!(example().surface_area(), 64);
+        assert_eq!(example().external_surface_area(), 58);
+    }
+
+    #[test]
+    fn measures_interior_volume() {
+        assert_eq!(example().interior_volume(), 1);
+
+        // A hollow 3x3x3 cube has a single pocket in its middle.
+        let hollow = itertools::iproduct!(0..3, 0..3, 0..3)
+            .map(|(x, y, z)| Point::new(x, y, z))
+            .filter(|point| *point != Point::new(1, 1, 1))
+            .collect::<VoxelSet>();
+        assert_eq!(hollow.interior_volume(), 1);
+        assert_eq!(hollow.external_surface_area(), 54);
+        assert_eq!(hollow.surface_area(), 60);
+    }
+
+    #[test]
+    fn labels_connected_components() {
+        let voxels: VoxelSet = "0,0,0\n0,0,1\n5,5,5\n1,1,0".parse().unwrap();
+        let mut sizes = voxels
+            .components()
+            .iter()
+            .map(|component| component.len())
+            .collect::<Vec<_>>();
+        sizes.sort();
+        assert_eq!(sizes, [1, 1, 2]);
+        assert_eq!(voxels.component_count(), 3);
+        assert_eq!(example().component_count(), example().components().len());
+    }
+
+    // A 5x5x5 shell with a single voxel floating in the middle of its cavity.
+    fn nested() -> VoxelSet {
+        itertools::iproduct!(0..5, 0..5, 0..5)
+            .map(|(x, y, z)| Point::new(x, y, z))
+            .filter(|point| {
+                let inside = |n: i64| (1..4).contains(&n);
+                !(inside(point.x) && inside(point.y) && inside(point.z))
+                    || *point == Point::new(2, 2, 2)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn measures_separated_components() {
+        let voxels = itertools::iproduct!(0..3, 0..3, 0..3)
+            .map(|(x, y, z)| Point::new(x, y, z))
+            .filter(|point| *point != Point::new(1, 1, 1))
+            .chain([Point::new(10, 10, 10)])
+            .collect::<VoxelSet>();
+        let stats = voxels.component_stats();
+        let measures = stats
+            .iter()
+            .map(|c| {
+                (
+                    c.volume,
+                    c.surface_area,
+                    c.external_surface_area,
+                    c.cavities,
+                    c.cavity_volume,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(measures, [(26, 60, 54, 1, 1), (1, 6, 6, 0, 0)]);
+        assert_eq!(voxels.surface_area(), 66);
+        assert_eq!(voxels.external_surface_area(), 60);
+    }
+
+    #[test]
+    fn measures_nested_components() {
+        let voxels = nested();
+        let stats = voxels.component_stats();
+        let measures = stats
+            .iter()
+            .map(|c| {
+                (
+                    c.volume,
+                    c.surface_area,
+                    c.external_surface_area,
+                    c.cavities,
+                    c.cavity_volume,
+                )
+            })
+            .collect::<Vec<_>>();
+        // The voxel in the middle cannot be reached from outside, and the shell's
+        // cavity is measured as if the voxel were not there.
+        assert_eq!(measures, [(98, 204, 150, 1, 27), (1, 6, 0, 0, 0)]);
+        assert_eq!(
+            stats.iter().map(|c| c.surface_area).sum::<u64>(),
+            voxels.surface_area()
+        );
+        assert_eq!(
+            stats.iter().map(|c| c.external_surface_area).sum::<u64>(),
+            voxels.external_surface_area()
+        );
+        assert_eq!(voxels.interior_volume(), 26);
+    }
+
+    #[test]
+    fn builds_surface_meshes() {
+        let cube: VoxelSet = "0,0,0".parse().unwrap();
+        let obj = cube.surface_mesh().to_obj();
+        assert_eq!(obj.lines().filter(|line| line.starts_with("v ")).count(), 8);
+        assert_eq!(obj.lines().filter(|line| line.starts_with("f ")).count(), 6);
+        assert_eq!(
+            cube.surface_mesh().to_stl().matches("facet normal").count(),
+            12
+        );
+
+        assert_eq!(example().surface_mesh().len(), 64);
+        assert_eq!(example().external_surface_mesh().len(), 58);
+    }
+
+    #[test]
+    fn winds_faces_counterclockwise_from_outside() {
+        let cube: VoxelSet = "0,0,0".parse().unwrap();
+        for ([a, b, _, d], normal) in cube.surface_mesh().quads {
+            assert_eq!((b - a).cross(&(d - a)), normal);
+        }
+    }
+
+    #[test]
+    fn empty_set_has_no_area() {
+        let empty = VoxelSet::default();
+        assert_eq!(empty.bounding_box(), None);
+        assert_eq!(empty.surface_area(), 0);
+        assert_eq!(empty.external_surface_area(), 0);
+        assert_eq!(empty.interior_volume(), 0);
+        assert!(empty.components().is_empty());
+        assert!(empty.component_stats().is_empty());
+    }
+}
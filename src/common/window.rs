@@ -0,0 +1,57 @@
+// Finds the first window of `length` bytes that are all different from each
+// other, returning the index just past the end of the window.
+//
+// Runs in a single pass by counting the bytes in the current window and how
+// many of those bytes appear more than once.
+pub fn find_distinct_window(bytes: &[u8], length: usize) -> Option<usize> {
+    let mut counts = [0usize; 256];
+    let mut repeated = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        if i >= length {
+            // Slide the oldest byte out of the window.
+            let oldest = &mut counts[bytes[i - length] as usize];
+            *oldest -= 1;
+            if *oldest == 1 {
+                repeated -= 1;
+            }
+        }
+        let count = &mut counts[byte as usize];
+        *count += 1;
+        if *count == 2 {
+            repeated += 1;
+        }
+        if i + 1 >= length && repeated == 0 {
+            return Some(i + 1);
+        }
+    }
+    (length == 0).then_some(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_puzzle_markers() {
+        for (buffer, packet, message) in [
+            ("mjqjpqmgbljsphdztnvjfqwrcgsmlb", 7, 19),
+            ("bvwbjplbgvbhsrlpgdmjqwftvncz", 5, 23),
+            ("nppdvjthqldpwncqszvftbrmjlhg", 6, 23),
+            ("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg", 10, 29),
+            ("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw", 11, 26),
+        ] {
+            assert_eq!(find_distinct_window(buffer.as_bytes(), 4), Some(packet));
+            assert_eq!(find_distinct_window(buffer.as_bytes(), 14), Some(message));
+        }
+    }
+
+    #[test]
+    fn handles_short_buffers() {
+        assert_eq!(find_distinct_window(b"", 0), Some(0));
+        assert_eq!(find_distinct_window(b"", 4), None);
+        assert_eq!(find_distinct_window(b"abc", 4), None);
+        assert_eq!(find_distinct_window(b"abcd", 4), Some(4));
+        assert_eq!(find_distinct_window(b"aaaa", 1), Some(1));
+        assert_eq!(find_distinct_window(b"aaaa", 2), None);
+    }
+}
@@ -1,106 +1,558 @@
-use super::*;
+use super::{registry::Registry, REGISTRATIONS};
 use crate::{
-    common::{AocError, AocResult, AocSolution, IntoAocResult, Solver},
-    program::{ProgramArgs, SolutionPart},
+    common::{
+        format_bytes, input::Input, parallel_map, progress::Tracker, timing, with_timeout,
+        AocError, AocResult, AocSolution, DayArgs, IntoAocResult, PeakMemory, SolutionPart, Solver,
+    },
+    program::{
+        config, file_hash, input_hash, input_sets, read_local_input, read_or_download_input,
+        AllArgs, BenchArgs, CacheMode, ProgramArgs, ProgressView, SolutionCache,
+    },
 };
 use std::{
+    fmt::{Display, Formatter, Result as DisplayResult},
     fs,
+    path::{Path, PathBuf},
     time::{Duration, Instant},
 };
 
-const SOLVERS: [[Solver; 2]; 25] = [
-    [Solver::Int(day01::solve_a), Solver::Int(day01::solve_b)],
-    [Solver::Int(day02::solve_a), Solver::Int(day02::solve_b)],
-    [Solver::Int(day03::solve_a), Solver::Int(day03::solve_b)],
-    [Solver::Int(day04::solve_a), Solver::Int(day04::solve_b)],
-    [Solver::Str(day05::solve_a), Solver::Str(day05::solve_b)],
-    [Solver::Int(day06::solve_a), Solver::Int(day06::solve_b)],
-    [Solver::Int(day07::solve_a), Solver::Int(day07::solve_b)],
-    [Solver::Int(day08::solve_a), Solver::Int(day08::solve_b)],
-    [Solver::Int(day09::solve_a), Solver::Int(day09::solve_b)],
-    [Solver::Int(day10::solve_a), Solver::Str(day10::solve_b)],
-    [Solver::Int(day11::solve_a), Solver::Int(day11::solve_b)],
-    [Solver::Int(day12::solve_a), Solver::Int(day12::solve_b)],
-    [Solver::Int(day13::solve_a), Solver::Int(day13::solve_b)],
-    [Solver::Int(day14::solve_a), Solver::Int(day14::solve_b)],
-    [Solver::Int(day15::solve_a), Solver::Int(day15::solve_b)],
-    [Solver::Int(day16::solve_a), Solver::Int(day16::solve_b)],
-    [Solver::Int(day17::solve_a), Solver::Int(day17::solve_b)],
-    [Solver::Int(day18::solve_a), Solver::Int(day18::solve_b)],
-    [Solver::Int(day19::solve_a), Solver::Int(day19::solve_b)],
-    [Solver::Int(day20::solve_a), Solver::Int(day20::solve_b)],
-    [Solver::Int(day21::solve_a), Solver::Int(day21::solve_b)],
-    [Solver::Int(day22::solve_a), Solver::Int(day22::solve_b)],
-    [Solver::Int(day23::solve_a), Solver::Int(day23::solve_b)],
-    [Solver::Int(day24::solve_a), Solver::Int(day24::solve_b)],
-    [Solver::Str(day25::solve_a), Solver::Str(day25::solve_b)],
-];
-
-fn get_solver(args: &ProgramArgs) -> AocResult<&Solver> {
-    if args.day() as usize > SOLVERS.len() {
-        return Err(AocError::new("day not implemented"));
-    }
-    let part_index = match args.part() {
+fn registry() -> AocResult<Registry> {
+    Registry::new(REGISTRATIONS)
+}
+
+// Every registered day with its part A and part B solvers, in day order.
+pub fn registered_days() -> AocResult<Vec<(u8, [Solver; 2])>> {
+    Ok(registry()?
+        .iter()
+        .map(|(day, solvers)| (day, solvers.clone()))
+        .collect())
+}
+
+fn get_solver(day: u8, part: SolutionPart) -> AocResult<Solver> {
+    let part_index = match part {
         SolutionPart::A => 0,
         SolutionPart::B => 1,
+        SolutionPart::Both => {
+            return Err(AocError::invalid_args(
+                "both parts cannot be run by a single solver",
+            ))
+        }
     };
-    Ok(&SOLVERS[(args.day() - 1) as usize][part_index])
+    match registry()?.get(day) {
+        None => Err(AocError::invalid_args("day not implemented")),
+        Some(solvers) => Ok(solvers[part_index].clone()),
+    }
 }
 
-pub struct Solution {
-    pub solution: AocSolution,
-    pub time: Duration,
+// How long each phase of a solution took. Reading is only measured when the
+// whole input is read from a file up front, and parsing only for solvers that mark it with
+// `timing::parse_phase`.
+#[derive(Clone, Copy, Default)]
+pub struct TimingBreakdown {
+    pub io: Option<Duration>,
+    pub parse: Option<Duration>,
+    pub solve: Duration,
 }
 
-impl Solution {
-    pub fn new(solution: AocSolution, time: Duration) -> Self {
-        Solution { solution, time }
+impl TimingBreakdown {
+    // The time taken by the solver, which includes parsing but not reading.
+    pub fn solver_time(&self) -> Duration {
+        self.parse.unwrap_or_default() + self.solve
     }
 }
 
-fn run_solver(args: &ProgramArgs, solver: &Solver) -> AocResult<Solution> {
-    let filename = match args.filename() {
-        None => format!("input/{}.txt", args.day()),
-        Some(filename) => format!("input/{}", filename),
-    };
-    let input = fs::read_to_string(filename).into_aoc_result()?;
+impl Display for TimingBreakdown {
+    fn fmt(&self, f: &mut Formatter) -> DisplayResult {
+        if let Some(io) = self.io {
+            write!(f, "read {} us, ", io.as_micros())?;
+        }
+        if let Some(parse) = self.parse {
+            write!(f, "parse {} us, ", parse.as_micros())?;
+        }
+        write!(f, "solve {} us", self.solve.as_micros())
+    }
+}
+
+// Everything known about one run of a solver, which every command produces
+// and every output format is written from.
+pub struct SolveRecord {
+    pub day: u8,
+    pub part: SolutionPart,
+    // The file that was solved instead of the day's own input, if any.
+    pub filename: Option<String>,
+    // The input set that was solved, if it was not the day's own input.
+    pub input_set: Option<String>,
+    // Solvers that failed, such as by timing out or having no input, keep their
+    // error so that one failure does not hide the rest of the results.
+    pub answer: AocResult<AocSolution>,
+    pub timings: TimingBreakdown,
+    // Only measured with the `memory-stats` feature.
+    pub peak_memory: Option<usize>,
+    // The BLAKE3 hash of the input, if it was read in full. Streamed inputs
+    // are never read all at once, so they are not hashed.
+    pub input_hash: Option<String>,
+    // Whether the answer was the expected one, if there was one to check, and
+    // what was expected.
+    pub verified: Option<bool>,
+    pub expected: Option<String>,
+    // Whether the answer was read from the cache instead of solved again, in
+    // which case the timings are from when it was solved.
+    pub cached: bool,
+}
+
+impl SolveRecord {
+    pub fn new(
+        day: u8,
+        part: SolutionPart,
+        answer: AocSolution,
+        timings: TimingBreakdown,
+        peak_memory: Option<usize>,
+    ) -> Self {
+        Self {
+            day,
+            part,
+            filename: None,
+            input_set: None,
+            answer: Ok(answer),
+            timings,
+            peak_memory,
+            input_hash: None,
+            verified: None,
+            expected: None,
+            cached: false,
+        }
+    }
+
+    pub fn failed(args: &ProgramArgs, err: AocError) -> Self {
+        Self {
+            day: args.day(),
+            part: args.part(),
+            filename: args.filename().clone(),
+            input_set: args.input_set().map(str::to_owned),
+            answer: Err(err),
+            timings: TimingBreakdown::default(),
+            peak_memory: None,
+            input_hash: None,
+            verified: None,
+            expected: None,
+            cached: false,
+        }
+    }
+
+    // The time taken by the solver, if it found an answer.
+    pub fn time(&self) -> Option<Duration> {
+        self.answer.is_ok().then(|| self.timings.solver_time())
+    }
+
+    // The time taken and, if measured, the peak heap usage.
+    pub fn usage(&self) -> String {
+        let time = self.timings.solver_time().as_micros();
+        match self.peak_memory {
+            None => format!("{} us", time),
+            Some(bytes) => format!("{} us, {} peak", time, format_bytes(bytes)),
+        }
+    }
+
+    // Checks the answer against the expected one. Failed solvers are never
+    // verified.
+    pub fn verify(&mut self, expected: Option<&str>) {
+        self.expected = expected.map(str::to_owned);
+        self.verified = match (&self.answer, expected) {
+            (Ok(answer), Some(expected)) => Some(answer.to_string() == expected),
+            _ => None,
+        };
+    }
+}
+
+pub(super) fn read_input(args: &ProgramArgs) -> AocResult<String> {
+    match args.filename() {
+        None if args.download() => read_or_download_input(args.input_dir(), args.day()),
+        None => read_local_input(args.input_dir(), args.day()),
+        Some(_) => {
+            let path = args.input_path();
+            fs::read_to_string(&path).into_aoc_result_msg(&format!("failed to read {}", path))
+        }
+    }
+}
+
+// The file holding the input, if it is there to be streamed from.
+fn input_file(args: &ProgramArgs) -> Option<PathBuf> {
+    let path = args.input_path();
+    Path::new(&path).is_file().then(|| path.into())
+}
+
+fn run_solver(args: &ProgramArgs, solver: &Solver, tracker: &Tracker) -> AocResult<SolveRecord> {
+    // Solvers that stream their input read it as part of solving, so reading
+    // is not timed on its own.
+    if let Some(path) = input_file(args).filter(|_| solver.streams_lines()) {
+        return run_solver_on_input(args, solver, &Input::File(path), tracker);
+    }
     let now = Instant::now();
-    let solution = solver.run(&input)?;
+    let input = read_input(args)?;
+    let io = now.elapsed();
+    let mut record = run_solver_on_input(args, solver, &Input::from(input.as_str()), tracker)?;
+    record.timings.io = Some(io);
+    record.input_hash = Some(input_hash(input.as_bytes()));
+    Ok(record)
+}
+
+fn run_solver_on_input(
+    args: &ProgramArgs,
+    solver: &Solver,
+    input: &Input,
+    tracker: &Tracker,
+) -> AocResult<SolveRecord> {
+    let memory = PeakMemory::start();
+    timing::take_parse_time();
+    let now = Instant::now();
+    let solution = solver
+        .run_with_progress(input, args.day_args(), tracker)
+        .map_err(|err| err.in_solver(args.day(), args.part()))?;
     let then = now.elapsed();
-    Ok(Solution::new(solution, then))
-}
-
-pub fn solve(args: &ProgramArgs) -> AocResult<Solution> {
-    run_solver(args, get_solver(args)?)
-}
-
-pub fn solve_all() -> AocResult<Duration> {
-    let mut total_time = Duration::new(0, 0);
-    for (day, solvers) in SOLVERS.iter().enumerate() {
-        for (part, solver) in solvers.iter().enumerate() {
-            let part = match part {
-                0 => SolutionPart::A,
-                1 => SolutionPart::B,
-                _ => return Err(AocError::new(&format!("unknown part: {}", part + 1))),
-            };
-            let day = day as u8 + 1;
-            let args = ProgramArgs::new(day, part, None);
-            match run_solver(&args, solver) {
-                Err(err) => {
-                    return Err(AocError::new(&format!(
-                        "Day {day} Part {part} failed: {err:?}"
-                    )))
-                }
-                Ok(result) => {
-                    println!(
-                        "{day} {part}: {} ({} us)",
-                        result.solution,
-                        result.time.as_micros()
-                    );
-                    total_time += result.time;
-                }
+    let parse = timing::take_parse_time();
+    if args.check() {
+        solver
+            .check_alternates(input, args.day_args(), &solution)
+            .map_err(|err| err.in_solver(args.day(), args.part()))?;
+    }
+    let timings = TimingBreakdown {
+        io: None,
+        parse,
+        solve: then.saturating_sub(parse.unwrap_or_default()),
+    };
+    let mut record = SolveRecord::new(args.day(), args.part(), solution, timings, memory.finish());
+    record.filename = args.filename().clone();
+    record.input_set = args.input_set().map(str::to_owned);
+    Ok(record)
+}
+
+pub fn solve(args: &ProgramArgs) -> AocResult<SolveRecord> {
+    run_solver(
+        args,
+        &get_solver(args.day(), args.part())?,
+        &Tracker::default(),
+    )
+}
+
+// Solves with the given input instead of reading it from a file.
+pub fn solve_input(args: &ProgramArgs, input: &str) -> AocResult<SolveRecord> {
+    let mut record = run_solver_on_input(
+        args,
+        &get_solver(args.day(), args.part())?,
+        &input.into(),
+        &Tracker::default(),
+    )?;
+    record.input_hash = Some(input_hash(input.as_bytes()));
+    Ok(record)
+}
+
+// Solves with the given input, without timing the solver or reading any files.
+pub fn solve_puzzle(day: u8, part: SolutionPart, input: &str) -> AocResult<AocSolution> {
+    get_solver(day, part)?
+        .run(&input.into(), &DayArgs::default())
+        .map_err(|err| err.in_solver(day, part))
+}
+
+// Solves part A and part B for the same input, which is only read once.
+pub fn solve_both(args: &ProgramArgs) -> AocResult<[SolveRecord; 2]> {
+    let now = Instant::now();
+    let input = read_input(args)?;
+    let io = now.elapsed();
+    let hash = input_hash(input.as_bytes());
+    let run_part = |part| -> AocResult<SolveRecord> {
+        let args = args.for_part(part);
+        let mut record = run_solver_on_input(
+            &args,
+            &get_solver(args.day(), args.part())?,
+            &Input::from(input.as_str()),
+            &Tracker::default(),
+        )?;
+        record.input_hash = Some(hash.clone());
+        Ok(record)
+    };
+    // The time to read the input is only counted once, with part A.
+    let mut a = run_part(SolutionPart::A)?;
+    a.timings.io = Some(io);
+    Ok([a, run_part(SolutionPart::B)?])
+}
+
+pub struct Benchmark {
+    // Which implementation was run, such as "primary" or "alternate 1".
+    pub implementation: String,
+    // The answer found, timed by the median sample.
+    pub record: SolveRecord,
+    pub samples: Vec<Duration>,
+}
+
+// Runs a solver repeatedly on the same input. The input is only read once, so
+// the samples measure nothing but the solver itself.
+//
+// With `--alternates`, every alternate implementation is run the same way after
+// the primary one, and must find the same answer.
+pub fn bench(args: &BenchArgs) -> AocResult<Vec<Benchmark>> {
+    let (day, part) = (args.program_args().day(), args.program_args().part());
+    let solver = get_solver(day, part)?;
+    let input = read_input(args.program_args())?;
+    let hash = input_hash(input.as_bytes());
+    let input = Input::from(input.as_str());
+    let day_args = args.program_args().day_args();
+    let implementations =
+        solver
+            .implementations()
+            .take(if args.alternates() { usize::MAX } else { 1 });
+    let mut benchmarks: Vec<Benchmark> = Vec::new();
+    for (i, implementation) in implementations.enumerate() {
+        let run = || {
+            implementation
+                .run(&input, day_args)
+                .map_err(|err| err.in_solver(day, part))
+        };
+        for _ in 0..args.warmup() {
+            run()?;
+        }
+        let mut solution = None;
+        let mut samples = Vec::with_capacity(args.iterations());
+        for _ in 0..args.iterations() {
+            let now = Instant::now();
+            let result = run()?;
+            samples.push(now.elapsed());
+            solution = Some(result);
+        }
+        let solution = solution.into_aoc_result_msg("no iterations were run")?;
+        if let Some(Ok(primary)) = benchmarks
+            .first()
+            .map(|primary| &primary.record.answer)
+            .filter(|primary| primary.as_ref().is_ok_and(|primary| *primary != solution))
+        {
+            return Err(AocError::no_solution(format!(
+                "alternate implementation {} found {}, but the primary implementation found {}",
+                i, solution, primary
+            ))
+            .in_solver(day, part));
+        }
+        let mut sorted = samples.clone();
+        sorted.sort();
+        let timings = TimingBreakdown {
+            solve: sorted[sorted.len() / 2],
+            ..Default::default()
+        };
+        let mut record = SolveRecord::new(day, part, solution, timings, None);
+        record.filename = args.program_args().filename().clone();
+        record.input_set = args.program_args().input_set().map(str::to_owned);
+        record.input_hash = Some(hash.clone());
+        benchmarks.push(Benchmark {
+            implementation: match i {
+                0 => "primary".to_owned(),
+                _ => format!("alternate {}", i),
+            },
+            record,
+            samples,
+        });
+    }
+    Ok(benchmarks)
+}
+
+fn all_jobs(check: bool) -> AocResult<Vec<(ProgramArgs, Solver)>> {
+    let mut jobs = Vec::new();
+    for (day, [solve_a, solve_b]) in registry()?.iter() {
+        jobs.push((
+            ProgramArgs::new(day, SolutionPart::A, None).with_check(check),
+            solve_a.clone(),
+        ));
+        jobs.push((
+            ProgramArgs::new(day, SolutionPart::B, None).with_check(check),
+            solve_b.clone(),
+        ));
+    }
+    Ok(jobs)
+}
+
+// Reuses the cached solution for the input if there is one, and otherwise
+// solves it and records the solution. Alternates are never cached, so checking
+// them always solves again. Only solvers that actually run get a progress bar.
+fn run_solver_cached(
+    args: &ProgramArgs,
+    solver: &Solver,
+    timeout: Option<Duration>,
+    cache: &SolutionCache,
+    progress: Option<&ProgressView>,
+) -> AocResult<SolveRecord> {
+    // The input is hashed once, a piece at a time, both to look up the
+    // solution and to record it under.
+    let hash_input_file = || {
+        input_file(args)
+            .filter(|_| cache.mode() != CacheMode::Off)
+            .and_then(|path| file_hash(&path).ok())
+    };
+    let hash = hash_input_file();
+    if !args.check() {
+        if let Some(hash) = &hash {
+            if let Some(mut record) = cache.get(args.day(), args.part(), hash) {
+                record.filename = args.filename().clone();
+                record.input_set = args.input_set().map(str::to_owned);
+                record.input_hash = Some(hash.clone());
+                return Ok(record);
             }
         }
     }
-    Ok(total_time)
+    let bar = progress.map(|view| view.bar(format!("day {} {}", args.day(), args.part())));
+    let tracker = bar.as_ref().map(|bar| bar.tracker()).unwrap_or_default();
+    let mut record = run_solver_with_timeout(args, solver, timeout, &tracker)?;
+    drop(bar);
+    // A missing input is downloaded while solving, so it can only be hashed
+    // afterwards.
+    if let Some(hash) = hash.or_else(hash_input_file) {
+        cache.put(args.day(), args.part(), &hash, &record)?;
+        record.input_hash = Some(hash);
+    }
+    Ok(record)
+}
+
+fn run_jobs(jobs: Vec<(ProgramArgs, Solver)>, cache: CacheMode) -> Vec<SolveRecord> {
+    let cache = SolutionCache::new(&config().cache_dir, cache);
+    jobs.into_iter()
+        .map(|(args, solver)| {
+            run_solver_cached(&args, &solver, None, &cache, None)
+                .unwrap_or_else(|err| SolveRecord::failed(&args, err))
+        })
+        .collect()
+}
+
+pub fn run_all_solvers(cache: CacheMode) -> AocResult<Vec<SolveRecord>> {
+    Ok(run_jobs(all_jobs(false)?, cache))
+}
+
+// Solves every input set, or only the one named, for each day that has an
+// input in it. Days without one are skipped rather than failed, since most
+// sets will not cover every day.
+pub fn run_input_sets(cache: CacheMode, only: Option<&str>) -> AocResult<Vec<SolveRecord>> {
+    let input_dir = &config().input_dir;
+    let mut jobs = all_jobs(false)?
+        .into_iter()
+        .flat_map(|(args, solver)| {
+            input_sets(input_dir, args.day())
+                .into_iter()
+                .filter(|input_set| only.is_none_or(|only| only == input_set))
+                .map(|input_set| {
+                    (
+                        args.for_part(args.part()).with_input_set(&input_set),
+                        solver.clone(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    // Grouped by input set, each in day and part order.
+    jobs.sort_by(|(a, _), (b, _)| a.input_set().cmp(&b.input_set()));
+    Ok(run_jobs(jobs, cache))
+}
+
+pub struct AllSolutions {
+    // One record for every solver, in day and part order, including the ones
+    // that failed.
+    pub records: Vec<SolveRecord>,
+    pub total_time: Duration,
+    pub wall_time: Duration,
+}
+
+impl AllSolutions {
+    // How many solvers failed.
+    pub fn failures(&self) -> usize {
+        self.records
+            .iter()
+            .filter(|record| record.answer.is_err())
+            .count()
+    }
+}
+
+// Runs a solver on a worker thread if there is a timeout, giving up on it once
+// the timeout passes.
+fn run_solver_with_timeout(
+    args: &ProgramArgs,
+    solver: &Solver,
+    timeout: Option<Duration>,
+    tracker: &Tracker,
+) -> AocResult<SolveRecord> {
+    let timeout = match timeout {
+        None => return run_solver(args, solver, tracker),
+        Some(timeout) => timeout,
+    };
+    let tracker = tracker.clone().cancellable();
+    let (worker_args, worker_solver, worker_tracker) =
+        (args.for_part(args.part()), solver.clone(), tracker.clone());
+    with_timeout(timeout, move || {
+        run_solver(&worker_args, &worker_solver, &worker_tracker)
+    })
+    .unwrap_or_else(|| {
+        // Solvers that check for it stop soon after, rather than running on.
+        tracker.cancel();
+        Err(AocError::timeout(timeout))
+    })
+}
+
+// Counts a job as done in the progress view, if there is one, recording the
+// error if it failed.
+fn finish_job(
+    progress: Option<&ProgressView>,
+    args: &ProgramArgs,
+    result: AocResult<SolveRecord>,
+) -> SolveRecord {
+    if let Some(view) = progress {
+        view.finish_job();
+    }
+    result.unwrap_or_else(|err| SolveRecord::failed(args, err))
+}
+
+fn solve_all_sequential(
+    jobs: Vec<(ProgramArgs, Solver)>,
+    timeout: Option<Duration>,
+    cache: &SolutionCache,
+    progress: Option<&ProgressView>,
+) -> Vec<SolveRecord> {
+    jobs.into_iter()
+        .map(|(args, solver)| {
+            let result = run_solver_cached(&args, &solver, timeout, cache, progress);
+            finish_job(progress, &args, result)
+        })
+        .collect()
+}
+
+fn solve_all_parallel(
+    jobs: Vec<(ProgramArgs, Solver)>,
+    timeout: Option<Duration>,
+    cache: &SolutionCache,
+    progress: Option<&ProgressView>,
+) -> Vec<SolveRecord> {
+    // Results stay in day/part order, regardless of which thread finished
+    // first.
+    parallel_map(&jobs, |(args, solver)| {
+        finish_job(
+            progress,
+            args,
+            run_solver_cached(args, solver, timeout, cache, progress),
+        )
+    })
+}
+
+pub fn solve_all(args: &AllArgs) -> AocResult<AllSolutions> {
+    let jobs = all_jobs(args.check())?;
+    let cache = SolutionCache::new(&config().cache_dir, args.cache());
+    let view = args.progress().then(|| ProgressView::new(jobs.len()));
+    let now = Instant::now();
+    let run = || {
+        if args.parallel() {
+            solve_all_parallel(jobs, args.timeout(), &cache, view.as_ref())
+        } else {
+            solve_all_sequential(jobs, args.timeout(), &cache, view.as_ref())
+        }
+    };
+    let records = match &view {
+        Some(view) => view.show(run),
+        None => run(),
+    };
+    Ok(AllSolutions {
+        total_time: records.iter().filter_map(SolveRecord::time).sum(),
+        wall_time: now.elapsed(),
+        records,
+    })
 }
@@ -1,32 +1,41 @@
-use crate::common::{AocResult, IntoAocResult, NewlineBlocks};
+use crate::common::{input::InputLines, lines::map_lines, timing, AocResult, IntoAocResult};
 use itertools::Itertools;
 
-fn read_groups(input: &str) -> AocResult<Vec<Vec<u64>>> {
-    input
-        .newline_blocks(2)
-        .map(|lines| {
-            lines
-                .lines()
-                .map(|line| line.parse::<u64>())
-                .collect::<Result<Vec<u64>, _>>()
-        })
-        .collect::<Result<Vec<Vec<u64>>, _>>()
-        .into_aoc_result()
+// The total of each group of numbers, where groups are separated by blank lines.
+fn read_group_totals(lines: InputLines) -> AocResult<Vec<u64>> {
+    let mut totals = Vec::new();
+    let mut total = 0;
+    let calories = map_lines(lines, |line| {
+        (!line.is_empty())
+            .then(|| line.parse::<u64>().into_aoc_result_msg("invalid calories"))
+            .transpose()
+    });
+    for item in calories {
+        match item? {
+            None => {
+                totals.push(total);
+                total = 0;
+            }
+            Some(calories) => total += calories,
+        }
+    }
+    totals.push(total);
+    Ok(totals)
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    read_groups(input)?
+pub fn solve_a(lines: InputLines) -> AocResult<u64> {
+    timing::parse_phase(|| read_group_totals(lines))?
         .into_iter()
-        .map(|group| group.into_iter().sum())
         .max()
         .into_aoc_result()
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    Ok(read_groups(input)?
+pub fn solve_b(lines: InputLines) -> AocResult<u64> {
+    Ok(timing::parse_phase(|| read_group_totals(lines))?
         .into_iter()
-        .map(|group| group.into_iter().sum::<u64>())
         .sorted_by(|a, b| b.cmp(a))
         .take(3)
         .sum())
 }
+
+register_day!(1, lines solve_a, lines solve_b);
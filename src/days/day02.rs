@@ -1,6 +1,8 @@
-use crate::common::{AocError, AocResult, IntoAocResult};
+use crate::common::{
+    input::InputLines, lines::map_lines, timing, AocError, AocResult, IntoAocResult,
+};
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 enum Outcome {
     Lose,
     Draw,
@@ -14,151 +16,223 @@ impl TryFrom<char> for Outcome {
             'X' => Ok(Outcome::Lose),
             'Y' => Ok(Outcome::Draw),
             'Z' => Ok(Outcome::Win),
-            _ => Err(AocError::new("invalid outcome char")),
+            _ => Err(AocError::parse(format!("invalid outcome {ch}"))),
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-#[repr(u8)]
-enum Hand {
-    Rock,
-    Paper,
-    Scissors,
+// A hand, as its position in the game's list of hands.
+type Hand = usize;
+
+// A game like rock-paper-scissors, where both players throw a hand and every
+// pair of different hands has a winner.
+struct Game {
+    // The hands that each hand beats, as bitmasks.
+    beats: Vec<u64>,
+    hand_scores: Vec<u64>,
+    // The score for losing, drawing, and winning.
+    outcome_scores: [u64; 3],
+    // The symbols for each hand in the strategy guide, for the opponent and
+    // for you.
+    opponent_symbols: &'static str,
+    your_symbols: &'static str,
 }
 
-impl Hand {
-    pub fn try_from_opponent(ch: char) -> AocResult<Self> {
-        match ch {
-            'A' => Ok(Self::Rock),
-            'B' => Ok(Self::Paper),
-            'C' => Ok(Self::Scissors),
-            _ => Err(AocError::new("invalid opponent char")),
+impl Game {
+    pub fn new(
+        beats: &[&[Hand]],
+        hand_scores: Vec<u64>,
+        outcome_scores: [u64; 3],
+        opponent_symbols: &'static str,
+        your_symbols: &'static str,
+    ) -> AocResult<Self> {
+        let hands = beats.len();
+        if hands > 64
+            || hand_scores.len() != hands
+            || opponent_symbols.chars().count() != hands
+            || your_symbols.chars().count() != hands
+        {
+            return Err(AocError::invalid_args(
+                "every hand needs a score and symbols, and there can be at most 64 hands",
+            ));
         }
-    }
-
-    pub fn try_from_yours(ch: char) -> AocResult<Self> {
-        match ch {
-            'X' => Ok(Self::Rock),
-            'Y' => Ok(Self::Paper),
-            'Z' => Ok(Self::Scissors),
-            _ => Err(AocError::new("invalid yours char")),
+        let beats = beats
+            .iter()
+            .map(|beaten| {
+                beaten.iter().try_fold(0, |mask, &hand| {
+                    if hand < hands {
+                        Ok(mask | (1 << hand))
+                    } else {
+                        Err(AocError::invalid_args(format!("unknown hand {hand}")))
+                    }
+                })
+            })
+            .collect::<AocResult<Vec<u64>>>()?;
+        for a in 0..hands {
+            for b in 0..hands {
+                let (a_wins, b_wins) = (beats[a] & (1 << b) != 0, beats[b] & (1 << a) != 0);
+                if (a == b && a_wins) || (a != b && a_wins == b_wins) {
+                    return Err(AocError::invalid_args(format!(
+                        "exactly one of hands {a} and {b} must beat the other"
+                    )));
+                }
+            }
         }
+        Ok(Self {
+            beats,
+            hand_scores,
+            outcome_scores,
+            opponent_symbols,
+            your_symbols,
+        })
     }
 
-    pub fn beats(&self) -> Self {
-        match self {
-            Self::Rock => Self::Scissors,
-            Self::Paper => Self::Rock,
-            Self::Scissors => Self::Paper,
-        }
+    pub fn rock_paper_scissors() -> Self {
+        const ROCK: Hand = 0;
+        const PAPER: Hand = 1;
+        const SCISSORS: Hand = 2;
+        Self::new(
+            &[&[SCISSORS], &[ROCK], &[PAPER]],
+            vec![1, 2, 3],
+            [0, 3, 6],
+            "ABC",
+            "XYZ",
+        )
+        .unwrap()
     }
 
-    pub fn loses_to(&self) -> Self {
-        match self {
-            Self::Rock => Self::Paper,
-            Self::Paper => Self::Scissors,
-            Self::Scissors => Self::Rock,
-        }
+    fn hand(symbols: &str, ch: char) -> AocResult<Hand> {
+        symbols
+            .chars()
+            .position(|symbol| symbol == ch)
+            .into_aoc_result_msg(&format!("invalid hand {ch}"))
     }
 
-    pub fn outcome_against(&self, other: &Self) -> Outcome {
-        let (self_beats, other_beats) = (self.beats(), other.beats());
-        if self_beats == *other {
-            Outcome::Win
-        } else if other_beats == *self {
-            Outcome::Lose
-        } else {
-            Outcome::Draw
-        }
+    pub fn opponent_hand(&self, ch: char) -> AocResult<Hand> {
+        Self::hand(self.opponent_symbols, ch)
     }
 
-    pub fn needed_for_outcome(&self, outcome: &Outcome) -> Self {
-        match outcome {
-            Outcome::Lose => self.beats(),
-            Outcome::Draw => *self,
-            Outcome::Win => self.loses_to(),
-        }
+    pub fn your_hand(&self, ch: char) -> AocResult<Hand> {
+        Self::hand(self.your_symbols, ch)
     }
-}
 
-trait Scored {
-    fn score(&self) -> u64;
-}
-
-impl Scored for Hand {
-    fn score(&self) -> u64 {
-        match self {
-            Self::Rock => 1,
-            Self::Paper => 2,
-            Self::Scissors => 3,
+    pub fn outcome(&self, yours: Hand, theirs: Hand) -> Outcome {
+        if yours == theirs {
+            Outcome::Draw
+        } else if self.beats[yours] & (1 << theirs) != 0 {
+            Outcome::Win
+        } else {
+            Outcome::Lose
         }
     }
-}
 
-impl Scored for Outcome {
-    fn score(&self) -> u64 {
-        match self {
-            Self::Lose => 0,
-            Self::Draw => 3,
-            Self::Win => 6,
+    // The first hand that gets the outcome against the opponent's hand.
+    pub fn needed_for_outcome(&self, theirs: Hand, outcome: Outcome) -> Hand {
+        match outcome {
+            Outcome::Draw => theirs,
+            Outcome::Lose => self.beats[theirs].trailing_zeros() as Hand,
+            Outcome::Win => self
+                .beats
+                .iter()
+                .position(|beaten| beaten & (1 << theirs) != 0)
+                .unwrap(),
         }
     }
-}
-
-impl Scored for (Hand, Hand) {
-    fn score(&self) -> u64 {
-        self.1.score() + self.1.outcome_against(&self.0).score()
-    }
-}
 
-impl Scored for (Hand, Outcome) {
-    fn score(&self) -> u64 {
-        self.1.score() + self.0.needed_for_outcome(&self.1).score()
+    pub fn score(&self, yours: Hand, theirs: Hand) -> u64 {
+        self.hand_scores[yours] + self.outcome_scores[self.outcome(yours, theirs) as usize]
     }
 }
 
-fn line_to_hands(line: &str) -> AocResult<(Hand, Hand)> {
+// Splits a round of the strategy guide into its two columns.
+fn read_round(line: &str) -> AocResult<(char, char)> {
     let mut chars = line.chars();
     let lhs = chars
         .next()
         .into_aoc_result_msg("missing first character")?;
     if chars.next() != Some(' ') {
-        return Err(AocError::new("expected space after first character"));
+        return Err(AocError::parse("expected space after first character"));
     }
     let rhs = chars
         .next()
         .into_aoc_result_msg("missing character after space")?;
-    Ok((Hand::try_from_opponent(lhs)?, Hand::try_from_yours(rhs)?))
+    Ok((lhs, rhs))
 }
 
-fn line_to_outcome(line: &str) -> AocResult<(Hand, Outcome)> {
-    let mut chars = line.chars();
-    let opponent = chars
-        .next()
-        .into_aoc_result_msg("missing first character")?;
-    if chars.next() != Some(' ') {
-        return Err(AocError::new("expected space after first character"))?;
-    }
-    let outcome = chars
-        .next()
-        .into_aoc_result_msg("missing character after space")?;
-    Ok((
-        Hand::try_from_opponent(opponent)?,
-        Outcome::try_from(outcome)?,
-    ))
+// Scores a strategy guide where the second column is the hand to throw.
+fn score_hands(game: &Game, lines: InputLines) -> AocResult<u64> {
+    map_lines(lines, |line| {
+        let (theirs, yours) = timing::parse_phase(|| read_round(line))?;
+        Ok(game.score(game.your_hand(yours)?, game.opponent_hand(theirs)?))
+    })
+    .sum()
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    input
-        .lines()
-        .map(|line| line_to_hands(line).and_then(|round| Ok(round.score())))
-        .sum()
+// Scores a strategy guide where the second column is how the round must end.
+fn score_outcomes(game: &Game, lines: InputLines) -> AocResult<u64> {
+    map_lines(lines, |line| {
+        let (theirs, outcome) = timing::parse_phase(|| read_round(line))?;
+        let theirs = game.opponent_hand(theirs)?;
+        let yours = game.needed_for_outcome(theirs, Outcome::try_from(outcome)?);
+        Ok(game.score(yours, theirs))
+    })
+    .sum()
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    input
-        .lines()
-        .map(|line| line_to_outcome(line).and_then(|round| Ok(round.score())))
-        .sum()
+pub fn solve_a(lines: InputLines) -> AocResult<u64> {
+    score_hands(&Game::rock_paper_scissors(), lines)
+}
+
+pub fn solve_b(lines: InputLines) -> AocResult<u64> {
+    score_outcomes(&Game::rock_paper_scissors(), lines)
+}
+
+register_day!(2, lines solve_a, lines solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Rock, paper, scissors, lizard, and Spock.
+    fn lizard_spock() -> Game {
+        Game::new(
+            &[&[2, 3], &[0, 4], &[1, 3], &[1, 4], &[0, 2]],
+            vec![1, 2, 3, 4, 5],
+            [0, 3, 6],
+            "ABCDE",
+            "VWXYZ",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn solves_example() {
+        let input = include_str!("../../examples/2.txt");
+        assert_eq!(solve_a(input.into()).unwrap(), 15);
+        assert_eq!(solve_b(input.into()).unwrap(), 12);
+    }
+
+    #[test]
+    fn plays_other_rule_sets() {
+        let game = lizard_spock();
+        // Lizard poisons Spock, and Spock vaporizes rock.
+        assert_eq!(
+            score_hands(&game, "E Y\nA Z".into()).unwrap(),
+            4 + 6 + 5 + 6
+        );
+        // Losing to lizard takes paper, and beating it takes rock.
+        assert_eq!(game.needed_for_outcome(3, Outcome::Win), 0);
+        assert_eq!(game.needed_for_outcome(3, Outcome::Lose), 1);
+        assert_eq!(score_outcomes(&game, "D Z\nD X".into()).unwrap(), 7 + 2);
+    }
+
+    #[test]
+    fn rejects_games_without_a_winner_for_every_pair() {
+        let game = |beats: &[&[Hand]]| Game::new(beats, vec![1, 2, 3], [0, 3, 6], "ABC", "XYZ");
+        assert!(game(&[&[2], &[0], &[1]]).is_ok());
+        assert!(game(&[&[1, 2], &[0], &[1]]).is_err());
+        assert!(game(&[&[2], &[], &[1]]).is_err());
+        assert!(game(&[&[0, 2], &[0, 1], &[1]]).is_err());
+        assert!(game(&[&[2, 3], &[0], &[1]]).is_err());
+    }
 }
@@ -1,72 +1,88 @@
-use std::collections::HashSet;
-
-use crate::common::{AocError, AocResult, IntoAocResult};
+use crate::common::{
+    bits::{multi_intersection, ones},
+    input::InputLines,
+    lines::{at_line, map_lines},
+    timing, AocError, AocResult,
+};
 use itertools::Itertools;
 
-fn priority(letter: u8) -> AocResult<u64> {
+fn priority(letter: u8) -> AocResult<u32> {
     match letter as char {
-        'a'..='z' => Ok((letter - ('a' as u8) + 1).into()),
-        'A'..='Z' => Ok((letter - ('A' as u8) + 27).into()),
-        _ => Err(AocError::new("unknown item code")),
+        'a'..='z' => Ok((letter - b'a' + 1).into()),
+        'A'..='Z' => Ok((letter - b'A' + 27).into()),
+        _ => Err(AocError::parse(format!(
+            "unknown item code {}",
+            letter as char
+        ))),
     }
 }
 
-fn read_compartments(input: &str) -> Vec<(&str, &str)> {
-    input
-        .lines()
-        .map(|line| line.split_at(line.len() / 2))
-        .collect()
+fn item(priority: u32) -> char {
+    match priority {
+        1..=26 => (b'a' + priority as u8 - 1) as char,
+        _ => (b'A' + priority as u8 - 27) as char,
+    }
 }
 
-fn read_grouped_rupsacks(input: &str) -> Vec<(&str, &str, &str)> {
-    input.lines().tuples().collect()
+// The set of items, with one bit for each priority.
+fn items(items: &str) -> AocResult<u64> {
+    items
+        .bytes()
+        .map(priority)
+        .fold_ok(0, |items, priority| items | (1 << priority))
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    read_compartments(input)
-        .into_iter()
-        .map(|(first, second)| {
-            let set: HashSet<u8> = first.bytes().collect();
-            second
-                .bytes()
-                .find(|c| set.contains(c))
-                .into_aoc_result_msg("no common item")
-        })
-        .map(|c| priority(c?))
-        .sum()
+// The priority of the one item in a set.
+fn single_item(items: u64) -> AocResult<u64> {
+    match items.count_ones() {
+        1 => Ok(items.trailing_zeros().into()),
+        0 => Err(AocError::parse("no common item")),
+        _ => Err(AocError::parse(format!(
+            "more than one common item: {}",
+            ones(items).map(item).join(", ")
+        ))),
+    }
 }
 
-fn multi_intersection(sets: impl IntoIterator<Item = HashSet<u8>>) -> HashSet<u8> {
-    let mut sets_iter = sets.into_iter();
-    let intersection = sets_iter.next();
-    match intersection {
-        None => HashSet::new(),
-        Some(mut intersection) => {
-            for set in sets_iter {
-                intersection.retain(|v| set.contains(v));
-            }
-            intersection
-        }
-    }
+pub fn solve_a(lines: InputLines) -> AocResult<u64> {
+    map_lines(lines, |line| {
+        let (first, second) = line.split_at(line.len() / 2);
+        single_item(timing::parse_phase(|| items(first))? & timing::parse_phase(|| items(second))?)
+    })
+    .sum()
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    read_grouped_rupsacks(input)
-        .into_iter()
-        .map(|(a, b, c)| {
-            let common = multi_intersection([
-                a.bytes().collect(),
-                b.bytes().collect(),
-                c.bytes().collect(),
-            ]);
-            match common.len() {
-                1 => Ok(common.into_iter().next().unwrap()),
-                _ => Err(AocError::new(format!(
-                    "intersection does not have a single item, contains {}",
-                    common.into_iter().map(|v| v.to_string()).join(", ")
-                ))),
-            }
+pub fn solve_b(lines: InputLines) -> AocResult<u64> {
+    // Errors for a group are reported on its first line.
+    map_lines(lines, |line| timing::parse_phase(|| items(line)))
+        .tuples()
+        .enumerate()
+        .map(|(group, (a, b, c))| {
+            single_item(multi_intersection([a?, b?, c?])).map_err(|err| at_line(group * 3 + 1, err))
         })
-        .map(|c| priority(c?))
         .sum()
 }
+
+register_day!(3, lines solve_a, lines solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_example() {
+        let input = include_str!("../../examples/3.txt");
+        assert_eq!(solve_a(input.into()).unwrap(), 157);
+        assert_eq!(solve_b(input.into()).unwrap(), 70);
+    }
+
+    #[test]
+    fn reports_groups_without_a_single_item() {
+        let err = solve_b("abc\nabd\nabe\nx\ny\nz".into()).unwrap_err();
+        assert_eq!(err.message(), "line 1: more than one common item: a, b");
+        let err = solve_b("ab\nac\nad\nx\ny\nz".into()).unwrap_err();
+        assert_eq!(err.message(), "line 4: no common item");
+        let err = solve_a("aa\nb!".into()).unwrap_err();
+        assert_eq!(err.message(), "line 2: unknown item code !");
+    }
+}
@@ -1,60 +1,46 @@
-use std::str::FromStr;
-
-use crate::common::{AocError, AocResult, IntoAocResult};
-
-struct Range {
-    pub min: u64,
-    pub max: u64,
+use crate::common::{
+    input::InputLines, interval::Interval, lines::map_lines, timing, AocResult, IntoAocResult,
+};
+use itertools::Itertools;
+
+fn parse_interval(s: &str) -> AocResult<Interval<u64>> {
+    let (first, second) = s
+        .split_once('-')
+        .into_aoc_result_msg("invalid range, no hyphen")?;
+    Ok(Interval::new(
+        first
+            .parse::<u64>()
+            .into_aoc_result_msg("invalid minimum")?,
+        second
+            .parse::<u64>()
+            .into_aoc_result_msg("invalid maximum")?,
+    ))
 }
 
-impl Range {
-    pub fn fully_contains(&self, other: &Range) -> bool {
-        self.min <= other.min && self.max >= other.max
-    }
-
-    pub fn overlaps(&self, other: &Range) -> bool {
-        self.min <= other.max && other.min <= self.max
-    }
+fn read_assignment(line: &str) -> AocResult<(Interval<u64>, Interval<u64>)> {
+    let (first, second) = line.split_once(',').into_aoc_result_msg("no comma")?;
+    Ok((parse_interval(first)?, parse_interval(second)?))
 }
 
-impl FromStr for Range {
-    type Err = AocError;
-    fn from_str(s: &str) -> AocResult<Self> {
-        let (first, second) = s
-            .split_once('-')
-            .into_aoc_result_msg("invalid range, no hyphen")?;
-        Ok(Range {
-            min: first
-                .parse::<u64>()
-                .into_aoc_result_msg("invalid minimum")?,
-            max: second
-                .parse::<u64>()
-                .into_aoc_result_msg("invalid maximum")?,
-        })
-    }
+// Counts the pairs of assignments that match, one line at a time. Parsing
+// each line is timed on its own and adds up to the parse time.
+fn count_assignments(
+    lines: InputLines,
+    matches: impl Fn(&Interval<u64>, &Interval<u64>) -> bool,
+) -> AocResult<u64> {
+    map_lines(lines, |line| timing::parse_phase(|| read_assignment(line)))
+        .filter_ok(|(first, second)| matches(first, second))
+        .fold_ok(0, |count, _| count + 1)
 }
 
-fn read_assignments(input: &str) -> AocResult<Vec<(Range, Range)>> {
-    input
-        .lines()
-        .map(|line| {
-            line.split_once(',')
-                .into_aoc_result_msg("no comma")
-                .and_then(|(first, second)| Ok((Range::from_str(first)?, Range::from_str(second)?)))
-        })
-        .collect()
+pub fn solve_a(lines: InputLines) -> AocResult<u64> {
+    count_assignments(lines, |first, second| {
+        first.fully_contains(second) || second.fully_contains(first)
+    })
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    Ok(read_assignments(input)?
-        .into_iter()
-        .filter(|(first, second)| first.fully_contains(second) || second.fully_contains(first))
-        .count() as u64)
+pub fn solve_b(lines: InputLines) -> AocResult<u64> {
+    count_assignments(lines, |first, second| first.overlaps(second))
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    Ok(read_assignments(input)?
-        .into_iter()
-        .filter(|(first, second)| first.overlaps(second))
-        .count() as u64)
-}
+register_day!(4, lines solve_a, lines solve_b);
@@ -1,9 +1,11 @@
-use std::{cmp::max, str::FromStr};
+use std::{cmp::max, iter, str::FromStr};
 
-use crate::common::{AocError, AocResult, IntoAocResult, NewlineBlocks};
+use crate::common::{
+    timing, viz::Visualize, AocError, AocResult, IntoAocResult, NewlineBlocks, SolutionPart,
+};
 use itertools::Itertools;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Stack {
     pub crates: Vec<char>,
 }
@@ -41,7 +43,7 @@ fn read_stacks(input: &str) -> AocResult<Vec<Stack>> {
     for line in lines {
         for (stack, mut chunk) in stacks.iter_mut().zip(&line.chars().chunks(4)) {
             match chunk.nth(1) {
-                None => return Err(AocError::new("missing block id")),
+                None => return Err(AocError::parse("missing block id")),
                 Some(' ') => (),
                 Some(c) => stack.push(c),
             }
@@ -82,19 +84,62 @@ impl FromStr for Move {
     }
 }
 
-trait CanMakeMove {
-    fn make_move(&mut self, m: Move) -> AocResult<()>;
+// The models of crane on the ship. They differ only in how a single move
+// carries blocks between stacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CraneModel {
+    // Moves one block at a time.
+    CraneMover9000,
+    // Moves blocks one at a time too, but reverses the whole batch at once.
+    CraneMover9000v2,
+    // Moves all blocks at once, keeping their order.
+    CraneMover9001,
 }
 
+impl CraneModel {
+    fn carry(self, m: &Move, from: &mut Stack, to: &mut Stack) -> AocResult<()> {
+        match self {
+            Self::CraneMover9000 => {
+                for i in 1..=m.number_of_blocks {
+                    to.push(from.pop().into_aoc_result_msg(&format!(
+                        "from stack does not have a block to move for move {}",
+                        i
+                    ))?);
+                }
+            }
+            Self::CraneMover9000v2 | Self::CraneMover9001 => {
+                if from.crates.len() < m.number_of_blocks {
+                    return Err(AocError::no_solution(
+                        "from stack does not enough blocks to move",
+                    ));
+                }
+                let mut moved = from
+                    .crates
+                    .split_off(from.crates.len() - m.number_of_blocks);
+                if self == Self::CraneMover9000v2 {
+                    moved.reverse();
+                }
+                to.crates.append(&mut moved);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
 struct CraneMover {
     stacks: Vec<Stack>,
 }
 
 impl CraneMover {
+    pub fn new(stacks: Vec<Stack>) -> Self {
+        Self { stacks }
+    }
+
     fn get_stacks(&mut self, m: &Move) -> AocResult<(&mut Stack, &mut Stack)> {
         let max = max(m.to, m.from);
         if self.stacks.len() < max {
-            return Err(AocError::new(&format!(
+            return Err(AocError::parse(format!(
                 "index {max} overflows number of stacks ({})",
                 self.stacks.len()
             )));
@@ -107,66 +152,42 @@ impl CraneMover {
         }
     }
 
-    pub fn top_crates(&self) -> String {
-        self.stacks
-            .iter()
-            .filter_map(|stack| {
-                stack
-                    .top()
-                    .and_then(|c| Some(c.clone()))
-                    .filter(|c| *c != ' ')
-            })
-            .collect()
+    fn make_move(&mut self, m: &Move, model: CraneModel) -> AocResult<()> {
+        let (from, to) = self.get_stacks(m)?;
+        model.carry(m, from, to)
     }
-}
-
-struct CraneMover9000(pub CraneMover);
 
-impl CraneMover9000 {
-    pub fn new(stacks: Vec<Stack>) -> Self {
-        Self(CraneMover { stacks })
+    pub fn execute(&mut self, moves: &[Move], model: CraneModel) -> AocResult<()> {
+        moves.iter().try_for_each(|m| self.make_move(m, model))
     }
-}
 
-impl CanMakeMove for CraneMover9000 {
-    fn make_move(&mut self, m: Move) -> AocResult<()> {
-        let (from, to) = self.0.get_stacks(&m)?;
-        for i in 1..=m.number_of_blocks {
-            to.push(from.pop().into_aoc_result_msg(&format!(
-                "from stack does not have a block to move for move {}",
-                i
-            ))?);
-        }
-        Ok(())
+    // Makes each move in turn, yielding a copy of the stacks after each one.
+    pub fn into_states(
+        mut self,
+        moves: &[Move],
+        model: CraneModel,
+    ) -> impl Iterator<Item = AocResult<Vec<Stack>>> + '_ {
+        moves.iter().map(move |m| {
+            self.make_move(m, model)?;
+            Ok(self.stacks.clone())
+        })
     }
-}
-
-struct CraneMover9000v2(pub CraneMover);
 
-impl CraneMover9000v2 {
-    #[allow(dead_code)]
-    pub fn new(stacks: Vec<Stack>) -> Self {
-        Self(CraneMover { stacks })
+    pub fn stacks(&self) -> &[Stack] {
+        &self.stacks
     }
-}
 
-impl CanMakeMove for CraneMover9000v2 {
-    fn make_move(&mut self, m: Move) -> AocResult<()> {
-        let (from, to) = self.0.get_stacks(&m)?;
-        if from.crates.len() < m.number_of_blocks {
-            return Err(AocError::new("from stack does not enough blocks to move"));
-        }
-        let moved = from
-            .crates
-            .split_off(from.crates.len() - m.number_of_blocks);
-        to.crates.extend(moved.into_iter().rev());
-        Ok(())
+    pub fn top_crates(&self) -> String {
+        self.stacks
+            .iter()
+            .filter_map(|stack| stack.top().copied().filter(|c| *c != ' '))
+            .collect()
     }
 }
 
-pub fn solve_a(input: &str) -> AocResult<String> {
+fn read_input(input: &str) -> AocResult<(CraneMover, Vec<Move>)> {
     let mut blocks = input.newline_blocks(2);
-    let mut mover = CraneMover9000::new(read_stacks(
+    let mover = CraneMover::new(read_stacks(
         blocks
             .next()
             .into_aoc_result_msg("input is missing initial configuration")?,
@@ -175,55 +196,140 @@ pub fn solve_a(input: &str) -> AocResult<String> {
         .next()
         .into_aoc_result_msg("input is missing moves")?
         .lines()
-        .map(|line| Move::from_str(line))
+        .map(Move::from_str)
         .collect::<AocResult<Vec<Move>>>()?;
+    Ok((mover, moves))
+}
 
-    for m in moves {
-        mover.make_move(m)?;
-    }
+fn solve(input: &str, model: CraneModel) -> AocResult<String> {
+    let (mut mover, moves) = timing::parse_phase(|| read_input(input))?;
+    mover.execute(&moves, model)?;
+    Ok(mover.top_crates())
+}
 
-    Ok(mover.0.top_crates())
+pub fn solve_a(input: &str) -> AocResult<String> {
+    solve(input, CraneModel::CraneMover9000)
 }
 
-struct CraneMover9001(pub CraneMover);
+// Alternate implementation that moves each batch of blocks at once, checked
+// against the one-at-a-time moves with `--check`.
+pub fn solve_a_in_batches(input: &str) -> AocResult<String> {
+    solve(input, CraneModel::CraneMover9000v2)
+}
 
-impl CraneMover9001 {
-    pub fn new(stacks: Vec<Stack>) -> Self {
-        Self(CraneMover { stacks })
+pub fn solve_b(input: &str) -> AocResult<String> {
+    solve(input, CraneModel::CraneMover9001)
+}
+
+// Draws the stacks the same way as the puzzle input.
+fn render_stacks(stacks: &[Stack]) -> String {
+    let height = stacks
+        .iter()
+        .map(|stack| stack.crates.len())
+        .max()
+        .unwrap_or(0);
+    let mut frame = String::new();
+    for level in (0..height).rev() {
+        let row = stacks
+            .iter()
+            .map(|stack| match stack.crates.get(level) {
+                Some(c) => format!("[{c}]"),
+                None => "   ".to_owned(),
+            })
+            .join(" ");
+        frame += row.trim_end();
+        frame.push('\n');
     }
+    frame += &(1..=stacks.len()).map(|i| format!(" {i} ")).join(" ");
+    frame.push('\n');
+    frame
 }
 
-impl CanMakeMove for CraneMover9001 {
-    fn make_move(&mut self, m: Move) -> AocResult<()> {
-        let (from, to) = self.0.get_stacks(&m)?;
-        if from.crates.len() < m.number_of_blocks {
-            return Err(AocError::new("from stack does not enough blocks to move"));
-        }
-        let mut moved = from
-            .crates
-            .split_off(from.crates.len() - m.number_of_blocks);
-        to.crates.append(&mut moved);
-        Ok(())
+pub struct CratesViz {
+    mover: CraneMover,
+    moves: Vec<Move>,
+    model: CraneModel,
+}
+
+impl Visualize for CratesViz {
+    fn frames(&self) -> impl Iterator<Item = String> {
+        let start = render_stacks(self.mover.stacks());
+        // A failed move ends the animation where the stacks got stuck.
+        iter::once(start).chain(
+            self.mover
+                .clone()
+                .into_states(&self.moves, self.model)
+                .map_while(Result::ok)
+                .map(|stacks| render_stacks(&stacks)),
+        )
     }
 }
 
-pub fn solve_b(input: &str) -> AocResult<String> {
-    let mut blocks = input.newline_blocks(2);
-    let mut mover = CraneMover9001::new(read_stacks(
-        blocks
-            .next()
-            .into_aoc_result_msg("input is missing initial configuration")?,
-    )?);
-    let moves = blocks
-        .next()
-        .into_aoc_result_msg("input is missing moves")?
-        .lines()
-        .map(|line| Move::from_str(line))
-        .collect::<AocResult<Vec<Move>>>()?;
+pub fn visualize(input: &str, part: SolutionPart) -> AocResult<impl Visualize> {
+    let (mover, moves) = read_input(input)?;
+    let model = match part {
+        SolutionPart::A => CraneModel::CraneMover9000,
+        _ => CraneModel::CraneMover9001,
+    };
+    Ok(CratesViz {
+        mover,
+        moves,
+        model,
+    })
+}
+
+register_day!(5, solve_a | solve_a_in_batches, solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for m in moves {
-        mover.make_move(m)?;
+    const EXAMPLE: &str = include_str!("../../examples/5.txt");
+
+    fn tops(stacks: &[Stack]) -> String {
+        CraneMover::new(stacks.to_vec()).top_crates()
+    }
+
+    #[test]
+    fn yields_stacks_after_each_move() {
+        let (mover, moves) = read_input(EXAMPLE).unwrap();
+        let states = mover
+            .into_states(&moves, CraneModel::CraneMover9000)
+            .collect::<AocResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            states.iter().map(|stacks| tops(stacks)).collect::<Vec<_>>(),
+            ["DCP", "CZ", "MZ", "CMZ"]
+        );
+        assert_eq!(states[1][2].crates, ['P', 'D', 'N', 'Z']);
     }
 
-    Ok(mover.0.top_crates())
+    #[test]
+    fn crate_mover_9000_models_agree_on_every_move() {
+        let (mover, moves) = read_input(EXAMPLE).unwrap();
+        for (a, b) in mover
+            .clone()
+            .into_states(&moves, CraneModel::CraneMover9000)
+            .zip(mover.into_states(&moves, CraneModel::CraneMover9000v2))
+        {
+            let (a, b) = (a.unwrap(), b.unwrap());
+            assert!(a.iter().zip(&b).all(|(a, b)| a.crates == b.crates));
+        }
+    }
+
+    #[test]
+    fn crate_mover_9001_keeps_batch_order() {
+        let (mut mover, moves) = read_input(EXAMPLE).unwrap();
+        mover.execute(&moves, CraneModel::CraneMover9001).unwrap();
+        assert_eq!(mover.top_crates(), "MCD");
+    }
+
+    #[test]
+    fn renders_stacks_like_the_input() {
+        let (mover, _) = read_input(EXAMPLE).unwrap();
+        assert_eq!(
+            render_stacks(mover.stacks()),
+            "    [D]\n[N] [C]\n[Z] [M] [P]\n 1   2   3 \n"
+        );
+    }
 }
@@ -1,79 +1,24 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::collections::BTreeMap;
 
-use crate::common::{AocError, AocResult, IntoAocResult};
+use crate::common::{timing, AocError, AocResult, IntoAocResult};
 
-#[repr(u8)]
-#[derive(PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NodeId(usize);
+
+#[derive(Debug, PartialEq, Eq)]
 enum NodeType {
     File,
     Directory,
 }
 
+#[derive(Debug)]
 struct Node<'a> {
     pub name: &'a str,
     pub node_type: NodeType,
-    pub contents_size: u64,
-    pub parent: Option<Rc<RefCell<Node<'a>>>>,
-    pub children: HashMap<&'a str, Rc<RefCell<Node<'a>>>>,
-}
-
-impl<'a> Node<'a> {
-    pub fn new_dir(name: &'a str, parent: Option<Rc<RefCell<Node<'a>>>>) -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(Self {
-            name,
-            node_type: NodeType::Directory,
-            contents_size: 0,
-            parent,
-            children: HashMap::new(),
-        }))
-    }
-
-    pub fn new_file(
-        name: &'a str,
-        size: u64,
-        parent: Option<Rc<RefCell<Node<'a>>>>,
-    ) -> Rc<RefCell<Self>> {
-        Rc::new(RefCell::new(Self {
-            name,
-            node_type: NodeType::File,
-            contents_size: size,
-            parent,
-            children: HashMap::new(),
-        }))
-    }
-
-    pub fn size(&self) -> u64 {
-        match self.node_type {
-            NodeType::File => self.contents_size,
-            NodeType::Directory => self.children.values().map(|n| n.borrow().size()).sum(),
-        }
-    }
-}
-
-struct NodeTreeIterator<'a> {
-    stack: Vec<Rc<RefCell<Node<'a>>>>,
-}
-
-impl<'a> NodeTreeIterator<'a> {
-    pub fn new(root: &Rc<RefCell<Node<'a>>>) -> Self {
-        Self {
-            stack: vec![root.clone()],
-        }
-    }
-}
-
-impl<'a> Iterator for NodeTreeIterator<'a> {
-    type Item = Rc<RefCell<Node<'a>>>;
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.stack.pop() {
-            None => None,
-            Some(node) => {
-                self.stack
-                    .extend(node.borrow().children.values().map(|n| n.clone()));
-                Some(node)
-            }
-        }
-    }
+    // For a directory, the size of everything inside of it.
+    pub size: u64,
+    pub parent: Option<NodeId>,
+    pub children: BTreeMap<&'a str, NodeId>,
 }
 
 struct Command<'a> {
@@ -96,99 +41,222 @@ impl<'a> Command<'a> {
     }
 }
 
-fn read_directory_tree<'a>(input: &'a str) -> AocResult<Rc<RefCell<Node>>> {
-    let root = Node::new_dir("/", None);
-    let mut current = root.clone();
-    let mut lines = input.lines();
-    while let Some(line) = lines.next() {
-        if line.starts_with('$') {
-            let command = Command::from_line(line[1..].trim_start());
-            match command.cmd {
-                "cd" => match command.args.into_aoc_result_msg("missing args for cd")? {
-                    "/" => current = root.clone(),
-                    ".." => {
-                        current = current
-                            .clone()
-                            .borrow()
-                            .parent
-                            .as_ref()
-                            .into_aoc_result_msg("cannot traverse past root")?
-                            .clone();
-                    }
-                    name => {
-                        current = current
-                            .clone()
-                            .borrow()
-                            .children
-                            .get(name)
-                            .into_aoc_result_msg(&format!(
+// A file system rebuilt from the output of a terminal session.
+//
+// Nodes are stored in an arena and refer to each other by index. A node is
+// always created after its parent, so directory sizes can be totaled in a
+// single backwards pass once the whole tree is known.
+#[derive(Debug)]
+pub struct FileSystem<'a> {
+    nodes: Vec<Node<'a>>,
+}
+
+impl<'a> FileSystem<'a> {
+    const ROOT: NodeId = NodeId(0);
+
+    pub fn from_terminal_output(input: &'a str) -> AocResult<Self> {
+        let mut fs = Self {
+            nodes: vec![Node {
+                name: "/",
+                node_type: NodeType::Directory,
+                size: 0,
+                parent: None,
+                children: BTreeMap::new(),
+            }],
+        };
+        let mut current = Self::ROOT;
+        for line in input.lines() {
+            if let Some(command) = line.strip_prefix('$') {
+                let command = Command::from_line(command.trim_start());
+                match command.cmd {
+                    "cd" => match command.args.into_aoc_result_msg("missing args for cd")? {
+                        "/" => current = Self::ROOT,
+                        ".." => {
+                            current = fs
+                                .node(current)
+                                .parent
+                                .into_aoc_result_msg("cannot traverse past root")?
+                        }
+                        name => {
+                            current = fs.child(current, name).into_aoc_result_msg(&format!(
                                 "file {name} does not exist in directory {}",
-                                current.borrow().name
+                                fs.path(current)
                             ))?
-                            .clone();
+                        }
+                    },
+                    "ls" => (),
+                    cmd => return Err(AocError::parse(format!("unknown command {cmd}"))),
+                };
+            } else {
+                match line.split_once(' ') {
+                    Some(("dir", name)) => fs.add(current, name, NodeType::Directory, 0),
+                    Some((size, name)) => {
+                        let size = size.parse::<u64>().into_aoc_result_msg("invalid size")?;
+                        fs.add(current, name, NodeType::File, size);
                     }
-                },
-                "ls" => (),
-                cmd => return Err(AocError::new(&format!("unknown command {cmd}"))),
-            };
-        } else {
-            match line.split_once(' ') {
-                Some(("dir", name)) => {
-                    current
-                        .borrow_mut()
-                        .children
-                        .insert(name, Node::new_dir(name, Some(current.clone())));
-                }
-                Some((size, name)) => {
-                    let size = size.parse::<u64>().into_aoc_result_msg("invalid size")?;
-                    current
-                        .borrow_mut()
-                        .children
-                        .insert(name, Node::new_file(name, size, Some(current.clone())));
+                    None => return Err(AocError::parse(format!("invalid output line: {}", line))),
                 }
+            }
+        }
 
-                None => return Err(AocError::new(&format!("invalid output line: {}", line))),
+        for i in (1..fs.nodes.len()).rev() {
+            if let Some(NodeId(parent)) = fs.nodes[i].parent {
+                fs.nodes[parent].size += fs.nodes[i].size;
             }
         }
+        Ok(fs)
+    }
+
+    fn node(&self, id: NodeId) -> &Node<'a> {
+        &self.nodes[id.0]
+    }
+
+    // Adds a node to a directory, unless it was already listed.
+    fn add(&mut self, parent: NodeId, name: &'a str, node_type: NodeType, size: u64) {
+        if self.child(parent, name).is_some() {
+            return;
+        }
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            name,
+            node_type,
+            size,
+            parent: Some(parent),
+            children: BTreeMap::new(),
+        });
+        self.nodes[parent.0].children.insert(name, id);
+    }
+
+    fn child(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.node(id).children.get(name).copied()
+    }
+
+    pub fn root(&self) -> NodeId {
+        Self::ROOT
+    }
+
+    pub fn size(&self, id: NodeId) -> u64 {
+        self.node(id).size
+    }
+
+    // The absolute path to a node, such as `/a/e`.
+    pub fn path(&self, id: NodeId) -> String {
+        let mut names = Vec::new();
+        let mut node = self.node(id);
+        while let Some(parent) = node.parent {
+            names.push(node.name);
+            node = self.node(parent);
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // Finds the node at an absolute path.
+    pub fn lookup(&self, path: &str) -> Option<NodeId> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(Self::ROOT, |id, name| self.child(id, name))
+    }
+
+    // The size of the file or directory at an absolute path.
+    pub fn total_size(&self, path: &str) -> AocResult<u64> {
+        self.lookup(path)
+            .map(|id| self.size(id))
+            .into_aoc_result_msg(&format!("{path} does not exist"))
+    }
+
+    // Every directory, including the root.
+    pub fn directories(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len())
+            .map(NodeId)
+            .filter(|&id| self.node(id).node_type == NodeType::Directory)
+    }
+
+    // The smallest directory that is at least the given size.
+    pub fn find_smallest_dir_at_least(&self, bytes: u64) -> Option<NodeId> {
+        self.directories()
+            .filter(|&id| self.size(id) >= bytes)
+            .min_by_key(|&id| self.size(id))
+    }
+
+    // Lists every directory with its size, like `du`: contents are listed in
+    // name order, and each directory comes after everything inside of it.
+    pub fn du(&self) -> Vec<(String, u64)> {
+        let mut listing = Vec::new();
+        self.du_into(Self::ROOT, &mut listing);
+        listing
+    }
+
+    fn du_into(&self, id: NodeId, listing: &mut Vec<(String, u64)>) {
+        for &child in self.node(id).children.values() {
+            if self.node(child).node_type == NodeType::Directory {
+                self.du_into(child, listing);
+            }
+        }
+        listing.push((self.path(id), self.size(id)));
     }
-    Ok(root)
 }
 
 pub fn solve_a(input: &str) -> AocResult<u64> {
-    let root = read_directory_tree(input)?;
-    Ok(NodeTreeIterator::new(&root)
-        .filter_map(|n| {
-            let node = n.borrow();
-            let size = node.size();
-            if node.node_type == NodeType::Directory && size <= 100000 {
-                Some(size)
-            } else {
-                None
-            }
-        })
+    let fs = timing::parse_phase(|| FileSystem::from_terminal_output(input))?;
+    Ok(fs
+        .directories()
+        .map(|id| fs.size(id))
+        .filter(|&size| size <= 100000)
         .sum())
 }
 
 pub fn solve_b(input: &str) -> AocResult<u64> {
     const TOTAL_DISK_SPACE: u64 = 70000000;
     const NEEDED_UNUSED_SPACE: u64 = 30000000;
-    let root = read_directory_tree(input)?;
-    let currently_used = root.borrow().size();
+    let fs = timing::parse_phase(|| FileSystem::from_terminal_output(input))?;
+    let currently_used = fs.total_size("/")?;
     let currently_unused = TOTAL_DISK_SPACE - currently_used;
     if currently_unused >= NEEDED_UNUSED_SPACE {
-        return Err(AocError::new("already have enough unused disk space"));
+        return Err(AocError::no_solution(
+            "already have enough unused disk space",
+        ));
     }
     let min_to_remove = NEEDED_UNUSED_SPACE - currently_unused;
-    NodeTreeIterator::new(&root)
-        .filter_map(|n| {
-            let node = n.borrow();
-            let size = node.size();
-            if node.node_type == NodeType::Directory && size >= min_to_remove {
-                Some(size)
-            } else {
-                None
-            }
-        })
-        .min()
-        .into_aoc_result_msg("no directory can be deleted")
+    fs.find_smallest_dir_at_least(min_to_remove)
+        .map(|id| fs.size(id))
+        .ok_or_else(|| AocError::no_solution("no directory can be deleted"))
+}
+
+register_day!(7, solve_a, solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../examples/7.txt");
+
+    #[test]
+    fn looks_up_paths() {
+        let fs = FileSystem::from_terminal_output(EXAMPLE).unwrap();
+        assert_eq!(fs.lookup("/"), Some(fs.root()));
+        assert_eq!(
+            fs.lookup("/a/e").map(|id| fs.path(id)).as_deref(),
+            Some("/a/e")
+        );
+        assert_eq!(fs.total_size("/a/e").unwrap(), 584);
+        assert_eq!(fs.total_size("/d/k").unwrap(), 7214296);
+        assert_eq!(fs.total_size("/").unwrap(), 48381165);
+        assert!(fs.lookup("/a/x").is_none());
+        assert!(fs.total_size("/a/x").is_err());
+    }
+
+    #[test]
+    fn lists_directories_like_du() {
+        let fs = FileSystem::from_terminal_output(EXAMPLE).unwrap();
+        assert_eq!(
+            fs.du(),
+            [
+                ("/a/e".to_owned(), 584),
+                ("/a".to_owned(), 94853),
+                ("/d".to_owned(), 24933642),
+                ("/".to_owned(), 48381165),
+            ]
+        );
+    }
 }
@@ -1,137 +1,145 @@
-use crate::common::{AocResult, IntoAocResult};
-use itertools::enumerate;
+use crate::common::{grid::Grid, timing, AocError, AocResult, Direction, IntoAocResult, Point2};
 
-fn read_tree_map(input: &str) -> AocResult<Vec<Vec<u8>>> {
-    input
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| Ok(c.to_digit(10).into_aoc_result_msg("invalid character")? as u8))
-                .collect::<AocResult<Vec<_>>>()
-        })
-        .collect::<AocResult<Vec<_>>>()
+type Point = Point2<i64>;
+
+fn read_tree_map(input: &str) -> AocResult<Grid<u8>> {
+    Grid::parse(input, |c| {
+        Ok(c.to_digit(10).into_aoc_result_msg("invalid character")? as u8)
+    })
 }
 
-fn count_visible(trees: Vec<Vec<u8>>) -> u64 {
-    // Duplicate map of the forest that marks each tree as visible.
-    let mut visible = Vec::new();
-    for row in &trees {
-        visible.push(vec![false; row.len()]);
+// Marks every tree along a line that is taller than all of the trees before it,
+// which are the trees that can be seen from the start of the line.
+fn mark_visible<'a>(line: impl Iterator<Item = (Point, &'a u8)>, visible: &mut Grid<bool>) {
+    let mut tallest = None;
+    for (point, &height) in line {
+        if tallest.is_none_or(|tallest| height > tallest) {
+            visible[point] = true;
+            tallest = Some(height);
+        }
     }
+}
 
-    // All trees in top edge.
-    if let Some(top) = visible.first_mut() {
-        top.fill(true);
+fn count_visible(trees: Grid<u8>) -> u64 {
+    let mut visible = trees.map(|_| false);
+    for row in trees.rows() {
+        mark_visible(row.clone(), &mut visible);
+        mark_visible(row.rev(), &mut visible);
     }
-    // All trees in bottom edge, if it is different from the top edge.
-    if visible.len() > 1 {
-        if let Some(bottom) = visible.last_mut() {
-            bottom.fill(true);
-        }
+    for col in trees.cols() {
+        mark_visible(col.clone(), &mut visible);
+        mark_visible(col.rev(), &mut visible);
     }
+    visible.iter().filter(|(_, visible)| **visible).count() as u64
+}
 
-    // Check visibility from left and right at the same time.
-    for i in 1..(trees.len() - 1) {
-        let row = &trees[i];
-        let mut max = (-1i8, -1i8);
-        for (j, (left, right)) in enumerate(row.into_iter().zip(row.into_iter().rev())) {
-            if *left as i8 > max.0 {
-                visible[i][j] = true;
-                max.0 = *left as i8;
-            }
-            if *right as i8 > max.1 {
-                visible[i][row.len() - 1 - j] = true;
-                max.1 = *right as i8;
-            }
+// Multiplies the score of every tree along a line by how far it can see looking
+// back along the line, which is up to the closest tree at least as tall.
+//
+// The stack holds the trees that could still block the view of a later tree,
+// in decreasing height, so each tree is pushed and popped at most once.
+fn multiply_viewing_distances<'a>(
+    line: impl Iterator<Item = (Point, &'a u8)>,
+    scores: &mut Grid<u64>,
+    stack: &mut Vec<(usize, u8)>,
+) {
+    stack.clear();
+    for (k, (point, &height)) in line.enumerate() {
+        while stack.last().is_some_and(|&(_, blocker)| blocker < height) {
+            stack.pop();
         }
+        let distance = stack.last().map_or(k, |&(blocker, _)| k - blocker);
+        scores[point] *= distance as u64;
+        stack.push((k, height));
     }
+}
 
-    let max_row_length = trees.iter().map(|row| row.len()).max().unwrap_or(0);
-
-    // Check visibility from top and bottom at the same time.
-    for j in 1..(max_row_length - 1) {
-        let mut max = (-1i8, -1i8);
-        for i in 0..trees.len() {
-            if let Some(top) = trees[i].get(j) {
-                if *top as i8 > max.0 {
-                    visible[i][j] = true;
-                    max.0 = *top as i8;
-                }
-            }
-            let bottom_index = trees.len() - 1 - i;
-            if let Some(bottom) = trees[bottom_index].get(j) {
-                if *bottom as i8 > max.1 {
-                    visible[bottom_index][j] = true;
-                    max.1 = *bottom as i8;
-                }
-            }
-        }
+fn highest_scenic_score(trees: Grid<u8>) -> AocResult<u64> {
+    let mut scores = trees.map(|_| 1);
+    let mut stack = Vec::new();
+    for row in trees.rows() {
+        multiply_viewing_distances(row.clone(), &mut scores, &mut stack);
+        multiply_viewing_distances(row.rev(), &mut scores, &mut stack);
     }
-
-    visible
-        .into_iter()
-        .map(|row| row.into_iter().filter(|v| *v).count() as u64)
-        .sum()
+    for col in trees.cols() {
+        multiply_viewing_distances(col.clone(), &mut scores, &mut stack);
+        multiply_viewing_distances(col.rev(), &mut scores, &mut stack);
+    }
+    scores
+        .iter()
+        .map(|(_, score)| *score)
+        .max()
+        .ok_or_else(|| AocError::no_solution("no max scenic score found"))
 }
 
-fn highest_scenic_score(trees: Vec<Vec<u8>>) -> AocResult<u64> {
-    const MOVEMENT: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+// Walks out from every tree in each direction until the view is blocked.
+fn highest_scenic_score_by_walking(trees: Grid<u8>) -> AocResult<u64> {
     trees
         .iter()
-        .enumerate()
-        .flat_map(|(i, row)| {
-            row.into_iter()
-                .enumerate()
-                .map(move |(j, height)| (i, j, height))
-        })
-        .map(|(i, j, height)| {
-            MOVEMENT
+        .map(|(point, height)| {
+            Direction::ALL
                 .iter()
-                .map(|(di, dj)| {
-                    // How far can we get in one direction?
-                    let mut distance: u64 = 0;
-                    let mut max = -1i8;
-                    let (mut i, mut j) = (i as isize, j as isize);
-                    loop {
-                        // Move our current location.
-                        i += di;
-                        j += dj;
-
-                        // Bounds check before converting to usize.
-                        if i < 0 || j < 0 {
+                .map(|dir| {
+                    // Trees as tall as ours block the view, but are still seen.
+                    let mut distance = 0;
+                    for viewed_height in trees.sightline(point, *dir) {
+                        distance += 1;
+                        if viewed_height >= height {
                             break;
                         }
-                        match trees.get(i as usize).and_then(|row| row.get(j as usize)) {
-                            Some(viewed_height) => {
-                                // We can see another tree.
-                                distance += 1;
-
-                                // New maximum tree height.
-                                if *viewed_height as i8 > max {
-                                    max = *viewed_height as i8;
-                                }
-
-                                // Same height or taller than our tree.
-                                // Cannot see anything behind it.
-                                if viewed_height >= height {
-                                    break;
-                                }
-                            }
-                            None => break,
-                        }
                     }
                     distance
                 })
                 .product::<u64>()
         })
         .max()
-        .into_aoc_result_msg("no max scenic score found")
+        .ok_or_else(|| AocError::no_solution("no max scenic score found"))
 }
 
 pub fn solve_a(input: &str) -> AocResult<u64> {
-    Ok(count_visible(read_tree_map(input)?))
+    Ok(count_visible(timing::parse_phase(|| read_tree_map(input))?))
 }
 
 pub fn solve_b(input: &str) -> AocResult<u64> {
-    highest_scenic_score(read_tree_map(input)?)
+    highest_scenic_score(timing::parse_phase(|| read_tree_map(input))?)
+}
+
+pub fn solve_b_by_walking(input: &str) -> AocResult<u64> {
+    highest_scenic_score_by_walking(timing::parse_phase(|| read_tree_map(input))?)
+}
+
+register_day!(8, solve_a, solve_b | solve_b_by_walking);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn solves_example() {
+        let input = include_str!("../../examples/8.txt");
+        assert_eq!(solve_a(input).unwrap(), 21);
+        assert_eq!(solve_b(input).unwrap(), 8);
+        assert_eq!(solve_b_by_walking(input).unwrap(), 8);
+    }
+
+    #[test]
+    fn rejects_ragged_maps() {
+        let err = solve_a("123\n45").unwrap_err();
+        assert_eq!(err.message(), "row 2 has 2 cells, but row 1 has 3");
+        assert!(solve_b("123\n45").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn stacks_agree_with_walking(rows in (1..12usize, 1..12usize).prop_flat_map(|(height, width)| {
+            prop::collection::vec(prop::collection::vec(0..10u8, width), height)
+        })) {
+            let trees = Grid::from_rows(rows)?;
+            prop_assert_eq!(
+                highest_scenic_score(trees.clone())?,
+                highest_scenic_score_by_walking(trees)?
+            );
+        }
+    }
 }
@@ -1,30 +1,26 @@
-use std::collections::HashSet;
-
-use crate::common::{AocError, AocResult, IntoAocResult};
-
-#[repr(u8)]
-#[derive(Debug)]
-enum Direction {
-    Up,
-    Down,
-    Right,
-    Left,
-}
-
-impl TryFrom<&str> for Direction {
-    type Error = AocError;
-    fn try_from(s: &str) -> AocResult<Self> {
-        match s {
-            "U" => Ok(Self::Up),
-            "D" => Ok(Self::Down),
-            "R" => Ok(Self::Right),
-            "L" => Ok(Self::Left),
-            _ => Err(AocError::new(&format!("invalid direction: {}", s))),
-        }
+use std::{collections::HashSet, iter, str::FromStr};
+
+use rustc_hash::FxHashSet;
+
+use crate::common::{
+    input::InputLines,
+    lines::parse_lines,
+    timing,
+    viz::{self, Visualize},
+    AocError, AocResult, Direction, IntoAocResult, Point2, SolutionPart,
+};
+
+fn parse_direction(s: &str) -> AocResult<Direction> {
+    match s {
+        "U" => Ok(Direction::Up),
+        "D" => Ok(Direction::Down),
+        "R" => Ok(Direction::Right),
+        "L" => Ok(Direction::Left),
+        _ => Err(AocError::parse(format!("invalid direction: {}", s))),
     }
 }
 
-struct Motion {
+pub struct Motion {
     pub direction: Direction,
     pub steps: i64,
 }
@@ -35,71 +31,323 @@ impl Motion {
     }
 }
 
-fn read_motions(input: &str) -> AocResult<Vec<Motion>> {
-    input
-        .lines()
-        .map(|line| match line.split_once(' ') {
-            None => Err(AocError::new("missing space")),
-            Some((first, second)) => Ok(Motion::new(
-                Direction::try_from(first)?,
-                second.parse().into_aoc_result()?,
-            )),
-        })
-        .collect()
+impl FromStr for Motion {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        let (first, second) = s.split_once(' ').into_aoc_result_msg("missing space")?;
+        Ok(Motion::new(
+            parse_direction(first)?,
+            second.parse().into_aoc_result_msg("invalid step count")?,
+        ))
+    }
 }
 
-type Position = (i64, i64);
-
-fn difference(a: Position, b: Position) -> Position {
-    (a.0 - b.0, a.1 - b.1)
+fn read_motions(lines: InputLines) -> AocResult<Vec<Motion>> {
+    parse_lines(lines)
 }
 
+type Position = Point2<i64>;
+
 fn touching(a: Position, b: Position) -> bool {
-    let diff = difference(a, b);
-    diff.0 >= -1 && diff.0 <= 1 && diff.1 >= -1 && diff.1 <= 1
-}
-
-fn tail_visited(start: Position, segments: usize, motions: Vec<Motion>) -> HashSet<Position> {
-    let mut rope = vec![start; segments];
-    let mut visited = HashSet::from([*rope.last().unwrap()]);
-    for Motion { direction, steps } in motions {
-        for _ in 0..steps {
-            // Change the head position.
-            match direction {
-                Direction::Up => rope[0].1 += 1,
-                Direction::Down => rope[0].1 -= 1,
-                Direction::Right => rope[0].0 += 1,
-                Direction::Left => rope[0].0 -= 1,
-            };
-
-            for i in 1..rope.len() {
-                let leader = rope[i - 1];
-                let follower = &mut rope[i];
-
-                if touching(leader, *follower) {
-                    // Already touching, so no segments after this one move either.
-                    break;
-                }
-
-                // Apply the difference, at most one step in both directions.
-                let diff = difference(leader, *follower);
-                if diff.0 >= 1 || diff.0 <= -1 {
-                    follower.0 += diff.0.clamp(-1, 1);
-                }
-                if diff.1 >= 1 || diff.1 <= -1 {
-                    follower.1 += diff.1.clamp(-1, 1);
-                }
+    a.chebyshev_distance(&b) <= 1
+}
+
+// A rope made of knots, where each knot follows the one in front of it.
+#[derive(Debug, Clone)]
+pub struct Rope {
+    knots: Vec<Position>,
+}
+
+impl Rope {
+    pub fn new(start: Position, knots: usize) -> AocResult<Self> {
+        if knots == 0 {
+            return Err(AocError::invalid_args("a rope needs at least one knot"));
+        }
+        Ok(Self {
+            knots: vec![start; knots],
+        })
+    }
+
+    // Every knot, starting from the head.
+    pub fn knots(&self) -> &[Position] {
+        &self.knots
+    }
+
+    pub fn knot(&self, i: usize) -> Position {
+        self.knots[i]
+    }
+
+    // A rope always has at least one knot, so it is never empty.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.knots.len()
+    }
+
+    // Moves the head one step, pulling the rest of the rope along.
+    pub fn step(&mut self, direction: Direction) {
+        self.knots[0] += direction.delta();
+
+        for i in 1..self.knots.len() {
+            let leader = self.knots[i - 1];
+            let follower = &mut self.knots[i];
+
+            if touching(leader, *follower) {
+                // Already touching, so no segments after this one move either.
+                break;
+            }
+
+            // Apply the difference, at most one step in both directions.
+            *follower += (leader - *follower).signum();
+        }
+    }
+
+    // Performs every motion one step at a time, calling back after each step.
+    pub fn run(&mut self, motions: &[Motion], mut on_step: impl FnMut(&Self)) {
+        for Motion { direction, steps } in motions {
+            for _ in 0..*steps {
+                self.step(*direction);
+                on_step(self);
             }
-            visited.insert(*rope.last().unwrap());
         }
     }
-    visited
+
+    // Every state of the rope after each step of the motions.
+    pub fn states(self, motions: &[Motion]) -> impl Iterator<Item = Rope> + '_ {
+        motions
+            .iter()
+            .flat_map(|motion| iter::repeat_n(motion.direction, motion.steps as usize))
+            .scan(self, |rope, direction| {
+                rope.step(direction);
+                Some(rope.clone())
+            })
+    }
+
+    // The positions of a knot, starting with where it is now and followed by
+    // where it is after each step.
+    pub fn path_of(&mut self, knot: usize, motions: &[Motion]) -> Vec<Position> {
+        let mut path = vec![self.knot(knot)];
+        self.run(motions, |rope| path.push(rope.knot(knot)));
+        path
+    }
+
+    // Every position a knot visits, including where it is now.
+    pub fn visited_by<S: VisitedSet>(&mut self, knot: usize, motions: &[Motion]) -> AocResult<S> {
+        let mut visited = S::for_motions(self.knot(0), motions)?;
+        visited.insert(self.knot(knot));
+        self.run(motions, |rope| visited.insert(rope.knot(knot)));
+        Ok(visited)
+    }
+}
+
+// The rectangle around every position the head moves through. Each knot only
+// ever moves toward the one in front of it, so the whole rope stays inside it.
+fn head_bounds(start: Position, motions: &[Motion]) -> (Position, Position) {
+    let corners = motions.iter().scan(start, |head, motion| {
+        *head += motion.direction.delta() * motion.steps;
+        Some(*head)
+    });
+    viz::bounds(iter::once(start).chain(corners)).unwrap_or((start, start))
+}
+
+// A set of the positions a knot has visited.
+pub trait VisitedSet: Sized {
+    // An empty set that can hold every position the rope reaches during the
+    // motions.
+    fn for_motions(start: Position, motions: &[Motion]) -> AocResult<Self>;
+    fn insert(&mut self, position: Position);
+    // How many positions have been visited.
+    fn count(&self) -> usize;
+}
+
+impl VisitedSet for HashSet<Position> {
+    fn for_motions(_: Position, _: &[Motion]) -> AocResult<Self> {
+        Ok(HashSet::new())
+    }
+
+    fn insert(&mut self, position: Position) {
+        HashSet::insert(self, position);
+    }
+
+    fn count(&self) -> usize {
+        self.len()
+    }
+}
+
+// Maps signed integers onto unsigned ones, alternating between positive and
+// negative so that small numbers of either sign stay small.
+fn zigzag(n: i32) -> u32 {
+    ((n << 1) ^ (n >> 31)) as u32
+}
+
+// Visited positions packed into one integer each, with both coordinates
+// zigzag-encoded into 32 bits, which is quicker to hash than a pair of `i64`s.
+pub struct PackedSet(FxHashSet<u64>);
+
+impl VisitedSet for PackedSet {
+    fn for_motions(start: Position, motions: &[Motion]) -> AocResult<Self> {
+        let (min, max) = head_bounds(start, motions);
+        let fits = |n: i64| i32::try_from(n).is_ok();
+        if ![min.x, min.y, max.x, max.y].into_iter().all(fits) {
+            return Err(AocError::invalid_args(
+                "rope moves too far to pack positions into 32 bits",
+            ));
+        }
+        Ok(Self(FxHashSet::default()))
+    }
+
+    fn insert(&mut self, position: Position) {
+        let (x, y) = (zigzag(position.x as i32), zigzag(position.y as i32));
+        self.0.insert((x as u64) << 32 | y as u64);
+    }
+
+    fn count(&self) -> usize {
+        self.0.len()
+    }
+}
+
+// Visited positions as one bit per tile in the rectangle the rope stays
+// inside of.
+pub struct VisitedBitmap {
+    min: Position,
+    width: usize,
+    bits: Vec<u64>,
+    count: usize,
+}
+
+impl VisitedSet for VisitedBitmap {
+    fn for_motions(start: Position, motions: &[Motion]) -> AocResult<Self> {
+        let (min, max) = head_bounds(start, motions);
+        let (width, height) = ((max.x - min.x + 1) as usize, (max.y - min.y + 1) as usize);
+        let tiles = width
+            .checked_mul(height)
+            .into_aoc_result_msg("rope moves too far for a bitmap")?;
+        Ok(Self {
+            min,
+            width,
+            bits: vec![0; tiles.div_ceil(64)],
+            count: 0,
+        })
+    }
+
+    fn insert(&mut self, position: Position) {
+        let offset = position - self.min;
+        let i = offset.y as usize * self.width + offset.x as usize;
+        let bit = 1 << (i % 64);
+        if self.bits[i / 64] & bit == 0 {
+            self.bits[i / 64] |= bit;
+            self.count += 1;
+        }
+    }
+
+    fn count(&self) -> usize {
+        self.count
+    }
+}
+
+fn tail_visited<S: VisitedSet>(segments: usize, lines: InputLines) -> AocResult<u64> {
+    let mut rope = Rope::new(Point2::new(0, 0), segments)?;
+    let tail = rope.len() - 1;
+    Ok(rope
+        .visited_by::<S>(tail, &timing::parse_phase(|| read_motions(lines))?)?
+        .count() as u64)
+}
+
+pub fn solve_a_with<S: VisitedSet>(lines: InputLines) -> AocResult<u64> {
+    tail_visited::<S>(2, lines)
+}
+
+pub fn solve_b_with<S: VisitedSet>(lines: InputLines) -> AocResult<u64> {
+    tail_visited::<S>(10, lines)
+}
+
+pub fn solve_a(lines: InputLines) -> AocResult<u64> {
+    solve_a_with::<HashSet<Position>>(lines)
+}
+
+pub fn solve_b(lines: InputLines) -> AocResult<u64> {
+    solve_b_with::<HashSet<Position>>(lines)
+}
+
+// The rope moving through every motion, leaving a trail where its tail has been.
+struct RopeViz {
+    rope: Rope,
+    motions: Vec<Motion>,
+    min: Position,
+    max: Position,
+}
+
+impl Visualize for RopeViz {
+    fn frames(&self) -> impl Iterator<Item = String> {
+        let tail = self.rope.len() - 1;
+        let start = self.rope.knot(0);
+        iter::once(self.rope.clone())
+            .chain(self.rope.clone().states(&self.motions))
+            .scan(HashSet::new(), move |visited, rope| {
+                visited.insert(rope.knot(tail));
+                Some(viz::render_grid(self.min, self.max, |point| {
+                    match rope.knots().iter().position(|knot| *knot == point) {
+                        Some(0) => 'H',
+                        Some(i) if rope.len() == 2 => ['H', 'T'][i],
+                        Some(i) => char::from_digit(i as u32, 10).unwrap_or('?'),
+                        None if visited.contains(&point) => '#',
+                        None if point == start => 's',
+                        None => '.',
+                    }
+                }))
+            })
+    }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    Ok(tail_visited((0, 0), 2, read_motions(input)?).len() as u64)
+pub fn visualize(input: &str, part: SolutionPart) -> AocResult<impl Visualize> {
+    let knots = if part == SolutionPart::A { 2 } else { 10 };
+    let rope = Rope::new(Point2::new(0, 0), knots)?;
+    let motions = read_motions(input.into())?;
+    // Each knot only ever moves toward the one in front of it, so the whole rope
+    // stays inside the rectangle around the head's path.
+    let (min, max) = viz::bounds(rope.clone().path_of(0, &motions))
+        .into_aoc_result_msg("rope has no positions")?;
+    Ok(RopeViz {
+        rope,
+        motions,
+        min,
+        max,
+    })
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    return Ok(tail_visited((0, 0), 10, read_motions(input)?).len() as u64);
+register_day!(
+    9,
+    lines solve_a | solve_a_with::<PackedSet> | solve_a_with::<VisitedBitmap>,
+    lines solve_b | solve_b_with::<PackedSet> | solve_b_with::<VisitedBitmap>,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zigzags_small_numbers_to_small_numbers() {
+        assert_eq!([0, -1, 1, -2, 2].map(zigzag), [0, 1, 2, 3, 4]);
+        assert_eq!(zigzag(i32::MIN), u32::MAX);
+    }
+
+    fn tail_visits<S: VisitedSet>(knots: usize, motions: &[Motion]) -> usize {
+        let mut rope = Rope::new(Point2::new(0, 0), knots).unwrap();
+        rope.visited_by::<S>(knots - 1, motions).unwrap().count()
+    }
+
+    #[test]
+    fn visited_sets_agree() {
+        for input in [
+            include_str!("../../examples/9.txt"),
+            include_str!("../../examples/9-larger.txt"),
+            "L 5\nD 7\nR 2\nU 20\nL 1",
+        ] {
+            let motions = read_motions(input.into()).unwrap();
+            for knots in [2, 10] {
+                let expected = tail_visits::<HashSet<_>>(knots, &motions);
+                assert_eq!(tail_visits::<PackedSet>(knots, &motions), expected);
+                assert_eq!(tail_visits::<VisitedBitmap>(knots, &motions), expected);
+            }
+        }
+    }
 }
@@ -1,122 +1,54 @@
 use std::fmt::{Display, Formatter, Result as DisplayResult, Write};
 
-use crate::common::{AocError, AocResult, IntoAocResult};
-
-#[derive(Debug)]
-enum Instruction {
-    Addx(i64),
-    Noop,
-}
-
-impl Instruction {
-    pub fn cycles(&self) -> u64 {
-        match self {
-            Self::Addx(_) => 2,
-            Self::Noop => 1,
-        }
-    }
-}
-
-impl TryFrom<&str> for Instruction {
-    type Error = AocError;
-    fn try_from(s: &str) -> AocResult<Self> {
-        Ok(match s.split_once(' ') {
-            Some(("addx", val)) => Instruction::Addx(
-                val.parse()
-                    .into_aoc_result_msg(&format!("invalid operand for addx: {val}"))?,
-            ),
-            None => match s {
-                "noop" => Instruction::Noop,
-                _ => return Err(AocError::new(&format!("unknown instruction: {s}"))),
-            },
-            _ => return Err(AocError::new(&format!("unknown instruction: {s}"))),
-        })
-    }
-}
-
-struct ExecutingInstruction {
-    pub instruction: Instruction,
-    pub cycles: u64,
-}
-
-impl ExecutingInstruction {
-    pub fn new(instruction: Instruction) -> Self {
-        Self {
-            instruction,
-            cycles: 0,
-        }
-    }
-
-    pub fn tick(&mut self) {
-        self.cycles += 1;
-    }
-
-    pub fn finished(&self) -> bool {
-        self.cycles >= self.instruction.cycles()
-    }
-}
-
-trait Clocked {
-    fn tick(&mut self);
-}
-
-struct Cpu {
-    x: i64,
-    executing: Option<ExecutingInstruction>,
-}
-
-impl Cpu {
-    pub fn new() -> Self {
-        Self {
-            x: 1,
-            executing: None,
-        }
-    }
-
-    pub fn x(&self) -> i64 {
-        self.x
-    }
-
-    pub fn ready_for_instruction(&self) -> bool {
-        self.executing.is_none()
-    }
-
-    pub fn execute(&mut self, instruction: Instruction) {
-        self.executing = Some(ExecutingInstruction::new(instruction));
-    }
-
-    fn finish_instruction(&mut self) {
-        match self.executing.as_mut().unwrap().instruction {
-            Instruction::Addx(val) => self.x += val,
-            Instruction::Noop => (),
-        }
-        self.executing = None;
-    }
-}
-
-impl Clocked for Cpu {
-    fn tick(&mut self) {
-        if let Some(instr) = &mut self.executing {
-            instr.tick();
-            if instr.finished() {
-                self.finish_instruction()
-            }
+use crate::common::{
+    ocr,
+    render::{Color, GridImage},
+    timing,
+    vm::{parse_program, Instruction, Peripheral, Register, Registers, Vm},
+    AocError, AocResult, DayArgs,
+};
+
+// The size of the screen and of the sprite drawn on it, which are the puzzle's
+// 40 by 6 pixel screen and 3 pixel wide sprite unless given with `--width`,
+// `--height`, and `--sprite`.
+#[derive(Debug, Clone, Copy)]
+struct CrtSize {
+    pub width: usize,
+    pub height: usize,
+    pub sprite_width: usize,
+}
+
+impl CrtSize {
+    fn from_args(day_args: &DayArgs) -> AocResult<Self> {
+        let size = Self {
+            width: day_args.get_or("width", 40)?,
+            height: day_args.get_or("height", ocr::LETTER_HEIGHT)?,
+            sprite_width: day_args.get_or("sprite", 3)?,
+        };
+        if size.width == 0 || size.height == 0 || size.sprite_width == 0 {
+            return Err(AocError::invalid_args(
+                "--width, --height, and --sprite must be at least 1",
+            ));
         }
+        Ok(size)
     }
 }
 
 struct Crt {
     width: usize,
     height: usize,
+    sprite_width: usize,
     pixels: Vec<bool>,
+    // The pixel being drawn.
     cycle: u64,
 }
 
 impl Crt {
-    pub fn new(width: usize, height: usize) -> Self {
+    pub fn new(width: usize, height: usize, sprite_width: usize) -> Self {
         Self {
             width,
             height,
+            sprite_width,
             pixels: vec![false; width * height],
             cycle: 0,
         }
@@ -126,13 +58,41 @@ impl Crt {
         self.cycle % (self.width as u64)
     }
 
+    fn row(&self) -> u64 {
+        self.cycle / (self.width as u64)
+    }
+
+    // Whether the sprite, centered on the given column, covers the pixel being
+    // drawn. Sprites with an even width reach one further to the right.
+    pub fn sprite_visible(&self, x: i64) -> bool {
+        let left = x - (self.sprite_width as i64 - 1) / 2;
+        (left..left + self.sprite_width as i64).contains(&(self.column() as i64))
+    }
+
+    // Lights the pixel being drawn. Pixels drawn after the last row are off the
+    // bottom of the screen.
     pub fn set(&mut self) {
-        self.pixels[self.cycle as usize] = true;
+        let (row, column) = (self.row() as usize, self.column() as usize);
+        if row < self.height {
+            self.pixels[row * self.width + column] = true;
+        }
+    }
+
+    pub fn image(&self) -> GridImage {
+        let mut image = GridImage::new(self.width, self.height, Color(30, 30, 30));
+        for (i, _) in self.pixels.iter().enumerate().filter(|(_, lit)| **lit) {
+            image.set(i % self.width, i / self.width, Color(120, 230, 120));
+        }
+        image
     }
 }
 
-impl Clocked for Crt {
-    fn tick(&mut self) {
+// The CRT draws one pixel every cycle, lighting it if the sprite is there.
+impl Peripheral for Crt {
+    fn during_cycle(&mut self, _: u64, registers: &Registers) {
+        if self.sprite_visible(registers[Register::X]) {
+            self.set();
+        }
         self.cycle += 1;
     }
 }
@@ -151,63 +111,122 @@ impl Display for Crt {
     }
 }
 
-fn read_instructions(input: &str) -> AocResult<Vec<Instruction>> {
-    input
-        .lines()
-        .map(|line| Instruction::try_from(line))
-        .collect()
+// The program starts with x at 1.
+fn cpu(program: Vec<Instruction>) -> Vm {
+    Vm::new(program).with_register(Register::X, 1)
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
+pub fn solve_a(input: &str) -> AocResult<i64> {
     const OFFSET: u64 = 20;
     const PERIOD: u64 = 40;
     const CHECKS: u64 = 6;
     const MAX_CYCLE: u64 = OFFSET + PERIOD * (CHECKS - 1);
 
-    let mut instructions = read_instructions(input)?.into_iter();
-    let mut cpu = Cpu::new();
-    let mut signal_strenghts = Vec::new();
-
-    for cycle in 1..=MAX_CYCLE {
-        if cpu.ready_for_instruction() {
-            match instructions.next() {
-                Some(instruction) => cpu.execute(instruction),
-                None => (),
-            }
+    let mut cpu = cpu(timing::parse_phase(|| parse_program(input))?);
+    let mut signal_strength = 0;
+    cpu.run_for(MAX_CYCLE, &mut |cycle: u64, registers: &Registers| {
+        if cycle >= OFFSET && (cycle - OFFSET).is_multiple_of(PERIOD) {
+            signal_strength += cycle as i64 * registers[Register::X];
         }
+    });
+    Ok(signal_strength)
+}
 
-        if cycle >= OFFSET && (cycle - OFFSET) % PERIOD == 0 {
-            signal_strenghts.push(cycle as i64 * cpu.x());
-        }
+// Runs the program until it ends or the screen is full, drawing on a CRT of
+// the given size.
+fn draw(program: Vec<Instruction>, size: CrtSize) -> Crt {
+    let mut crt = Crt::new(size.width, size.height, size.sprite_width);
+    cpu(program).run_at_most((size.width * size.height) as u64, &mut crt);
+    crt
+}
 
-        cpu.tick();
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<String> {
+    let size = CrtSize::from_args(day_args)?;
+    let crt = draw(timing::parse_phase(|| parse_program(input))?, size);
+
+    // Letters only fit on a screen as tall as they are, so anything else is
+    // shown as it was drawn.
+    if crt.height != ocr::LETTER_HEIGHT {
+        return Ok(crt.to_string());
     }
+    // Show what was drawn if it cannot be read, so it can be read by eye instead.
+    ocr::read_letters(&crt.pixels, crt.width)
+        .map_err(|err| AocError::parse(format!("{}, the CRT shows:\n{}", err.message(), crt)))
+}
 
-    Ok(signal_strenghts.into_iter().sum::<i64>() as u64)
+// What the CRT shows once the program ends, as an image or as text.
+pub fn snapshot(input: &str, day_args: &DayArgs) -> AocResult<GridImage> {
+    Ok(draw(parse_program(input)?, CrtSize::from_args(day_args)?).image())
 }
 
-pub fn solve_b(input: &str) -> AocResult<String> {
-    let mut instructions = read_instructions(input)?.into_iter();
-    let mut cpu = Cpu::new();
-    let mut crt = Crt::new(40, 6);
+pub fn draw_text(input: &str, day_args: &DayArgs) -> AocResult<String> {
+    Ok(format!(
+        "{}\n",
+        draw(parse_program(input)?, CrtSize::from_args(day_args)?)
+    ))
+}
 
-    loop {
-        if cpu.ready_for_instruction() {
-            match instructions.next() {
-                Some(instruction) => cpu.execute(instruction),
-                None => break,
-            }
-        }
+register_day!(10, solve_a, args solve_b);
 
-        match (crt.column() as i64) - cpu.x() {
-            -1 | 0 | 1 => crt.set(),
-            _ => (),
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        cpu.tick();
-        crt.tick();
-    }
+    const EXAMPLE: &str = include_str!("../../examples/10.txt");
 
-    println!("{}", crt);
-    Ok("check stdout".to_owned())
+    fn draw_example(width: usize, height: usize, sprite_width: usize) -> String {
+        let size = CrtSize {
+            width,
+            height,
+            sprite_width,
+        };
+        draw(parse_program(EXAMPLE).unwrap(), size).to_string()
+    }
+
+    #[test]
+    fn draws_example_on_any_screen() {
+        let stripes = "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######.....";
+        assert_eq!(draw_example(40, 6, 3), stripes);
+        // Rows past the end of the program are left dark, and rows past the
+        // bottom of the screen are never drawn.
+        assert_eq!(
+            draw_example(40, 8, 3),
+            format!("{stripes}\n{}\n{}", ".".repeat(40), ".".repeat(40))
+        );
+        assert_eq!(
+            draw_example(40, 3, 3),
+            stripes.lines().take(3).collect::<Vec<_>>().join("\n")
+        );
+        // A narrower sprite lights only some of the same pixels.
+        let narrow = draw_example(40, 6, 1);
+        assert!(narrow
+            .chars()
+            .zip(stripes.chars())
+            .all(|(narrow, wide)| narrow != '#' || wide == '#'));
+        assert!(narrow.matches('#').count() < stripes.matches('#').count());
+        assert!(solve_b(EXAMPLE, &DayArgs::default().with("sprite", 0)).is_err());
+        assert_eq!(
+            solve_b(EXAMPLE, &DayArgs::default().with("height", 3)).unwrap(),
+            draw_example(40, 3, 3)
+        );
+    }
+
+    #[test]
+    fn stops_drawing_a_program_that_never_ends() {
+        let size = CrtSize {
+            width: 4,
+            height: 2,
+            sprite_width: 3,
+        };
+        assert_eq!(
+            draw(parse_program("jmp 0").unwrap(), size).to_string(),
+            "###.\n###."
+        );
+    }
 }
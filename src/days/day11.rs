@@ -1,67 +1,543 @@
-use crate::common::{AocError, AocResult, IntoAocResult, NewlineBlocks, ParseIntegers};
+use crate::common::{
+    parse::{delimited, either, map, parse_all, tag, unsigned, whitespace, ParseResult},
+    timing, AocError, AocResult, DayArgs, IntoAocResult, NewlineBlocks, ParseIntegers,
+};
 use itertools::Itertools;
-use num::Integer;
-use std::cell::RefCell;
+use num::{BigUint, CheckedSub, Integer, ToPrimitive, Zero};
+use std::{cell::RefCell, str::FromStr};
 
-struct Monkey {
-    pub worry_levels: Vec<u64>,
-    pub operation: Box<dyn Fn(u64) -> u64>,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Old,
+    Const(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    fn perform(&self, lhs: u64, rhs: u64) -> AocResult<u64> {
+        match self {
+            Self::Add => lhs.checked_add(rhs),
+            Self::Subtract => lhs.checked_sub(rhs),
+            Self::Multiply => lhs.checked_mul(rhs),
+            Self::Divide => lhs.checked_div(rhs),
+        }
+        .ok_or_else(|| AocError::no_solution(format!("{lhs} {self:?} {rhs} is out of range")))
+    }
+
+    // Performs the operation on two residues, keeping the result reduced.
+    fn perform_mod(&self, lhs: u64, rhs: u64, modulus: u64) -> u64 {
+        let (lhs, rhs) = (lhs as u128, rhs as u128);
+        let result = match self {
+            Self::Add => lhs + rhs,
+            Self::Multiply => lhs * rhs,
+            Self::Subtract | Self::Divide => unreachable!("{self:?} is not modular"),
+        };
+        (result % modulus as u128) as u64
+    }
+
+    // Whether the operation carries over to modular arithmetic. A quotient of
+    // residues is not the residue of the quotient, and a residue cannot tell
+    // whether a difference went negative, which is an error for exact worry
+    // levels.
+    fn is_modular(&self) -> bool {
+        matches!(self, Self::Add | Self::Multiply)
+    }
+}
+
+fn reduce(n: u64, modulus: u64) -> u64 {
+    if n < modulus {
+        n
+    } else {
+        n % modulus
+    }
+}
+
+// The right-hand side of a monkey's operation, such as `old * (old + 3)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    Operand(Operand),
+    Binary(Box<Expression>, Operator, Box<Expression>),
+}
+
+impl Expression {
+    pub fn evaluate(&self, old: u64) -> AocResult<u64> {
+        match self {
+            Self::Operand(Operand::Old) => Ok(old),
+            Self::Operand(Operand::Const(n)) => Ok(*n),
+            Self::Binary(lhs, op, rhs) => op.perform(lhs.evaluate(old)?, rhs.evaluate(old)?),
+        }
+    }
+
+    // Evaluates the expression modulo some number, which keeps worry levels
+    // small without changing which numbers they are divisible by. Only modular
+    // expressions can be evaluated this way.
+    pub fn evaluate_mod(&self, old: u64, modulus: u64) -> u64 {
+        match self {
+            Self::Operand(Operand::Old) => reduce(old, modulus),
+            Self::Operand(Operand::Const(n)) => reduce(*n, modulus),
+            Self::Binary(lhs, op, rhs) => op.perform_mod(
+                lhs.evaluate_mod(old, modulus),
+                rhs.evaluate_mod(old, modulus),
+                modulus,
+            ),
+        }
+    }
+
+    // Evaluates the expression exactly with integers of any size.
+    pub fn evaluate_big(&self, old: &BigUint) -> AocResult<BigUint> {
+        match self {
+            Self::Operand(Operand::Old) => Ok(old.clone()),
+            Self::Operand(Operand::Const(n)) => Ok(BigUint::from(*n)),
+            Self::Binary(lhs, op, rhs) => {
+                let (lhs, rhs) = (lhs.evaluate_big(old)?, rhs.evaluate_big(old)?);
+                match op {
+                    Operator::Add => Ok(lhs + rhs),
+                    Operator::Multiply => Ok(lhs * rhs),
+                    Operator::Subtract => lhs.checked_sub(&rhs).ok_or_else(|| {
+                        AocError::no_solution(format!("{lhs} - {rhs} is out of range"))
+                    }),
+                    Operator::Divide if rhs.is_zero() => {
+                        Err(AocError::no_solution(format!("{lhs} / 0 is out of range")))
+                    }
+                    Operator::Divide => Ok(lhs / rhs),
+                }
+            }
+        }
+    }
+
+    pub fn is_modular(&self) -> bool {
+        match self {
+            Self::Operand(_) => true,
+            Self::Binary(lhs, op, rhs) => op.is_modular() && lhs.is_modular() && rhs.is_modular(),
+        }
+    }
+}
+
+// Parses a token, ignoring any whitespace before it.
+fn token<T>(parser: impl Fn(&str) -> ParseResult<'_, T>) -> impl Fn(&str) -> ParseResult<'_, T> {
+    move |input| parser(whitespace(input)?.1)
+}
+
+fn operator(symbol: &'static str, op: Operator) -> impl Fn(&str) -> ParseResult<'_, Operator> {
+    map(token(tag(symbol)), move |_| op)
+}
+
+fn operand(input: &str) -> ParseResult<'_, Expression> {
+    let operand = either(
+        map(tag("old"), |_| Operand::Old),
+        map(unsigned, Operand::Const),
+    );
+    either(
+        map(token(operand), Expression::Operand),
+        delimited(token(tag("(")), expression, token(tag(")"))),
+    )(input)
+}
+
+// Parses a chain of operands joined by the given operators, which are all
+// left-associative and have the same precedence.
+fn chain<'a>(
+    input: &'a str,
+    next: impl Fn(&str) -> ParseResult<'_, Expression>,
+    op: impl Fn(&str) -> ParseResult<'_, Operator>,
+) -> ParseResult<'a, Expression> {
+    let (mut lhs, mut rest) = next(input)?;
+    while let Ok((op, after_op)) = op(rest) {
+        let (rhs, after_rhs) = next(after_op)?;
+        lhs = Expression::Binary(Box::new(lhs), op, Box::new(rhs));
+        rest = after_rhs;
+    }
+    Ok((lhs, rest))
+}
+
+fn term(input: &str) -> ParseResult<'_, Expression> {
+    chain(
+        input,
+        operand,
+        either(
+            operator("*", Operator::Multiply),
+            operator("/", Operator::Divide),
+        ),
+    )
+}
+
+fn expression(input: &str) -> ParseResult<'_, Expression> {
+    chain(
+        input,
+        term,
+        either(
+            operator("+", Operator::Add),
+            operator("-", Operator::Subtract),
+        ),
+    )
+}
+
+impl FromStr for Expression {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        parse_all(expression, s.trim())
+    }
+}
+
+// A monkey's operation, along with whether it can be evaluated with modular
+// arithmetic.
+pub struct Operation {
+    pub expression: Expression,
+    pub modular: bool,
+}
+
+impl Operation {
+    fn new(expression: Expression) -> Self {
+        Self {
+            modular: expression.is_modular(),
+            expression,
+        }
+    }
+}
+
+// A way of keeping track of how worried we are about an item. Part B's worry
+// levels grow far too large to keep exactly, but only their divisibility by
+// each monkey's test matters, which some representations keep far more
+// cheaply.
+pub trait Worry: Sized {
+    // What every worry level needs to know, such as what to reduce them by.
+    type Context;
+
+    fn context(divisors: &[u64]) -> Self::Context;
+    fn new(level: u64, context: &Self::Context) -> Self;
+    fn evaluate(&self, operation: &Operation, context: &Self::Context) -> AocResult<Self>;
+    // Divides the worry level, rounding down, which only exact worry levels
+    // can do.
+    fn relieve(&self, divisor: u64) -> AocResult<Self>;
+    // Whether the worry level passes the test of the given monkey.
+    fn passes_test(&self, monkey: usize, context: &Self::Context) -> bool;
+    // The worry level itself, if it is kept exactly and fits in 64 bits.
+    fn exact(&self) -> Option<u64>;
+}
+
+// Worry levels kept exactly, which fails once they no longer fit in 64 bits.
+impl Worry for u64 {
+    type Context = Vec<u64>;
+
+    fn context(divisors: &[u64]) -> Self::Context {
+        divisors.to_vec()
+    }
+
+    fn new(level: u64, _: &Self::Context) -> Self {
+        level
+    }
+
+    fn evaluate(&self, operation: &Operation, _: &Self::Context) -> AocResult<Self> {
+        operation.expression.evaluate(*self)
+    }
+
+    fn relieve(&self, divisor: u64) -> AocResult<Self> {
+        Ok(self / divisor)
+    }
+
+    fn passes_test(&self, monkey: usize, divisors: &Self::Context) -> bool {
+        Integer::is_multiple_of(self, &divisors[monkey])
+    }
+
+    fn exact(&self) -> Option<u64> {
+        Some(*self)
+    }
+}
+
+// Worry levels kept exactly, however large they get.
+pub struct BigWorry(BigUint);
+
+impl Worry for BigWorry {
+    type Context = Vec<u64>;
+
+    fn context(divisors: &[u64]) -> Self::Context {
+        divisors.to_vec()
+    }
+
+    fn new(level: u64, _: &Self::Context) -> Self {
+        Self(BigUint::from(level))
+    }
+
+    fn evaluate(&self, operation: &Operation, _: &Self::Context) -> AocResult<Self> {
+        operation.expression.evaluate_big(&self.0).map(Self)
+    }
+
+    fn relieve(&self, divisor: u64) -> AocResult<Self> {
+        Ok(Self(&self.0 / divisor))
+    }
+
+    fn passes_test(&self, monkey: usize, divisors: &Self::Context) -> bool {
+        (&self.0 % divisors[monkey]).is_zero()
+    }
+
+    fn exact(&self) -> Option<u64> {
+        self.0.to_u64()
+    }
+}
+
+// Worry levels reduced modulo the least common multiple of every test's
+// divisor, which does not change which tests they pass.
+pub struct LcmWorry(u64);
+
+pub struct LcmContext {
+    lcm: u64,
+    divisors: Vec<u64>,
+}
+
+impl Worry for LcmWorry {
+    type Context = LcmContext;
+
+    fn context(divisors: &[u64]) -> Self::Context {
+        LcmContext {
+            lcm: divisors.iter().fold(1, |acc, n| acc.lcm(n)),
+            divisors: divisors.to_vec(),
+        }
+    }
+
+    fn new(level: u64, context: &Self::Context) -> Self {
+        Self(reduce(level, context.lcm))
+    }
+
+    fn evaluate(&self, operation: &Operation, context: &Self::Context) -> AocResult<Self> {
+        if !operation.modular {
+            return Err(AocError::no_solution(
+                "operations that divide or subtract cannot be evaluated on reduced worry levels",
+            ));
+        }
+        Ok(Self(operation.expression.evaluate_mod(self.0, context.lcm)))
+    }
+
+    fn relieve(&self, _: u64) -> AocResult<Self> {
+        Err(AocError::invalid_args(
+            "worry levels reduced by the tests' least common multiple cannot be relieved",
+        ))
+    }
+
+    fn passes_test(&self, monkey: usize, context: &Self::Context) -> bool {
+        Integer::is_multiple_of(&self.0, &context.divisors[monkey])
+    }
+
+    fn exact(&self) -> Option<u64> {
+        None
+    }
+}
+
+// Worry levels kept as their remainder for each test's divisor, as in a residue
+// number system. No residue grows beyond its own divisor, however many tests
+// there are.
+pub struct ResidueWorry(Vec<u64>);
+
+impl Worry for ResidueWorry {
+    type Context = Vec<u64>;
+
+    fn context(divisors: &[u64]) -> Self::Context {
+        divisors.to_vec()
+    }
+
+    fn new(level: u64, divisors: &Self::Context) -> Self {
+        Self(divisors.iter().map(|&d| reduce(level, d)).collect())
+    }
+
+    fn evaluate(&self, operation: &Operation, divisors: &Self::Context) -> AocResult<Self> {
+        if !operation.modular {
+            return Err(AocError::no_solution(
+                "operations that divide or subtract cannot be evaluated on residues",
+            ));
+        }
+        Ok(Self(
+            self.0
+                .iter()
+                .zip(divisors)
+                .map(|(&residue, &divisor)| operation.expression.evaluate_mod(residue, divisor))
+                .collect(),
+        ))
+    }
+
+    fn relieve(&self, _: u64) -> AocResult<Self> {
+        Err(AocError::invalid_args(
+            "worry levels kept as residues cannot be relieved",
+        ))
+    }
+
+    fn passes_test(&self, monkey: usize, _: &Self::Context) -> bool {
+        self.0[monkey] == 0
+    }
+
+    fn exact(&self) -> Option<u64> {
+        None
+    }
+}
+
+// How much our worry about an item drops after a monkey inspects it without
+// damaging it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReliefPolicy {
+    // Worry is divided, rounding down, as in part A.
+    Divide(u64),
+    // Worry does not drop at all, as in part B.
+    None,
+}
+
+impl ReliefPolicy {
+    // Reads `--relief N` to divide by `N`, where dividing by 1 is no relief.
+    fn from_args(day_args: &DayArgs, default: Self) -> AocResult<Self> {
+        match day_args.get("relief")? {
+            None => Ok(default),
+            Some(0) => Err(AocError::invalid_args("--relief must be at least 1")),
+            Some(1) => Ok(Self::None),
+            Some(divisor) => Ok(Self::Divide(divisor)),
+        }
+    }
+}
+
+struct Monkey<W> {
+    pub worry_levels: Vec<W>,
+    pub operation: Operation,
     pub divisible_test: u64,
     pub if_true: usize,
     pub if_false: usize,
     pub inspect_count: u64,
 }
 
-struct KeepAway {
-    pub monkeys: Vec<RefCell<Monkey>>,
-    maximum_worry_level: u64,
+// What a single monkey has done by the end of some round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonkeySnapshot {
+    pub inspect_count: u64,
+    pub item_count: usize,
+    // The worry levels of the monkey's items in the order it will inspect them,
+    // if the worry representation keeps them exactly.
+    pub worry_levels: Option<Vec<u64>>,
 }
 
-impl KeepAway {
-    pub fn new(monkeys: Vec<Monkey>) -> Self {
-        let maximum_worry_level = monkeys
-            .iter()
-            .map(|m| m.divisible_test)
-            .fold(1, |acc, n| acc.lcm(&n));
+// The state of the game at the end of some round, like the puzzle text shows
+// after rounds 1, 20, 1000, and so on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoundReport {
+    pub round: u64,
+    pub monkeys: Vec<MonkeySnapshot>,
+}
+
+impl RoundReport {
+    pub fn inspect_counts(&self) -> Vec<u64> {
+        self.monkeys.iter().map(|m| m.inspect_count).collect()
+    }
+}
+
+pub struct KeepAway<W: Worry> {
+    monkeys: Vec<RefCell<Monkey<W>>>,
+    context: W::Context,
+    relief: ReliefPolicy,
+    round: u64,
+}
+
+impl<W: Worry> KeepAway<W> {
+    pub fn from_input(input: &str, relief: ReliefPolicy) -> AocResult<Self> {
+        Ok(Self::new(read_monkeys(input)?, relief))
+    }
+
+    fn new(monkeys: Vec<Monkey<u64>>, relief: ReliefPolicy) -> Self {
+        let divisors = monkeys.iter().map(|m| m.divisible_test).collect::<Vec<_>>();
+        let context = W::context(&divisors);
+        let monkeys = monkeys
+            .into_iter()
+            .map(|monkey| {
+                RefCell::new(Monkey {
+                    worry_levels: monkey
+                        .worry_levels
+                        .into_iter()
+                        .map(|level| W::new(level, &context))
+                        .collect(),
+                    operation: monkey.operation,
+                    divisible_test: monkey.divisible_test,
+                    if_true: monkey.if_true,
+                    if_false: monkey.if_false,
+                    inspect_count: monkey.inspect_count,
+                })
+            })
+            .collect();
         Self {
-            monkeys: monkeys
-                .into_iter()
-                .map(|monkey| RefCell::new(monkey))
-                .collect(),
-            maximum_worry_level,
+            monkeys,
+            context,
+            relief,
+            round: 0,
         }
     }
 
-    pub fn do_round(&mut self, with_relief: bool) {
+    pub fn do_round(&mut self) -> AocResult<()> {
         for i in 0..self.monkeys.len() {
-            self.take_turn(i, with_relief);
+            self.take_turn(i)?;
+        }
+        self.round += 1;
+        Ok(())
+    }
+
+    // Plays until the end of round `rounds`, reporting the state of the game at
+    // the end of each round in `at`, along with the last one.
+    pub fn play_with_snapshots(&mut self, rounds: u64, at: &[u64]) -> AocResult<Vec<RoundReport>> {
+        let mut reports = Vec::new();
+        while self.round < rounds {
+            self.do_round()?;
+            if self.round == rounds || at.contains(&self.round) {
+                reports.push(self.report());
+            }
         }
+        Ok(reports)
     }
 
-    fn take_turn(&self, id: usize, with_relief: bool) {
+    pub fn report(&self) -> RoundReport {
+        RoundReport {
+            round: self.round,
+            monkeys: self
+                .monkeys
+                .iter()
+                .map(|monkey| {
+                    let monkey = monkey.borrow();
+                    MonkeySnapshot {
+                        inspect_count: monkey.inspect_count,
+                        item_count: monkey.worry_levels.len(),
+                        worry_levels: monkey.worry_levels.iter().map(W::exact).collect(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    // Monkeys inspect their items in the order they were caught, which only
+    // matters for the worry levels shown in a report.
+    fn take_turn(&self, id: usize) -> AocResult<()> {
         let mut monkey = self.monkeys[id].borrow_mut();
-        while let Some(mut item) = monkey.worry_levels.pop() {
+        for item in std::mem::take(&mut monkey.worry_levels) {
             monkey.inspect_count += 1;
-            item = (monkey.operation)(item);
 
-            if with_relief {
-                item /= 3;
-            } else if item > self.maximum_worry_level {
-                item = item.mod_floor(&self.maximum_worry_level);
+            let mut item = item.evaluate(&monkey.operation, &self.context)?;
+            if let ReliefPolicy::Divide(divisor) = self.relief {
+                item = item.relieve(divisor)?;
             }
 
-            if item.is_multiple_of(&monkey.divisible_test) {
-                self.monkeys[monkey.if_true]
-                    .borrow_mut()
-                    .worry_levels
-                    .push(item);
+            let target = if item.passes_test(id, &self.context) {
+                monkey.if_true
             } else {
-                self.monkeys[monkey.if_false]
-                    .borrow_mut()
-                    .worry_levels
-                    .push(item);
+                monkey.if_false
+            };
+            if target == id {
+                return Err(AocError::no_solution(format!(
+                    "monkey {id} throws an item to itself"
+                )));
             }
+            self.monkeys
+                .get(target)
+                .into_aoc_result_msg("item thrown to a monkey that does not exist")?
+                .borrow_mut()
+                .worry_levels
+                .push(item);
         }
+        Ok(())
     }
 
     pub fn monkey_business(&self) -> u64 {
@@ -74,54 +550,45 @@ impl KeepAway {
     }
 }
 
-fn read_monkeys(input: &str) -> AocResult<Vec<Monkey>> {
+fn read_monkeys(input: &str) -> AocResult<Vec<Monkey<u64>>> {
     input
         .newline_blocks(2)
         .map(|block| {
             let lines = block.lines().map(|line| line.trim()).collect::<Vec<_>>();
             if lines.len() != 6 {
-                return Err(AocError::new(&format!(
+                return Err(AocError::parse(format!(
                     "invalid input, found {} lines, expected 6",
                     lines.len()
                 )));
             }
 
-            let starting_levels = lines[1].parse_integers(10).collect();
-            let operation: Box<dyn Fn(u64) -> u64> = match lines[2].split_once(':') {
-                Some(("Operation", operation)) => {
-                    match operation.trim().split(' ').collect::<Vec<_>>().as_slice() {
-                        ["new", "=", "old", "*", "old"] => Box::new(|old| old * old),
-                        ["new", "=", "old", op, num] => {
-                            let n = num
-                                .parse::<u64>()
-                                .into_aoc_result_msg("invalid right operand")?;
-                            match *op {
-                                "+" => Box::new(move |old| old + n),
-                                "*" => Box::new(move |old| old * n),
-                                _ => return Err(AocError::new("unexpected operator")),
-                            }
-                        }
-                        _ => return Err(AocError::new("unexpected operation form")),
-                    }
-                }
-                _ => return Err(AocError::new("invalid operation")),
+            let starting_levels = lines[1].parse_integers(10).collect::<AocResult<_>>()?;
+            let operation = match lines[2].split_once(':') {
+                Some(("Operation", operation)) => match operation.trim().strip_prefix("new =") {
+                    Some(expression) => Expression::from_str(expression)?,
+                    None => return Err(AocError::parse("unexpected operation form")),
+                },
+                _ => return Err(AocError::parse("invalid operation")),
             };
             let divisible_test = lines[3]
                 .parse_integers(10)
                 .next()
-                .into_aoc_result_msg("missing divisible test number")?;
+                .into_aoc_result_msg("missing divisible test number")??;
+            if divisible_test == 0 {
+                return Err(AocError::parse("cannot test divisibility by 0"));
+            }
             let if_true = lines[4]
                 .parse_integers(10)
                 .next()
-                .into_aoc_result_msg("missing if true number")?;
+                .into_aoc_result_msg("missing if true number")??;
             let if_false = lines[5]
                 .parse_integers(10)
                 .next()
-                .into_aoc_result_msg("missing if false number")?;
+                .into_aoc_result_msg("missing if false number")??;
 
             Ok(Monkey {
                 worry_levels: starting_levels,
-                operation: Box::new(operation),
+                operation: Operation::new(operation),
                 divisible_test,
                 if_true,
                 if_false,
@@ -131,20 +598,183 @@ fn read_monkeys(input: &str) -> AocResult<Vec<Monkey>> {
         .collect()
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    const ROUNDS: u64 = 20;
-    let mut game = KeepAway::new(read_monkeys(input)?);
-    for _ in 0..ROUNDS {
-        game.do_round(true);
-    }
+// Plays keep away for `--rounds` rounds, relieving worry as `--relief` says.
+fn play<W: Worry>(
+    input: &str,
+    day_args: &DayArgs,
+    rounds: u64,
+    relief: ReliefPolicy,
+) -> AocResult<u64> {
+    let rounds = day_args.get_or("rounds", rounds)?;
+    let relief = ReliefPolicy::from_args(day_args, relief)?;
+    let mut game = timing::parse_phase(|| KeepAway::<W>::from_input(input, relief))?;
+    game.play_with_snapshots(rounds, &[])?;
     Ok(game.monkey_business())
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    const ROUNDS: u64 = 10000;
-    let mut game = KeepAway::new(read_monkeys(input)?);
-    for _ in 0..ROUNDS {
-        game.do_round(false);
+pub fn solve_a_with<W: Worry>(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    play::<W>(input, day_args, 20, ReliefPolicy::Divide(3))
+}
+
+pub fn solve_b_with<W: Worry>(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    play::<W>(input, day_args, 10000, ReliefPolicy::None)
+}
+
+pub fn solve_a(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    solve_a_with::<u64>(input, day_args)
+}
+
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    solve_b_with::<LcmWorry>(input, day_args)
+}
+
+register_day!(
+    11,
+    args solve_a | solve_a_with::<BigWorry>,
+    args solve_b | solve_b_with::<ResidueWorry>,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../examples/11.txt");
+
+    #[test]
+    fn representations_agree() {
+        let day_args = DayArgs::default();
+        assert_eq!(solve_a_with::<BigWorry>(EXAMPLE, &day_args).unwrap(), 10605);
+        let day_args = day_args.with("rounds", 1000);
+        let expected = 5204 * 5192;
+        assert_eq!(
+            solve_b_with::<LcmWorry>(EXAMPLE, &day_args).unwrap(),
+            expected
+        );
+        assert_eq!(
+            solve_b_with::<ResidueWorry>(EXAMPLE, &day_args).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn reports_worry_levels_like_puzzle_text() {
+        let mut game = KeepAway::<u64>::from_input(EXAMPLE, ReliefPolicy::Divide(3)).unwrap();
+        let reports = game.play_with_snapshots(20, &[1]).unwrap();
+        let worry_levels = |report: &RoundReport| {
+            report
+                .monkeys
+                .iter()
+                .map(|m| m.worry_levels.clone().unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(reports.iter().map(|r| r.round).collect::<Vec<_>>(), [1, 20]);
+        assert_eq!(
+            worry_levels(&reports[0]),
+            [
+                vec![20, 23, 27, 26],
+                vec![2080, 25, 167, 207, 401, 1046],
+                vec![],
+                vec![],
+            ]
+        );
+        assert_eq!(
+            worry_levels(&reports[1]),
+            [
+                vec![10, 12, 14, 26, 34],
+                vec![245, 93, 53, 199, 115],
+                vec![],
+                vec![],
+            ]
+        );
+        assert_eq!(reports[1].inspect_counts(), [101, 95, 7, 105]);
+    }
+
+    #[test]
+    fn reports_inspect_counts_like_puzzle_text() {
+        let mut game = KeepAway::<ResidueWorry>::from_input(EXAMPLE, ReliefPolicy::None).unwrap();
+        let reports = game
+            .play_with_snapshots(10000, &[1, 20, 1000, 5000])
+            .unwrap();
+        let inspect_counts = reports
+            .iter()
+            .map(|r| (r.round, r.inspect_counts()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inspect_counts,
+            [
+                (1, vec![2, 4, 3, 6]),
+                (20, vec![99, 97, 8, 103]),
+                (1000, vec![5204, 4792, 199, 5192]),
+                (5000, vec![26075, 23921, 974, 26000]),
+                (10000, vec![52166, 47830, 1938, 52013]),
+            ]
+        );
+        assert!(reports[0].monkeys[0].worry_levels.is_none());
+        assert_eq!(
+            reports[0]
+                .monkeys
+                .iter()
+                .map(|m| m.item_count)
+                .sum::<usize>(),
+            10
+        );
+    }
+
+    #[test]
+    fn big_worry_levels_do_not_overflow() {
+        // Without relief, squaring makes worry levels outgrow 64 bits quickly.
+        let day_args = DayArgs::default().with("relief", 1);
+        assert!(solve_a_with::<u64>(EXAMPLE, &day_args).is_err());
+        let big = solve_a_with::<BigWorry>(EXAMPLE, &day_args).unwrap();
+        assert_eq!(big, 99 * 103);
+        assert_eq!(
+            solve_b_with::<LcmWorry>(EXAMPLE, &day_args.with("rounds", 20)).unwrap(),
+            big
+        );
+    }
+
+    #[test]
+    fn only_exact_worry_levels_are_relieved() {
+        let day_args = DayArgs::default().with("relief", 3);
+        assert_eq!(
+            solve_b_with::<u64>(EXAMPLE, &day_args.clone().with("rounds", 20)).unwrap(),
+            10605
+        );
+        assert!(solve_b_with::<LcmWorry>(EXAMPLE, &day_args).is_err());
+        assert!(solve_b_with::<ResidueWorry>(EXAMPLE, &day_args).is_err());
+        assert!(solve_a(EXAMPLE, &DayArgs::default().with("relief", 0)).is_err());
+    }
+
+    #[test]
+    fn reduced_worry_levels_are_not_divided() {
+        // Halving after the monkey's operation is not modular, so only exact
+        // worry levels can follow it.
+        let input = EXAMPLE.replace("new = old * 19", "new = old * 19 / 2");
+        let day_args = DayArgs::default().with("relief", 1).with("rounds", 20);
+        assert!(solve_b_with::<BigWorry>(&input, &day_args).is_ok());
+        assert!(solve_b_with::<LcmWorry>(&input, &day_args).is_err());
+        assert!(solve_b_with::<ResidueWorry>(&input, &day_args).is_err());
+        // Relief divides as well, so it is refused in the same way.
+        let relieved = DayArgs::default().with("relief", 3).with("rounds", 20);
+        assert_eq!(solve_b_with::<BigWorry>(EXAMPLE, &relieved).unwrap(), 10605);
+        assert!(solve_b_with::<LcmWorry>(EXAMPLE, &relieved).is_err());
+    }
+
+    #[test]
+    fn negative_worry_levels_are_errors() {
+        // Exact worry levels fail once the difference goes negative, and
+        // reduced worry levels cannot tell when it does, so they refuse to
+        // subtract at all.
+        let input = EXAMPLE.replace("new = old + 6", "new = old - 60");
+        let day_args = DayArgs::default().with("relief", 1).with("rounds", 1);
+        let err = solve_b_with::<u64>(&input, &day_args).unwrap_err();
+        assert!(err.to_string().contains("54 Subtract 60"), "{err}");
+        assert!(solve_b_with::<BigWorry>(&input, &day_args).is_err());
+        assert!(solve_b_with::<LcmWorry>(&input, &day_args).is_err());
+        assert!(solve_b_with::<ResidueWorry>(&input, &day_args).is_err());
+        // Differences that stay positive are fine when kept exactly.
+        let input = EXAMPLE.replace("new = old + 6", "new = old - 0");
+        assert!(solve_b_with::<BigWorry>(&input, &day_args).is_ok());
+        assert!(solve_b_with::<LcmWorry>(&input, &day_args).is_err());
     }
-    Ok(game.monkey_business())
 }
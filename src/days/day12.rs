@@ -1,36 +1,81 @@
-use std::{
-    cell::RefCell,
-    collections::{HashMap, VecDeque},
-    str::FromStr,
+use std::str::FromStr;
+
+use rustc_hash::FxHashSet;
+
+use crate::common::{
+    search, timing,
+    viz::{self, Visualize},
+    AocError, AocResult, DayArgs, IntoAocResult, Point2, SolutionPart,
 };
 
-use num::Integer;
+type Point = Point2<i64>;
 
-use crate::common::{AocError, AocResult, IntoAocResult};
+// Which steps between neighboring heights are allowed, and what they cost.
+// Closures from the height stepped from and to can be used as well.
+pub trait ClimbRule {
+    fn step_cost(&self, from: u8, to: u8) -> Option<u64>;
 
-type Point = (u64, u64, u64);
-type Delta = (i64, i64, i64);
+    // The cost of every allowed step, if they all cost the same, so that a
+    // breadth-first search finds the cheapest path.
+    fn uniform_cost(&self) -> Option<u64> {
+        None
+    }
+}
 
-trait Transform<T>
-where
-    Self: Sized,
-{
-    fn transform(&self, delta: &T) -> Option<Self>;
+impl<F: Fn(u8, u8) -> Option<u64>> ClimbRule for F {
+    fn step_cost(&self, from: u8, to: u8) -> Option<u64> {
+        self(from, to)
+    }
 }
 
-impl Transform<Delta> for Point {
-    fn transform(&self, delta: &Delta) -> Option<Self> {
-        Some((
-            u64::try_from((self.0 as i64).checked_add(delta.0)?).ok()?,
-            u64::try_from((self.1 as i64).checked_add(delta.1)?).ok()?,
-            u64::try_from((self.2 as i64).checked_add(delta.2)?).ok()?,
-        ))
+// How far a single step may climb or descend, and how much each unit of height
+// climbed or descended adds to the step's cost of 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClimbLimits {
+    pub max_ascent: u8,
+    pub max_descent: u8,
+    pub ascent_cost: u64,
+    pub descent_cost: u64,
+}
+
+impl ClimbLimits {
+    // We can move down any distance, but only up by one.
+    pub const PUZZLE: Self = Self {
+        max_ascent: 1,
+        max_descent: b'z' - b'a',
+        ascent_cost: 0,
+        descent_cost: 0,
+    };
+
+    pub fn from_args(day_args: &DayArgs) -> AocResult<Self> {
+        Ok(Self {
+            max_ascent: day_args.get_or("max-ascent", Self::PUZZLE.max_ascent)?,
+            max_descent: day_args.get_or("max-descent", Self::PUZZLE.max_descent)?,
+            ascent_cost: day_args.get_or("ascent-cost", Self::PUZZLE.ascent_cost)?,
+            descent_cost: day_args.get_or("descent-cost", Self::PUZZLE.descent_cost)?,
+        })
     }
 }
 
-struct Heightmap {
-    pub flat_map: Vec<u64>,
-    pub width: usize,
+impl ClimbRule for ClimbLimits {
+    fn step_cost(&self, from: u8, to: u8) -> Option<u64> {
+        if to >= from {
+            let ascent = to - from;
+            (ascent <= self.max_ascent).then(|| 1 + ascent as u64 * self.ascent_cost)
+        } else {
+            let descent = from - to;
+            (descent <= self.max_descent).then(|| 1 + descent as u64 * self.descent_cost)
+        }
+    }
+
+    fn uniform_cost(&self) -> Option<u64> {
+        (self.ascent_cost == 0 && self.descent_cost == 0).then_some(1)
+    }
+}
+
+pub struct Heightmap {
+    pub heights: Vec<u8>,
+    pub size: Point,
     pub start: Point,
     pub end: Point,
 }
@@ -38,159 +83,406 @@ struct Heightmap {
 impl FromStr for Heightmap {
     type Err = AocError;
     fn from_str(s: &str) -> AocResult<Self> {
-        let start = RefCell::new((0, 0, 0));
-        let end = RefCell::new((0, 0, 0));
+        let mut start = None;
+        let mut end = None;
         let height = s.lines().count();
         let width = s.lines().next().into_aoc_result_msg("no row")?.len();
-        let mut flat_map = Vec::new();
-        flat_map.reserve(height * width);
-        // No iterators because of the mutable references to the start and end
-        // locations.
+        let mut heights = Vec::with_capacity(height * width);
         for (y, line) in s.lines().enumerate() {
+            if line.len() != width {
+                return Err(AocError::parse("heightmap rows are not the same length"));
+            }
             for (x, b) in line.bytes().enumerate() {
                 let h = match b {
                     b'S' => {
-                        let h = b'a' - b'a';
-                        *start.borrow_mut() = (x as u64, y as u64, h as u64);
-                        h
+                        start = Some(Point::new(x as i64, y as i64));
+                        0
                     }
                     b'E' => {
-                        let h = b'z' - b'a';
-                        *end.borrow_mut() = (x as u64, y as u64, h as u64);
-                        h
+                        end = Some(Point::new(x as i64, y as i64));
+                        b'z' - b'a'
                     }
                     b'a'..=b'z' => b - b'a',
                     _ => {
-                        return Err(AocError::new(&format!(
+                        return Err(AocError::parse(format!(
                             "invalid byte in heightmap: {}",
                             b as char
                         )))
                     }
                 };
-                flat_map.push(h as u64)
+                heights.push(h)
             }
         }
         Ok(Heightmap {
-            flat_map,
-            width,
-            start: start.into_inner(),
-            end: end.into_inner(),
+            heights,
+            size: Point::new(width as i64, height as i64),
+            start: start.into_aoc_result_msg("no start position")?,
+            end: end.into_aoc_result_msg("no end position")?,
         })
     }
 }
 
-const MOVES: [Delta; 4] = [(-1, 0, 0), (0, -1, 0), (1, 0, 0), (0, 1, 0)];
+impl Heightmap {
+    pub fn get(&self, point: &Point) -> Option<u8> {
+        if point.in_bounds(&Point::default(), &self.size) {
+            self.heights
+                .get((point.y * self.size.x + point.x) as usize)
+                .copied()
+        } else {
+            None
+        }
+    }
+
+    fn low_points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.heights
+            .iter()
+            .enumerate()
+            .filter(|(_, &h)| h == 0)
+            .map(|(i, _)| Point::new(i as i64 % self.size.x, i as i64 / self.size.x))
+    }
 
-struct Neighbors<'a> {
-    location: &'a Point,
-    i: usize,
-    end: usize,
-}
+    // Whether one step can be taken from one position to the other.
+    pub fn can_step(&self, from: &Point, to: &Point) -> bool {
+        from.manhattan_distance(to) == 1 && self.step_cost(from, to, &ClimbLimits::PUZZLE).is_some()
+    }
 
-impl<'a> Neighbors<'a> {
-    pub fn new(location: &'a Point) -> Self {
-        Self {
-            location,
-            i: 0,
-            end: MOVES.len(),
-        }
+    fn step_cost(&self, from: &Point, to: &Point, rule: &impl ClimbRule) -> Option<u64> {
+        rule.step_cost(self.get(from)?, self.get(to)?)
     }
-}
 
-impl<'a> Iterator for Neighbors<'a> {
-    type Item = Point;
-    fn next(&mut self) -> Option<Self::Item> {
-        while self.i < self.end {
-            let option = MOVES
-                .get(self.i)
-                .and_then(|delta| self.location.transform(delta));
-            self.i += 1;
-            if let Some(point) = option {
-                return Some(point);
-            }
-        }
-        None
+    // The neighbors that can be stepped to under the rule, with the cost of
+    // stepping to each.
+    fn neighbors_with<'a>(
+        &'a self,
+        position: &Point,
+        rule: &'a impl ClimbRule,
+    ) -> impl Iterator<Item = (Point, u64)> + 'a {
+        let position = *position;
+        position
+            .neighbors4()
+            .into_iter()
+            .filter_map(move |neighbor| {
+                Some((neighbor, self.step_cost(&position, &neighbor, rule)?))
+            })
     }
-}
 
-trait ExploreNeighbors {
-    fn explore_neighbors<'a>(&'a self) -> Neighbors<'a>;
-}
+    fn climbable_neighbors(&self, position: &Point) -> impl Iterator<Item = Point> + '_ {
+        self.neighbors_with(position, &ClimbLimits::PUZZLE)
+            .map(|(neighbor, _)| neighbor)
+    }
 
-impl ExploreNeighbors for Point {
-    fn explore_neighbors<'a>(&'a self) -> Neighbors<'a> {
-        Neighbors::new(self)
+    // The neighbors that can climb to this position, for searching backwards.
+    fn descendable_neighbors(&self, position: &Point) -> impl Iterator<Item = Point> + '_ {
+        let height = self.get(position).unwrap_or_default();
+        position
+            .neighbors4()
+            .into_iter()
+            .filter(move |neighbor| matches!(self.get(neighbor), Some(h) if h + 1 >= height))
     }
-}
 
-impl Heightmap {
-    pub fn get(&self, point: &Point) -> Option<u64> {
-        match self.width.overflowing_mul(point.1 as usize) {
-            (_, true) => None,
-            (offset, false) => match offset.overflowing_add(point.0 as usize) {
-                (_, true) => None,
-                (index, false) => self.flat_map.get(index).copied(),
-            },
+    pub fn shortest_path(&self) -> AocResult<u64> {
+        self.shortest_path_with(&ClimbLimits::PUZZLE)
+    }
+
+    // The cheapest climb from the start to the end under the rule.
+    pub fn shortest_path_with(&self, rule: &impl ClimbRule) -> AocResult<u64> {
+        self.cheapest_path_from([self.start], rule)
+    }
+
+    // The cheapest climb from any low point to the end under the rule. The
+    // puzzle's own rule is searched backwards from the end instead, as in
+    // `shortest_path_from_any_low_point`.
+    pub fn shortest_path_from_any_low_point_with(&self, rule: &impl ClimbRule) -> AocResult<u64> {
+        self.cheapest_path_from(self.low_points(), rule)
+    }
+
+    // Searches breadth-first when every step costs the same, and with Dijkstra's
+    // algorithm otherwise.
+    fn cheapest_path_from(
+        &self,
+        starts: impl IntoIterator<Item = Point>,
+        rule: &impl ClimbRule,
+    ) -> AocResult<u64> {
+        let is_end = |position: &Point| *position == self.end;
+        match rule.uniform_cost() {
+            Some(cost) => search::bfs(
+                starts,
+                |position| {
+                    self.neighbors_with(position, rule)
+                        .map(|(neighbor, _)| neighbor)
+                },
+                is_end,
+            )
+            .map(|steps| steps as u64 * cost),
+            None => search::dijkstra(
+                starts,
+                |position| self.neighbors_with(position, rule),
+                is_end,
+            ),
         }
+        .ok_or_else(|| AocError::no_solution("no path found"))
     }
 
-    pub fn shortest_path(&self, from_any_low_point: bool) -> AocResult<u64> {
-        // BFS implementation.
-        let mut to_explore = VecDeque::new();
-        let mut seen = HashMap::new();
-
-        if from_any_low_point {
-            for position in self
-                .flat_map
-                .iter()
-                .enumerate()
-                .filter(|(_, &h)| h == 0u64)
-                .map(|(i, h)| {
-                    let (y, x) = i.div_mod_floor(&self.width);
-                    (x as u64, y as u64, *h)
-                })
-            {
-                to_explore.push_back((position, 0));
-            }
+    // The cheapest route from the start to the end under the rule, including
+    // both of them.
+    pub fn weighted_route_with(
+        &self,
+        rule: &impl ClimbRule,
+    ) -> AocResult<search::Path<Point, u64>> {
+        search::dijkstra_path(
+            [self.start],
+            |position| self.neighbors_with(position, rule),
+            |position| *position == self.end,
+        )
+        .ok_or_else(|| AocError::no_solution("no path found"))
+    }
+
+    // Searches backwards from the end, so that only the closest low point needs to
+    // be reached rather than searching from every one of them.
+    pub fn shortest_path_from_any_low_point(&self) -> AocResult<u64> {
+        search::bfs_reverse(
+            [self.end],
+            |position| self.descendable_neighbors(position),
+            |position| self.get(position) == Some(0),
+        )
+        .map(|steps| steps as u64)
+        .ok_or_else(|| AocError::no_solution("no path found"))
+    }
+
+    // Every position on a shortest path from the start to the end, including
+    // both of them.
+    pub fn route(&self) -> AocResult<Vec<Point>> {
+        search::bfs_path(
+            [self.start],
+            |position| self.climbable_neighbors(position),
+            |position| *position == self.end,
+        )
+        .map(|path| path.states)
+        .ok_or_else(|| AocError::no_solution("no path found"))
+    }
+
+    // Every position on a shortest path from the closest low point to the end.
+    pub fn route_from_any_low_point(&self) -> AocResult<Vec<Point>> {
+        search::bfs_reverse_path(
+            [self.end],
+            |position| self.descendable_neighbors(position),
+            |position| self.get(position) == Some(0),
+        )
+        .map(|path| path.states)
+        .ok_or_else(|| AocError::no_solution("no path found"))
+    }
+
+    // Part A climbs from the start, and part B from any low point.
+    pub fn route_for(&self, part: SolutionPart) -> AocResult<Vec<Point>> {
+        if part == SolutionPart::A {
+            self.route()
         } else {
-            to_explore.push_back((self.start, 0));
+            self.route_from_any_low_point()
         }
+    }
+}
 
-        while let Some((position, steps)) = to_explore.pop_front() {
-            if position == self.end {
-                // We have reached our destination.
-                return Ok(steps);
-            }
+// Both parts can climb by other rules with `--max-ascent`, `--max-descent`,
+// `--ascent-cost`, and `--descent-cost`.
+pub fn solve_a(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let rule = ClimbLimits::from_args(day_args)?;
+    let heightmap = timing::parse_phase(|| Heightmap::from_str(input))?;
+    heightmap.shortest_path_with(&rule)
+}
 
-            if steps >= seen.get(&position).copied().unwrap_or(u64::MAX) {
-                // There is some better path than this one through this position, so ignore this
-                // path.
-                continue;
-            }
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let rule = ClimbLimits::from_args(day_args)?;
+    let heightmap = timing::parse_phase(|| Heightmap::from_str(input))?;
+    if rule == ClimbLimits::PUZZLE {
+        heightmap.shortest_path_from_any_low_point()
+    } else {
+        heightmap.shortest_path_from_any_low_point_with(&rule)
+    }
+}
 
-            seen.insert(position, steps);
+// The route taken up the hill for the given part.
+pub fn route(input: &str, part: SolutionPart) -> AocResult<Vec<Point>> {
+    Heightmap::from_str(input)?.route_for(part)
+}
 
-            for mut neighbor in position.explore_neighbors() {
-                if let Some(height) = self.get(&neighbor) {
-                    // Update the height of the next point with what the heightmap says.
-                    neighbor.2 = height;
-                    if neighbor.2 <= position.2 || neighbor.2 - position.2 == 1 {
-                        // We can move up or down to this point.
-                        to_explore.push_back((neighbor, steps + 1));
-                    }
+// The climb up the hill, one step at a time, over the heightmap.
+struct ClimbViz {
+    heightmap: Heightmap,
+    route: Vec<Point>,
+}
+
+impl Visualize for ClimbViz {
+    fn frames(&self) -> impl Iterator<Item = String> {
+        let max = self.heightmap.size - Point::new(1, 1);
+        // Every tile left behind so far, added to as the climb goes on.
+        let mut climbed = FxHashSet::default();
+        let mut previous = None;
+        self.route.iter().map(move |&position| {
+            climbed.extend(previous.replace(position));
+            viz::render_grid(Point::default(), max, |point| {
+                if point == position {
+                    '@'
+                } else if climbed.contains(&point) {
+                    '#'
+                } else if point == self.heightmap.end {
+                    'E'
+                } else {
+                    self.heightmap
+                        .get(&point)
+                        .map_or(' ', |height| (b'a' + height) as char)
                 }
-            }
-        }
-        Err(AocError::new("no path found"))
+            })
+        })
     }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
+pub fn visualize(input: &str, part: SolutionPart) -> AocResult<impl Visualize> {
     let heightmap = Heightmap::from_str(input)?;
-    heightmap.shortest_path(false)
+    let route = heightmap.route_for(part)?;
+    Ok(ClimbViz { heightmap, route })
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    let heightmap = Heightmap::from_str(input)?;
-    heightmap.shortest_path(true)
+register_day!(12, args solve_a, args solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Heightmap {
+        Heightmap::from_str(include_str!("../../examples/12.txt")).unwrap()
+    }
+
+    fn assert_legal_route(heightmap: &Heightmap, route: &[Point], steps: usize) {
+        assert_eq!(route.len(), steps + 1);
+        assert_eq!(route.last(), Some(&heightmap.end));
+        assert!(route
+            .iter()
+            .zip(route.iter().skip(1))
+            .all(|(from, to)| heightmap.can_step(from, to)));
+    }
+
+    #[test]
+    fn reconstructs_legal_routes() {
+        let heightmap = example();
+        let route = heightmap.route().unwrap();
+        assert_legal_route(&heightmap, &route, 31);
+        assert_eq!(route.first(), Some(&heightmap.start));
+        let route = heightmap.route_from_any_low_point().unwrap();
+        assert_legal_route(&heightmap, &route, 29);
+        assert_eq!(heightmap.get(&route[0]), Some(0));
+    }
+
+    #[test]
+    fn animates_every_step() {
+        let input = include_str!("../../examples/12.txt");
+        let frames = visualize(input, SolutionPart::A)
+            .unwrap()
+            .frames()
+            .collect::<Vec<_>>();
+        assert_eq!(frames.len(), 32);
+        assert!(frames[0].starts_with("@abqponm\n"));
+        assert_eq!(frames[31].matches('#').count(), 31);
+    }
+
+    #[test]
+    fn search_strategies_agree_from_start() {
+        let heightmap = example();
+        let successors = |position: &Point| heightmap.climbable_neighbors(position);
+        let predecessors = |position: &Point| heightmap.descendable_neighbors(position);
+        let is_end = |position: &Point| *position == heightmap.end;
+        let is_start = |position: &Point| *position == heightmap.start;
+        assert_eq!(search::bfs([heightmap.start], successors, is_end), Some(31));
+        assert_eq!(
+            search::bfs_reverse([heightmap.end], predecessors, is_start),
+            Some(31)
+        );
+        assert_eq!(
+            search::bfs_bidirectional([heightmap.start], [heightmap.end], successors, predecessors),
+            Some(31)
+        );
+    }
+
+    #[test]
+    fn search_strategies_agree_from_any_low_point() {
+        let heightmap = example();
+        let successors = |position: &Point| heightmap.climbable_neighbors(position);
+        let predecessors = |position: &Point| heightmap.descendable_neighbors(position);
+        let is_end = |position: &Point| *position == heightmap.end;
+        let is_low_point = |position: &Point| heightmap.get(position) == Some(0);
+        assert_eq!(
+            search::bfs(heightmap.low_points(), successors, is_end),
+            Some(29)
+        );
+        assert_eq!(
+            search::bfs_reverse([heightmap.end], predecessors, is_low_point),
+            Some(29)
+        );
+        assert_eq!(
+            search::bfs_bidirectional(
+                heightmap.low_points(),
+                [heightmap.end],
+                successors,
+                predecessors
+            ),
+            Some(29)
+        );
+    }
+
+    #[test]
+    fn climbs_by_other_rules() {
+        let heightmap = example();
+        // The puzzle's rule, searched breadth-first and with Dijkstra's algorithm
+        // through a closure.
+        let puzzle = |from: u8, to: u8| (to <= from + 1).then_some(1);
+        assert_eq!(
+            heightmap.shortest_path_with(&ClimbLimits::PUZZLE).unwrap(),
+            31
+        );
+        assert_eq!(heightmap.shortest_path_with(&puzzle).unwrap(), 31);
+        assert_eq!(
+            heightmap
+                .shortest_path_from_any_low_point_with(&puzzle)
+                .unwrap(),
+            29
+        );
+        // Climbing any height walks straight to the end.
+        let unlimited = ClimbLimits {
+            max_ascent: 25,
+            ..ClimbLimits::PUZZLE
+        };
+        assert_eq!(
+            heightmap.shortest_path_with(&unlimited).unwrap(),
+            heightmap.start.manhattan_distance(&heightmap.end) as u64
+        );
+        // The end is higher than the start, so it cannot be reached without
+        // climbing.
+        let flat = ClimbLimits {
+            max_ascent: 0,
+            ..ClimbLimits::PUZZLE
+        };
+        assert!(heightmap.shortest_path_with(&flat).is_err());
+    }
+
+    #[test]
+    fn weighs_steps_by_height_climbed() {
+        let heightmap = example();
+        let rule = ClimbLimits {
+            ascent_cost: 2,
+            descent_cost: 1,
+            ..ClimbLimits::PUZZLE
+        };
+        let route = heightmap.weighted_route_with(&rule).unwrap();
+        assert_eq!(route.states.first(), Some(&heightmap.start));
+        assert_eq!(route.states.last(), Some(&heightmap.end));
+        let cost = route
+            .states
+            .iter()
+            .zip(route.states.iter().skip(1))
+            .map(|(from, to)| heightmap.step_cost(from, to, &rule).unwrap())
+            .sum::<u64>();
+        assert_eq!(route.cost, cost);
+        assert_eq!(heightmap.shortest_path_with(&rule).unwrap(), route.cost);
+        // Every route climbs all 25 units of height.
+        assert!(route.cost >= 31 + 2 * 25);
+    }
 }
@@ -1,19 +1,82 @@
 use std::{
     cmp::Ordering,
-    collections::VecDeque,
     fmt::{Display, Formatter, Result as DisplayResult},
+    ops::Index,
     slice,
     str::FromStr,
 };
 
-use crate::common::{AocError, AocResult, IntoAocResult, NewlineBlocks};
+use crate::common::{
+    parse::{delimited, either, map, parse_all, separated_list, tag, unsigned, ParseResult},
+    timing, AocError, AocResult, DayArgs, IntoAocResult, NewlineBlocks,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum Packet {
+pub enum Packet {
     Integer(u64),
     List(Vec<Packet>),
 }
 
+impl Packet {
+    pub fn integer(n: u64) -> Self {
+        Self::Integer(n)
+    }
+
+    pub fn list(packets: impl IntoIterator<Item = Packet>) -> Self {
+        Self::List(packets.into_iter().collect())
+    }
+
+    // A list holding only this packet.
+    pub fn wrap(self) -> Self {
+        Self::List(vec![self])
+    }
+
+    // A divider packet, which is an integer wrapped in two lists, like `[[2]]`.
+    pub fn divider(n: u64) -> Self {
+        Self::integer(n).wrap().wrap()
+    }
+
+    // The packets in a list. An integer is compared as a list holding only
+    // itself, so that is what it is here too.
+    pub fn as_slice(&self) -> &[Packet] {
+        match self {
+            Self::Integer(_) => slice::from_ref(self),
+            Self::List(list) => list,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_slice().is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Packet> {
+        self.as_slice().get(index)
+    }
+}
+
+impl From<u64> for Packet {
+    fn from(n: u64) -> Self {
+        Self::Integer(n)
+    }
+}
+
+impl From<Vec<Packet>> for Packet {
+    fn from(packets: Vec<Packet>) -> Self {
+        Self::List(packets)
+    }
+}
+
+impl Index<usize> for Packet {
+    type Output = Packet;
+    fn index(&self, index: usize) -> &Packet {
+        &self.as_slice()[index]
+    }
+}
+
 impl Ord for Packet {
     fn cmp(&self, other: &Self) -> Ordering {
         match (self, other) {
@@ -31,7 +94,7 @@ impl Ord for Packet {
 
 impl PartialOrd for Packet {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
@@ -54,100 +117,221 @@ impl Display for Packet {
     }
 }
 
+// A packet is an integer or a bracketed, comma-separated list of packets.
+fn packet(input: &str) -> ParseResult<'_, Packet> {
+    either(
+        map(unsigned, Packet::Integer),
+        map(
+            delimited(tag("["), separated_list(packet, tag(",")), tag("]")),
+            Packet::List,
+        ),
+    )(input)
+}
+
 impl FromStr for Packet {
     type Err = AocError;
     fn from_str(s: &str) -> AocResult<Self> {
-        let mut chars = s.chars();
-        let mut stack = VecDeque::new();
-        let mut list = Vec::new();
-        let mut number = None;
-
-        while let Some(c) = chars.next() {
-            match c {
-                c if c.is_digit(10) => {
-                    number = Some(number.unwrap_or(0) * 10 + (c.to_digit(10).unwrap() as u64))
-                }
-                ',' => {
-                    if let Some(number) = number.take() {
-                        list.push(Packet::Integer(number));
-                    }
-                }
-                '[' => {
-                    stack.push_back((list, number));
-                    list = Vec::new();
-                    number = None;
-                }
-                ']' => {
-                    if let Some(number) = number.take() {
-                        list.push(Packet::Integer(number));
-                    }
+        parse_all(packet, s)
+    }
+}
 
-                    let packet = Packet::List(list);
-                    (list, number) = stack
-                        .pop_back()
-                        .into_aoc_result_msg("unexpected closing bracket")?;
-                    list.push(packet);
-                }
-                _ => return Err(AocError::new(&format!("nexpected char: {c}"))),
-            }
+// Packets are also valid JSON, so they can be read as JSON values instead.
+#[cfg(feature = "serde_json")]
+fn parse_packet_json(s: &str) -> AocResult<Packet> {
+    fn to_packet(value: serde_json::Value) -> AocResult<Packet> {
+        match value {
+            serde_json::Value::Number(n) => n
+                .as_u64()
+                .map(Packet::Integer)
+                .into_aoc_result_msg(&format!("invalid integer: {n}")),
+            serde_json::Value::Array(list) => list
+                .into_iter()
+                .map(to_packet)
+                .collect::<AocResult<_>>()
+                .map(Packet::List),
+            value => Err(AocError::parse(format!("invalid packet: {value}"))),
         }
+    }
+    to_packet(serde_json::from_str(s).into_aoc_result_msg(&format!("invalid packet: {s}"))?)
+}
 
-        if !stack.is_empty() {
-            Err(AocError::new("missing closing bracket(s)"))
-        } else {
-            Ok(list.remove(0))
-        }
+// Every packet received, in the pairs they arrived in.
+pub struct DistressSignal {
+    pub pairs: Vec<(Packet, Packet)>,
+}
+
+impl DistressSignal {
+    pub fn parse_with(input: &str, parse: fn(&str) -> AocResult<Packet>) -> AocResult<Self> {
+        input
+            .newline_blocks(2)
+            .map(|block| {
+                let mut lines = block.lines();
+                Ok((
+                    parse(lines.next().into_aoc_result_msg("missing first packet")?)?,
+                    parse(lines.next().into_aoc_result_msg("missing second packet")?)?,
+                ))
+            })
+            .collect::<AocResult<_>>()
+            .map(|pairs| Self { pairs })
+    }
+
+    // The sum of the indices, starting at 1, of pairs that are in the right
+    // order.
+    pub fn sum_ordered_indices(&self) -> u64 {
+        self.pairs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (left, right))| {
+                if left < right {
+                    Some((i + 1) as u64)
+                } else {
+                    None
+                }
+            })
+            .sum()
+    }
+
+    // Sorts every packet along with the dividers, returning the decoder key,
+    // which is the product of the positions of the dividers, starting at 1. A
+    // divider equal to another packet is placed before it.
+    pub fn sort_with_dividers(&self, dividers: &[Packet]) -> u64 {
+        let mut packets = self
+            .pairs
+            .iter()
+            .flat_map(|(left, right)| [left, right])
+            .chain(dividers)
+            .collect::<Vec<_>>();
+        packets.sort();
+        dividers
+            .iter()
+            .map(|divider| (packets.partition_point(|packet| *packet < divider) + 1) as u64)
+            .product()
     }
 }
 
-fn parse_packet_pairs(input: &str) -> AocResult<Vec<(Packet, Packet)>> {
-    input
-        .newline_blocks(2)
-        .map(|block| {
-            let mut lines = block.lines();
-            Ok((
-                Packet::from_str(lines.next().into_aoc_result_msg("missing first packet")?)?,
-                Packet::from_str(lines.next().into_aoc_result_msg("missing second packet")?)?,
-            ))
-        })
-        .collect()
+impl FromStr for DistressSignal {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        Self::parse_with(s, Packet::from_str)
+    }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    Ok(parse_packet_pairs(input)?
-        .iter()
-        .enumerate()
-        .filter_map(|(i, (left, right))| {
-            if left < right {
-                Some((i + 1) as u64)
-            } else {
-                None
+// The divider packets to sort with, `[[2]]` and `[[6]]` unless others are
+// given with `--dividers`, separated by spaces.
+fn dividers(day_args: &DayArgs) -> AocResult<Vec<Packet>> {
+    match day_args.get::<String>("dividers")? {
+        None => Ok(vec![Packet::divider(2), Packet::divider(6)]),
+        Some(dividers) => {
+            let dividers = dividers
+                .split_whitespace()
+                .map(Packet::from_str)
+                .collect::<AocResult<Vec<_>>>()
+                .map_err(|err| {
+                    AocError::invalid_args(format!("invalid divider: {}", err.message()))
+                })?;
+            match dividers.is_empty() {
+                true => Err(AocError::invalid_args(
+                    "--dividers needs at least one packet",
+                )),
+                false => Ok(dividers),
             }
-        })
-        .sum())
-}
-
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    let dividers = vec![
-        Packet::List(vec![Packet::List(vec![Packet::Integer(2)])]),
-        Packet::List(vec![Packet::List(vec![Packet::Integer(6)])]),
-    ];
-    let mut packets = parse_packet_pairs(input)?
-        .into_iter()
-        .map(|(left, right)| [left, right])
-        .flatten()
-        .collect::<Vec<_>>();
-    packets.extend(dividers.clone().into_iter());
-    packets.sort();
-    Ok(dividers
-        .into_iter()
-        .map(|divider| {
-            packets
-                .binary_search(&divider)
-                .into_aoc_result_msg(&format!("failed to find divider {divider:?}"))
-                .and_then(|i| Ok(i + 1))
-        })
-        .collect::<AocResult<Vec<_>>>()?
-        .into_iter()
-        .product::<usize>() as u64)
+        }
+    }
+}
+
+pub fn solve_a(input: &str) -> AocResult<u64> {
+    Ok(timing::parse_phase(|| DistressSignal::from_str(input))?.sum_ordered_indices())
+}
+
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let dividers = dividers(day_args)?;
+    Ok(timing::parse_phase(|| DistressSignal::from_str(input))?.sort_with_dividers(&dividers))
+}
+
+// Alternate implementations that read packets with serde_json, checked against
+// the hand-written parser with `--check`.
+#[cfg(feature = "serde_json")]
+pub fn solve_a_with_serde(input: &str) -> AocResult<u64> {
+    Ok(
+        timing::parse_phase(|| DistressSignal::parse_with(input, parse_packet_json))?
+            .sum_ordered_indices(),
+    )
+}
+
+#[cfg(feature = "serde_json")]
+pub fn solve_b_with_serde(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let dividers = dividers(day_args)?;
+    Ok(
+        timing::parse_phase(|| DistressSignal::parse_with(input, parse_packet_json))?
+            .sort_with_dividers(&dividers),
+    )
+}
+
+#[cfg(feature = "serde_json")]
+register_day!(13, solve_a | solve_a_with_serde, args solve_b | solve_b_with_serde);
+#[cfg(not(feature = "serde_json"))]
+register_day!(13, solve_a, args solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../examples/13.txt");
+
+    #[test]
+    fn builds_and_indexes_packets() {
+        let packet = Packet::from_str("[1,[2,[3]],4]").unwrap();
+        assert_eq!(packet.len(), 3);
+        assert_eq!(packet[0], Packet::integer(1));
+        assert_eq!(packet[1][1], Packet::integer(3).wrap());
+        assert_eq!(packet.get(3), None);
+        assert_eq!(
+            packet,
+            Packet::list([
+                1.into(),
+                vec![2.into(), Packet::integer(3).wrap()].into(),
+                4.into()
+            ])
+        );
+        // An integer acts like a list of just itself.
+        assert_eq!(Packet::integer(7)[0], Packet::integer(7));
+        assert!(Packet::list([]).is_empty());
+        assert_eq!(Packet::divider(2).to_string(), "[[2]]");
+    }
+
+    #[test]
+    fn sorts_with_custom_dividers() {
+        let signal = DistressSignal::from_str(EXAMPLE).unwrap();
+        assert_eq!(
+            signal.sort_with_dividers(&[Packet::divider(2), Packet::divider(6)]),
+            140
+        );
+        // `[]` sorts before every other packet, and `[10]` after all of them.
+        assert_eq!(signal.sort_with_dividers(&[Packet::list([])]), 1);
+        assert_eq!(
+            signal.sort_with_dividers(&[Packet::list([]), Packet::integer(10).wrap()]),
+            18
+        );
+
+        let dividers = |dividers: &str| DayArgs::default().with("dividers", dividers);
+        assert_eq!(solve_b(EXAMPLE, &DayArgs::default()).unwrap(), 140);
+        assert_eq!(solve_b(EXAMPLE, &dividers("[[2]] [[6]]")).unwrap(), 140);
+        assert_eq!(solve_b(EXAMPLE, &dividers("[[6]]")).unwrap(), 13);
+        assert!(solve_b(EXAMPLE, &dividers("")).is_err());
+        assert!(solve_b(EXAMPLE, &dividers("[[2]")).is_err());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn parses_packets_as_json() {
+        assert_eq!(
+            DistressSignal::parse_with(EXAMPLE, parse_packet_json)
+                .unwrap()
+                .pairs,
+            DistressSignal::from_str(EXAMPLE).unwrap().pairs
+        );
+        assert!(parse_packet_json("[1,[2,\"3\"]]").is_err());
+        assert!(parse_packet_json("[1,-2]").is_err());
+        assert!(parse_packet_json("[1,2").is_err());
+    }
 }
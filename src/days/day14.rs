@@ -1,47 +1,138 @@
 use std::{
     collections::{HashMap, VecDeque},
+    iter,
     str::FromStr,
 };
 
-use crate::common::{AocError, AocResult, IntoAocResult};
+use crate::common::{
+    render::{Color, GridImage},
+    timing,
+    viz::{self, Visualize},
+    AocError, AocResult, IntoAocResult, Point2, SolutionPart,
+};
 use itertools::Itertools;
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Tile {
-    Rock,
-    Sand,
+    Rock = 1,
+    Sand = 2,
+}
+
+impl Tile {
+    // Empty tiles are stored as 0 in a dense grid.
+    const EMPTY: u8 = 0;
+
+    fn from_u8(tile: u8) -> Option<Self> {
+        match tile {
+            1 => Some(Self::Rock),
+            2 => Some(Self::Sand),
+            _ => None,
+        }
+    }
 }
 
-type Point = (u64, u64);
-type Delta = (i64, i64);
+type Point = Point2<i64>;
 
-trait Transform<T>
-where
-    Self: Sized,
-{
-    fn transform(&self, delta: &T) -> Option<Self>;
+// The tiles of a cave. Most caves are small enough to store every tile in a
+// grid, but caves so spread out that a grid would be too large keep only the
+// tiles that are filled.
+#[derive(Clone)]
+enum Tiles {
+    Dense {
+        min: Point,
+        width: usize,
+        height: usize,
+        tiles: Vec<u8>,
+    },
+    Sparse(HashMap<Point, Tile>),
 }
 
-impl Transform<Delta> for Point {
-    fn transform(&self, delta: &Delta) -> Option<Self> {
-        Some((
-            u64::try_from((self.0 as i64).checked_add(delta.0)?).ok()?,
-            u64::try_from((self.1 as i64).checked_add(delta.1)?).ok()?,
-        ))
+impl Tiles {
+    // The most tiles stored in a grid, which takes one byte per tile.
+    const MAX_DENSE_TILES: usize = 1 << 24;
+
+    // Room for every tile between the corners, inclusive.
+    fn new(min: Point, max: Point) -> Self {
+        let (width, height) = ((max.x - min.x + 1) as usize, (max.y - min.y + 1) as usize);
+        match width.checked_mul(height) {
+            Some(size) if size <= Self::MAX_DENSE_TILES => Self::Dense {
+                min,
+                width,
+                height,
+                tiles: vec![Tile::EMPTY; size],
+            },
+            _ => Self::Sparse(HashMap::new()),
+        }
+    }
+
+    fn index(min: Point, width: usize, height: usize, point: &Point) -> Option<usize> {
+        let (x, y) = (point.x - min.x, point.y - min.y);
+        (x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height)
+            .then(|| y as usize * width + x as usize)
+    }
+
+    fn get(&self, point: &Point) -> Option<Tile> {
+        match self {
+            Self::Dense {
+                min,
+                width,
+                height,
+                tiles,
+            } => Tile::from_u8(tiles[Self::index(*min, *width, *height, point)?]),
+            Self::Sparse(map) => map.get(point).copied(),
+        }
+    }
+
+    fn set(&mut self, point: &Point, tile: Tile) {
+        match self {
+            Self::Dense {
+                min,
+                width,
+                height,
+                tiles,
+            } => match Self::index(*min, *width, *height, point) {
+                Some(i) => tiles[i] = tile as u8,
+                // Nothing is lost by moving to a map, only speed.
+                None => {
+                    let mut map = self.iter().collect::<HashMap<_, _>>();
+                    map.insert(*point, tile);
+                    *self = Self::Sparse(map);
+                }
+            },
+            Self::Sparse(map) => {
+                map.insert(*point, tile);
+            }
+        }
+    }
+
+    // Every filled tile.
+    fn iter(&self) -> Box<dyn Iterator<Item = (Point, Tile)> + '_> {
+        match self {
+            Self::Dense {
+                min, width, tiles, ..
+            } => Box::new(tiles.iter().enumerate().filter_map(move |(i, tile)| {
+                let point = *min + Point::new((i % width) as i64, (i / width) as i64);
+                Some((point, Tile::from_u8(*tile)?))
+            })),
+            Self::Sparse(map) => Box::new(map.iter().map(|(point, tile)| (*point, *tile))),
+        }
     }
 }
 
+#[derive(Clone)]
 struct CaveMap {
-    map: HashMap<Point, Tile>,
-    deepest: u64,
+    tiles: Tiles,
+    // The corners of the smallest rectangle containing every rock.
+    rocks: (Point, Point),
+    deepest: i64,
     floor: bool,
 }
 
 impl FromStr for CaveMap {
     type Err = AocError;
     fn from_str(s: &str) -> AocResult<Self> {
-        let mut map = HashMap::new();
+        let mut rocks = Vec::new();
         for line in s.lines() {
             let coords = line
                 .split("->")
@@ -51,45 +142,53 @@ impl FromStr for CaveMap {
                         .split_once(',')
                         .into_aoc_result_msg("invalid coordinates")
                         .and_then(|(x, y)| {
-                            Ok((
-                                x.parse::<u64>()
+                            Ok(Point::new(
+                                x.parse::<i64>()
                                     .into_aoc_result_msg("invalid x coordinate")?,
-                                y.parse::<u64>()
+                                y.parse::<i64>()
                                     .into_aoc_result_msg("invalid y coordinate")?,
                             ))
                         })
                 })
                 .collect::<AocResult<Vec<_>>>()?;
             for (from, to) in coords.iter().tuple_windows() {
-                match (from, to) {
-                    ((x1, y1), (x2, y2)) if x1 == x2 => {
-                        for y in *y1.min(y2)..=(*y1.max(y2)) {
-                            map.insert((*x1, y), Tile::Rock);
-                        }
-                    }
-                    ((x1, y1), (x2, y2)) if y1 == y2 => {
-                        for x in *x1.min(x2)..=(*x1.max(x2)) {
-                            map.insert((x, *y1), Tile::Rock);
-                        }
-                    }
-                    _ => return Err(AocError::new("cannot draw diagonal wall")),
+                if from.x != to.x && from.y != to.y {
+                    return Err(AocError::parse("cannot draw diagonal wall"));
+                }
+                let step = (*to - *from).signum();
+                let mut point = *from;
+                rocks.push(point);
+                while point != *to {
+                    point += step;
+                    rocks.push(point);
                 }
             }
         }
-        Self::from_map(map)
+        Self::from_rocks(rocks)
     }
 }
 
 impl CaveMap {
-    pub fn from_map(map: HashMap<Point, Tile>) -> AocResult<Self> {
-        let deepest = map
-            .iter()
-            .max_by_key(|((_, y), _)| y)
-            .into_aoc_result_msg("failed to find deepest height in cave")?
-            .0
-             .1;
+    pub fn from_rocks(rocks: Vec<Point>) -> AocResult<Self> {
+        let bounds = viz::bounds(rocks.iter().copied())
+            .into_aoc_result_msg("failed to find deepest height in cave")?;
+        let deepest = bounds.1.y;
+        // Sand from the source spreads out at most one tile for each tile it
+        // falls, and it never falls past the floor.
+        let spread = deepest + 2 - SAND_SOURCE.y.min(bounds.0.y);
+        let mut tiles = Tiles::new(
+            Point::new(
+                bounds.0.x.min(SAND_SOURCE.x - spread) - 1,
+                bounds.0.y.min(SAND_SOURCE.y),
+            ),
+            Point::new(bounds.1.x.max(SAND_SOURCE.x + spread) + 1, deepest + 2),
+        );
+        for point in rocks {
+            tiles.set(&point, Tile::Rock);
+        }
         Ok(Self {
-            map,
+            tiles,
+            rocks: bounds,
             deepest,
             floor: false,
         })
@@ -100,78 +199,243 @@ impl CaveMap {
     }
 
     pub fn get(&self, point: &Point) -> Option<Tile> {
-        if self.floor && point.1 == self.deepest + 2 {
+        if self.floor && point.y == self.deepest + 2 {
             Some(Tile::Rock)
         } else {
-            self.map.get(point).copied()
+            self.tiles.get(point)
         }
     }
 
     pub fn set(&mut self, point: &Point, tile: Tile) {
-        self.map.insert(*point, tile);
+        self.tiles.set(point, tile);
     }
 
-    const SAND_MOVES: [Delta; 3] = [(0, 1), (-1, 1), (1, 1)];
+    const SAND_MOVES: [Point; 3] = [Point::new(0, 1), Point::new(-1, 1), Point::new(1, 1)];
 
-    fn pour_sand(&mut self, source: Point) -> AocResult<u64> {
-        let mut sand_count = 0;
-        // We keep a stack of the current path. Once a single piece of sand has come to
-        // rest, the next piece immediately starts at the previous position.
-        let mut path = VecDeque::from([source]);
-        'outer: loop {
-            let resting_position;
-            'inner: loop {
-                // Current sand position.
-                let sand_position = path
-                    .back()
-                    .into_aoc_result_msg("missing last sand position")?;
-
-                if !self.floor && sand_position.1 > self.deepest {
-                    // This piece of sand will begin falling infinitely.
-                    break 'outer;
-                }
+    // Drops a single piece of sand, returning where it came to rest, or nothing if
+    // it falls forever.
+    //
+    // We keep a stack of the current path. Once a single piece of sand has come to
+    // rest, the next piece immediately starts at the previous position.
+    fn drop_sand(&mut self, path: &mut VecDeque<Point>) -> AocResult<Option<Point>> {
+        loop {
+            // Current sand position.
+            let sand_position = path
+                .back()
+                .into_aoc_result_msg("missing last sand position")?;
 
-                // Find the first move that puts us in an open space.
-                match Self::SAND_MOVES
-                    .iter()
-                    .map(|delta| sand_position.transform(delta))
-                    .find(|pos| match pos {
-                        None => false,
-                        Some(pos) => self.get(pos).is_none(),
-                    }) {
-                    // Found a new position to move to.
-                    Some(Some(pos)) => path.push_back(pos),
-                    // Failed to find a new position; this sand is at rest.
-                    _ => {
-                        // Unwrap is safe here because we checked that the back exists at the
-                        // beginning of this loop iteration.
-                        resting_position = path.pop_back().unwrap();
-                        break 'inner;
-                    }
+            if !self.floor && sand_position.y > self.deepest {
+                // This piece of sand will begin falling infinitely.
+                return Ok(None);
+            }
+
+            // Find the first move that puts us in an open space.
+            match Self::SAND_MOVES
+                .iter()
+                .map(|delta| *sand_position + *delta)
+                .find(|pos| self.get(pos).is_none())
+            {
+                // Found a new position to move to.
+                Some(pos) => path.push_back(pos),
+                // Failed to find a new position; this sand is at rest.
+                _ => {
+                    // Unwrap is safe here because we checked that the back exists at the
+                    // beginning of this loop iteration.
+                    let resting_position = path.pop_back().unwrap();
+                    self.set(&resting_position, Tile::Sand);
+                    return Ok(Some(resting_position));
                 }
             }
+        }
+    }
 
+    fn pour_sand(&mut self, source: Point) -> AocResult<u64> {
+        let mut sand_count = 0;
+        let mut path = VecDeque::from([source]);
+        while let Some(resting_position) = self.drop_sand(&mut path)? {
             sand_count += 1;
-            self.set(&resting_position, Tile::Sand);
-
             if resting_position == source {
                 // This piece of sand did not move, so the source is covered.
-                break 'outer;
+                break;
             }
         }
         Ok(sand_count)
     }
+
+    // Counts the sand that comes to rest above the floor without dropping any of
+    // it. Sand keeps piling up until the source is covered, and every grain that
+    // rests lets the next ones reach all three tiles below it, so the sand ends up
+    // filling every tile reachable from the source. Those are found one row at a
+    // time, each row from the open tiles below the row above it.
+    fn fill_to_floor(&self, source: Point) -> AocResult<u64> {
+        if !self.floor {
+            return Err(AocError::no_solution(
+                "sand only fills the cave when there is a floor",
+            ));
+        }
+        let floor = self.deepest + 2;
+        let mut row = vec![source.x];
+        let mut sand_count = 0;
+        for y in source.y..floor {
+            sand_count += row.len() as u64;
+            row = row
+                .iter()
+                .flat_map(|x| [x - 1, *x, x + 1])
+                .filter(|x| self.get(&Point::new(*x, y + 1)).is_none())
+                .sorted()
+                .dedup()
+                .collect();
+        }
+        Ok(sand_count)
+    }
+
+    // Every tile that can be reached by falling sand, as the top-left and
+    // bottom-right corners of a rectangle.
+    fn bounds(&self, source: Point) -> AocResult<(Point, Point)> {
+        let (min, max) = viz::bounds([self.rocks.0, self.rocks.1, source])
+            .into_aoc_result_msg("cave is empty")?;
+        if self.floor {
+            // Sand piles up in a triangle under the source.
+            let spread = self.deepest + 2 - source.y;
+            Ok((
+                Point::new(min.x.min(source.x - spread), min.y),
+                Point::new(max.x.max(source.x + spread), self.deepest + 2),
+            ))
+        } else {
+            // Sand can come to rest just beside the outermost rocks before falling
+            // off of the edge.
+            Ok((Point::new(min.x - 1, min.y), Point::new(max.x + 1, max.y)))
+        }
+    }
+
+    fn render(&self, source: Point, min: Point, max: Point) -> String {
+        viz::render_grid(min, max, |point| match self.get(&point) {
+            Some(Tile::Rock) => '#',
+            Some(Tile::Sand) => 'o',
+            None if point == source => '+',
+            None => '.',
+        })
+    }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    const SAND_SOURCE: Point = (500, 0);
+const SAND_SOURCE: Point = Point::new(500, 0);
+
+// Sand filling up the cave, one piece at a time.
+struct SandViz {
+    cave: CaveMap,
+    min: Point,
+    max: Point,
+}
+
+impl Visualize for SandViz {
+    fn frames(&self) -> impl Iterator<Item = String> {
+        let mut cave = self.cave.clone();
+        let mut path = VecDeque::from([SAND_SOURCE]);
+        let first = cave.render(SAND_SOURCE, self.min, self.max);
+        iter::once(first).chain(iter::from_fn(move || {
+            // Stops once sand falls forever, or once the source is covered and
+            // there is no path left to drop sand from.
+            cave.drop_sand(&mut path).ok()??;
+            Some(cave.render(SAND_SOURCE, self.min, self.max))
+        }))
+    }
+}
+
+// The cave once sand stops coming to rest.
+pub fn snapshot(input: &str, part: SolutionPart) -> AocResult<GridImage> {
     let mut cave = CaveMap::from_str(input)?;
+    if part == SolutionPart::B {
+        cave.add_floor();
+    }
+    let (min, max) = cave.bounds(SAND_SOURCE)?;
+    cave.pour_sand(SAND_SOURCE)?;
+    let size = max - min + Point::new(1, 1);
+    let mut image = GridImage::new(size.x as usize, size.y as usize, Color(30, 30, 30));
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            let color = match cave.get(&Point::new(x, y)) {
+                Some(Tile::Rock) => Color(110, 110, 120),
+                Some(Tile::Sand) => Color(230, 190, 100),
+                None => continue,
+            };
+            image.set((x - min.x) as usize, (y - min.y) as usize, color);
+        }
+    }
+    Ok(image)
+}
+
+pub fn visualize(input: &str, part: SolutionPart) -> AocResult<impl Visualize> {
+    let mut cave = CaveMap::from_str(input)?;
+    if part == SolutionPart::B {
+        cave.add_floor();
+    }
+    let (min, max) = cave.bounds(SAND_SOURCE)?;
+    Ok(SandViz { cave, min, max })
+}
+
+pub fn solve_a(input: &str) -> AocResult<u64> {
+    let mut cave = timing::parse_phase(|| CaveMap::from_str(input))?;
     cave.pour_sand(SAND_SOURCE)
 }
 
 pub fn solve_b(input: &str) -> AocResult<u64> {
-    const SAND_SOURCE: Point = (500, 0);
-    let mut cave = CaveMap::from_str(input)?;
+    let mut cave = timing::parse_phase(|| CaveMap::from_str(input))?;
     cave.add_floor();
     cave.pour_sand(SAND_SOURCE)
 }
+
+// Alternate implementation that finds where the sand ends up instead of
+// dropping every grain, checked against the simulation with `--check`.
+pub fn solve_b_by_filling(input: &str) -> AocResult<u64> {
+    let mut cave = timing::parse_phase(|| CaveMap::from_str(input))?;
+    cave.add_floor();
+    cave.fill_to_floor(SAND_SOURCE)
+}
+
+register_day!(14, solve_a, solve_b | solve_b_by_filling);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filling_matches_pouring() {
+        for input in [
+            include_str!("../../examples/14.txt"),
+            // A cup that traps a pocket of air below its rim.
+            "495,4 -> 495,8 -> 505,8 -> 505,4\n499,6 -> 501,6",
+            // A shelf right below the source.
+            "499,1 -> 501,1",
+        ] {
+            let mut cave = CaveMap::from_str(input).unwrap();
+            cave.add_floor();
+            assert!(matches!(cave.tiles, Tiles::Dense { .. }));
+            let filled = cave.fill_to_floor(SAND_SOURCE).unwrap();
+            assert_eq!(filled, cave.pour_sand(SAND_SOURCE).unwrap(), "{input}");
+        }
+    }
+
+    #[test]
+    fn stores_spread_out_caves_sparsely() {
+        let input = "0,5 -> 0,6\n100000000,5 -> 100000000,6\n499,8 -> 501,8";
+        let mut cave = CaveMap::from_str(input).unwrap();
+        assert!(matches!(cave.tiles, Tiles::Sparse(_)));
+        let mut shelf = CaveMap::from_str("499,8 -> 501,8").unwrap();
+        assert!(matches!(shelf.tiles, Tiles::Dense { .. }));
+        assert_eq!(
+            cave.pour_sand(SAND_SOURCE).unwrap(),
+            shelf.pour_sand(SAND_SOURCE).unwrap()
+        );
+    }
+
+    #[test]
+    fn keeps_tiles_set_outside_of_the_grid() {
+        let mut tiles = Tiles::new(Point::new(0, 0), Point::new(2, 2));
+        tiles.set(&Point::new(1, 1), Tile::Rock);
+        tiles.set(&Point::new(5, -1), Tile::Sand);
+        assert!(matches!(tiles, Tiles::Sparse(_)));
+        assert_eq!(tiles.get(&Point::new(1, 1)), Some(Tile::Rock));
+        assert_eq!(tiles.get(&Point::new(5, -1)), Some(Tile::Sand));
+        assert_eq!(tiles.get(&Point::new(0, 0)), None);
+    }
+}
@@ -1,52 +1,20 @@
-use std::{collections::VecDeque, str::FromStr};
+use std::str::FromStr;
 
-use crate::common::{AocError, AocResult, IntoAocResult, ParseIntegers};
+use crate::common::{
+    constants::Constants,
+    interval::{Interval, IntervalSet},
+    timing, AocError, AocResult, DayArgs, IntoAocResult, ParseIntegers, Point2,
+};
 use itertools::{iproduct, Itertools};
 
-// A single point on a 2D plane.
-#[derive(Debug, Clone, Copy)]
-struct Point {
-    pub x: i64,
-    pub y: i64,
-}
-
-impl Point {
-    pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
-    }
-
-    pub fn manhatten_distance(&self, other: &Point) -> u64 {
-        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
-    }
-
-    pub fn tuning_frequency(&self) -> i64 {
-        self.x * 4_000_000 + self.y
-    }
-}
-
-// A range of integers.
-#[derive(Debug, Clone, Copy)]
-struct Range {
-    pub begin: i64,
-    pub end: i64,
-}
-
-impl Range {
-    pub fn new(begin: i64, end: i64) -> Self {
-        Self { begin, end }
-    }
+type Point = Point2<i64>;
 
-    pub fn contains(&self, num: i64) -> bool {
-        self.begin <= num && num <= self.end
-    }
-
-    pub fn size(&self) -> i64 {
-        self.end - self.begin
-    }
+fn tuning_frequency(point: &Point) -> i64 {
+    point.x * 4_000_000 + point.y
 }
 
 // A scanned area in a Manhatten 2D plane.
-struct ScannedArea {
+pub struct ScannedArea {
     pub center: Point,
     pub radius: u64,
 }
@@ -54,136 +22,63 @@ struct ScannedArea {
 impl ScannedArea {
     // Returns the range of numbers that are contained in this scanned area in the
     // given row, if any.
-    pub fn range_on_row(&self, row: i64) -> Option<Range> {
+    pub fn range_on_row(&self, row: i64) -> Option<Interval<i64>> {
         let distance_to_row = self.center.y.abs_diff(row);
         (self.radius >= distance_to_row).then(|| {
             let width = (self.radius - distance_to_row) as i64;
-            Range::new(self.center.x - width, self.center.x + width)
+            Interval::new(self.center.x - width, self.center.x + width)
         })
     }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        self.center.manhattan_distance(point) as u64 <= self.radius
+    }
 }
 
-// A line segment on a 2D plane.
+// A point in the plane rotated by 45 degrees, where u = x + y and v = x - y.
 //
-// Represented in slope-intercept form.
-#[derive(Debug)]
-struct LineSegment {
-    x_range: Range,
-    slope: f64,
-    constant: f64,
+// Scanned areas are diamonds in the original plane, but become axis-aligned
+// squares in this one, so their borders are exact integer lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Rotated {
+    u: i64,
+    v: i64,
 }
 
-impl LineSegment {
-    pub fn new(a: Point, b: Point) -> Self {
-        let slope = ((b.y - a.y) as f64) / ((b.x - a.x) as f64);
+impl Rotated {
+    pub fn from_point(point: &Point) -> Self {
         Self {
-            x_range: Range::new(a.x.min(b.x), a.x.max(b.x)),
-            slope,
-            constant: (a.y as f64) - slope * (a.x as f64),
-        }
-    }
-
-    pub fn has_infinite_slope(&self) -> bool {
-        self.slope.is_infinite()
-    }
-
-    // Returns the point of intersection, if any, between two lines.
-    pub fn intersect(&self, other: &Self) -> Option<Point> {
-        if self.slope == other.slope {
-            // Parallel lines; no intersection or infinitely many.
-            return None;
-        }
-
-        let intersection_x = (other.constant - self.constant) / (self.slope - other.slope);
-        if !self.x_range.contains(intersection_x as i64)
-            || !other.x_range.contains(intersection_x as i64)
-        {
-            return None;
+            u: point.x + point.y,
+            v: point.x - point.y,
         }
-
-        Some(Point::new(
-            intersection_x as i64,
-            self.y_from_x(intersection_x as i64),
-        ))
     }
 
-    // Returns the y-coordinate on the line segment with respect to the
-    // x-coordinate.
-    //
-    // Does not check if the x-coordinate is actually on the line segment.
-    pub fn y_from_x(&self, x: i64) -> i64 {
-        (self.slope * (x as f64) + self.constant) as i64
+    // Converts back to the original plane. Only points where u and v have the
+    // same parity land on integer coordinates.
+    pub fn into_point(self) -> Option<Point> {
+        ((self.u + self.v) % 2 == 0)
+            .then(|| Point::new((self.u + self.v) / 2, (self.u - self.v) / 2))
     }
 }
 
-// A square on a 2D plane. Represents the perimeter of a square, not the area
-// enclosed by it.
+// The perimeter one step outside of a scanned area, which is where the beacon
+// must be if it is next to the area.
 //
-// Stores all four vertices and line segments that make up the square.
+// Stored as the two lines of constant u and the two lines of constant v that
+// make up the border in rotated coordinates.
 #[derive(Debug)]
-struct Square {
-    top: Point,
-    bottom: Point,
-    left: Point,
-    right: Point,
-    top_right: LineSegment,
-    bottom_right: LineSegment,
-    bottom_left: LineSegment,
-    top_left: LineSegment,
+struct Border {
+    u_lines: [i64; 2],
+    v_lines: [i64; 2],
 }
 
-impl Square {
+impl Border {
     pub fn surrounding(area: &ScannedArea) -> Self {
-        let top = Point::new(area.center.x, area.center.y - (area.radius as i64) - 1);
-        let bottom = Point::new(area.center.x, area.center.y + (area.radius as i64) + 1);
-        let left = Point::new(area.center.x - (area.radius as i64) - 1, area.center.y);
-        let right = Point::new(area.center.x + (area.radius as i64) + 1, area.center.y);
+        let center = Rotated::from_point(&area.center);
+        let distance = area.radius as i64 + 1;
         Self {
-            top,
-            bottom,
-            left,
-            right,
-            top_right: LineSegment::new(top, right),
-            bottom_right: LineSegment::new(right, bottom),
-            bottom_left: LineSegment::new(bottom, left),
-            top_left: LineSegment::new(left, top),
-        }
-    }
-
-    pub fn edges(&self) -> [&LineSegment; 4] {
-        [
-            &self.top_right,
-            &self.bottom_right,
-            &self.bottom_left,
-            &self.top_left,
-        ]
-    }
-
-    // Returns all points of intersection between the two square perimeters.
-    pub fn intersect(&self, other: &Self) -> Vec<Point> {
-        iproduct!(self.edges(), other.edges())
-            .filter_map(|(a, b)| a.intersect(&b))
-            .collect()
-    }
-
-    // Checks if the point is contained within the square perimeter, excluding the
-    // perimeter itself.
-    pub fn contains(&self, Point { x, y }: &Point) -> bool {
-        if self.edges().iter().any(|edge| edge.has_infinite_slope()) {
-            // Square is aligned to the grid, which means we just need to check ranges.
-            Range::new(self.left.x, self.right.x).contains(*x)
-                && Range::new(self.bottom.y, self.top.y).contains(*y)
-        } else {
-            // Check that the y-coordinate fits within the bounds of the edges.
-
-            // Below the top-right edge.
-            *y > self.top_right.y_from_x(*x)
-                // Above the bottom-right edge.
-                && *y < self.bottom_right.y_from_x(*x)
-                // Above the bottom-left edge.
-                && *y < self.bottom_left.y_from_x(*x)
-                // Below the top-left edge.
-                && *y > self.top_left.y_from_x(*x)
+            u_lines: [center.u - distance, center.u + distance],
+            v_lines: [center.v - distance, center.v + distance],
         }
     }
 }
@@ -197,7 +92,7 @@ impl Reading {
     pub fn into_scanned_area(self) -> ScannedArea {
         ScannedArea {
             center: self.sensor,
-            radius: self.sensor.manhatten_distance(&self.closest_beacon),
+            radius: self.sensor.manhattan_distance(&self.closest_beacon) as u64,
         }
     }
 }
@@ -209,82 +104,310 @@ impl FromStr for Reading {
         Ok(Reading {
             sensor: Point::new(
                 ints.next()
-                    .into_aoc_result_msg("missing x coordinate for sensor")?,
+                    .into_aoc_result_msg("missing x coordinate for sensor")??,
                 ints.next()
-                    .into_aoc_result_msg("missing y coordinate for sensor")?,
+                    .into_aoc_result_msg("missing y coordinate for sensor")??,
             ),
             closest_beacon: Point::new(
                 ints.next()
-                    .into_aoc_result_msg("missing x coordinate for closest beacon")?,
+                    .into_aoc_result_msg("missing x coordinate for closest beacon")??,
                 ints.next()
-                    .into_aoc_result_msg("missing y coordinate for closest beacon")?,
+                    .into_aoc_result_msg("missing y coordinate for closest beacon")??,
             ),
         })
     }
 }
 
 fn parse_readings(input: &str) -> AocResult<Vec<Reading>> {
-    input.lines().map(|line| Reading::from_str(line)).collect()
+    input.lines().map(Reading::from_str).collect()
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    const ROW: i64 = 2_000_000;
-    let ranges = parse_readings(input)?
+fn parse_scanned_areas(input: &str) -> AocResult<Vec<ScannedArea>> {
+    Ok(parse_readings(input)?
         .into_iter()
-        .filter_map(|reading| reading.into_scanned_area().range_on_row(ROW))
-        .sorted_by(|a, b| a.begin.cmp(&b.begin))
-        .collect::<Vec<_>>();
-    if ranges.is_empty() {
-        return Err(AocError::new("no ranges"));
+        .map(|reading| reading.into_scanned_area())
+        .collect())
+}
+
+// Where to look for the distress beacon.
+#[derive(Debug, Clone, Copy)]
+pub struct SearchParameters {
+    // The row to count covered positions on for part A.
+    pub row: i64,
+    // The range of both coordinates the beacon can be at for part B.
+    pub bounds: Interval<i64>,
+}
+
+impl Constants for SearchParameters {
+    const EXAMPLE: Self = Self {
+        row: 10,
+        bounds: Interval::new(0, 20),
+    };
+    const REAL: Self = Self {
+        row: 2_000_000,
+        bounds: Interval::new(0, 4_000_000),
+    };
+}
+
+impl SearchParameters {
+    // The puzzle does not say which parameters an input is meant for, so guess
+    // from the sensors: the example's sensors all fit in its small search area,
+    // which is practically impossible for a real input.
+    pub fn for_areas(areas: &[ScannedArea]) -> Self {
+        let bounds = Self::EXAMPLE.bounds;
+        if areas
+            .iter()
+            .all(|area| bounds.contains(area.center.x) && bounds.contains(area.center.y))
+        {
+            Self::EXAMPLE
+        } else {
+            Self::REAL
+        }
     }
 
-    // Merge all ranges for this row into a stack of ranges, eliminating any
-    // duplicates.
-    let no_beacon_ranges = ranges.iter().skip(1).fold(
-        VecDeque::from([ranges.first().copied().unwrap()]),
-        |mut stack, &range| {
-            let top = stack.back_mut().unwrap();
-            if top.contains(range.begin) {
-                // Merge the two ranges if they overlap.
-                top.end = top.end.max(range.end);
-            } else {
-                // No overlap, add a new range.
-                stack.push_back(range);
-            }
-            stack
-        },
-    );
-
-    Ok(no_beacon_ranges
-        .into_iter()
-        .map(|range| range.size() as u64)
-        .sum())
+    // The parameters for the input if it is known which one it is, or else
+    // guessed, with `--row` and `--bound` taking their place when given.
+    pub fn for_input(areas: &[ScannedArea], day_args: &DayArgs) -> AocResult<Self> {
+        let guess = day_args
+            .constants()
+            .unwrap_or_else(|| Self::for_areas(areas));
+        Ok(Self {
+            row: day_args.get_or("row", guess.row)?,
+            bounds: match day_args.get("bound")? {
+                Some(bound) => Interval::new(0, bound),
+                None => guess.bounds,
+            },
+        })
+    }
+}
+
+// Returns the positions on a row that are covered by at least one scanned
+// area.
+pub fn coverage_on_row(areas: &[ScannedArea], y: i64) -> IntervalSet<i64> {
+    areas
+        .iter()
+        .filter_map(|area| area.range_on_row(y))
+        .collect()
+}
+
+// The number of positions within the given columns and rows that are covered by
+// at least one scanned area. The union of the areas is no simple shape, but each
+// of its rows is a handful of intervals, so this sweeps over the rows.
+pub fn total_coverage_area(
+    areas: &[ScannedArea],
+    columns: Interval<i64>,
+    rows: Interval<i64>,
+) -> u64 {
+    (rows.start..=rows.end)
+        .map(|y| {
+            areas
+                .iter()
+                .filter_map(|area| area.range_on_row(y))
+                .map(|range| range.intersection(&columns))
+                .filter(|range| !range.is_empty())
+                .collect::<IntervalSet<_>>()
+                .total_len() as u64
+        })
+        .sum()
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    const BEACON_MIN: i64 = 0;
-    const BEACON_MAX: i64 = 4_000_000;
-    let beacon_range = Range::new(BEACON_MIN, BEACON_MAX);
+// Finds a position within the bounds, in both coordinates, that is not covered
+// by any scanned area.
+//
+// If only one such position exists, it must be surrounded by scanned areas or
+// the edge of the bounds, so it lies where the border of one area crosses the
+// border of another area or the edge of the bounds.
+pub fn find_uncovered(areas: &[ScannedArea], bounds: Interval<i64>) -> Option<Point> {
+    let borders = areas.iter().map(Border::surrounding).collect::<Vec<_>>();
+    let u_lines = borders
+        .iter()
+        .flat_map(|border| border.u_lines)
+        .unique()
+        .collect::<Vec<_>>();
+    let v_lines = borders
+        .iter()
+        .flat_map(|border| border.v_lines)
+        .unique()
+        .collect::<Vec<_>>();
 
-    // Convert each reading into its perimeter, which is the square one step outside
-    // of the scanned area.
-    let squares = parse_readings(input)?
+    // Every line of constant u crosses every line of constant v exactly once.
+    let border_crossings = iproduct!(u_lines.iter(), v_lines.iter())
+        .filter_map(|(&u, &v)| Rotated { u, v }.into_point());
+
+    // Lines of constant u and v cross the edges of the bounds where x or y is
+    // fixed.
+    let edges = [bounds.start, bounds.end];
+    let edge_crossings = iproduct!(edges, u_lines.iter())
+        .flat_map(|(edge, &u)| [Point::new(edge, u - edge), Point::new(u - edge, edge)])
+        .chain(
+            iproduct!(edges, v_lines.iter())
+                .flat_map(|(edge, &v)| [Point::new(edge, edge - v), Point::new(v + edge, edge)]),
+        );
+
+    // The corners of the bounds can be walled off by a single area.
+    let corners = iproduct!(edges, edges).map(|(x, y)| Point::new(x, y));
+
+    border_crossings
+        .chain(edge_crossings)
+        .chain(corners)
+        .find(|candidate| {
+            bounds.contains(candidate.x)
+                && bounds.contains(candidate.y)
+                && areas.iter().all(|area| !area.contains(candidate))
+        })
+}
+
+pub fn solve_a(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let readings = timing::parse_phase(|| parse_readings(input))?;
+    let beacons = readings
+        .iter()
+        .map(|reading| reading.closest_beacon)
+        .collect::<Vec<_>>();
+    let areas = readings
         .into_iter()
-        .map(|sensor| Square::surrounding(&sensor.into_scanned_area()))
+        .map(|reading| reading.into_scanned_area())
         .collect::<Vec<_>>();
+    let parameters = SearchParameters::for_input(&areas, day_args)?;
+    let mut no_beacon_positions = coverage_on_row(&areas, parameters.row);
+    if no_beacon_positions.is_empty() {
+        return Err(AocError::no_solution("no ranges"));
+    }
+    // The beacons detected on the row are not places a beacon cannot be.
+    for beacon in beacons.iter().filter(|beacon| beacon.y == parameters.row) {
+        no_beacon_positions.subtract(Interval::point(beacon.x));
+    }
+    Ok(no_beacon_positions.total_len() as u64)
+}
 
-    // For each pair of squares, find all points of intersection.
-    // Return the first point of intersection that does not contained by any square.
-    for (square, other) in squares.iter().tuple_combinations() {
-        for intersection in square.intersect(other) {
-            if beacon_range.contains(intersection.x)
-                && beacon_range.contains(intersection.y)
-                && squares.iter().all(|square| !square.contains(&intersection))
-            {
-                return Ok(intersection.tuning_frequency() as u64);
-            }
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<i64> {
+    let areas = timing::parse_phase(|| parse_scanned_areas(input))?;
+    let parameters = SearchParameters::for_input(&areas, day_args)?;
+    find_uncovered(&areas, parameters.bounds)
+        .map(|beacon| tuning_frequency(&beacon))
+        .ok_or_else(|| AocError::no_solution("no beacon found"))
+}
+
+register_day!(15, args solve_a, args solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::constants::Profile;
+
+    fn area(x: i64, y: i64, radius: u64) -> ScannedArea {
+        ScannedArea {
+            center: Point::new(x, y),
+            radius,
         }
     }
 
-    Err(AocError::new("no beacon found"))
+    // Checks every position within the bounds to find the only one that is not
+    // covered.
+    fn brute_force_uncovered(areas: &[ScannedArea], bounds: Interval<i64>) -> Point {
+        let uncovered = iproduct!(bounds.start..=bounds.end, bounds.start..=bounds.end)
+            .map(|(x, y)| Point::new(x, y))
+            .filter(|point| areas.iter().all(|area| !area.contains(point)))
+            .collect::<Vec<_>>();
+        assert_eq!(uncovered.len(), 1, "expected one uncovered position");
+        uncovered[0]
+    }
+
+    // Checks that the position found is the only one left uncovered, as the
+    // puzzle claims.
+    fn assert_finds_uncovered(areas: &[ScannedArea], bounds: Interval<i64>) {
+        assert_eq!(
+            find_uncovered(areas, bounds),
+            Some(brute_force_uncovered(areas, bounds))
+        );
+        assert_eq!(
+            total_coverage_area(areas, bounds, bounds),
+            (bounds.len() * bounds.len()) as u64 - 1
+        );
+    }
+
+    #[test]
+    fn finds_example_beacon() {
+        let areas = parse_scanned_areas(include_str!("../../examples/15.txt")).unwrap();
+        let bounds = SearchParameters::EXAMPLE.bounds;
+        assert_eq!(find_uncovered(&areas, bounds), Some(Point::new(14, 11)));
+        assert_finds_uncovered(&areas, bounds);
+    }
+
+    #[test]
+    fn measures_coverage_within_bounds() {
+        let areas = [area(0, 0, 2), area(3, 0, 1), area(10, 10, 0)];
+        // The first two diamonds overlap at (2, 0), and the last is out of
+        // bounds.
+        assert_eq!(
+            total_coverage_area(&areas, Interval::new(-5, 5), Interval::new(-5, 5)),
+            13 + 5 - 1
+        );
+        // The right half of the first diamond, center column included, where the
+        // second diamond only reaches the already covered (2, 0).
+        assert_eq!(
+            total_coverage_area(&areas, Interval::new(0, 2), Interval::new(-2, 2)),
+            9
+        );
+        let areas = parse_scanned_areas(include_str!("../../examples/15.txt")).unwrap();
+        let row = Interval::new(10, 10);
+        assert_eq!(
+            total_coverage_area(&areas, Interval::new(-100, 100), row),
+            coverage_on_row(&areas, 10).total_len() as u64
+        );
+    }
+
+    #[test]
+    fn overrides_parameters_from_args() {
+        let input = include_str!("../../examples/15.txt");
+        let day_args = DayArgs::default();
+        assert_eq!(solve_a(input, &day_args).unwrap(), 26);
+        assert_eq!(
+            solve_a(input, &day_args.clone().with("row", 9)).unwrap(),
+            25
+        );
+        assert_eq!(solve_b(input, &day_args).unwrap(), 56_000_011);
+        assert!(solve_b(input, &day_args.clone().with("bound", 10)).is_err());
+        assert!(solve_a(input, &day_args.with("row", "ten")).is_err());
+    }
+
+    #[test]
+    fn uses_constants_for_profile() {
+        let input = include_str!("../../examples/15.txt");
+        let example = DayArgs::default().with_profile(Profile::Example);
+        assert_eq!(solve_a(input, &example).unwrap(), 26);
+        // The real row is far below every sensor in the example.
+        let real = DayArgs::default().with_profile(Profile::Real);
+        assert!(solve_a(input, &real).is_err());
+    }
+
+    #[test]
+    fn finds_gap_between_edge_adjacent_areas() {
+        // The first two areas leave only the diagonal x + y = 10 uncovered, and
+        // the last two cover all of it except for its middle.
+        let areas = [
+            area(0, 0, 9),
+            area(10, 10, 9),
+            area(0, 10, 9),
+            area(10, 0, 9),
+        ];
+        let bounds = Interval::new(0, 10);
+        assert_eq!(find_uncovered(&areas, bounds), Some(Point::new(5, 5)));
+        assert_finds_uncovered(&areas, bounds);
+    }
+
+    #[test]
+    fn finds_gap_on_edge_of_bounds() {
+        let areas = [area(2, 0, 3), area(2, 4, 3), area(4, 2, 1)];
+        let bounds = Interval::new(0, 4);
+        assert_eq!(find_uncovered(&areas, bounds), Some(Point::new(0, 2)));
+        assert_finds_uncovered(&areas, bounds);
+    }
+
+    #[test]
+    fn finds_gap_in_corner_of_bounds() {
+        let areas = [area(4, 4, 7)];
+        let bounds = Interval::new(0, 4);
+        assert_eq!(find_uncovered(&areas, bounds), Some(Point::new(0, 0)));
+        assert_finds_uncovered(&areas, bounds);
+    }
 }
@@ -1,9 +1,13 @@
 use std::{
-    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+    iter,
     str::FromStr,
 };
 
-use crate::common::{AocError, AocResult, IntoAocResult};
+use crate::common::{timing, AocError, AocResult, DayArgs, IntoAocResult, SolutionPart};
+use itertools::Itertools;
 use lazy_static::lazy_static;
 use regex::Regex;
 
@@ -18,7 +22,7 @@ struct Volcano {
     valves: BTreeMap<String, Valve>,
 }
 
-impl<'a> FromStr for Volcano {
+impl FromStr for Volcano {
     type Err = AocError;
     fn from_str(s: &str) -> AocResult<Self> {
         lazy_static! {
@@ -52,44 +56,81 @@ impl<'a> FromStr for Volcano {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct PressureReleaseExplorationState {
-    pub position: u8,
-    pub valves_opened: u32,
-    pub pressure_released: u32,
-    pub time_remaining: u8,
-}
-
-impl PressureReleaseExplorationState {
-    pub fn visited_and_opened(&self, valve: usize) -> bool {
-        (self.valves_opened & (1 << valve)) != 0
-    }
-
-    pub fn move_to(&mut self, position: u8) {
-        self.position = position
-    }
-
-    pub fn open(&mut self, valve: usize, pressure_released: u32) {
-        self.valves_opened |= 1 << valve;
-        self.pressure_released += pressure_released * (self.time_remaining as u32)
-    }
-
-    pub fn spend_time(&mut self, time: u8) {
-        self.time_remaining = if time > self.time_remaining {
-            0
+impl Volcano {
+    // The tunnels as an undirected Graphviz graph, with each valve labeled by
+    // its flow rate. When compressed, only the starting valve and the valves
+    // worth opening are kept, connected by the length of the shortest path
+    // between them.
+    pub fn to_dot(&self, starting_position: &str, compressed: bool) -> String {
+        let mut dot = "graph volcano {\n".to_owned();
+        let shown = |name: &str, valve: &Valve| {
+            !compressed || name == starting_position || valve.flow_rate != 0
+        };
+        for (name, valve) in &self.valves {
+            if !shown(name, valve) {
+                continue;
+            }
+            let shape = if name == starting_position {
+                "doublecircle"
+            } else if valve.flow_rate != 0 {
+                "circle"
+            } else {
+                "point"
+            };
+            // Writing to a string cannot fail.
+            let _ = writeln!(
+                dot,
+                "  {name} [label=\"{name}\\n{}\", shape={shape}];",
+                valve.flow_rate
+            );
+        }
+        if compressed {
+            let distances = VolcanoValveMap::floyd_warshall(self);
+            let ids = &distances.valve_ids;
+            for ((a, valve_a), (b, valve_b)) in self.valves.iter().tuple_combinations() {
+                let distance = distances.get_distance(ids[a.as_str()], ids[b.as_str()]);
+                if shown(a, valve_a) && shown(b, valve_b) && distance != u64::MAX {
+                    let _ = writeln!(dot, "  {a} -- {b} [label=\"{distance}\"];");
+                }
+            }
         } else {
-            self.time_remaining - time
+            for (name, valve) in &self.valves {
+                // Tunnels go both ways, so each is only drawn from one end.
+                for other in valve.tunnels.iter().filter(|other| *other > name).sorted() {
+                    let _ = writeln!(dot, "  {name} -- {other};");
+                }
+            }
         }
+        dot += "}\n";
+        dot
     }
 }
 
+// How much of the search for the best order of opening valves was done, for
+// tuning the upper bound.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    // States walked into.
+    pub explored: u64,
+    // States skipped because they could not beat the best answer so far.
+    pub pruned: u64,
+}
+
+// The state shared by every branch of the pruned search.
+struct Search {
+    // Valve IDs from the highest flow rate to the lowest.
+    by_flow_rate: Vec<usize>,
+    // The most pressure released by any order of opening valves so far.
+    best: u64,
+    stats: SearchStats,
+}
+
 #[derive(Debug)]
 struct OptimizedVolcanoValveMap {
     pub starting_position_id: usize,
     pub valve_id_to_flow_rate: Vec<u64>,
     pub num_valves: usize,
     pub minimum_distances: Vec<u64>,
-    pub valve_subset_to_relief: HashMap<u32, u32>,
 }
 
 impl OptimizedVolcanoValveMap {
@@ -101,98 +142,204 @@ impl OptimizedVolcanoValveMap {
         &mut self.minimum_distances[from * self.num_valves + to]
     }
 
-    fn initial_state(&self, minutes: u64) -> PressureReleaseExplorationState {
-        let mut initial_state = PressureReleaseExplorationState {
-            position: self.starting_position_id as u8,
-            valves_opened: 0,
-            pressure_released: 0,
-            time_remaining: minutes as u8,
-        };
-
-        // If the flow rate at the start is 0, we don't need to explore states that may
-        // or may not open it, so just mark it as open.
-        if self.valve_id_to_flow_rate[self.starting_position_id] == 0 {
-            initial_state.open(self.starting_position_id, 0);
+    // Walks every order of opening valves from the given state, recording the most pressure
+    // released for each subset of opened valves.
+    fn explore(
+        &self,
+        position: usize,
+        time_remaining: u64,
+        opened: usize,
+        pressure_released: u64,
+        best: &mut [u64],
+    ) {
+        if pressure_released > best[opened] {
+            best[opened] = pressure_released;
+        }
+        for (valve, flow_rate) in self.valve_id_to_flow_rate.iter().enumerate() {
+            // Valves with no flow rate are never worth opening. Only the starting valve can have
+            // no flow rate.
+            if *flow_rate == 0 || opened & (1 << valve) != 0 {
+                continue;
+            }
+            let time = self.get_distance(position, valve) + 1;
+            if time >= time_remaining {
+                continue;
+            }
+            let time_remaining = time_remaining - time;
+            self.explore(
+                valve,
+                time_remaining,
+                opened | (1 << valve),
+                pressure_released + flow_rate * time_remaining,
+                best,
+            );
         }
-
-        initial_state
     }
 
-    pub fn maximize_released_pressure(&mut self, minutes: u64) -> u64 {
-        let start_state = self.initial_state(minutes);
-        let mut queue = VecDeque::from([start_state.clone()]);
-        let mut maximum_pressure_released = start_state.pressure_released;
+    // The most pressure that can be released by opening each subset of valves, indexed by the
+    // bitmask of valves opened.
+    fn best_by_subset(&self, minutes: u64) -> Vec<u64> {
+        let mut best = vec![0; 1 << self.num_valves];
+        self.explore(self.starting_position_id, minutes, 0, 0, &mut best);
+        best
+    }
 
-        while let Some(state) = queue.pop_front() {
-            if state.pressure_released > maximum_pressure_released {
-                maximum_pressure_released = state.pressure_released;
-            }
+    // The most pressure that could possibly be released from a state, found by
+    // opening the remaining valves from highest flow rate to lowest, one every
+    // two minutes. Reaching and opening a valve always takes at least that
+    // long, so no real order of opening can beat it.
+    fn upper_bound(
+        &self,
+        by_flow_rate: &[usize],
+        time_remaining: u64,
+        opened: usize,
+        pressure_released: u64,
+    ) -> u64 {
+        by_flow_rate
+            .iter()
+            .filter(|valve| opened & (1 << **valve) == 0)
+            .zip((0..time_remaining.saturating_sub(1)).rev().step_by(2))
+            .map(|(valve, time)| self.valve_id_to_flow_rate[*valve] * time)
+            .sum::<u64>()
+            + pressure_released
+    }
 
-            // Used for part B.
-            let max_pressure_relieved_at_subset = self
-                .valve_subset_to_relief
-                .entry(state.valves_opened)
-                .or_insert(0);
-            if state.pressure_released > *max_pressure_relieved_at_subset {
-                *max_pressure_relieved_at_subset = state.pressure_released;
+    // Walks orders of opening valves like `explore`, but skips any state that
+    // cannot release more pressure than the best found so far.
+    fn branch_and_bound(
+        &self,
+        search: &mut Search,
+        position: usize,
+        time_remaining: u64,
+        opened: usize,
+        pressure_released: u64,
+    ) {
+        search.stats.explored += 1;
+        search.best = search.best.max(pressure_released);
+        let moves = self
+            .valve_id_to_flow_rate
+            .iter()
+            .enumerate()
+            .filter(|(valve, flow_rate)| **flow_rate != 0 && opened & (1 << valve) == 0)
+            .filter_map(|(valve, flow_rate)| {
+                let time_remaining =
+                    time_remaining.checked_sub(self.get_distance(position, valve) + 1)?;
+                (time_remaining > 0).then_some((
+                    valve,
+                    time_remaining,
+                    pressure_released + flow_rate * time_remaining,
+                ))
+            })
+            // Trying the most valuable valves first finds a good answer early,
+            // which lets more of the later states be skipped.
+            .sorted_by(|a, b| b.2.cmp(&a.2))
+            .collect::<Vec<_>>();
+        for (valve, time_remaining, pressure_released) in moves {
+            let opened = opened | (1 << valve);
+            let bound = self.upper_bound(
+                &search.by_flow_rate,
+                time_remaining,
+                opened,
+                pressure_released,
+            );
+            if bound <= search.best {
+                search.stats.pruned += 1;
+                continue;
             }
+            self.branch_and_bound(search, valve, time_remaining, opened, pressure_released);
+        }
+    }
 
-            for (valve, flow_rate) in self.valve_id_to_flow_rate.iter().enumerate() {
-                if !state.visited_and_opened(valve) {
-                    let time = self.get_distance(state.position as usize, valve) as u8 + 1;
-                    if state.time_remaining >= time {
-                        let mut next_state = state.clone();
-                        next_state.spend_time(time);
-                        next_state.move_to(valve as u8);
-                        next_state.open(valve, *flow_rate as u32);
+    pub fn maximize_released_pressure_with_stats(&self, minutes: u64) -> (u64, SearchStats) {
+        let mut search = Search {
+            by_flow_rate: (0..self.num_valves)
+                .sorted_by_key(|valve| Reverse(self.valve_id_to_flow_rate[*valve]))
+                .collect(),
+            best: 0,
+            stats: SearchStats::default(),
+        };
+        self.branch_and_bound(&mut search, self.starting_position_id, minutes, 0, 0);
+        debug!(
+            "{} states explored and {} pruned",
+            search.stats.explored, search.stats.pruned
+        );
+        (search.best, search.stats)
+    }
 
-                        queue.push_back(next_state);
-                    }
-                }
-            }
-        }
+    pub fn maximize_released_pressure(&self, minutes: u64) -> u64 {
+        self.maximize_released_pressure_with_stats(minutes).0
+    }
 
-        maximum_pressure_released as u64
+    // Finds the same answer by walking every order of opening valves, without
+    // skipping any.
+    pub fn maximize_released_pressure_exhaustively(&self, minutes: u64) -> u64 {
+        self.best_by_subset(minutes).into_iter().max().unwrap_or(0)
     }
 
-    pub fn maximize_released_pressure_with_elephant(&mut self, minutes: u64) -> u64 {
-        // First, visit all states as a single worker. This fills the
-        // valve_subset_to_relief map.
-        self.maximize_released_pressure(minutes);
-
-        // If the valve at the starting position has no flow rate, then the above
-        // algorithm only explores states where it is opened. Our disjoint sett will not
-        // necessarily be disjoint in this case, since the starting valve will always be
-        // open.
-        let disjoint_state = if self.valve_id_to_flow_rate[self.starting_position_id] == 0 {
-            1 << self.starting_position_id
-        } else {
-            0
-        };
+    // The most pressure released by several agents opening valves at the same time, such as us
+    // and the elephant. They all start together, so no two open the same valve.
+    pub fn maximize_with_agents(&self, agents: usize, minutes: u64) -> u64 {
+        let mut alone = self.best_by_subset(minutes);
+        debug!(
+            "{} of {} subsets of valves can be opened in time",
+            alone.iter().filter(|pressure| **pressure != 0).count(),
+            alone.len()
+        );
+        if agents == 0 {
+            return 0;
+        }
 
-        // Our goal is for each subset of valves opened, take the disjoint set of valves
-        // opened, which represents the elephant moving independently at the same time.
-        self.valve_subset_to_relief
+        // Not every subset can be opened in time, so let each subset take the best of any subset
+        // within it. Then, we can pair each subset one agent opens with the best result the other
+        // agents can get from the valves left alone.
+        for valve in 0..self.num_valves {
+            for subset in 0..alone.len() {
+                if subset & (1 << valve) != 0 {
+                    alone[subset] = alone[subset].max(alone[subset ^ (1 << valve)]);
+                }
+            }
+        }
+        // Valves with no flow rate are never opened, so they are left out of every subset.
+        let useful = self
+            .valve_id_to_flow_rate
             .iter()
-            .map(|(subset, pressure_released)| {
-                // My initial idea was to just flip all of the bits of each subset. However,
-                // this algorithm fails if there is no way for the two workers to open all
-                // valves at once for different subsets. It will cause the real maximum, which
-                // occurs when not all valves are opened by the two workers, to be missed.
-                pressure_released
-                    + self
-                        .valve_subset_to_relief
-                        .iter()
-                        .filter(|(&other_subset, _)| subset & other_subset == disjoint_state)
-                        .map(|(_, other_pressure_released)| other_pressure_released)
-                        .max()
-                        .unwrap_or(&0)
-            })
-            .max()
-            .unwrap() as u64
+            .enumerate()
+            .filter(|(_, flow_rate)| **flow_rate != 0)
+            .fold(0, |mask, (valve, _)| mask | (1 << valve));
+
+        // The most each number of agents can release with each subset, adding one agent at a
+        // time. Only the subsets of useful valves are ever needed.
+        let mut team = alone.clone();
+        for _ in 2..agents {
+            let mut next = vec![0; alone.len()];
+            for subset in subsets_of(useful) {
+                next[subset] = subsets_of(subset)
+                    .map(|opened| alone[opened] + team[subset ^ opened])
+                    .max()
+                    .unwrap_or(0);
+            }
+            team = next;
+        }
+        match agents {
+            1 => alone[useful],
+            _ => subsets_of(useful)
+                .map(|opened| alone[opened] + team[useful ^ opened])
+                .max()
+                .unwrap_or(0),
+        }
     }
 }
 
+// Every subset of the bits in a mask, including the empty set and the mask itself.
+fn subsets_of(mask: usize) -> impl Iterator<Item = usize> {
+    let mut next = Some(mask);
+    iter::from_fn(move || {
+        let subset = next?;
+        next = (subset != 0).then(|| (subset - 1) & mask);
+        Some(subset)
+    })
+}
+
 #[derive(Debug)]
 struct VolcanoValveMap<'a> {
     pub volcano: &'a Volcano,
@@ -269,13 +416,17 @@ impl<'a> VolcanoValveMap<'a> {
             .enumerate()
             .collect::<Vec<_>>();
         let num_included_valves = included.len();
+        debug!(
+            "kept {} of {} valves, the start and those with flow",
+            num_included_valves,
+            self.volcano.valves.len()
+        );
 
         let mut optimized = OptimizedVolcanoValveMap {
             starting_position_id: usize::MAX,
             valve_id_to_flow_rate: vec![0; num_included_valves],
             num_valves: num_included_valves,
             minimum_distances: vec![0; num_included_valves * num_included_valves],
-            valve_subset_to_relief: HashMap::new(),
         };
 
         for (new_id, (name, original_id, valve)) in &included {
@@ -295,20 +446,127 @@ impl<'a> VolcanoValveMap<'a> {
     }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
+fn optimize(input: &str) -> AocResult<OptimizedVolcanoValveMap> {
     const STARTING_POSITION: &str = "AA";
-    const MINUTES: u64 = 30;
-    let volcano = Volcano::from_str(input)?;
+    let volcano = timing::parse_phase(|| Volcano::from_str(input))?;
     let distance_map = VolcanoValveMap::floyd_warshall(&volcano);
-    let mut optimized = distance_map.optimize(STARTING_POSITION);
-    Ok(optimized.maximize_released_pressure(MINUTES))
+    Ok(distance_map.optimize(STARTING_POSITION))
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    const STARTING_POSITION: &str = "AA";
-    const MINUTES: u64 = 26;
+pub fn solve_a(input: &str) -> AocResult<u64> {
+    Ok(optimize(input)?.maximize_released_pressure(30))
+}
+
+// Alternate implementation that tries every order of opening valves, checked
+// against the pruned search with `--check`.
+pub fn solve_a_exhaustively(input: &str) -> AocResult<u64> {
+    Ok(optimize(input)?.maximize_released_pressure_exhaustively(30))
+}
+
+// Us and the elephant by default, with `--agents N` for more or fewer agents
+// and `--minutes N` for a different amount of time.
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let agents = day_args.get_or("agents", 2)?;
+    if agents == 0 {
+        return Err(AocError::invalid_args("--agents must be at least 1"));
+    }
+    let minutes = day_args.get_or("minutes", 26)?;
+    Ok(optimize(input)?.maximize_with_agents(agents, minutes))
+}
+
+// The tunnels in Graphviz's DOT format. Part A shows every tunnel, while part
+// B shows the distances between the valves that are worth opening.
+pub fn dot(input: &str, part: SolutionPart) -> AocResult<String> {
     let volcano = Volcano::from_str(input)?;
-    let distance_map = VolcanoValveMap::floyd_warshall(&volcano);
-    let mut optimized = distance_map.optimize(STARTING_POSITION);
-    Ok(optimized.maximize_released_pressure_with_elephant(MINUTES))
+    Ok(volcano.to_dot("AA", part != SolutionPart::A))
+}
+
+register_day!(16, solve_a | solve_a_exhaustively, args solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "Valve AA has flow rate=0; tunnels lead to valves BB, CC
+Valve BB has flow rate=13; tunnels lead to valves AA, CC
+Valve CC has flow rate=0; tunnels lead to valves AA, BB, DD
+Valve DD has flow rate=20; tunnel leads to valve CC";
+
+    #[test]
+    fn prunes_without_changing_the_answer() {
+        for input in [INPUT, include_str!("../../examples/16.txt")] {
+            let optimized = optimize(input).unwrap();
+            assert_eq!(
+                optimized.maximize_released_pressure(30),
+                optimized.maximize_released_pressure_exhaustively(30)
+            );
+        }
+        let (best, stats) = optimize(include_str!("../../examples/16.txt"))
+            .unwrap()
+            .maximize_released_pressure_with_stats(30);
+        assert_eq!(best, 1651);
+        assert!(stats.pruned > 0);
+        // Walking every order of opening the example's valves takes thousands of
+        // states.
+        assert!(stats.explored < 1000, "explored {} states", stats.explored);
+    }
+
+    #[test]
+    fn splits_valves_between_agents() {
+        let example = include_str!("../../examples/16.txt");
+        let optimized = optimize(example).unwrap();
+        assert_eq!(optimized.maximize_with_agents(1, 30), 1651);
+        assert_eq!(optimized.maximize_with_agents(2, 26), 1707);
+        let three = optimized.maximize_with_agents(3, 26);
+        assert!(three > 1707, "three agents released {three}");
+
+        // With an agent for every valve, each one goes straight to its own.
+        let start = optimized.starting_position_id;
+        let each_their_own = optimized
+            .valve_id_to_flow_rate
+            .iter()
+            .enumerate()
+            .map(|(valve, flow_rate)| {
+                flow_rate * 26u64.saturating_sub(optimized.get_distance(start, valve) + 1)
+            })
+            .sum::<u64>();
+        assert_eq!(optimized.maximize_with_agents(6, 26), each_their_own);
+        assert_eq!(optimized.maximize_with_agents(8, 26), each_their_own);
+
+        let agents = |n: usize| DayArgs::default().with("agents", n);
+        assert_eq!(solve_b(example, &agents(2)).unwrap(), 1707);
+        assert!(solve_b(example, &agents(0)).is_err());
+    }
+
+    #[test]
+    fn draws_every_tunnel_once() {
+        let dot = dot(INPUT, SolutionPart::A).unwrap();
+        let edges = dot
+            .lines()
+            .filter(|line| line.contains("--"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            ["  AA -- BB;", "  AA -- CC;", "  BB -- CC;", "  CC -- DD;"]
+        );
+        assert!(dot.contains("  DD [label=\"DD\\n20\", shape=circle];"));
+    }
+
+    #[test]
+    fn compresses_distances_between_useful_valves() {
+        let dot = dot(INPUT, SolutionPart::B).unwrap();
+        assert!(!dot.contains("CC"));
+        let edges = dot
+            .lines()
+            .filter(|line| line.contains("--"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            [
+                "  AA -- BB [label=\"1\"];",
+                "  AA -- DD [label=\"2\"];",
+                "  BB -- DD [label=\"2\"];"
+            ]
+        );
+    }
 }
@@ -1,92 +1,135 @@
-use std::{collections::VecDeque, str::FromStr};
+use crate::common::{progress::Tracker, timing, AocError, AocResult, IntoAocResult};
+use rayon::prelude::*;
+use rustc_hash::FxHashSet;
 
-use crate::common::{AocError, AocResult, IntoAocResult};
+// The most materials a blueprint can use. Each count is compiled separately,
+// so this only limits how many copies of the search are built.
+const MAX_MATERIALS: usize = 8;
 
+// A material, by the position of the robot that mines it in the first blueprint.
+// Robots are listed from least to most advanced, so later materials are the
+// more valuable ones.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
-#[repr(u8)]
-enum Material {
-    #[default]
-    Ore = 0,
-    Clay = 1,
-    Obsidian = 2,
-    Geode = 3,
-}
+pub struct Material(usize);
 
 impl Material {
-    pub const COUNT: usize = 4;
-
     pub fn index(&self) -> usize {
-        *self as u8 as usize
+        self.0
     }
 }
 
-impl FromStr for Material {
-    type Err = AocError;
-    fn from_str(s: &str) -> AocResult<Self> {
-        Ok(match s {
-            "ore" => Self::Ore,
-            "clay" => Self::Clay,
-            "obsidian" => Self::Obsidian,
-            "geode" => Self::Geode,
-            _ => return Err(AocError::new(&format!("invalid material: {s}"))),
-        })
+// The names of every material, which are read from the input rather than
+// assumed, so that variants of the puzzle with more materials can be solved.
+#[derive(Debug)]
+pub struct Materials {
+    names: Vec<String>,
+}
+
+impl Materials {
+    // Reads the materials mined by the robots of the first blueprint.
+    pub fn from_input(input: &str) -> AocResult<Self> {
+        let first = input.lines().next().into_aoc_result_msg("no blueprints")?;
+        let (_, blueprint) = first
+            .split_once(':')
+            .into_aoc_result_msg(&format!("invalid blueprint: {first}"))?;
+        let mut names = Vec::<String>::new();
+        for sentence in sentences(blueprint) {
+            match sentence.split(' ').collect::<Vec<_>>().as_slice() {
+                ["Each", mines, "robot", ..] if names.iter().any(|name| name == mines) => {
+                    return Err(AocError::parse(format!("duplicate robot: {mines}")))
+                }
+                ["Each", mines, "robot", ..] => names.push(mines.to_string()),
+                _ => return Err(AocError::parse(format!("invalid line: {sentence}"))),
+            }
+        }
+        match names.len() {
+            0 => Err(AocError::parse("blueprint has no robots")),
+            n if n > MAX_MATERIALS => Err(AocError::parse(format!(
+                "too many materials: {n}, at most {MAX_MATERIALS} are supported"
+            ))),
+            _ => Ok(Self { names }),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    // Never true for materials read from an input, which has at least one robot.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> AocResult<Material> {
+        self.names
+            .iter()
+            .position(|material| material == name)
+            .map(Material)
+            .into_aoc_result_msg(&format!("invalid material: {name}"))
     }
 }
 
-#[derive(Debug, Default, Clone, Copy)]
-struct RobotBlueprint {
+// Splits a blueprint into its sentences, one for each robot.
+fn sentences(blueprint: &str) -> impl Iterator<Item = &str> {
+    blueprint
+        .trim()
+        .split('.')
+        .map(|sentence| sentence.trim())
+        .filter(|sentence| !sentence.is_empty())
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RobotBlueprint<const N: usize> {
     pub mines: Material,
-    pub costs: [u64; Material::COUNT],
+    pub costs: [u64; N],
 }
 
-impl FromStr for RobotBlueprint {
-    type Err = AocError;
-    fn from_str(s: &str) -> AocResult<Self> {
+impl<const N: usize> RobotBlueprint<N> {
+    pub fn parse(s: &str, materials: &Materials) -> AocResult<Self> {
         match s.split(' ').collect::<Vec<_>>().as_slice() {
-            ["Each", mines, "robot", "costs", materials @ ..] => {
-                let mines = Material::from_str(mines)?;
+            ["Each", mines, "robot", "costs", costs @ ..] => {
+                let mines = materials.get(mines)?;
                 let mut result = Self {
                     mines,
-                    costs: [0; Material::COUNT],
+                    costs: [0; N],
                 };
-                let mut materials = materials;
+                let mut costs = costs;
                 loop {
-                    match materials {
+                    match costs {
                         [num, material, rest @ ..] => {
                             let num = num
                                 .parse::<u64>()
                                 .into_aoc_result_msg(&format!("invalid number: {num}"))?;
-                            let material = Material::from_str(material)?;
+                            let material = materials.get(material)?;
                             result.costs[material.index()] = num;
                             match rest.first() {
-                                Some(&"and") => materials = &rest[1..],
-                                Some(word @ _) => {
-                                    return Err(AocError::new(&format!(
+                                Some(&"and") => costs = &rest[1..],
+                                Some(word) => {
+                                    return Err(AocError::parse(format!(
                                         "invalid word after material: {word}"
                                     )))
                                 }
                                 None => break,
                             }
                         }
-                        _ => return Err(AocError::new(&format!("invalid materials: {s}"))),
+                        _ => return Err(AocError::parse(format!("invalid materials: {s}"))),
                     }
                 }
                 Ok(result)
             }
-            _ => Err(AocError::new(&format!("invalid line: {s}"))),
+            _ => Err(AocError::parse(format!("invalid line: {s}"))),
         }
     }
 }
 
 #[derive(Debug)]
-struct Blueprint {
+pub struct Blueprint<const N: usize> {
     pub id: u64,
-    pub robots: [RobotBlueprint; Material::COUNT],
+    pub robots: [RobotBlueprint<N>; N],
 }
 
-impl FromStr for Blueprint {
-    type Err = AocError;
-    fn from_str(s: &str) -> AocResult<Self> {
+impl<const N: usize> Blueprint<N> {
+    pub fn parse(s: &str, materials: &Materials) -> AocResult<Self> {
         let (prefix, blueprint) = s
             .split_once(':')
             .into_aoc_result_msg(&format!("invalid blueprint: {s}"))?;
@@ -95,57 +138,65 @@ impl FromStr for Blueprint {
                 .parse()
                 .into_aoc_result_msg(&format!("invalid blueprint id: {num}"))?,
             _ => {
-                return Err(AocError::new(&format!(
+                return Err(AocError::parse(format!(
                     "invalid blueprint prefix: {prefix}"
                 )))
             }
         };
 
+        let mut robots = [None; N];
+        for robot in sentences(blueprint).map(|robot| RobotBlueprint::parse(robot, materials)) {
+            let robot = robot?;
+            robots[robot.mines.index()] = Some(robot);
+        }
         let mut result = Self {
             id,
-            robots: [RobotBlueprint::default(); Material::COUNT],
+            robots: [RobotBlueprint {
+                mines: Material::default(),
+                costs: [0; N],
+            }; N],
         };
-
-        for robot in blueprint
-            .trim()
-            .split('.')
-            .filter_map(|sentence| {
-                let trimmed = sentence.trim();
-                if trimmed.is_empty() {
-                    None
-                } else {
-                    Some(trimmed)
-                }
-            })
-            .map(|robot| RobotBlueprint::from_str(robot))
-        {
-            let robot = robot?;
-            result.robots[robot.mines.index()] = robot;
+        for (i, robot) in robots.into_iter().enumerate() {
+            result.robots[i] = robot.into_aoc_result_msg(&format!(
+                "blueprint {id} has no {} robot",
+                materials.names[i]
+            ))?;
         }
 
         Ok(result)
     }
-}
 
-impl Blueprint {
     pub fn quality_level(&self, material: Material, minutes: u64) -> u64 {
         self.maximize(material, minutes) * self.id
     }
 
     pub fn maximize(&self, material: Material, minutes: u64) -> u64 {
-        BlueprintSimulation::new(self, material, minutes).maximize()
+        self.search(material, minutes).optimal
+    }
+
+    pub fn search(&self, material: Material, minutes: u64) -> SearchStats {
+        BlueprintSimulation::new(self, material, minutes).search()
     }
 }
 
+// How a search for the most of a material went, for comparing the greedy
+// rollout that seeds it with what the search found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchStats {
+    pub greedy: u64,
+    pub optimal: u64,
+    pub explored: u64,
+}
+
 #[derive(Debug, Clone)]
-struct BlueprintSimulationState {
+struct BlueprintSimulationState<const N: usize> {
     pub minutes_passed: u64,
-    pub inventory: [u64; Material::COUNT],
-    pub robots: [u64; Material::COUNT],
+    pub inventory: [u64; N],
+    pub robots: [u64; N],
 }
 
-impl BlueprintSimulationState {
-    pub fn time_to_build_robot(&self, robot: &RobotBlueprint) -> u64 {
+impl<const N: usize> BlueprintSimulationState<N> {
+    pub fn time_to_build_robot(&self, robot: &RobotBlueprint<N>) -> u64 {
         robot
             .costs
             .iter()
@@ -167,7 +218,7 @@ impl BlueprintSimulationState {
             .unwrap_or(u64::MAX)
     }
 
-    pub fn build_robot(&mut self, blueprint: &Blueprint, material: Material) {
+    pub fn build_robot(&mut self, blueprint: &Blueprint<N>, material: Material) {
         let robot_to_build = &blueprint.robots[material.index()];
         for (i, cost) in robot_to_build.costs.iter().enumerate() {
             self.inventory[i] -= cost;
@@ -183,54 +234,87 @@ impl BlueprintSimulationState {
     }
 }
 
-struct BlueprintSimulation<'a> {
-    blueprint: &'a Blueprint,
+#[derive(PartialEq, Eq, Hash)]
+struct StateKey<const N: usize> {
+    time_remaining: u16,
+    inventory: [u16; N],
+    robots: [u8; N],
+}
+
+struct BlueprintSimulation<'a, const N: usize> {
+    blueprint: &'a Blueprint<N>,
     target: Material,
     minutes: u64,
-    maximum_rates: [u64; Material::COUNT],
+    maximum_rates: [u64; N],
     best: u64,
+    // States walked into, for logging.
+    explored: u64,
 }
 
-impl<'a> BlueprintSimulation<'a> {
-    pub fn new(blueprint: &'a Blueprint, target: Material, minutes: u64) -> Self {
+impl<'a, const N: usize> BlueprintSimulation<'a, N> {
+    const MIN_MINUTES_TO_REMEMBER: u64 = 8;
+
+    pub fn new(blueprint: &'a Blueprint<N>, target: Material, minutes: u64) -> Self {
         Self {
             blueprint,
             target,
             minutes,
-            maximum_rates: [u64::MAX; Material::COUNT],
+            maximum_rates: [0; N],
             best: 0,
+            explored: 0,
         }
     }
 
+    // No more of a material can be spent in a minute than the most any robot costs, so there is
+    // no use in mining it any faster. Materials that nothing costs are never worth mining, except
+    // for the target, which is always worth more.
     fn initialize_maximum_rates(&mut self) {
         for robot in &self.blueprint.robots {
-            for (i, cost) in robot
-                .costs
-                .iter()
-                .enumerate()
-                .filter(|(_, &cost)| cost != 0)
-            {
-                let entry = &mut self.maximum_rates[i];
-                if *entry == u64::MAX || cost > entry {
-                    *entry = *cost;
-                }
+            for (rate, cost) in self.maximum_rates.iter_mut().zip(robot.costs) {
+                *rate = (*rate).max(cost);
             }
         }
+        self.maximum_rates[self.target.index()] = u64::MAX;
     }
 
-    fn initial_state() -> BlueprintSimulationState {
+    fn initial_state() -> BlueprintSimulationState<N> {
         let mut state = BlueprintSimulationState {
             minutes_passed: 0,
-            inventory: [0; Material::COUNT],
-            robots: [0; Material::COUNT],
+            inventory: [0; N],
+            robots: [0; N],
         };
-        state.robots[Material::Ore.index()] = 1;
+        // The first robot mines the least advanced material.
+        state.robots[0] = 1;
         state
     }
 
-    fn handle_final_state(&mut self, state: BlueprintSimulationState) {
+    // Plays out the blueprint by building the most advanced robot that can be
+    // afforded every minute. This is rarely optimal, but it is a good enough
+    // result to start from that much more of the search can be pruned.
+    fn greedy_rollout(&self) -> u64 {
+        let mut state = Self::initial_state();
+        while state.minutes_passed < self.minutes {
+            let robot = self.blueprint.robots.iter().rev().find(|robot| {
+                state.robots[robot.mines.index()] < self.maximum_rates[robot.mines.index()]
+                    && state.time_to_build_robot(robot) == 0
+            });
+            state.advance_time(1);
+            if let Some(robot) = robot {
+                state.build_robot(self.blueprint, robot.mines);
+            }
+        }
+        state.inventory[self.target.index()]
+    }
+
+    fn handle_final_state(&mut self, state: BlueprintSimulationState<N>) {
         let result = state.inventory[self.target.index()];
         if result > self.best {
+            trace!(
+                "blueprint {}: new best of {} {:?}",
+                self.blueprint.id,
+                result,
+                self.target
+            );
             self.best = result;
         }
     }
@@ -239,99 +323,287 @@ impl<'a> BlueprintSimulation<'a> {
         n * (n + 1) / 2
     }
 
-    pub fn run_simulation(&mut self) {
-        // Explore multiple state paths.
-        //
-        // For each state, create one branch for each material, creating a new robot for
-        // that material as soon as possible.
-        //
-        // There are several branch pruning rules detailed below.
-        let mut states = VecDeque::from([Self::initial_state()]);
-
-        while let Some(mut state) = states.pop_front() {
-            let time_remaining = self.minutes - state.minutes_passed;
-
-            // If we only have one second remaining, any robot we build is worthless.
-            if time_remaining <= 1 {
-                state.advance_time(time_remaining);
-                self.handle_final_state(state);
-                continue;
+    // Packs a state into a small key, after removing differences that cannot affect how much of
+    // the target material it goes on to mine. Every robot costs at most the maximum rate of each
+    // material, so no more than that can be spent in each remaining minute, and anything beyond
+    // is never used.
+    //
+    // States with counts too large to pack are never considered equivalent.
+    fn canonicalize(&self, state: &BlueprintSimulationState<N>) -> Option<StateKey<N>> {
+        let time_remaining = self.minutes - state.minutes_passed;
+        let mut key = StateKey {
+            time_remaining: u16::try_from(time_remaining).ok()?,
+            inventory: [0; N],
+            robots: [0; N],
+        };
+        for i in 0..N {
+            let mut inventory = state.inventory[i];
+            if i != self.target.index() {
+                inventory = inventory.min(self.maximum_rates[i].saturating_mul(time_remaining));
             }
+            key.inventory[i] = u16::try_from(inventory).ok()?;
+            key.robots[i] = u8::try_from(state.robots[i]).ok()?;
+        }
+        Some(key)
+    }
 
-            for robot in &self.blueprint.robots {
-                // Building any robot that does not mine our target in the second-to-last minute
-                // is worthless.
-                if robot.mines != self.target && time_remaining <= 2 {
-                    continue;
-                }
+    pub fn run_simulation(&mut self) {
+        let mut visited = FxHashSet::default();
+        self.explore(Self::initial_state(), &mut visited);
+        debug!(
+            "blueprint {}: {} {:?} in {} minutes after exploring {} states, remembering {}",
+            self.blueprint.id,
+            self.best,
+            self.target,
+            self.minutes,
+            self.explored,
+            visited.len()
+        );
+    }
 
-                // Do not exceed the maximum rate we need for this material.
-                if state.robots[robot.mines.index()] >= self.maximum_rates[robot.mines.index()] {
-                    continue;
-                }
+    // Explores every state path from the given state, depth first.
+    //
+    // For each state, create one branch for each material, creating a new robot for that material
+    // as soon as possible.
+    //
+    // There are several branch pruning rules detailed below.
+    fn explore(
+        &mut self,
+        mut state: BlueprintSimulationState<N>,
+        visited: &mut FxHashSet<StateKey<N>>,
+    ) {
+        self.explored += 1;
+        let time_remaining = self.minutes - state.minutes_passed;
+
+        // If we only have one second remaining, any robot we build is worthless.
+        if time_remaining <= 1 {
+            state.advance_time(time_remaining);
+            self.handle_final_state(state);
+            return;
+        }
 
-                // There is absolutely no way we can beat our current best using this state.
-                //
-                // Current inventory of target material...
-                // + how much material we will surely generate with our existing robots...
-                // + how much material we will generate if we build one target-mining robot
-                // every second (which is likely impossible, but represents the
-                // ideal situation).
-                //
-                // If this sum is not greater than the current best, this state is worthless.
-                if state.inventory[self.target.index()]
-                    + state.robots[self.target.index()] * time_remaining
-                    + Self::triangular_number(time_remaining)
-                    <= self.best
-                {
-                    continue;
+        // An equivalent state has already been explored, and nothing it found could have been
+        // pruned by a worse best than we have now. States close to the end are cheaper to explore
+        // again than to remember.
+        if time_remaining >= Self::MIN_MINUTES_TO_REMEMBER {
+            if let Some(key) = self.canonicalize(&state) {
+                if !visited.insert(key) {
+                    return;
                 }
+            }
+        }
+
+        // Robots for later materials come first, so good results are found early and more
+        // branches can be pruned.
+        let blueprint = self.blueprint;
+        for robot in blueprint.robots.iter().rev() {
+            // Building any robot that does not mine our target in the second-to-last minute is
+            // worthless.
+            if robot.mines != self.target && time_remaining <= 2 {
+                continue;
+            }
 
-                let mut next_state = state.clone();
+            // Do not exceed the maximum rate we need for this material.
+            if state.robots[robot.mines.index()] >= self.maximum_rates[robot.mines.index()] {
+                continue;
+            }
 
-                // Calculate the time it would take to build a new robot of this type.
-                let delta_mins = next_state.time_to_build_robot(robot).saturating_add(1);
-                if delta_mins < time_remaining {
-                    // Enough time to build the robot and make use of it for at least one minute.
-                    next_state.advance_time(delta_mins);
-                    next_state.build_robot(self.blueprint, robot.mines);
-                    states.push_back(next_state);
-                } else {
-                    // Cannot build a robot for this material, so this path is finished.
-                    next_state.advance_time(time_remaining);
-                    self.handle_final_state(next_state);
-                }
+            // There is absolutely no way we can beat our current best using this state.
+            //
+            // Current inventory of target material...
+            // + how much material we will surely generate with our existing robots...
+            // + how much material we will generate if we build one target-mining robot every
+            // second (which is likely impossible, but represents the ideal situation).
+            //
+            // If this sum is not greater than the current best, this state is worthless.
+            if state.inventory[self.target.index()]
+                + state.robots[self.target.index()] * time_remaining
+                + Self::triangular_number(time_remaining - 1)
+                <= self.best
+            {
+                continue;
+            }
+
+            let mut next_state = state.clone();
+
+            // Calculate the time it would take to build a new robot of this type.
+            let delta_mins = next_state.time_to_build_robot(robot).saturating_add(1);
+            if delta_mins < time_remaining {
+                // Enough time to build the robot and make use of it for at least one minute.
+                next_state.advance_time(delta_mins);
+                next_state.build_robot(self.blueprint, robot.mines);
+                self.explore(next_state, visited);
+            } else {
+                // Cannot build a robot for this material, so this path is finished.
+                next_state.advance_time(time_remaining);
+                self.handle_final_state(next_state);
             }
         }
     }
 
-    pub fn maximize(&mut self) -> u64 {
+    pub fn search(&mut self) -> SearchStats {
         self.initialize_maximum_rates();
+        let greedy = self.greedy_rollout();
+        self.best = greedy;
         self.run_simulation();
-        self.best
+        SearchStats {
+            greedy,
+            optimal: self.best,
+            explored: self.explored,
+        }
     }
 }
 
-fn parse_blueprints(input: &str) -> AocResult<Vec<Blueprint>> {
+// Reads every blueprint, with the materials read from the same input.
+pub fn parse_blueprints<const N: usize>(
+    input: &str,
+    materials: &Materials,
+) -> AocResult<Vec<Blueprint<N>>> {
     input
         .lines()
-        .map(|line| Blueprint::from_str(line))
+        .map(|line| Blueprint::parse(line, materials))
         .collect()
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    let blueprints = parse_blueprints(input)?;
+// Calls a function generic over the number of materials with the number read
+// from the input.
+macro_rules! with_material_count {
+    ($materials:expr, $f:ident($($arg:expr),*)) => {
+        match $materials.len() {
+            1 => $f::<1>($($arg),*),
+            2 => $f::<2>($($arg),*),
+            3 => $f::<3>($($arg),*),
+            4 => $f::<4>($($arg),*),
+            5 => $f::<5>($($arg),*),
+            6 => $f::<6>($($arg),*),
+            7 => $f::<7>($($arg),*),
+            8 => $f::<8>($($arg),*),
+            n => Err(AocError::parse(format!("too many materials: {n}"))),
+        }
+    };
+}
+
+fn total_quality_level<const N: usize>(input: &str, materials: &Materials) -> AocResult<u64> {
+    let blueprints = timing::parse_phase(|| parse_blueprints::<N>(input, materials))?;
+    let geode = materials.get("geode")?;
+    let progress = Tracker::current();
+    progress.start(Some(blueprints.len() as u64), "blueprints");
+    // Each blueprint is searched independently, with its own best result to prune against.
     Ok(blueprints
-        .into_iter()
-        .map(|blueprint| blueprint.quality_level(Material::Geode, 24))
+        .par_iter()
+        .map(|blueprint| {
+            // Blueprints left once the solver is cancelled are skipped.
+            if progress.cancelled() {
+                return 0;
+            }
+            let quality_level = blueprint.quality_level(geode, 24);
+            progress.advance(1);
+            quality_level
+        })
         .sum())
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    let blueprints = parse_blueprints(input)?;
+fn geode_product<const N: usize>(input: &str, materials: &Materials) -> AocResult<u64> {
+    let blueprints = timing::parse_phase(|| parse_blueprints::<N>(input, materials))?;
+    let blueprints = &blueprints[..blueprints.len().min(3)];
+    let geode = materials.get("geode")?;
+    let progress = Tracker::current();
+    progress.start(Some(blueprints.len() as u64), "blueprints");
     Ok(blueprints
-        .into_iter()
-        .take(3)
-        .map(|blueprint| blueprint.maximize(Material::Geode, 32))
+        .par_iter()
+        .map(|blueprint| {
+            if progress.cancelled() {
+                return 0;
+            }
+            let geodes = blueprint.maximize(geode, 32);
+            progress.advance(1);
+            geodes
+        })
         .product())
 }
+
+pub fn solve_a(input: &str) -> AocResult<u64> {
+    let materials = timing::parse_phase(|| Materials::from_input(input))?;
+    with_material_count!(materials, total_quality_level(input, &materials))
+}
+
+pub fn solve_b(input: &str) -> AocResult<u64> {
+    let materials = timing::parse_phase(|| Materials::from_input(input))?;
+    with_material_count!(materials, geode_product(input, &materials))
+}
+
+register_day!(19, solve_a, solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GEODE: Material = Material(3);
+
+    fn example() -> Vec<Blueprint<4>> {
+        let input = include_str!("../../examples/19.txt");
+        parse_blueprints(input, &Materials::from_input(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn maximizes_example_geodes() {
+        let blueprints = example();
+        assert_eq!(blueprints[0].maximize(GEODE, 24), 9);
+        assert_eq!(blueprints[1].maximize(GEODE, 24), 12);
+        assert_eq!(blueprints[0].maximize(GEODE, 28), 26);
+        assert_eq!(blueprints[1].maximize(GEODE, 28), 31);
+    }
+
+    #[test]
+    fn seeds_search_with_greedy_rollout() {
+        for blueprint in example() {
+            let stats = blueprint.search(GEODE, 24);
+            assert!(stats.greedy <= stats.optimal);
+            let unseeded = {
+                let mut simulation = BlueprintSimulation::new(&blueprint, GEODE, 24);
+                simulation.initialize_maximum_rates();
+                simulation.run_simulation();
+                simulation.explored
+            };
+            assert!(stats.explored <= unseeded);
+        }
+        // Building whatever can be afforded finds no geodes at all with the
+        // first blueprint, but comes close with the second.
+        let blueprints = example();
+        assert_eq!(blueprints[0].search(GEODE, 24).greedy, 0);
+        assert_eq!(blueprints[1].search(GEODE, 24).greedy, 10);
+        assert_eq!(blueprints[1].search(GEODE, 32).greedy, 55);
+    }
+
+    #[test]
+    fn reads_materials_from_input() {
+        // The example with an extra material that nothing is made of, listed before obsidian.
+        let input = include_str!("../../examples/19.txt")
+            .replace(
+                "Each obsidian robot",
+                "Each sand robot costs 1 ore. Each obsidian robot",
+            )
+            .replace("Blueprint 2", "Blueprint 7");
+        let materials = Materials::from_input(&input).unwrap();
+        assert_eq!(
+            materials.names,
+            ["ore", "clay", "sand", "obsidian", "geode"]
+        );
+        let blueprints = parse_blueprints::<5>(&input, &materials).unwrap();
+        let geode = materials.get("geode").unwrap();
+        assert_eq!(geode, Material(4));
+        assert_eq!(blueprints[0].maximize(geode, 24), 9);
+        assert_eq!(blueprints[1].maximize(geode, 24), 12);
+        assert_eq!(solve_a(&input).unwrap(), 9 + 7 * 12);
+
+        let missing = input.replace("Each sand robot costs 1 ore. ", "");
+        assert!(parse_blueprints::<5>(&missing, &materials).is_err());
+        let unknown = input.replace("14 clay", "14 mud");
+        assert!(parse_blueprints::<5>(&unknown, &materials).is_err());
+    }
+
+    #[test]
+    fn searches_past_32_minutes() {
+        assert_eq!(example()[0].maximize(GEODE, 34), 77);
+    }
+}
@@ -1,101 +1,214 @@
-use std::str::FromStr;
-
-use crate::common::{AocError, AocResult, IntoAocResult};
-
-#[derive(Clone)]
-struct EncryptedFile {
-    // Store each number with their original index, so that we can locate individual numbers by
-    // their original index.
-    pub indexed_numbers: Vec<(usize, i64)>,
+use std::ops::Range;
+
+use crate::common::{
+    input::InputLines, lines::map_lines, timing, AocResult, IntoAocResult, OrderList,
+};
+
+// A way of mixing the encrypted file, which moves each number as many places
+// along the circular list as its value, taking the numbers in their original
+// order.
+pub trait Mixer {
+    // Returns the numbers in their mixed order.
+    fn mix(numbers: &[i64], rounds: usize) -> Vec<i64>;
 }
 
-impl EncryptedFile {
-    pub fn new(indexed_numbers: Vec<(usize, i64)>) -> Self {
-        Self { indexed_numbers }
-    }
-
-    pub fn get(&self, i: isize) -> i64 {
-        let index = i.rem_euclid(self.indexed_numbers.len() as isize) as usize;
-        self.indexed_numbers[index].1
-    }
+// Where a number ends up after moving `n` places from `index`. The start and
+// end of the circular list are the same position, hence `length - 1`.
+fn destination(index: usize, n: i64, length: usize) -> usize {
+    (index as i64 + n).rem_euclid(length as i64 - 1) as usize
+}
 
-    pub fn get_current_index_by_original_index(&self, i: usize) -> Option<usize> {
-        self.indexed_numbers
-            .iter()
-            .position(|&(original_index, _)| original_index == i)
-    }
+// Moves the element at `from` to `to` in place, shifting everything between
+// them over by one with a memmove. Returns the positions whose elements
+// changed.
+fn shift<T: Copy>(slice: &mut [T], from: usize, to: usize) -> Range<usize> {
+    let element = slice[from];
+    let changed = if to < from {
+        slice.copy_within(to..from, to + 1);
+        to..from + 1
+    } else {
+        slice.copy_within(from + 1..to + 1, from);
+        from..to + 1
+    };
+    slice[to] = element;
+    changed
+}
 
-    pub fn get_index_by_value(&self, value: i64) -> Option<usize> {
-        self.indexed_numbers.iter().position(|&(_, n)| n == value)
-    }
+// Keeps the numbers in an order-statistic treap, where each number's handle is
+// its original index, so finding and moving a number takes O(log n) time.
+pub struct TreapMixer;
 
-    pub fn mix(&mut self, decryption_key: i64, rounds: i64) {
-        // Apply decryption key before we start.
-        for (_, n) in &mut self.indexed_numbers {
-            *n *= decryption_key;
+impl Mixer for TreapMixer {
+    fn mix(numbers: &[i64], rounds: usize) -> Vec<i64> {
+        let mut list = numbers.iter().copied().collect::<OrderList<_>>();
+        for _ in 0..rounds {
+            for (original_index, &n) in numbers.iter().enumerate() {
+                let current_index = list.index_of(original_index);
+                list.move_to(original_index, destination(current_index, n, numbers.len()));
+            }
         }
+        list.iter().copied().collect()
+    }
+}
 
-        let length = self.indexed_numbers.len();
+// Keeps the original indices of the numbers in a vector, which is searched to
+// find where each number currently is. The indices are 32 bits wide so that
+// there is half as much to search and shift.
+pub struct ScanMixer;
 
+impl Mixer for ScanMixer {
+    fn mix(numbers: &[i64], rounds: usize) -> Vec<i64> {
+        let mut order = (0..numbers.len() as u32).collect::<Vec<_>>();
         for _ in 0..rounds {
-            for original_index in 0..length {
-                let current_index = self
-                    .get_current_index_by_original_index(original_index)
+            for (original_index, &n) in (0..).zip(numbers) {
+                let current_index = order
+                    .iter()
+                    .position(|&index| index == original_index)
                     .unwrap();
+                shift(
+                    &mut order,
+                    current_index,
+                    destination(current_index, n, numbers.len()),
+                );
+            }
+        }
+        order
+            .into_iter()
+            .map(|index| numbers[index as usize])
+            .collect()
+    }
+}
 
-                let n = self.indexed_numbers[current_index].1;
-
-                let new_index = current_index as i64 + n;
-                // length - 1 because the start and end positions are the same.
-                let new_index = new_index.rem_euclid(length as i64 - 1) as usize;
+// Like `ScanMixer`, but also keeps where each number currently is by its
+// original index, so nothing has to be searched. Only the numbers shifted over
+// by a move need their positions updated.
+pub struct IndexedMixer;
 
-                // Shift the contents of the vector using memmove.
-                let wrapped = new_index < current_index;
-                if wrapped {
-                    let (begin, end) = (new_index as usize, current_index as usize);
-                    self.indexed_numbers.copy_within(begin..end, begin + 1);
-                } else {
-                    let (begin, end) = (current_index as usize, new_index as usize);
-                    self.indexed_numbers.copy_within((begin + 1)..=end, begin);
+impl Mixer for IndexedMixer {
+    fn mix(numbers: &[i64], rounds: usize) -> Vec<i64> {
+        let mut order = (0..numbers.len() as u32).collect::<Vec<_>>();
+        let mut positions = order.clone();
+        for _ in 0..rounds {
+            for (original_index, &n) in numbers.iter().enumerate() {
+                let current_index = positions[original_index] as usize;
+                let changed = shift(
+                    &mut order,
+                    current_index,
+                    destination(current_index, n, numbers.len()),
+                );
+                for position in changed {
+                    positions[order[position] as usize] = position as u32;
                 }
-
-                self.indexed_numbers[new_index] = (original_index, n);
             }
         }
+        order
+            .into_iter()
+            .map(|index| numbers[index as usize])
+            .collect()
+    }
+}
+
+struct EncryptedFile {
+    numbers: Vec<i64>,
+}
+
+impl EncryptedFile {
+    pub fn read(lines: InputLines) -> AocResult<Self> {
+        Ok(Self {
+            numbers: map_lines(lines, |line| {
+                line.parse()
+                    .into_aoc_result_msg("invalid integer in encrypted file")
+            })
+            .collect::<AocResult<_>>()?,
+        })
     }
 
-    pub fn sum_grove_coordinates(&self) -> AocResult<i64> {
-        let zero_index = self
-            .get_index_by_value(0)
-            .into_aoc_result_msg("no zero found")?;
-        Ok(self.get(zero_index as isize + 1000)
-            + self.get(zero_index as isize + 2000)
-            + self.get(zero_index as isize + 3000))
+    // Applies the decryption key and mixes the numbers, returning them in their
+    // mixed order.
+    pub fn decrypt<M: Mixer>(&self, decryption_key: i64, rounds: usize) -> Vec<i64> {
+        let numbers = self
+            .numbers
+            .iter()
+            .map(|n| n * decryption_key)
+            .collect::<Vec<_>>();
+        // A single number has nowhere to move.
+        if numbers.len() < 2 {
+            return numbers;
+        }
+        M::mix(&numbers, rounds)
     }
 }
 
-impl FromStr for EncryptedFile {
-    type Err = AocError;
-    fn from_str(s: &str) -> AocResult<Self> {
-        Ok(Self::new(
-            s.lines()
-                .enumerate()
-                .map(|(i, line)| line.parse().and_then(|n| Ok((i, n))))
-                .collect::<Result<_, _>>()
-                .into_aoc_result_msg("invalid integer in encrypted file")?,
-        ))
-    }
+fn sum_grove_coordinates(mixed: &[i64]) -> AocResult<i64> {
+    let zero_index = mixed
+        .iter()
+        .position(|&n| n == 0)
+        .into_aoc_result_msg("no zero found")?;
+    Ok([1000, 2000, 3000]
+        .into_iter()
+        .map(|offset| mixed[(zero_index + offset) % mixed.len()])
+        .sum())
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    let mut file = EncryptedFile::from_str(input)?;
-    file.mix(1, 1);
-    file.sum_grove_coordinates().map(|n| n as u64)
+pub fn solve_a_with<M: Mixer>(lines: InputLines) -> AocResult<i64> {
+    let file = timing::parse_phase(|| EncryptedFile::read(lines))?;
+    sum_grove_coordinates(&file.decrypt::<M>(1, 1))
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
+pub fn solve_b_with<M: Mixer>(lines: InputLines) -> AocResult<i64> {
     const DECRYPTION_KEY: i64 = 811589153;
-    let mut file = EncryptedFile::from_str(input)?;
-    file.mix(DECRYPTION_KEY, 10);
-    file.sum_grove_coordinates().map(|n| n as u64)
+    let file = timing::parse_phase(|| EncryptedFile::read(lines))?;
+    sum_grove_coordinates(&file.decrypt::<M>(DECRYPTION_KEY, 10))
+}
+
+pub fn solve_a(lines: InputLines) -> AocResult<i64> {
+    solve_a_with::<IndexedMixer>(lines)
+}
+
+pub fn solve_b(lines: InputLines) -> AocResult<i64> {
+    solve_b_with::<IndexedMixer>(lines)
+}
+
+register_day!(
+    20,
+    lines solve_a | solve_a_with::<TreapMixer> | solve_a_with::<ScanMixer>,
+    lines solve_b | solve_b_with::<TreapMixer> | solve_b_with::<ScanMixer>,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(input: &str) -> Vec<i64> {
+        input.lines().map(|line| line.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn shifts_elements_both_ways() {
+        let mut slice = [0, 1, 2, 3, 4];
+        assert_eq!(shift(&mut slice, 1, 3), 1..4);
+        assert_eq!(slice, [0, 2, 3, 1, 4]);
+        assert_eq!(shift(&mut slice, 4, 0), 0..5);
+        assert_eq!(slice, [4, 0, 2, 3, 1]);
+        assert_eq!(shift(&mut slice, 2, 2), 2..3);
+        assert_eq!(slice, [4, 0, 2, 3, 1]);
+    }
+
+    #[test]
+    fn mixers_agree() {
+        let example = numbers(include_str!("../../examples/20.txt"));
+        // Large numbers wrap around the list many times over.
+        let wrapping = [5, -17, 0, 811589153, -811589153 * 3, 2, 2, -1];
+        for numbers in [&example[..], &wrapping] {
+            for rounds in [1, 10] {
+                let mixed = IndexedMixer::mix(numbers, rounds);
+                assert_eq!(TreapMixer::mix(numbers, rounds), mixed);
+                assert_eq!(ScanMixer::mix(numbers, rounds), mixed);
+            }
+        }
+        assert_eq!(
+            sum_grove_coordinates(&IndexedMixer::mix(&example, 1)).unwrap(),
+            3
+        );
+    }
 }
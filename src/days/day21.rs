@@ -1,8 +1,8 @@
 use std::{collections::HashMap, str::FromStr};
 
-use crate::common::{AocError, AocResult, IntoAocResult};
+use crate::common::{timing, AocError, AocResult, IntoAocResult};
 use itertools::Itertools;
-use num::Integer;
+use num::{rational::Rational64, CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, Zero};
 
 // Operations supported by our calculator.
 #[derive(Debug, Clone)]
@@ -14,26 +14,6 @@ enum Operator {
     Divide,
 }
 
-impl Operator {
-    pub fn commutative(&self) -> bool {
-        match self {
-            Self::Plus => true,
-            Self::Minus => false,
-            Self::Times => true,
-            Self::Divide => false,
-        }
-    }
-
-    pub fn inverse(&self) -> Self {
-        match self {
-            Self::Plus => Self::Minus,
-            Self::Minus => Self::Plus,
-            Self::Times => Self::Divide,
-            Self::Divide => Self::Times,
-        }
-    }
-}
-
 impl FromStr for Operator {
     type Err = AocError;
     fn from_str(s: &str) -> AocResult<Self> {
@@ -42,86 +22,115 @@ impl FromStr for Operator {
             "-" => Ok(Self::Minus),
             "*" => Ok(Self::Times),
             "/" => Ok(Self::Divide),
-            _ => Err(AocError::new(&format!("invalid operator: {s}"))),
+            _ => Err(AocError::parse(format!("invalid operator: {s}"))),
         }
     }
 }
 
-// Types of operands for an operator.
-#[derive(Debug, Clone)]
-enum Operand {
-    // A variable with a currently-unknown value.
-    Variable,
-    // A known value.
-    Number(i64),
-    // A nested operation whose value is unknown. This means there is a variable somewhere in the
-    // operation stack.
-    Operation(Box<(Operand, Operator, Operand)>),
+// A value of the form `a*x + b`, where `x` is the single unknown variable.
+//
+// Every operation on the variable keeps it linear, except for multiplying two
+// values that both depend on it or dividing by one.
+#[derive(Debug, Clone, Copy)]
+struct LinearForm {
+    // The coefficient of the variable.
+    pub a: Rational64,
+    // The constant term.
+    pub b: Rational64,
 }
 
-impl Operand {
-    // Solves for a single variable in this operand stack, assuming it is an
-    // operation with one variable.
-    pub fn solve_for_single_variable(&self, rhs: i64) -> AocResult<i64> {
-        // Unwind the operation stack, starting from the top, until the variable is
-        // isolated. The variable must be a leaf node, and operation must have one
-        // number and one nested operation.
-        let mut stack = self;
-        let mut solution = rhs;
-        loop {
-            match stack {
-                Self::Variable => break,
-                Self::Number(_) => return Err(AocError::new("no variable found in operand stack")),
-                Self::Operation(operation) => match operation.as_ref() {
-                    (Self::Number(lhs), op, rhs @ _) => {
-                        solution = if op.commutative() {
-                            op.inverse().perform(solution, *lhs)
-                        } else {
-                            op.perform(*lhs, solution)
-                        };
-                        stack = rhs;
-                    }
-                    (lhs @ _, op, Self::Number(rhs)) => {
-                        solution = op.inverse().perform(solution, *rhs);
-                        stack = lhs;
-                    }
-                    _ => {
-                        return Err(AocError::new(
-                            "at least one side of each operation should be a number",
-                        ))
-                    }
-                },
-            }
+impl LinearForm {
+    pub fn constant(b: Rational64) -> Self {
+        Self {
+            a: Rational64::zero(),
+            b,
         }
-        Ok(solution)
     }
-}
 
-impl Operator {
-    pub fn perform<I: Integer>(&self, lhs: I, rhs: I) -> I {
-        match self {
-            Self::Plus => lhs.add(rhs),
-            Self::Minus => lhs.sub(rhs),
-            Self::Times => lhs.mul(rhs),
-            Self::Divide => lhs.div(rhs),
+    pub fn variable() -> Self {
+        Self {
+            a: Rational64::from_integer(1),
+            b: Rational64::zero(),
         }
     }
 
-    pub fn perform_variable(&self, lhs: Operand, rhs: Operand) -> Operand {
-        match (&lhs, &rhs) {
-            // The two operands are known, so the result is known.
-            (Operand::Number(lhs), Operand::Number(rhs)) => {
-                Operand::Number(self.perform(*lhs, *rhs))
-            }
-            // At least one operand is unknown, so the result is unknown.
-            _ => Operand::Operation(Box::new((lhs, self.clone(), rhs))),
+    pub fn is_constant(&self) -> bool {
+        self.a.is_zero()
+    }
+
+    // Solves `self = other` for the variable.
+    pub fn solve_equal(&self, other: &Self) -> AocResult<Rational64> {
+        let a = Operator::Minus.perform(self.a, other.a)?;
+        let b = Operator::Minus.perform(other.b, self.b)?;
+        if a.is_zero() {
+            return Err(AocError::no_solution(if b.is_zero() {
+                "every value of the variable is a solution"
+            } else {
+                "no value of the variable is a solution"
+            }));
         }
+        Operator::Divide.perform(b, a)
+    }
+}
+
+impl Operator {
+    // Performs the operation exactly, failing if the result does not fit or divides
+    // by zero.
+    pub fn perform(&self, lhs: Rational64, rhs: Rational64) -> AocResult<Rational64> {
+        let result = match self {
+            Self::Plus => lhs.checked_add(&rhs),
+            Self::Minus => lhs.checked_sub(&rhs),
+            Self::Times => lhs.checked_mul(&rhs),
+            Self::Divide if rhs.is_zero() => {
+                return Err(AocError::no_solution(format!("{lhs} / 0 is undefined")))
+            }
+            Self::Divide => lhs.checked_div(&rhs),
+        };
+        result.ok_or_else(|| AocError::no_solution(format!("{lhs} {self:?} {rhs} overflowed")))
+    }
+
+    pub fn perform_linear(&self, lhs: LinearForm, rhs: LinearForm) -> AocResult<LinearForm> {
+        let non_linear = || {
+            AocError::no_solution(format!(
+                "{self:?} of two values that depend on the variable is not linear"
+            ))
+        };
+        Ok(match self {
+            Self::Plus | Self::Minus => LinearForm {
+                a: self.perform(lhs.a, rhs.a)?,
+                b: self.perform(lhs.b, rhs.b)?,
+            },
+            Self::Times if lhs.is_constant() => LinearForm {
+                a: self.perform(lhs.b, rhs.a)?,
+                b: self.perform(lhs.b, rhs.b)?,
+            },
+            Self::Times if rhs.is_constant() => LinearForm {
+                a: self.perform(lhs.a, rhs.b)?,
+                b: self.perform(lhs.b, rhs.b)?,
+            },
+            Self::Divide if rhs.is_constant() => LinearForm {
+                a: self.perform(lhs.a, rhs.b)?,
+                b: self.perform(lhs.b, rhs.b)?,
+            },
+            Self::Times | Self::Divide => return Err(non_linear()),
+        })
+    }
+}
+
+// Converts an exact result back to an integer, which puzzle answers always are.
+fn into_integer(value: Rational64) -> AocResult<i64> {
+    if value.is_integer() {
+        Ok(value.to_integer())
+    } else {
+        Err(AocError::no_solution(format!(
+            "{value} is not a whole number"
+        )))
     }
 }
 
 #[derive(Debug, Default)]
 enum MonkeyRule {
-    Number(i64),
+    Number(Rational64),
     Equation(usize, Operator, usize),
     #[default]
     Variable,
@@ -143,16 +152,16 @@ impl MonkeyRiddle {
 
     pub fn solve(&self, name: &str) -> AocResult<i64> {
         let id = self.get_id_by_name(name)?;
-        self.solve_id(id)
+        into_integer(self.solve_id(id)?)
     }
 
-    fn solve_id(&self, id: usize) -> AocResult<i64> {
+    fn solve_id(&self, id: usize) -> AocResult<Rational64> {
         match &self.rules[id] {
             MonkeyRule::Number(n) => Ok(*n),
             MonkeyRule::Equation(lhs, op, rhs) => {
-                Ok(op.perform(self.solve_id(*lhs)?, self.solve_id(*rhs)?))
+                op.perform(self.solve_id(*lhs)?, self.solve_id(*rhs)?)
             }
-            MonkeyRule::Variable => Err(AocError::new(
+            MonkeyRule::Variable => Err(AocError::parse(
                 "variables not supported in normal solving mode",
             )),
         }
@@ -165,39 +174,25 @@ impl MonkeyRiddle {
         let test_id = self.get_id_by_name(test)?;
         match &self.rules[test_id] {
             MonkeyRule::Equation(lhs, _, rhs) => {
-                // Solve left and right sides.
-                let left_stack = self.solve_id_with_variables(*lhs);
-                let right_stack = self.solve_id_with_variables(*rhs);
-
-                // At this point, because there should be only one variable, one side should be
-                // a number and the other should be an operation stack.
-                //
-                // If not, we are unable to solve this equation, because there is more than one
-                // variable.
-                match (&left_stack, &right_stack) {
-                    (Operand::Operation(_), Operand::Number(equal)) => {
-                        left_stack.solve_for_single_variable(*equal)
-                    }
-                    (Operand::Number(equal), Operand::Operation(_)) => {
-                        right_stack.solve_for_single_variable(*equal)
-                    }
-                    _ => Err(AocError::new("unsupported use case")),
-                }
+                // Reduce both sides to linear forms, so the variable can appear on either
+                // side, or both.
+                let left = self.solve_id_linear(*lhs)?;
+                let right = self.solve_id_linear(*rhs)?;
+                into_integer(left.solve_equal(&right)?)
             }
-            _ => Err(AocError::new(&format!(
+            _ => Err(AocError::parse(format!(
                 "monkey {test} does not have an lhs and rhs to compare"
             ))),
         }
     }
 
-    pub fn solve_id_with_variables(&self, id: usize) -> Operand {
+    fn solve_id_linear(&self, id: usize) -> AocResult<LinearForm> {
         match &self.rules[id] {
-            MonkeyRule::Number(n) => Operand::Number(*n),
-            MonkeyRule::Equation(lhs, op, rhs) => op.perform_variable(
-                self.solve_id_with_variables(*lhs),
-                self.solve_id_with_variables(*rhs),
-            ),
-            MonkeyRule::Variable => Operand::Variable,
+            MonkeyRule::Number(n) => Ok(LinearForm::constant(*n)),
+            MonkeyRule::Equation(lhs, op, rhs) => {
+                op.perform_linear(self.solve_id_linear(*lhs)?, self.solve_id_linear(*rhs)?)
+            }
+            MonkeyRule::Variable => Ok(LinearForm::variable()),
         }
     }
 }
@@ -215,7 +210,7 @@ impl FromStr for MonkeyRiddle {
             .lines()
             .map(|line| {
                 line.split_once(':')
-                    .into_aoc_result_msg("invaid input line")
+                    .into_aoc_result_msg("invalid input line")
             })
             .collect::<AocResult<Vec<_>>>()?;
 
@@ -244,7 +239,9 @@ impl FromStr for MonkeyRiddle {
                     let operator = Operator::from_str(op)?;
                     MonkeyRule::Equation(*left_id, operator, *right_id)
                 }
-                None => MonkeyRule::Number(equation.parse().into_aoc_result()?),
+                None => MonkeyRule::Number(Rational64::from_integer(
+                    equation.parse().into_aoc_result()?,
+                )),
             }
         }
 
@@ -252,19 +249,69 @@ impl FromStr for MonkeyRiddle {
     }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
+pub fn solve_a(input: &str) -> AocResult<i64> {
     const ROOT: &str = "root";
-    let riddle = MonkeyRiddle::from_str(input)?;
-    riddle
-        .solve(ROOT)
-        .and_then(|n| n.try_into().into_aoc_result())
+    let riddle = timing::parse_phase(|| MonkeyRiddle::from_str(input))?;
+    riddle.solve(ROOT)
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
+pub fn solve_b(input: &str) -> AocResult<i64> {
     const ROOT: &str = "root";
     const HUMAN: &str = "humn";
-    let mut riddle = MonkeyRiddle::from_str(input)?;
-    riddle
-        .solve_for_variable(HUMAN, ROOT)
-        .and_then(|n| n.try_into().into_aoc_result())
+    let mut riddle = timing::parse_phase(|| MonkeyRiddle::from_str(input))?;
+    riddle.solve_for_variable(HUMAN, ROOT)
+}
+
+register_day!(21, solve_a, solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The operation of root is ignored when solving for humn, which only
+    // compares its two sides.
+    fn riddle(root: &str, monkeys: &str) -> String {
+        format!("root: {root}\nhumn: 0\ntwo: 2\nten: 10\n{monkeys}")
+    }
+
+    #[test]
+    fn solves_for_variable_on_both_sides() {
+        // 2x = x + 10
+        let input = riddle("left + right", "left: humn * two\nright: humn + ten");
+        assert_eq!(solve_b(&input).unwrap(), 10);
+    }
+
+    #[test]
+    fn refuses_non_linear_expressions() {
+        let input = riddle("left + ten", "left: humn * humn");
+        let err = solve_b(&input).unwrap_err();
+        assert!(err.to_string().contains("not linear"), "{err}");
+    }
+
+    #[test]
+    fn refuses_fractional_variables() {
+        // 2x + 10 = 13
+        let input = riddle(
+            "left + right",
+            "left: double + ten\ndouble: humn * two\nright: 13",
+        );
+        let err = solve_b(&input).unwrap_err();
+        assert!(
+            err.to_string().contains("3/2 is not a whole number"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn divides_exactly() {
+        // Truncating 7 / 2 would make root 6.
+        let input = riddle("half * two", "half: seven / two\nseven: 7");
+        assert_eq!(solve_a(&input).unwrap(), 7);
+        let input = riddle("seven / two", "seven: 7");
+        let err = solve_a(&input).unwrap_err();
+        assert!(
+            err.to_string().contains("7/2 is not a whole number"),
+            "{err}"
+        );
+    }
 }
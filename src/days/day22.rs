@@ -1,45 +1,19 @@
 use std::{
     collections::{HashSet, VecDeque},
-    ops::{Add, Sub},
+    iter,
     str::FromStr,
 };
 
-use crate::common::{AocError, AocResult, IntoAocResult, NewlineBlocks};
+use crate::common::{
+    constants::Constants, timing, viz::Visualize, AocError, AocResult, DayArgs, Direction,
+    IntoAocResult, NewlineBlocks, Point2, Point3, Rotation, SolutionPart,
+};
 use itertools::Itertools;
-use num::{FromPrimitive, ToPrimitive};
-
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point {
-    pub x: i64,
-    pub y: i64,
-}
-
-impl Point {
-    pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
-    }
 
-    pub fn in_range(&self, min: &Point, max: &Point) -> bool {
-        min.x <= self.x && self.x < max.x && min.y <= self.y && self.y < max.y
-    }
-}
-
-impl Add for Point {
-    type Output = Point;
-    fn add(self, rhs: Self) -> Self::Output {
-        Self::new(self.x + rhs.x, self.y + rhs.y)
-    }
-}
-
-impl Sub for Point {
-    type Output = Point;
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self::new(self.x - rhs.x, self.y - rhs.y)
-    }
-}
+pub type Point = Point2<i64>;
 
 #[derive(Debug)]
-enum Instruction {
+pub enum Instruction {
     Move(u64),
     RotateLeft,
     RotateRight,
@@ -55,7 +29,7 @@ fn parse_instructions(input: &str) -> AocResult<Vec<Instruction>> {
             '0'..='9' => {
                 let mut n = c.to_digit(10).unwrap();
                 while let Some(c) = chars.peek() {
-                    if c.is_digit(10) {
+                    if c.is_ascii_digit() {
                         n = 10 * n + c.to_digit(10).unwrap();
                         chars.next();
                     } else {
@@ -65,7 +39,7 @@ fn parse_instructions(input: &str) -> AocResult<Vec<Instruction>> {
                 instructions.push(Instruction::Move(n as u64));
             }
             _ => {
-                return Err(AocError::new(&format!(
+                return Err(AocError::parse(format!(
                     "invalid instruction character: {c}"
                 )))
             }
@@ -74,96 +48,201 @@ fn parse_instructions(input: &str) -> AocResult<Vec<Instruction>> {
     Ok(instructions)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
-#[repr(i8)]
-enum Direction {
-    Right = 0,
-    Down = 1,
-    Left = 2,
-    Up = 3,
+// A single block of uniform width in the monkey map.
+#[derive(Debug)]
+struct MonkeyMapBlock {
+    pub min: Point,
+    pub max: Point,
+    pub walls: HashSet<Point>,
 }
 
-impl Direction {
-    pub const COUNT: usize = 4;
+impl MonkeyMapBlock {
+    pub fn width(&self) -> i64 {
+        self.max.x - self.min.x + 1
+    }
 
-    pub fn is_horizontal(&self) -> bool {
-        self.to_i8().unwrap() % 2 == 0
+    pub fn height(&self) -> i64 {
+        self.max.y - self.min.y + 1
     }
+}
+
+pub trait Traversable {
+    // The leftmost open tile of the top row, where the puzzle starts.
+    fn default_start(&self) -> AocResult<Point>;
+
+    // Follows the instructions from the given position and facing, calling back
+    // with the index of the instruction and where we are facing after every step
+    // and turn.
+    fn walk(
+        &self,
+        start: Point,
+        facing: Direction,
+        instructions: &[Instruction],
+        on_step: &mut dyn FnMut(usize, Point, Direction),
+    ) -> AocResult<(Point, Direction)>;
+}
+
+// Scores the position and facing that a traversal ends at.
+pub type PasswordFormula = fn(Point, Direction) -> i64;
 
-    pub fn is_vertical(&self) -> bool {
-        !self.is_horizontal()
+// The password from the puzzle, with rows and columns counted from 1.
+fn puzzle_password(position: Point, dir: Direction) -> i64 {
+    1000 * (position.y + 1) + 4 * (position.x + 1) + dir.index() as i64
+}
+
+// A walk over a map following a list of instructions. By default, it starts
+// where the puzzle does and is scored with the puzzle's password, but each can
+// be changed for variants of the puzzle or maps made for testing.
+pub struct Traversal {
+    start: Option<Point>,
+    facing: Direction,
+    password: PasswordFormula,
+}
+
+impl Default for Traversal {
+    fn default() -> Self {
+        Self {
+            start: None,
+            facing: Direction::Right,
+            password: puzzle_password,
+        }
     }
+}
 
-    pub fn index(&self) -> usize {
-        self.to_usize().unwrap()
+impl Traversal {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub fn rotate_left(&self) -> Self {
-        Self::from_i8((self.to_i8().unwrap() - 1).rem_euclid(Self::COUNT as i8)).unwrap()
+    pub fn starting_at(mut self, start: Point) -> Self {
+        self.start = Some(start);
+        self
     }
 
-    pub fn rotate_right(&self) -> Self {
-        Self::from_i8((self.to_i8().unwrap() + 1).rem_euclid(Self::COUNT as i8)).unwrap()
+    pub fn facing(mut self, facing: Direction) -> Self {
+        self.facing = facing;
+        self
     }
 
-    pub fn inverse(&self) -> Self {
-        Self::from_i8((self.to_i8().unwrap() + 2).rem_euclid(Self::COUNT as i8)).unwrap()
+    pub fn with_password(mut self, password: PasswordFormula) -> Self {
+        self.password = password;
+        self
     }
 
-    pub fn delta(&self) -> Point {
-        match self {
-            Self::Right => Point::new(1, 0),
-            Self::Down => Point::new(0, 1),
-            Self::Left => Point::new(-1, 0),
-            Self::Up => Point::new(0, -1),
+    fn start_on(&self, map: &impl Traversable) -> AocResult<Point> {
+        match self.start {
+            Some(start) => Ok(start),
+            None => map.default_start(),
         }
     }
-}
 
-// A single block of uniform width in the monkey map.
-#[derive(Debug)]
-struct MonkeyMapBlock {
-    pub min: Point,
-    pub max: Point,
-    pub walls: HashSet<Point>,
-}
+    pub fn walk(
+        &self,
+        map: &impl Traversable,
+        instructions: &[Instruction],
+        on_step: &mut dyn FnMut(usize, Point, Direction),
+    ) -> AocResult<(Point, Direction)> {
+        map.walk(self.start_on(map)?, self.facing, instructions, on_step)
+    }
 
-impl MonkeyMapBlock {
-    pub fn width(&self) -> i64 {
-        self.max.x - self.min.x + 1
+    // Records every position and facing that the walk passes through.
+    pub fn trace(&self, map: &impl Traversable, instructions: &[Instruction]) -> AocResult<Trace> {
+        let start = self.start_on(map)?;
+        let mut trace = Trace {
+            states: vec![(start, self.facing)],
+            after_instruction: Vec::with_capacity(instructions.len()),
+        };
+        map.walk(start, self.facing, instructions, &mut |i, position, dir| {
+            // Moves straight into a wall leave no states behind.
+            while trace.after_instruction.len() < i {
+                trace.after_instruction.push(trace.states.len());
+            }
+            trace.states.push((position, dir));
+        })?;
+        trace
+            .after_instruction
+            .resize(instructions.len(), trace.states.len());
+        Ok(trace)
     }
 
-    pub fn height(&self) -> i64 {
-        self.max.y - self.min.y + 1
+    pub fn follow(
+        &self,
+        map: &impl Traversable,
+        instructions: &[Instruction],
+    ) -> AocResult<(Point, Direction)> {
+        self.walk(map, instructions, &mut |_, _, _| ())
     }
+
+    pub fn password(&self, map: &impl Traversable, instructions: &[Instruction]) -> AocResult<u64> {
+        let (position, dir) = self.follow(map, instructions)?;
+        debug!(
+            "ended at {position:?} facing {dir:?} after {} instructions",
+            instructions.len()
+        );
+        (self.password)(position, dir)
+            .try_into()
+            .into_aoc_result_msg("password is negative")
+    }
+}
+
+// Every position and facing a walk passed through, starting with where it
+// started.
+pub struct Trace {
+    states: Vec<(Point, Direction)>,
+    // How many states there are once each instruction has been followed.
+    after_instruction: Vec<usize>,
 }
 
-trait Traversable {
-    fn follow(&self, instructions: Vec<Instruction>) -> AocResult<(Point, Direction)>;
+impl Trace {
+    // The states added by each instruction, in order, leaving out the start.
+    pub fn by_instruction(&self) -> impl Iterator<Item = &[(Point, Direction)]> {
+        iter::once(1)
+            .chain(self.after_instruction.iter().copied())
+            .tuple_windows()
+            .map(|(from, to)| &self.states[from..to])
+    }
 }
 
 // A monkey map, which consists of several blocks with wraparounds.
 #[derive(Debug)]
-struct MonkeyMap {
+pub struct MonkeyMap {
     blocks: Vec<MonkeyMapBlock>,
 }
 
 impl Traversable for MonkeyMap {
-    fn follow(&self, instructions: Vec<Instruction>) -> AocResult<(Point, Direction)> {
-        if self.blocks.is_empty() {
-            return Err(AocError::new("map is empty"));
-        }
-        let mut current_block_index = 0;
+    fn default_start(&self) -> AocResult<Point> {
+        let top = self.blocks.first().into_aoc_result_msg("map is empty")?;
+        (top.min.x..=top.max.x)
+            .map(|x| Point::new(x, top.min.y))
+            .find(|point| !top.walls.contains(point))
+            .into_aoc_result_msg("top row has no open tiles")
+    }
+
+    fn walk(
+        &self,
+        start: Point,
+        facing: Direction,
+        instructions: &[Instruction],
+        on_step: &mut dyn FnMut(usize, Point, Direction),
+    ) -> AocResult<(Point, Direction)> {
+        let mut current_block_index = self
+            .blocks
+            .iter()
+            .position(|block| start.in_bounds(&block.min, &(block.max + Point::new(1, 1))))
+            .into_aoc_result_msg("start is not on the map")?;
         let mut current_block = &self.blocks[current_block_index];
-        let mut position = current_block.min;
-        let mut dir = Direction::Right;
-        for instruction in instructions {
+        if current_block.walls.contains(&start) {
+            return Err(AocError::invalid_args("cannot start on a wall"));
+        }
+        let mut position = start;
+        let mut dir = facing;
+        for (i, instruction) in instructions.iter().enumerate() {
             match instruction {
                 Instruction::RotateLeft => dir = dir.rotate_left(),
                 Instruction::RotateRight => dir = dir.rotate_right(),
                 Instruction::Move(n) => {
                     let delta = dir.delta();
-                    for _ in 0..n {
+                    for _ in 0..*n {
                         let mut next = position + delta;
 
                         // Wrap around for x coordinate.
@@ -220,9 +299,13 @@ impl Traversable for MonkeyMap {
                         }
 
                         position = next;
+                        on_step(i, position, dir);
                     }
                 }
             }
+            if !matches!(instruction, Instruction::Move(_)) {
+                on_step(i, position, dir);
+            }
         }
 
         Ok((position, dir))
@@ -245,7 +328,7 @@ impl FromStr for MonkeyMap {
             let x_min = line.find(|c: char| !c.is_whitespace());
             let x_max = line.rfind(|c: char| !c.is_whitespace());
             let (x_min, x_max) = match (x_min, x_max) {
-                (None, _) | (_, None) => return Err(AocError::new("line has no mapped tiles")),
+                (None, _) | (_, None) => return Err(AocError::parse("line has no mapped tiles")),
                 (Some(x_min), Some(x_max)) => (x_min <= x_max)
                     .then_some((x_min, x_max))
                     .into_aoc_result_msg("invalid minimum and maximum x coordinates")?,
@@ -289,7 +372,8 @@ impl FromStr for MonkeyMap {
     }
 }
 
-fn parse_map_and_instructions(input: &str) -> AocResult<(MonkeyMap, Vec<Instruction>)> {
+// Reads the map and the instructions that follow it.
+pub fn parse_map_and_instructions(input: &str) -> AocResult<(MonkeyMap, Vec<Instruction>)> {
     let (map, instructions) = input
         .newline_blocks(2)
         .collect_tuple()
@@ -300,148 +384,288 @@ fn parse_map_and_instructions(input: &str) -> AocResult<(MonkeyMap, Vec<Instruct
     ))
 }
 
-// The direction a cube face is facing when laying down parallel to the ground.
-//
-// It is either face up or face down (mirrored).
-#[derive(Debug, Default, Clone, Copy)]
-#[repr(u8)]
-enum Facing {
-    #[default]
-    FaceUp,
-    FaceDown,
+// A unit vector along one of the axes of the folded cube.
+type Axis = [i64; 3];
+
+fn negate(axis: Axis) -> Axis {
+    axis.map(|c| -c)
 }
 
-// An even rotation (multiple of 90 degrees) of a cube face.
-//
-// Rotatations follow a counterclockwise direction:
-//      0
-//  90    270
-//     180
-#[derive(Debug, Default, Clone, Copy, FromPrimitive, ToPrimitive)]
-#[repr(i8)]
-enum Rotation {
-    #[default]
-    Zero = 0,
-    Ninety = 1,
-    OneEighty = 2,
-    TwoSeventy = 3,
+// The orientation of a face of the folded cube, as seen from outside of the cube.
+#[derive(Debug, Clone, Copy)]
+struct FaceOrientation {
+    // Points out of the cube.
+    normal: Axis,
+    // Point along the x and y axes of the face as it is laid out in the monkey map.
+    right: Axis,
+    down: Axis,
 }
 
-impl Rotation {
-    pub const COUNT: usize = 4;
+impl FaceOrientation {
+    // The orientation of the first face of the net, which every other face is folded around.
+    const FIRST: Self = Self {
+        normal: [0, 0, 1],
+        right: [1, 0, 0],
+        down: [0, 1, 0],
+    };
 
-    // Applies the rotation to the given direction in the counterclockwise
-    // direction.
-    pub fn apply(&self, mut dir: Direction) -> Direction {
-        for _ in 0..self.to_u8().unwrap() {
-            dir = dir.rotate_left();
+    // Points from the center of the face toward its edge in the given direction.
+    //
+    // Once folded, this is also the normal of the face on the other side of that edge.
+    fn edge(&self, dir: Direction) -> Axis {
+        match dir {
+            Direction::Right => self.right,
+            Direction::Down => self.down,
+            Direction::Left => negate(self.right),
+            Direction::Up => negate(self.down),
         }
-        dir
     }
 
-    // Increments the rotation.
-    pub fn rotate_left(&self) -> Rotation {
-        Self::from_i8((self.to_i8().unwrap() + 1).rem_euclid(Self::COUNT as i8)).unwrap()
+    // The orientation of the face next to this one in the cube net in the given direction, once it
+    // is folded behind this face.
+    //
+    // The folded face points in the direction of the shared edge, and its own side of the shared
+    // edge points in the direction this face is pointing. The axis running along the shared edge
+    // is unchanged.
+    fn fold(&self, dir: Direction) -> Self {
+        let mut folded = *self;
+        folded.normal = self.edge(dir);
+        match dir {
+            Direction::Right => folded.right = negate(self.normal),
+            Direction::Down => folded.down = negate(self.normal),
+            Direction::Left => folded.right = self.normal,
+            Direction::Up => folded.down = self.normal,
+        }
+        folded
     }
+}
+
+// The faces of the monkey map, laid out flat as a cube net.
+//
+// Any of the 11 cube nets with any face length can be folded, in any rotation or reflection.
+#[derive(Debug)]
+struct CubeNet {
+    face_length: i64,
+    // The top-left tile of each face in the monkey map, in reading order.
+    origins: [Point; 6],
+    // The face and edge on the other side of each edge of each face once folded.
+    neighbors: [[(usize, Direction); Direction::COUNT]; 6],
+}
 
-    // Mirrors the rotation across the relevant axis.
-    pub fn mirror(&self) -> Rotation {
-        Self::from_i8((self.to_i8().unwrap() + 2).rem_euclid(Self::COUNT as i8)).unwrap()
+impl CubeNet {
+    // Finds the length of each face from the number of tiles in the map, which must be six
+    // squares of the given length, if there is one.
+    fn face_length(map: &MonkeyMap, face_length: Option<i64>) -> AocResult<i64> {
+        let tiles: i64 = map
+            .blocks
+            .iter()
+            .map(|block| block.width() * block.height())
+            .sum();
+        let face_length = face_length.unwrap_or(((tiles / 6) as f64).sqrt().round() as i64);
+        if face_length == 0 || 6 * face_length * face_length != tiles {
+            return Err(AocError::parse(format!(
+                "map with {tiles} tiles cannot be folded into a cube with faces {face_length} tiles across"
+            )));
+        }
+        Ok(face_length)
     }
 
-    // Calculates the rotational difference between two directions.
-    pub fn difference(from: Direction, to: Direction) -> Self {
-        let mut dir = from;
-        let mut diff = Self::Zero;
-        while dir != to {
-            dir = dir.rotate_left();
-            diff = diff.rotate_left();
+    // Finds the top-left tile of every face in the map, in reading order.
+    //
+    // Every tile of the map must fall on a face.
+    fn face_origins(map: &MonkeyMap, face_length: i64) -> AocResult<Vec<Point>> {
+        let height = map.blocks.last().map_or(0, |block| block.max.y + 1);
+        let width = map
+            .blocks
+            .iter()
+            .map(|block| block.max.x + 1)
+            .max()
+            .unwrap_or(0);
+        let mut origins = Vec::new();
+        for y in (0..height).step_by(face_length as usize) {
+            for x in (0..width).step_by(face_length as usize) {
+                let covered: i64 = (y..y + face_length)
+                    .filter_map(|row| {
+                        map.blocks
+                            .iter()
+                            .find(|block| block.min.y <= row && row <= block.max.y)
+                    })
+                    .map(|block| {
+                        (block.max.x.min(x + face_length - 1) - block.min.x.max(x) + 1).max(0)
+                    })
+                    .sum();
+                if covered == face_length * face_length {
+                    origins.push(Point::new(x, y));
+                } else if covered != 0 {
+                    return Err(AocError::parse(format!(
+                        "map does not divide into cube faces of length {face_length}"
+                    )));
+                }
+            }
         }
-        diff
+        Ok(origins)
     }
 }
 
-// A single cube face that rotates around, imitiating how a cube net is folded.
-#[derive(Debug, Clone, Copy)]
-enum RotatingCubeFace {
-    // When a cube face is lying flat, we must know whether it is face up or down and how it is
-    // rotated.
-    Flat(Facing, Rotation),
-    // When a cube is standing up, we must know what edge it is standing on and which direction it
-    // is facing. The facing direction always faces the inside of the cube.
-    Standing(Direction, Direction),
+impl TryFrom<&MonkeyMap> for CubeNet {
+    type Error = AocError;
+    fn try_from(map: &MonkeyMap) -> AocResult<Self> {
+        Self::new(map, None)
+    }
 }
 
-impl Default for RotatingCubeFace {
-    fn default() -> Self {
-        Self::Flat(Facing::default(), Rotation::default())
+impl CubeNet {
+    // Folds the map with faces of the given length, or whatever length fits the map if there is
+    // none.
+    fn new(map: &MonkeyMap, face_length: Option<i64>) -> AocResult<Self> {
+        let face_length = Self::face_length(map, face_length)?;
+        let origins: [Point; 6] =
+            Self::face_origins(map, face_length)?
+                .try_into()
+                .map_err(|origins: Vec<Point>| {
+                    AocError::parse(format!("expected 6 faces, found {}", origins.len()))
+                })?;
+
+        // Walk the cube net from the first face, folding each face behind the face we reached it
+        // from.
+        let mut orientations: [Option<FaceOrientation>; 6] = [None; 6];
+        orientations[0] = Some(FaceOrientation::FIRST);
+        let mut queue = VecDeque::from([0]);
+        while let Some(face) = queue.pop_front() {
+            let orientation = orientations[face].into_aoc_result()?;
+            for dir in Direction::ALL {
+                let next = origins[face] + dir.delta() * face_length;
+                if let Some(neighbor) = origins.iter().position(|&origin| origin == next) {
+                    if orientations[neighbor].is_none() {
+                        orientations[neighbor] = Some(orientation.fold(dir));
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+        }
+        let orientations = orientations
+            .iter()
+            .map(|orientation| orientation.into_aoc_result_msg("cube net is not connected"))
+            .collect::<AocResult<Vec<_>>>()?;
+
+        // A valid net has exactly one face on each side of the cube.
+        for (i, a) in orientations.iter().enumerate() {
+            if let Some(j) = orientations[i + 1..]
+                .iter()
+                .position(|b| a.normal == b.normal)
+            {
+                return Err(AocError::parse(format!(
+                    "faces {i} and {} overlap when the cube net is folded",
+                    i + 1 + j
+                )));
+            }
+        }
+
+        // Each edge of a face borders the face on that side of the cube. The same edge on the
+        // other face is the one pointing back toward the side of the cube we started on.
+        let mut neighbors = [[(0, Direction::Right); Direction::COUNT]; 6];
+        for (face, orientation) in orientations.iter().enumerate() {
+            for dir in Direction::ALL {
+                let edge = orientation.edge(dir);
+                let neighbor = orientations
+                    .iter()
+                    .position(|other| other.normal == edge)
+                    .into_aoc_result_msg("no face on the other side of an edge")?;
+                let neighbor_edge = Direction::ALL
+                    .into_iter()
+                    .find(|&other| orientations[neighbor].edge(other) == orientation.normal)
+                    .into_aoc_result_msg("faces do not share an edge")?;
+                neighbors[face][dir.index()] = (neighbor, neighbor_edge);
+            }
+        }
+
+        debug!("folded a cube net with faces {face_length} tiles across");
+        for (face, origin) in origins.iter().enumerate() {
+            trace!(
+                "face {face} at {origin:?} borders faces {:?}",
+                neighbors[face].map(|(neighbor, _)| neighbor)
+            );
+        }
+        Ok(Self {
+            face_length,
+            origins,
+            neighbors,
+        })
     }
 }
 
-impl RotatingCubeFace {
-    // Rotate the cube face in the given direction.
-    //
-    // To be honest, this code is pretty fragile. Some of the logic is quite sound,
-    // but some of the conditions are not intuitive from a first glance.
-    pub fn rotate(&self, dir: Direction) -> Self {
-        match &self {
-            // The cube is laying flat. Any rotation will stand it up.
-            Self::Flat(facing, rotated) => Self::Standing(
-                match facing {
-                    // Face up, rotate clockwise.
-                    Facing::FaceUp => rotated.mirror().apply(dir.inverse()),
-                    // Face down, rotate counterclockwise.
-                    Facing::FaceDown => rotated.apply(if dir.is_horizontal() {
-                        // Direction is flipped if we are rotating horizontal.
-                        dir.inverse()
-                    } else {
-                        dir
-                    }),
-                },
-                match facing {
-                    Facing::FaceUp => dir,
-                    Facing::FaceDown => dir.inverse(),
-                },
-            ),
-            // The cube is standing up. It could potentially be laid flat, or it could just rotate
-            // to another standing position.
-            Self::Standing(standing_on, facing) => {
-                if facing.is_vertical() && dir.is_vertical() {
-                    // If we rotate in the direction we are already facing, we will be face down.
-                    let flat_facing = if dir == *facing {
-                        Facing::FaceDown
-                    } else {
-                        Facing::FaceUp
-                    };
+// A cube whose faces are squares of the monkey map, where each face has its own
+// coordinates counted from its top-left tile.
+trait CubeFaces {
+    fn face_length(&self) -> i64;
 
-                    let mut rotated = Rotation::difference(*standing_on, *facing);
-                    if standing_on == &dir {
-                        rotated = rotated.mirror();
-                    }
-                    Self::Flat(flat_facing, rotated)
-                } else if facing.is_horizontal() && dir.is_horizontal() {
-                    // If we rotate in the direction we are already facing, we will be face down.
-                    let flat_facing = if dir == *facing {
-                        Facing::FaceDown
-                    } else {
-                        Facing::FaceUp
-                    };
+    // The top-left tile of each face in the monkey map, in reading order.
+    fn origins(&self) -> [Point; 6];
 
-                    let rotated = if standing_on == facing {
-                        Rotation::Zero
-                    } else if standing_on == &facing.inverse() {
-                        Rotation::OneEighty
-                    } else {
-                        Rotation::difference(dir, *standing_on)
-                    };
-                    Self::Flat(flat_facing, rotated)
-                } else {
-                    // Rotating to another standing state.
-                    let rotation = Rotation::difference(*facing, dir);
-                    Self::Standing(rotation.apply(*standing_on), *facing)
+    fn is_wall(&self, face: usize, position: Point) -> bool;
+
+    // Takes a single step, ignoring walls, wrapping around to the next face of
+    // the cube if we go off the edge of the current face.
+    fn step(&self, face: usize, position: Point, dir: Direction) -> (usize, Point, Direction);
+}
+
+impl<C: CubeFaces> Traversable for C {
+    fn default_start(&self) -> AocResult<Point> {
+        // Faces are ordered from the top-left of the map.
+        (0..self.face_length())
+            .map(|x| Point::new(x, 0))
+            .find(|&point| !self.is_wall(0, point))
+            .map(|point| point + self.origins()[0])
+            .into_aoc_result_msg("top row has no open tiles")
+    }
+
+    fn walk(
+        &self,
+        start: Point,
+        facing: Direction,
+        instructions: &[Instruction],
+        on_step: &mut dyn FnMut(usize, Point, Direction),
+    ) -> AocResult<(Point, Direction)> {
+        // Traverse the cube with each cube face having its own coordinate space.
+        // The point we land on will be converted to the original coordinate space in
+        // the end.
+        let origins = self.origins();
+        let size = Point::new(self.face_length(), self.face_length());
+        let mut current_face = origins
+            .iter()
+            .position(|&origin| start.in_bounds(&origin, &(origin + size)))
+            .into_aoc_result_msg("start is not on the cube")?;
+        let mut position = start - origins[current_face];
+        if self.is_wall(current_face, position) {
+            return Err(AocError::invalid_args("cannot start on a wall"));
+        }
+        let mut dir = facing;
+        for (i, instruction) in instructions.iter().enumerate() {
+            match instruction {
+                Instruction::RotateLeft => dir = dir.rotate_left(),
+                Instruction::RotateRight => dir = dir.rotate_right(),
+                Instruction::Move(n) => {
+                    for _ in 0..*n {
+                        let (next_face, next_position, next_dir) =
+                            self.step(current_face, position, dir);
+
+                        // Now that we know where we are going, we make sure we do not hit a wall.
+                        if self.is_wall(next_face, next_position) {
+                            break;
+                        }
+
+                        (current_face, position, dir) = (next_face, next_position, next_dir);
+                        on_step(i, position + origins[current_face], dir);
+                    }
                 }
             }
+            if !matches!(instruction, Instruction::Move(_)) {
+                on_step(i, position + origins[current_face], dir);
+            }
         }
+
+        // Position is relative to the current cube face.
+        Ok((position + origins[current_face], dir))
     }
 }
 
@@ -449,285 +673,671 @@ impl RotatingCubeFace {
 #[derive(Debug)]
 struct MonkeyCubeFace {
     pub min: Point,
-    pub max: Point,
     pub walls: HashSet<Point>,
-    pub neighbors: [usize; Direction::COUNT],
+    pub neighbors: [(usize, Direction); Direction::COUNT],
 }
 
-// The monkey map correctly folded as a cube.
+// The monkey map folded as a cube, with the edges found by folding the cube net
+// one face at a time. This is checked against `FoldedCube`.
 #[derive(Debug)]
 struct MonkeyCube {
     face_length: i64,
     faces: [MonkeyCubeFace; 6],
 }
 
-impl TryFrom<MonkeyMap> for MonkeyCube {
+impl TryFrom<&MonkeyMap> for MonkeyCube {
     type Error = AocError;
-    fn try_from(map: MonkeyMap) -> AocResult<Self> {
-        let cube_face_length = map.blocks.iter().map(|block| block.height()).max().unwrap();
+    fn try_from(map: &MonkeyMap) -> AocResult<Self> {
+        Self::new(map, None)
+    }
+}
 
-        // First, convert all blocks to faces.
-        let mut faces = Vec::new();
-        for block in &map.blocks {
-            // One block can contain multiple cube faces, so we need to segment it into even
-            // cube faces.
-            let x_blocks = block.width() / cube_face_length;
-            let y_blocks = block.height() / cube_face_length;
-            for i in 0..x_blocks {
-                for j in 0..y_blocks {
-                    // Get the start and end range for this face.
-                    let min = Point::new(
-                        block.min.x + i * cube_face_length,
-                        block.min.y + j * cube_face_length,
-                    );
-                    let max = min + Point::new(cube_face_length, cube_face_length);
-                    // Get all walls in this block that belong on this cube face.
-                    let walls_on_face = block
-                        .walls
-                        .iter()
-                        .filter(|point| point.in_range(&min, &max))
-                        .map(|&point| point - min)
-                        .collect();
-                    // Add the cube face, along with its coordinates in the block map for use in the
-                    // next step. The neighbors field is a placeholder until it is fully
-                    // constructed.
-                    faces.push(MonkeyCubeFace {
-                        min,
-                        max,
-                        walls: walls_on_face,
-                        neighbors: [usize::MAX; Direction::COUNT],
-                    })
-                }
-            }
+impl MonkeyCube {
+    fn new(map: &MonkeyMap, face_length: Option<i64>) -> AocResult<Self> {
+        let net = CubeNet::new(map, face_length)?;
+        let size = Point::new(net.face_length, net.face_length);
+        let mut faces = net.origins.map(|min| MonkeyCubeFace {
+            min,
+            walls: HashSet::new(),
+            neighbors: [(0, Direction::Right); Direction::COUNT],
+        });
+        for (face, neighbors) in faces.iter_mut().zip(net.neighbors) {
+            face.neighbors = neighbors;
+            // Walls are stored relative to the face they are on.
+            face.walls = map
+                .blocks
+                .iter()
+                .flat_map(|block| block.walls.iter())
+                .filter(|point| point.in_bounds(&face.min, &(face.min + size)))
+                .map(|&point| point - face.min)
+                .collect();
         }
-        if faces.len() != 6 {
-            return Err(AocError::new(&format!(
-                "expected 6 faces, found {}",
-                faces.len()
-            )));
+        Ok(Self {
+            face_length: net.face_length,
+            faces,
+        })
+    }
+}
+
+impl MonkeyCube {
+    // The offset of a position along the edge of a face in the given direction, counted clockwise
+    // around the face.
+    fn edge_offset(&self, position: Point, edge: Direction) -> i64 {
+        match edge {
+            Direction::Right => position.y,
+            Direction::Down => self.face_length - position.x - 1,
+            Direction::Left => self.face_length - position.y - 1,
+            Direction::Up => position.x,
         }
+    }
 
-        // Next, we need to construct how each face relates to one another. We use the
-        // block map again, because it should represent a cube net.
-        //
-        // Start by constructing the cube net as it is represented by the flat monkey
-        // map. This creates a cube net.
-        let mut cube_net = [[None; Direction::COUNT]; 6];
-        for i in 0..faces.len() {
-            let MonkeyCubeFace { min, max, .. } = faces[i];
+    // The position on the edge of a face in the given direction at the given clockwise offset.
+    fn on_edge(&self, offset: i64, edge: Direction) -> Point {
+        let last = self.face_length - 1;
+        match edge {
+            Direction::Right => Point::new(last, offset),
+            Direction::Down => Point::new(last - offset, last),
+            Direction::Left => Point::new(0, last - offset),
+            Direction::Up => Point::new(offset, 0),
+        }
+    }
+}
 
-            if cube_net[i][Direction::Right.index()].is_none() {
-                let right = Point::new(max.x, min.y);
-                if let Some(right_index) = faces
-                    .iter()
-                    .position(|face| right.in_range(&face.min, &face.max))
-                {
-                    cube_net[i][Direction::Right.index()] = Some(right_index);
-                    cube_net[right_index][Direction::Left.index()] = Some(i);
-                }
-            }
+impl CubeFaces for MonkeyCube {
+    fn face_length(&self) -> i64 {
+        self.face_length
+    }
 
-            if cube_net[i][Direction::Left.index()].is_none() {
-                let left = min - Point::new(1, 0);
-                if let Some(left_index) = faces
-                    .iter()
-                    .position(|face| left.in_range(&face.min, &face.max))
-                {
-                    cube_net[i][Direction::Left.index()] = Some(left_index);
-                    cube_net[left_index][Direction::Right.index()] = Some(i);
-                }
-            }
+    fn origins(&self) -> [Point; 6] {
+        self.faces.each_ref().map(|face| face.min)
+    }
 
-            if cube_net[i][Direction::Up.index()].is_none() {
-                let up = min - Point::new(0, 1);
-                if let Some(up_index) = faces
-                    .iter()
-                    .position(|face| up.in_range(&face.min, &face.max))
-                {
-                    cube_net[i][Direction::Up.index()] = Some(up_index);
-                    cube_net[up_index][Direction::Down.index()] = Some(i);
-                }
-            }
+    fn is_wall(&self, face: usize, position: Point) -> bool {
+        self.faces[face].walls.contains(&position)
+    }
 
-            if cube_net[i][Direction::Down.index()].is_none() {
-                let down = Point::new(min.x, max.y);
-                if let Some(down_index) = faces
-                    .iter()
-                    .position(|face| down.in_range(&face.min, &face.max))
-                {
-                    cube_net[i][Direction::Down.index()] = Some(down_index);
-                    cube_net[down_index][Direction::Up.index()] = Some(i);
-                }
-            }
+    fn step(&self, face: usize, position: Point, dir: Direction) -> (usize, Point, Direction) {
+        let next_position = position + dir.delta();
+        if next_position.in_bounds(
+            &Point::new(0, 0),
+            &Point::new(self.face_length, self.face_length),
+        ) {
+            return (face, next_position, dir);
         }
 
-        // At this point, the whole cube net is connected by some order of edges.
-        // Now, we run BFS from each face, walking along the cube map and rotating that
-        // cube face as we go.
-        //
-        // Each rotation should clue us into a new neighbor: if the cube face is
-        // standing on a given edge after a move, we have found a new neighbor.
-        let mut folded_cube_net = cube_net;
-        for i in 0..faces.len() {
-            let mut queue = VecDeque::from([i]);
-            let mut seen = [false; 6];
-            let mut state = [RotatingCubeFace::default(); 6];
-            seen[i] = true;
-            state[i] = RotatingCubeFace::Flat(Facing::FaceUp, Rotation::Zero);
-            while let Some(position) = queue.pop_front() {
-                for edge in 0..Direction::COUNT {
-                    if let Some(neighbor) = cube_net[position][edge] {
-                        if seen[neighbor] {
-                            continue;
-                        }
+        // Both faces are seen from outside of the cube, so going clockwise around one face is
+        // going counterclockwise around the other.
+        let (next_face, on_edge) = self.faces[face].neighbors[dir.index()];
+        let offset = self.face_length - self.edge_offset(position, dir) - 1;
+        (next_face, self.on_edge(offset, on_edge), on_edge.inverse())
+    }
+}
 
-                        let next_state =
-                            state[position].rotate(Direction::from_usize(edge).unwrap());
-                        if let RotatingCubeFace::Standing(standing_on, _) = next_state {
-                            // New neighbor in the direction of the edge we have rotated to stand
-                            // on.
-                            folded_cube_net[i][standing_on.to_usize().unwrap()] = Some(neighbor);
-                        }
+// A direction on a face as a vector in the face's own axes, where x is right, y
+// is down, and z points out of the cube.
+fn face_vector(dir: Direction) -> Point3<i64> {
+    let delta = dir.delta();
+    Point3::new(delta.x, delta.y, 0)
+}
+
+// Turns a face's side of an edge into the neighboring face once it is folded
+// down behind the edge, in the face's own axes. The neighbor points out of the
+// edge, and its side of the edge points back the way the face was pointing.
+fn fold(dir: Direction) -> Rotation {
+    let out = Point3::new(0, 0, 1);
+    let (right, down) = match dir {
+        Direction::Right => (-out, face_vector(Direction::Down)),
+        Direction::Down => (face_vector(Direction::Right), -out),
+        Direction::Left => (out, face_vector(Direction::Down)),
+        Direction::Up => (face_vector(Direction::Right), out),
+    };
+    // Both are perpendicular axes.
+    Rotation::from_axes(right, down).unwrap()
+}
 
-                        seen[neighbor] = true;
-                        state[neighbor] = next_state;
+struct FoldedFace {
+    min: Point,
+    walls: HashSet<Point>,
+    // Takes the face's own axes to the cube's axes.
+    rotation: Rotation,
+    normal: Point3<i64>,
+}
+
+// The monkey map folded into a cube in 3D space, with the edges worked out from
+// where the faces end up.
+//
+// Tiles are placed on the cube by their centers, counted in half tiles so that
+// they land on whole numbers. That puts the center of the cube at the origin and
+// every face `face_length` half tiles out from it.
+struct FoldedCube {
+    face_length: i64,
+    faces: [FoldedFace; 6],
+}
+
+impl TryFrom<&MonkeyMap> for FoldedCube {
+    type Error = AocError;
+    fn try_from(map: &MonkeyMap) -> AocResult<Self> {
+        Self::new(map, None)
+    }
+}
+
+impl FoldedCube {
+    fn new(map: &MonkeyMap, face_length: Option<i64>) -> AocResult<Self> {
+        let face_length = CubeNet::face_length(map, face_length)?;
+        let origins: [Point; 6] = CubeNet::face_origins(map, face_length)?
+            .try_into()
+            .map_err(|origins: Vec<Point>| {
+                AocError::parse(format!("expected 6 faces, found {}", origins.len()))
+            })?;
+
+        // Walk the cube net from the first face, folding each face behind the
+        // face we reached it from.
+        let mut rotations = [None; 6];
+        rotations[0] = Some(Rotation::IDENTITY);
+        let mut queue = VecDeque::from([0]);
+        while let Some(face) = queue.pop_front() {
+            let rotation = rotations[face].into_aoc_result()?;
+            for dir in Direction::ALL {
+                let next = origins[face] + dir.delta() * face_length;
+                if let Some(neighbor) = origins.iter().position(|&origin| origin == next) {
+                    if rotations[neighbor].is_none() {
+                        rotations[neighbor] = Some(fold(dir).then(&rotation));
                         queue.push_back(neighbor);
                     }
                 }
             }
         }
 
-        // Assign our completed neighbor map to each cube face.
-        for i in 0..faces.len() {
-            for dir in 0..Direction::COUNT {
-                faces[i].neighbors[dir] = folded_cube_net[i][dir].into_aoc_result_msg(&format!(
-                    "missing neighbor on {:?} edge for face {i}",
-                    Direction::from_usize(dir).unwrap()
-                ))?;
+        let mut faces = Vec::with_capacity(6);
+        for (face, (min, rotation)) in origins.into_iter().zip(rotations).enumerate() {
+            let rotation = rotation.into_aoc_result_msg("cube net is not connected")?;
+            let normal = rotation.apply(Point3::new(0, 0, 1));
+            // A valid net has exactly one face on each side of the cube.
+            if let Some(other) = faces
+                .iter()
+                .position(|other: &FoldedFace| other.normal == normal)
+            {
+                return Err(AocError::parse(format!(
+                    "faces {other} and {face} overlap when the cube net is folded"
+                )));
             }
+            let size = Point::new(face_length, face_length);
+            faces.push(FoldedFace {
+                min,
+                // Walls are stored relative to the face they are on.
+                walls: map
+                    .blocks
+                    .iter()
+                    .flat_map(|block| block.walls.iter())
+                    .filter(|point| point.in_bounds(&min, &(min + size)))
+                    .map(|&point| point - min)
+                    .collect(),
+                rotation,
+                normal,
+            });
+        }
+        debug!("folded a cube with faces {face_length} tiles across");
+        for (face, folded) in faces.iter().enumerate() {
+            trace!(
+                "face {face} at {:?} faces {:?}",
+                folded.min,
+                folded.normal.to_array()
+            );
         }
-
         Ok(Self {
-            face_length: cube_face_length,
-            faces: faces.try_into().unwrap(),
+            face_length,
+            faces: faces
+                .try_into()
+                .map_err(|_| AocError::parse("expected 6 faces"))?,
         })
     }
 }
 
-impl MonkeyCube {
-    // Returns the cube face and edge that neighbors the given face on the given
-    // edge.
-    fn get_neighbor(&self, face: usize, edge: Direction) -> (usize, Direction) {
-        let next_face = self.faces[face].neighbors[edge.index()];
-        let next_edge = Direction::from_usize(
-            self.faces[next_face]
-                .neighbors
-                .iter()
-                .position(|&neighbor| neighbor == face)
-                .unwrap(),
-        )
-        .unwrap();
-        (next_face, next_edge)
+impl FoldedCube {
+    // The center of a tile of a face, in the cube's axes.
+    fn on_cube(&self, face: usize, position: Point) -> Point3<i64> {
+        let n = self.face_length;
+        let center = Point3::new(2 * position.x + 1 - n, 2 * position.y + 1 - n, n);
+        self.faces[face].rotation.apply(center)
     }
 }
 
-impl Traversable for MonkeyCube {
-    fn follow(&self, instructions: Vec<Instruction>) -> AocResult<(Point, Direction)> {
-        // Traverse the cube with each cube face having its own coordinate space.
-        // The point we land on will be converted to the original coordinate space in
-        // the end.
-        let mut current_face = 0;
-        let mut position = Point::new(0, 0);
-        let mut dir = Direction::Right;
-        for instruction in instructions {
-            match instruction {
-                Instruction::RotateLeft => dir = dir.rotate_left(),
-                Instruction::RotateRight => dir = dir.rotate_right(),
-                Instruction::Move(n) => {
-                    for _ in 0..n {
-                        let next_position = position + dir.delta();
-
-                        // Check if we have wrapped around the cube.
-                        let wrapped = if next_position.x < 0 {
-                            Some(self.get_neighbor(current_face, Direction::Left))
-                        } else if next_position.x >= self.face_length {
-                            Some(self.get_neighbor(current_face, Direction::Right))
-                        } else if next_position.y < 0 {
-                            Some(self.get_neighbor(current_face, Direction::Up))
-                        } else if next_position.y >= self.face_length {
-                            Some(self.get_neighbor(current_face, Direction::Down))
-                        } else {
-                            None
-                        };
-
-                        let (next_face, next_position, next_dir) = match wrapped {
-                            None => (current_face, next_position, dir),
-                            Some((next_face, on_edge)) => {
-                                let next_dir = on_edge.inverse();
-                                let next_x = match on_edge {
-                                    Direction::Right => self.face_length - 1,
-                                    Direction::Left => 0,
-                                    Direction::Down | Direction::Up => {
-                                        match Rotation::difference(dir, next_dir) {
-                                            Rotation::Zero => position.x,
-                                            Rotation::Ninety => position.y,
-                                            Rotation::OneEighty => {
-                                                self.face_length - position.x - 1
-                                            }
-                                            Rotation::TwoSeventy => {
-                                                self.face_length - position.y - 1
-                                            }
-                                        }
-                                    }
-                                };
-                                let next_y = match on_edge {
-                                    Direction::Down => self.face_length - 1,
-                                    Direction::Up => 0,
-                                    Direction::Right | Direction::Left => {
-                                        match Rotation::difference(dir, next_dir) {
-                                            Rotation::Zero => position.y,
-                                            Rotation::Ninety => self.face_length - position.x - 1,
-                                            Rotation::OneEighty => {
-                                                self.face_length - position.y - 1
-                                            }
-                                            Rotation::TwoSeventy => position.x,
-                                        }
-                                    }
-                                };
-                                (next_face, Point::new(next_x, next_y), next_dir)
-                            }
-                        };
+impl CubeFaces for FoldedCube {
+    fn face_length(&self) -> i64 {
+        self.face_length
+    }
 
-                        // Now that we know where we are going, we make sure we do not hit a wall.
-                        if self.faces[next_face].walls.contains(&next_position) {
-                            break;
-                        }
+    fn origins(&self) -> [Point; 6] {
+        self.faces.each_ref().map(|face| face.min)
+    }
 
-                        (current_face, position, dir) = (next_face, next_position, next_dir);
-                    }
+    fn is_wall(&self, face: usize, position: Point) -> bool {
+        self.faces[face].walls.contains(&position)
+    }
+
+    fn step(&self, face: usize, position: Point, dir: Direction) -> (usize, Point, Direction) {
+        let next_position = position + dir.delta();
+        if next_position.in_bounds(
+            &Point::new(0, 0),
+            &Point::new(self.face_length, self.face_length),
+        ) {
+            return (face, next_position, dir);
+        }
+
+        // The next tile is on the face pointing the way we were heading. Its
+        // center is half a tile from ours to reach the edge, then half a tile
+        // down the other side of it. From there, we head away from the face we
+        // left.
+        let (rotation, normal) = (self.faces[face].rotation, self.faces[face].normal);
+        let heading = rotation.apply(face_vector(dir));
+        let next = self.on_cube(face, position) + heading - normal;
+        let next_face = self
+            .faces
+            .iter()
+            .position(|face| face.normal == heading)
+            .unwrap();
+
+        // Back into the next face's own axes.
+        let unrotate = self.faces[next_face].rotation.inverse();
+        let local = unrotate.apply(next);
+        let n = self.face_length;
+        let next_position = Point::new((local.x + n - 1) / 2, (local.y + n - 1) / 2);
+        let next_dir = Direction::ALL
+            .into_iter()
+            .find(|&dir| face_vector(dir) == unrotate.apply(-normal))
+            .unwrap();
+        (next_face, next_position, next_dir)
+    }
+}
+
+pub fn solve_a(input: &str) -> AocResult<u64> {
+    let (map, instructions) = timing::parse_phase(|| parse_map_and_instructions(input))?;
+    Traversal::new().password(&map, &instructions)
+}
+
+// The size of the cube's faces. Any size that fits the map can be folded, so
+// this is only used to check the map when it is known which input it is.
+struct CubeSize {
+    face_length: i64,
+}
+
+impl Constants for CubeSize {
+    const EXAMPLE: Self = Self { face_length: 4 };
+    const REAL: Self = Self { face_length: 50 };
+}
+
+fn face_length(day_args: &DayArgs) -> Option<i64> {
+    day_args.constants().map(|size: CubeSize| size.face_length)
+}
+
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let (map, instructions) = timing::parse_phase(|| parse_map_and_instructions(input))?;
+    let cube = FoldedCube::new(&map, face_length(day_args))?;
+    Traversal::new().password(&cube, &instructions)
+}
+
+pub fn solve_b_by_net(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let (map, instructions) = timing::parse_phase(|| parse_map_and_instructions(input))?;
+    let cube = MonkeyCube::new(&map, face_length(day_args))?;
+    Traversal::new().password(&cube, &instructions)
+}
+
+// The monkey map drawn the way the puzzle does, with arrows for where a walk
+// has been. Later steps are drawn over earlier ones.
+#[derive(Clone)]
+struct MapDrawing {
+    tiles: Vec<Vec<u8>>,
+}
+
+impl MapDrawing {
+    fn new(map: &MonkeyMap) -> Self {
+        let width = map
+            .blocks
+            .iter()
+            .map(|block| block.max.x + 1)
+            .max()
+            .unwrap_or(0);
+        let height = map.blocks.last().map_or(0, |block| block.max.y + 1);
+        let mut tiles = vec![vec![b' '; width as usize]; height as usize];
+        for block in &map.blocks {
+            for y in block.min.y..=block.max.y {
+                for x in block.min.x..=block.max.x {
+                    tiles[y as usize][x as usize] = if block.walls.contains(&Point::new(x, y)) {
+                        b'#'
+                    } else {
+                        b'.'
+                    };
                 }
             }
         }
+        Self { tiles }
+    }
 
-        // Position is relative to the current cube face.
-        Ok((position + self.faces[current_face].min, dir))
+    fn draw(&mut self, states: &[(Point, Direction)]) {
+        for (position, dir) in states {
+            self.tiles[position.y as usize][position.x as usize] = match dir {
+                Direction::Right => b'>',
+                Direction::Down => b'v',
+                Direction::Left => b'<',
+                Direction::Up => b'^',
+            };
+        }
     }
-}
 
-fn final_password(position: Point, dir: Direction) -> AocResult<u64> {
-    let password = 1000 * (position.y + 1) + 4 * (position.x + 1) + dir.to_i64().unwrap();
-    password.try_into().into_aoc_result()
+    fn render(&self) -> String {
+        self.tiles
+            .iter()
+            .map(|row| String::from_utf8_lossy(row).trim_end().to_owned() + "\n")
+            .collect()
+    }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
+fn trace_part(input: &str, part: SolutionPart) -> AocResult<(MonkeyMap, Trace)> {
     let (map, instructions) = parse_map_and_instructions(input)?;
-    let (position, dir) = map.follow(instructions)?;
-    final_password(position, dir)
+    let trace = match part {
+        SolutionPart::B => Traversal::new().trace(&FoldedCube::try_from(&map)?, &instructions)?,
+        _ => Traversal::new().trace(&map, &instructions)?,
+    };
+    Ok((map, trace))
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
-    let (map, instructions) = parse_map_and_instructions(input)?;
-    let cube = MonkeyCube::try_from(map)?;
-    let (position, dir) = cube.follow(instructions)?;
-    final_password(position, dir)
+// The whole path taken across the map, for checking where a walk went wrong,
+// such as across a badly folded cube edge.
+pub fn draw_path(input: &str, part: SolutionPart) -> AocResult<String> {
+    let (map, trace) = trace_part(input, part)?;
+    let mut drawing = MapDrawing::new(&map);
+    drawing.draw(&trace.states);
+    Ok(drawing.render())
+}
+
+// The path taken across the map, one instruction at a time.
+struct PathViz {
+    map: MapDrawing,
+    trace: Trace,
+}
+
+impl Visualize for PathViz {
+    // One frame per instruction, after the one for where the walk starts.
+    fn frames(&self) -> impl Iterator<Item = String> {
+        let mut drawing = self.map.clone();
+        iter::once(&self.trace.states[..1])
+            .chain(self.trace.by_instruction())
+            .map(move |states| {
+                drawing.draw(states);
+                drawing.render()
+            })
+    }
+}
+
+pub fn visualize(input: &str, part: SolutionPart) -> AocResult<impl Visualize> {
+    let (map, trace) = trace_part(input, part)?;
+    Ok(PathViz {
+        map: MapDrawing::new(&map),
+        trace,
+    })
+}
+
+register_day!(22, solve_a, args solve_b | solve_b_by_net);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::constants::Profile;
+
+    // The 11 hexomino cube nets, one face per character.
+    const CUBE_NETS: [&[&str]; 11] = [
+        &["X...", "XXXX", "X..."],
+        &["X...", "XXXX", ".X.."],
+        &["X...", "XXXX", "..X."],
+        &["X...", "XXXX", "...X"],
+        &[".X..", "XXXX", ".X.."],
+        &[".X..", "XXXX", "..X."],
+        &["XX..", ".XXX", ".X.."],
+        &["XX..", ".XXX", "..X."],
+        &["XX..", ".XXX", "...X"],
+        &["XX..", ".XX.", "..XX"],
+        &["XXX..", "..XXX"],
+    ];
+
+    // Rotates a layout 90 degrees clockwise.
+    fn rotate(layout: &[String]) -> Vec<String> {
+        let width = layout.iter().map(|row| row.len()).max().unwrap_or(0);
+        (0..width)
+            .map(|x| {
+                layout
+                    .iter()
+                    .rev()
+                    .map(|row| row.chars().nth(x).unwrap_or('.'))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Mirrors a layout horizontally.
+    fn mirror(layout: &[String]) -> Vec<String> {
+        let width = layout.iter().map(|row| row.len()).max().unwrap_or(0);
+        layout
+            .iter()
+            .map(|row| format!("{row:.<width$}").chars().rev().collect())
+            .collect()
+    }
+
+    // All eight rotations and reflections of a layout.
+    fn symmetries(layout: &[&str]) -> Vec<Vec<String>> {
+        let mut layout = layout.iter().map(|row| row.to_string()).collect::<Vec<_>>();
+        let mut symmetries = Vec::new();
+        for _ in 0..4 {
+            symmetries.push(mirror(&layout));
+            layout = rotate(&layout);
+            symmetries.push(layout.clone());
+        }
+        symmetries
+    }
+
+    // Expands a layout of faces into a monkey map with no walls.
+    fn monkey_map(layout: &[String], face_length: usize) -> MonkeyMap {
+        let map = layout
+            .iter()
+            .flat_map(|row| {
+                let line = row
+                    .chars()
+                    .map(|c| if c == 'X' { "." } else { " " }.repeat(face_length))
+                    .collect::<String>();
+                vec![line.trim_end().to_owned(); face_length]
+            })
+            .join("\n");
+        MonkeyMap::from_str(&map).unwrap()
+    }
+
+    #[test]
+    fn folds_every_cube_net() {
+        for net in CUBE_NETS {
+            for layout in symmetries(net) {
+                for face_length in [1, 2, 4, 7] {
+                    let net = CubeNet::try_from(&monkey_map(&layout, face_length))
+                        .unwrap_or_else(|err| panic!("{layout:?} failed to fold: {err}"));
+                    assert_eq!(net.face_length, face_length as i64);
+                    for (face, neighbors) in net.neighbors.iter().enumerate() {
+                        let mut faces = neighbors.map(|(neighbor, _)| neighbor);
+                        faces.sort();
+                        assert!(faces.windows(2).all(|pair| pair[0] != pair[1]));
+                        assert!(!faces.contains(&face));
+                        for (dir, (neighbor, edge)) in neighbors.iter().enumerate() {
+                            assert_eq!(
+                                net.neighbors[*neighbor][edge.index()],
+                                (face, Direction::from_index(dir)),
+                                "{layout:?} has mismatched edges"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Every tile of every face, facing every direction.
+    fn every_state(face_length: i64) -> impl Iterator<Item = (usize, Point, Direction)> {
+        itertools::iproduct!(0..6, 0..face_length, 0..face_length, Direction::ALL)
+            .map(|(face, x, y, dir)| (face, Point::new(x, y), dir))
+    }
+
+    #[test]
+    fn walks_around_every_cube_net() {
+        // Going straight around a cube comes back to where it started.
+        fn walk_around(cube: &impl CubeFaces, layout: &[String]) {
+            for start in every_state(cube.face_length()) {
+                let mut state = start;
+                for _ in 0..4 * cube.face_length() {
+                    state = cube.step(state.0, state.1, state.2);
+                }
+                assert_eq!(state, start, "{layout:?} walked off course");
+            }
+        }
+
+        let face_length = 3;
+        for net in CUBE_NETS {
+            for layout in symmetries(net) {
+                let map = monkey_map(&layout, face_length);
+                walk_around(&MonkeyCube::try_from(&map).unwrap(), &layout);
+                walk_around(&FoldedCube::try_from(&map).unwrap(), &layout);
+            }
+        }
+    }
+
+    #[test]
+    fn folds_like_the_cube_net() {
+        for net in CUBE_NETS {
+            for layout in symmetries(net) {
+                for face_length in [1, 2, 4] {
+                    let map = monkey_map(&layout, face_length);
+                    let (net_cube, folded) = (
+                        MonkeyCube::try_from(&map).unwrap(),
+                        FoldedCube::try_from(&map).unwrap(),
+                    );
+                    assert_eq!(net_cube.origins(), folded.origins());
+                    for (face, position, dir) in every_state(face_length as i64) {
+                        assert_eq!(
+                            folded.step(face, position, dir),
+                            net_cube.step(face, position, dir),
+                            "{layout:?} folds differently from {position:?} on face {face}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn traverses_from_configured_start() {
+        let map = MonkeyMap::from_str("....\n.#..\n....").unwrap();
+        let instructions = parse_instructions("5").unwrap();
+        let traversal = Traversal::new()
+            .starting_at(Point::new(3, 2))
+            .facing(Direction::Left)
+            .with_password(|position, dir| 100 * position.x + 10 * position.y + dir.index() as i64);
+        assert_eq!(
+            traversal.follow(&map, &instructions).unwrap(),
+            (Point::new(2, 2), Direction::Left)
+        );
+        assert_eq!(traversal.password(&map, &instructions).unwrap(), 222);
+
+        let blocked = Traversal::new().starting_at(Point::new(1, 1));
+        assert!(blocked.follow(&map, &instructions).is_err());
+        let off_map = Traversal::new().starting_at(Point::new(4, 0));
+        assert!(off_map.follow(&map, &instructions).is_err());
+    }
+
+    #[test]
+    fn starts_at_leftmost_open_tile() {
+        let map = MonkeyMap::from_str("#...\n....\n....\n....").unwrap();
+        assert_eq!(map.default_start().unwrap(), Point::new(1, 0));
+    }
+
+    #[test]
+    fn rejects_invalid_nets() {
+        for layout in [
+            &["XXXXXX"][..],
+            &["XXX", "XXX"],
+            &["XXXX", "XX.."],
+            &["XXXX", ".X.."],
+            &["XX..", ".XX.", "..X."],
+        ] {
+            let layout = layout.iter().map(|row| row.to_string()).collect::<Vec<_>>();
+            assert!(CubeNet::try_from(&monkey_map(&layout, 2)).is_err());
+            assert!(FoldedCube::try_from(&monkey_map(&layout, 2)).is_err());
+        }
+        let strip = MonkeyMap::from_str(&".".repeat(24)).unwrap();
+        assert!(CubeNet::try_from(&strip).is_err());
+        assert!(FoldedCube::try_from(&strip).is_err());
+    }
+
+    #[test]
+    fn checks_face_length_for_profile() {
+        let input = include_str!("../../examples/22.txt");
+        let example = DayArgs::default().with_profile(Profile::Example);
+        assert_eq!(solve_b(input, &example).unwrap(), 5031);
+        assert_eq!(solve_b_by_net(input, &example).unwrap(), 5031);
+        let real = DayArgs::default().with_profile(Profile::Real);
+        assert!(solve_b(input, &real).is_err());
+        assert!(solve_b_by_net(input, &real).is_err());
+    }
+
+    #[test]
+    fn traces_the_example_like_the_puzzle() {
+        let input = include_str!("../../examples/22.txt");
+        let expected_a = [
+            "        >>v#",
+            "        .#v.",
+            "        #.v.",
+            "        ..v.",
+            "...#...v..v#",
+            ">>>v...>#.>>",
+            "..#v...#....",
+            "...>>>>v..#.",
+            "        ...#....",
+            "        .....#..",
+            "        .#......",
+            "        ......#.",
+        ];
+        let expected_b = [
+            "        >>v#",
+            "        .#v.",
+            "        #.v.",
+            "        ..v.",
+            "...#..^...v#",
+            ".>>>>>^.#.>>",
+            ".^#....#....",
+            ".^........#.",
+            "        ...#..v.",
+            "        .....#v.",
+            "        .#v<<<<.",
+            "        ..v...#.",
+        ];
+        assert_eq!(
+            draw_path(input, SolutionPart::A).unwrap(),
+            expected_a.join("\n") + "\n"
+        );
+        assert_eq!(
+            draw_path(input, SolutionPart::B).unwrap(),
+            expected_b.join("\n") + "\n"
+        );
+    }
+
+    #[test]
+    fn traces_every_instruction() {
+        let map = MonkeyMap::from_str("....\n.#..\n....").unwrap();
+        let instructions = parse_instructions("1R5L2").unwrap();
+        let trace = Traversal::new().trace(&map, &instructions).unwrap();
+        assert_eq!(
+            trace
+                .by_instruction()
+                .map(|states| states.len())
+                .collect::<Vec<_>>(),
+            [1, 1, 0, 1, 2]
+        );
+        assert_eq!(
+            trace.states.last(),
+            Some(&Traversal::new().follow(&map, &instructions).unwrap())
+        );
+    }
 }
@@ -1,243 +1,456 @@
-use std::{ops::Add, str::FromStr};
+use std::{iter, str::FromStr};
 
-use crate::common::{AocError, AocResult};
-use lazy_static::lazy_static;
-use num::ToPrimitive;
-use rustc_hash::FxHashSet;
+use crate::common::{
+    progress::Tracker,
+    render::{Color, GridImage},
+    timing,
+    viz::Visualize,
+    AocError, AocResult, DayArgs, Point2, SolutionPart,
+};
+use rustc_hash::FxHashMap;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point {
-    pub x: i64,
-    pub y: i64,
+// The four directions an elf can propose moving in, in the order they are
+// considered on the first round.
+const PROPOSALS: usize = 4;
+
+// The elves are kept in square chunks of this many tiles on each side, so
+// that each row of a chunk is one word.
+const CHUNK: usize = 64;
+
+// A chunk of the grove, with one word per row where bit `i` is the tile in
+// column `i`.
+type Chunk = [u64; CHUNK];
+
+// Which chunk a tile is in, counting in chunks from the origin.
+type ChunkKey = Point2<i64>;
+
+// How many rows of the chunks above and below a chunk are needed to work out
+// where its elves end up. An elf two rows away can block the move of an elf
+// on the edge of the chunk.
+const MARGIN: usize = 2;
+
+// A chunk and everything around it, as rows of three words: the chunk to the
+// west, the chunk itself, and the chunk to the east.
+const WORDS: usize = 3;
+const WINDOW_ROWS: usize = CHUNK + 2 * MARGIN;
+type Window = [u64; WINDOW_ROWS * WORDS];
+
+// The word at `i` shifted so that each bit is set where the tile to its west
+// is set.
+fn west_of(rows: &Window, i: usize) -> u64 {
+    let mut word = rows[i] << 1;
+    if !i.is_multiple_of(WORDS) {
+        word |= rows[i - 1] >> 63;
+    }
+    word
 }
 
-impl Point {
-    pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
+// The word at `i` shifted so that each bit is set where the tile to its east
+// is set.
+fn east_of(rows: &Window, i: usize) -> u64 {
+    let mut word = rows[i] >> 1;
+    if !(i + 1).is_multiple_of(WORDS) {
+        word |= rows[i + 1] << 63;
     }
+    word
 }
 
-impl Add for Point {
-    type Output = Self;
-    fn add(self, rhs: Self) -> Self::Output {
-        Self::new(self.x + rhs.x, self.y + rhs.y)
+// Lays out a chunk and its eight neighbors as a window. Rows that are too far
+// from the chunk to matter are left out.
+fn window<'a>(key: ChunkKey, chunk: impl Fn(ChunkKey) -> Option<&'a Chunk>) -> Window {
+    let mut rows = [0; WINDOW_ROWS * WORDS];
+    for dy in -1..=1 {
+        // The rows of this neighbor that fall within the window, and where
+        // the first of them goes.
+        let (from, to) = match dy {
+            -1 => (CHUNK - MARGIN, 0),
+            0 => (0, MARGIN),
+            _ => (0, MARGIN + CHUNK),
+        };
+        let count = if dy == 0 { CHUNK } else { MARGIN };
+        for dx in -1..=1 {
+            let Some(neighbor) = chunk(key + Point2::new(dx, dy)) else {
+                continue;
+            };
+            for (y, &word) in neighbor[from..from + count].iter().enumerate() {
+                rows[(to + y) * WORDS + (dx + 1) as usize] = word;
+            }
+        }
     }
+    rows
 }
 
-#[derive(Debug, Clone, Copy, ToPrimitive)]
-#[repr(u8)]
-enum Direction {
-    North = 0b0001,
-    South = 0b0010,
-    West = 0b0100,
-    East = 0b1000,
-    NorthWest = 0b0101,
-    NorthEast = 0b1001,
-    SouthWest = 0b0110,
-    SouthEast = 0b1010,
+// The index in a window of each row of the chunk in the middle of it.
+fn middle() -> impl Iterator<Item = usize> {
+    (0..CHUNK).map(|y| (y + MARGIN) * WORDS + 1)
 }
 
-impl Direction {
-    pub fn has_north_component(&self) -> bool {
-        self.to_u8().unwrap() & Self::North.to_u8().unwrap() != 0
-    }
+// Finds which elves in the middle of a window propose to move in each
+// direction, as one chunk per direction in north, south, west, east order.
+fn propose(rows: &Window, round: u64) -> [Chunk; PROPOSALS] {
+    let mut proposals = [[0; CHUNK]; PROPOSALS];
+    for (y, i) in middle().enumerate() {
+        if rows[i] == 0 {
+            continue;
+        }
 
-    pub fn has_south_component(&self) -> bool {
-        self.to_u8().unwrap() & Self::South.to_u8().unwrap() != 0
-    }
+        // Set where there is an elf in each group of three tiles that blocks
+        // moving in a direction.
+        let (north, south) = (i - WORDS, i + WORDS);
+        let blocked_north = rows[north] | west_of(rows, north) | east_of(rows, north);
+        let blocked_south = rows[south] | west_of(rows, south) | east_of(rows, south);
+        let blocked_west = west_of(rows, north) | west_of(rows, i) | west_of(rows, south);
+        let blocked_east = east_of(rows, north) | east_of(rows, i) | east_of(rows, south);
+        let blocked = [blocked_north, blocked_south, blocked_west, blocked_east];
 
-    pub fn has_west_component(&self) -> bool {
-        self.to_u8().unwrap() & Self::West.to_u8().unwrap() != 0
+        // Elves with no neighbors do not move.
+        let mut undecided = rows[i] & blocked.iter().fold(0, |acc, word| acc | word);
+        for j in 0..PROPOSALS {
+            let direction = (j + round as usize) % PROPOSALS;
+            proposals[direction][y] = undecided & !blocked[direction];
+            undecided &= blocked[direction];
+        }
     }
+    proposals
+}
 
-    pub fn has_east_component(&self) -> bool {
-        self.to_u8().unwrap() & Self::East.to_u8().unwrap() != 0
-    }
+// Moves the elves into and out of the middle of a window, given what every
+// elf in the window proposed.
+fn settle(rows: &Window, proposals: &[Window; PROPOSALS]) -> Chunk {
+    let [north, south, west, east] = proposals;
 
-    pub fn delta(&self) -> Point {
-        Point::new(
-            if self.has_west_component() {
-                -1
-            } else if self.has_east_component() {
-                1
-            } else {
-                0
-            },
-            if self.has_north_component() {
-                -1
-            } else if self.has_south_component() {
-                1
-            } else {
-                0
-            },
-        )
-    }
+    // Conflicts must come from opposite directions, and there can only be one
+    // conflict for one space:
+    //
+    // If there are more than two elves one step away from a single position,
+    // then at least one of those elves is directly diagonal to another, which
+    // means this position cannot be proposed by either of those elves, which
+    // is a contradiction.
+    let vertical_conflicts = |i: usize| north[i + WORDS] & south[i - WORDS];
+    let horizontal_conflicts = |i: usize| east_of(west, i) & west_of(east, i);
 
-    pub fn index(&self) -> usize {
-        match self {
-            Self::North => 0,
-            Self::South => 1,
-            Self::West => 2,
-            Self::East => 3,
-            Self::NorthWest => 4,
-            Self::NorthEast => 5,
-            Self::SouthWest => 6,
-            Self::SouthEast => 7,
-        }
+    let mut chunk = [0; CHUNK];
+    for (y, i) in middle().enumerate() {
+        let staying = rows[i] & !(north[i] | south[i] | west[i] | east[i]);
+        // Where each elf would end up, based on the direction it came from.
+        let arrived = (north[i + WORDS] ^ south[i - WORDS]) | (east_of(west, i) ^ west_of(east, i));
+        let west_conflicts = horizontal_conflicts(i) << 1 | horizontal_conflicts(i - 1) >> 63;
+        let east_conflicts = horizontal_conflicts(i) >> 1 | horizontal_conflicts(i + 1) << 63;
+        // Elves that proposed a conflicting position stay where they are.
+        let pushed_back = (north[i] & vertical_conflicts(i - WORDS))
+            | (south[i] & vertical_conflicts(i + WORDS))
+            | (west[i] & west_conflicts)
+            | (east[i] & east_conflicts);
+        chunk[y] = staying | arrived | pushed_back;
     }
+    chunk
+}
 
-    pub fn bit(&self) -> u8 {
-        1 << self.index()
-    }
+// The rows and columns of a chunk that have an elf in them, as masks.
+fn occupancy(chunk: &Chunk) -> (u64, u64) {
+    chunk
+        .iter()
+        .enumerate()
+        .fold((0, 0), |(rows, columns), (y, &word)| {
+            (rows | (u64::from(word != 0) << y), columns | word)
+        })
 }
 
+// The elves in the grove, packed into one bit per tile.
+//
+// The grove is split into chunks, and only chunks with elves in them are
+// kept, so the elves can spread out as far as they like.
+#[derive(Clone)]
 struct Grove {
-    elves: FxHashSet<Point>,
+    chunks: FxHashMap<ChunkKey, Chunk>,
 }
 
 impl FromStr for Grove {
     type Err = AocError;
     fn from_str(s: &str) -> AocResult<Self> {
-        Ok(Self {
-            elves: s
-                .lines()
-                .enumerate()
-                .flat_map(|(y, line)| {
-                    line.char_indices().filter_map(move |(x, c)| match c {
-                        '#' => Some(Ok(Point::new(x as i64, y as i64))),
-                        '.' => None,
-                        _ => Some(Err(AocError::new("invalid character"))),
-                    })
-                })
-                .collect::<AocResult<_>>()?,
-        })
+        let mut grove = Self {
+            chunks: FxHashMap::default(),
+        };
+        for (y, line) in s.lines().enumerate() {
+            for (x, c) in line.char_indices() {
+                match c {
+                    '#' => grove.insert(Point2::new(x as i64, y as i64)),
+                    '.' => (),
+                    _ => return Err(AocError::parse("invalid character")),
+                }
+            }
+        }
+        Ok(grove)
     }
 }
 
 impl Grove {
+    // Stops early if the solver is cancelled, returning how many rounds were
+    // done.
     pub fn do_rounds(&mut self, max: u64) -> u64 {
+        // Without a limit, there is no telling how many rounds it will take.
+        let progress = Tracker::current();
+        progress.start((max != u64::MAX).then_some(max), "rounds");
         for round in 0..max {
+            if progress.cancelled() {
+                return round;
+            }
+            progress.advance(1);
             if self.do_round(round) {
+                debug!("no elf moved in round {}", round + 1);
                 return round + 1;
             }
+            trace!(
+                "round {}: {} elves in {} chunks",
+                round + 1,
+                self.num_elves(),
+                self.chunks.len()
+            );
         }
-        return u64::MAX;
+        debug!("elves still moving after {max} rounds");
+        u64::MAX
     }
 
-    fn proposals() -> &'static [(Direction, u8); 4] {
-        lazy_static! {
-            static ref PROPOSALS: [(Direction, u8); 4] = [
-                (
-                    Direction::North,
-                    Direction::North.bit()
-                        | Direction::NorthEast.bit()
-                        | Direction::NorthWest.bit()
-                ),
-                (
-                    Direction::South,
-                    Direction::South.bit()
-                        | Direction::SouthEast.bit()
-                        | Direction::SouthWest.bit()
-                ),
-                (
-                    Direction::West,
-                    Direction::West.bit() | Direction::NorthWest.bit() | Direction::SouthWest.bit()
-                ),
-                (
-                    Direction::East,
-                    Direction::East.bit() | Direction::NorthEast.bit() | Direction::SouthEast.bit()
-                )
-            ];
+    // The chunk a tile is in, and the row and bit for it within the chunk.
+    fn locate(tile: Point2<i64>) -> (ChunkKey, usize, u64) {
+        let size = CHUNK as i64;
+        let key = Point2::new(tile.x.div_euclid(size), tile.y.div_euclid(size));
+        let bit = 1 << tile.x.rem_euclid(size);
+        (key, tile.y.rem_euclid(size) as usize, bit)
+    }
+
+    fn insert(&mut self, tile: Point2<i64>) {
+        let (key, y, bit) = Self::locate(tile);
+        self.chunks.entry(key).or_insert([0; CHUNK])[y] |= bit;
+    }
+
+    fn contains(&self, tile: Point2<i64>) -> bool {
+        let (key, y, bit) = Self::locate(tile);
+        self.chunks
+            .get(&key)
+            .is_some_and(|chunk| chunk[y] & bit != 0)
+    }
+
+    fn do_round(&mut self, round: u64) -> bool {
+        // Every elf's proposal, kept in the chunk that the elf is in.
+        let mut proposals = FxHashMap::default();
+        for &key in self.chunks.keys() {
+            let proposed = propose(&window(key, |key| self.chunks.get(&key)), round);
+            if proposed.iter().flatten().any(|&word| word != 0) {
+                proposals.insert(key, proposed);
+            }
         }
-        &PROPOSALS
-    }
-
-    fn neighbors(&self, point: &Point) -> u8 {
-        lazy_static! {
-            static ref ALL_DIRECTIONS: [Direction; 8] = [
-                Direction::North,
-                Direction::South,
-                Direction::West,
-                Direction::East,
-                Direction::NorthWest,
-                Direction::NorthEast,
-                Direction::SouthWest,
-                Direction::SouthEast
-            ];
+        if proposals.is_empty() {
+            return true;
         }
-        let mut neighbors = 0;
-        for direction in *ALL_DIRECTIONS {
-            if self.elves.contains(&(*point + direction.delta())) {
-                neighbors |= direction.bit();
+
+        // Elves only move into the chunks next to the ones they are in.
+        let mut keys = self.chunks.keys().copied().collect::<Vec<_>>();
+        keys.extend(proposals.keys().flat_map(|key| key.neighbors4()));
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut chunks = FxHashMap::default();
+        for key in keys {
+            // Chunks with no proposals anywhere near them stay the same.
+            let mut nearby = iter::once(key).chain(key.neighbors8());
+            if !nearby.any(|key| proposals.contains_key(&key)) {
+                if let Some(&chunk) = self.chunks.get(&key) {
+                    chunks.insert(key, chunk);
+                }
+                continue;
+            }
+            let proposed = std::array::from_fn(|direction| {
+                window(key, |key| proposals.get(&key).map(|p| &p[direction]))
+            });
+            let chunk = settle(&window(key, |key| self.chunks.get(&key)), &proposed);
+            if chunk.iter().any(|&word| word != 0) {
+                chunks.insert(key, chunk);
             }
         }
-        neighbors
+        self.chunks = chunks;
+        false
     }
 
-    fn get_proposal(&self, point: &Point, round: u64) -> Option<Direction> {
-        match self.neighbors(point) {
-            0 => None,
-            neighbors @ _ => (0..Self::proposals().len())
-                .map(|i| Self::proposals()[(i + round as usize) % Self::proposals().len()])
-                .find_map(|(direction, bits)| (neighbors & bits == 0).then_some(direction)),
-        }
+    // The smallest rectangle containing every elf, as the minimum and maximum
+    // tile, found from which rows and columns of each chunk are occupied.
+    fn bounding_rectangle(&self) -> Option<(Point2<i64>, Point2<i64>)> {
+        let size = CHUNK as i64;
+        self.chunks
+            .iter()
+            .map(|(key, chunk)| {
+                let (rows, columns) = occupancy(chunk);
+                let origin = *key * size;
+                let min = Point2::new(columns.trailing_zeros(), rows.trailing_zeros());
+                let max = Point2::new(63 - columns.leading_zeros(), 63 - rows.leading_zeros());
+                (
+                    origin + Point2::new(min.x as i64, min.y as i64),
+                    origin + Point2::new(max.x as i64, max.y as i64),
+                )
+            })
+            .reduce(|(min_a, max_a), (min_b, max_b)| {
+                (
+                    Point2::new(min_a.x.min(min_b.x), min_a.y.min(min_b.y)),
+                    Point2::new(max_a.x.max(max_b.x), max_a.y.max(max_b.y)),
+                )
+            })
     }
 
-    fn do_round(&mut self, round: u64) -> bool {
-        let mut new_elves =
-            FxHashSet::with_capacity_and_hasher(self.elves.capacity(), Default::default());
-        let mut finished = true;
-        for elf in &self.elves {
-            match self.get_proposal(elf, round) {
-                None => {
-                    new_elves.insert(*elf);
-                }
-                Some(proposal) => {
-                    finished = false;
-                    let move_to = *elf + proposal.delta();
-                    if !new_elves.insert(move_to) {
-                        // This position has already been proposed by another elf.
-                        //
-                        // Conflicts must come from opposite directions, and there can only be one
-                        // conflict for one space:
-                        //
-                        // If there are more than two elves one step away from a single position,
-                        // then at least one of those elves is directly diagonal to another, which
-                        // means this position cannot be proposed by either of those elves, which is
-                        // a contradiction.
-                        new_elves.remove(&move_to);
-                        // Push the elf back that moved to this position.
-                        new_elves.insert(move_to + proposal.delta());
-                        new_elves.insert(*elf);
-                    }
+    pub fn bounding_rectangle_area(&self) -> u64 {
+        self.bounding_rectangle().map_or(0, |(min, max)| {
+            ((max.x - min.x + 1) * (max.y - min.y + 1)) as u64
+        })
+    }
+
+    // The elves within their bounding rectangle, as an image.
+    pub fn image(&self) -> GridImage {
+        let Some((min, max)) = self.bounding_rectangle() else {
+            return GridImage::new(0, 0, Color(30, 30, 30));
+        };
+        let mut image = GridImage::new(
+            (max.x - min.x + 1) as usize,
+            (max.y - min.y + 1) as usize,
+            Color(30, 60, 30),
+        );
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                if self.contains(Point2::new(x, y)) {
+                    image.set(
+                        (x - min.x) as usize,
+                        (y - min.y) as usize,
+                        Color(120, 220, 120),
+                    );
                 }
             }
         }
-
-        self.elves = new_elves;
-        finished
+        image
     }
 
-    pub fn bounding_rectangle_area(&self) -> u64 {
-        let min_x = self.elves.iter().min_by(|a, b| a.x.cmp(&b.x)).unwrap().x;
-        let max_x = self.elves.iter().max_by(|a, b| a.x.cmp(&b.x)).unwrap().x;
-        let min_y = self.elves.iter().min_by(|a, b| a.y.cmp(&b.y)).unwrap().y;
-        let max_y = self.elves.iter().max_by(|a, b| a.y.cmp(&b.y)).unwrap().y;
-        ((max_x - min_x + 1) * (max_y - min_y + 1)) as u64
+    // Draws the elves within their bounding rectangle, the way the puzzle does.
+    pub fn render(&self) -> String {
+        let (min, max) = match self.bounding_rectangle() {
+            Some(bounds) => bounds,
+            None => return String::new(),
+        };
+        let mut frame = String::new();
+        for y in min.y..=max.y {
+            frame.extend((min.x..=max.x).map(|x| {
+                if self.contains(Point2::new(x, y)) {
+                    '#'
+                } else {
+                    '.'
+                }
+            }));
+            frame.push('\n');
+        }
+        frame
     }
 
     pub fn num_elves(&self) -> u64 {
-        self.elves.len() as u64
+        self.chunks
+            .values()
+            .flatten()
+            .map(|word| word.count_ones() as u64)
+            .sum()
     }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    let mut grove = Grove::from_str(input)?;
-    grove.do_rounds(10);
+// Part A counts empty ground after `--rounds` rounds, 10 by default.
+pub fn solve_a(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let mut grove = timing::parse_phase(|| Grove::from_str(input))?;
+    grove.do_rounds(day_args.get_or("rounds", 10)?);
     Ok(grove.bounding_rectangle_area() - grove.num_elves())
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
+// Part B gives up after `--rounds` rounds, if given.
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let mut grove = timing::parse_phase(|| Grove::from_str(input))?;
+    let max = day_args.get_or("rounds", u64::MAX)?;
+    match grove.do_rounds(max) {
+        u64::MAX => Err(AocError::no_solution(format!(
+            "elves still moving after {max} rounds"
+        ))),
+        rounds => Ok(rounds),
+    }
+}
+
+// The elves spreading out, one round at a time.
+struct GroveViz {
+    grove: Grove,
+    // How many rounds to show, if the elves are not left to stop on their own.
+    max_rounds: Option<u64>,
+}
+
+impl Visualize for GroveViz {
+    fn frames(&self) -> impl Iterator<Item = String> {
+        let mut grove = self.grove.clone();
+        let mut stopped = false;
+        let rounds = (0..self.max_rounds.unwrap_or(u64::MAX)).map_while(move |round| {
+            if stopped {
+                return None;
+            }
+            stopped = grove.do_round(round);
+            Some(grove.render())
+        });
+        iter::once(self.grove.render()).chain(rounds)
+    }
+}
+
+// Where the elves are after the last round of the part.
+pub fn snapshot(input: &str, part: SolutionPart) -> AocResult<GridImage> {
     let mut grove = Grove::from_str(input)?;
-    Ok(grove.do_rounds(u64::MAX))
+    grove.do_rounds(if part == SolutionPart::A {
+        10
+    } else {
+        u64::MAX
+    });
+    Ok(grove.image())
+}
+
+pub fn visualize(input: &str, part: SolutionPart) -> AocResult<impl Visualize> {
+    Ok(GroveViz {
+        grove: Grove::from_str(input)?,
+        max_rounds: (part == SolutionPart::A).then_some(10),
+    })
+}
+
+register_day!(23, args solve_a, args solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_example() {
+        let input = include_str!("../../examples/23.txt");
+        assert_eq!(solve_a(input, &DayArgs::default()).unwrap(), 110);
+        assert_eq!(solve_b(input, &DayArgs::default()).unwrap(), 20);
+    }
+
+    #[test]
+    fn moves_elves_across_chunks() {
+        // The same elves, straddling the corner of four chunks.
+        let input = include_str!("../../examples/23.txt")
+            .lines()
+            .map(|line| format!("{}{line}\n", ".".repeat(60)))
+            .collect::<String>();
+        let input = format!("{}{input}", ".\n".repeat(60));
+        assert_eq!(solve_a(&input, &DayArgs::default()).unwrap(), 110);
+        assert_eq!(solve_b(&input, &DayArgs::default()).unwrap(), 20);
+
+        // Elves spread out in every direction, into chunks with negative keys.
+        let mut grove = Grove::from_str(include_str!("../../examples/23.txt")).unwrap();
+        grove.do_rounds(10);
+        assert!(grove.chunks.keys().any(|key| key.x < 0 || key.y < 0));
+        assert_eq!(grove.num_elves(), 22);
+    }
+
+    #[test]
+    fn limits_rounds_from_args() {
+        let input = include_str!("../../examples/23.txt");
+        let rounds = |n: u64| DayArgs::default().with("rounds", n);
+        assert_eq!(solve_a(input, &rounds(0)).unwrap(), 27);
+        assert_eq!(solve_b(input, &rounds(20)).unwrap(), 20);
+        assert!(solve_b(input, &rounds(5)).is_err());
+    }
 }
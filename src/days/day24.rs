@@ -1,102 +1,106 @@
-use std::{collections::VecDeque, ops::Add, str::FromStr};
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    iter,
+    str::FromStr,
+};
 
-use crate::common::{AocError, AocResult, IntoAocResult};
+use crate::common::{
+    search, timing,
+    viz::{self, Visualize},
+    AocError, AocResult, DayArgs, IntoAocResult, Point2, SolutionPart,
+};
 use itertools::Itertools;
-use lazy_static::lazy_static;
 use num::Integer;
-use rustc_hash::FxHashSet;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Point {
-    x: i64,
-    y: i64,
-}
+type Point = Point2<i64>;
 
-impl Point {
-    pub fn new(x: i64, y: i64) -> Self {
-        Self { x, y }
-    }
+#[derive(Debug, Clone, Copy)]
+struct Blizzard {
+    negate: bool,
+    start: i64,
 }
 
-impl Add for Point {
-    type Output = Point;
-    fn add(self, rhs: Self) -> Self::Output {
-        Self::new(self.x + rhs.x, self.y + rhs.y)
+impl Blizzard {
+    pub fn position_at(&self, time: i64, bound: i64) -> i64 {
+        (if self.negate {
+            self.start - time
+        } else {
+            self.start + time
+        })
+        .rem_euclid(bound)
     }
 }
 
-struct Neighbors<'a> {
-    location: &'a Point,
-    i: usize,
+// How the valley checks whether a tile is free of blizzards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlizzardStrategy {
+    // Scans every blizzard on the tile's row and column.
+    Lazy,
+    // Saves which tiles are occupied at every minute of the blizzard cycle
+    // before pathfinding, so every check is a single bit test. Falls back to
+    // scanning if the occupancy would not fit in `OCCUPANCY_MEMORY_BUDGET`.
+    #[default]
+    Precomputed,
 }
 
-impl<'a> Neighbors<'a> {
-    pub fn new(location: &'a Point) -> Self {
-        Self { location, i: 0 }
-    }
-
-    fn moves() -> &'static [Point; 4] {
-        lazy_static! {
-            static ref MOVES: [Point; 4] = [
-                Point::new(-1, 0),
-                Point::new(1, 0),
-                Point::new(0, -1),
-                Point::new(0, 1)
-            ];
+impl FromStr for BlizzardStrategy {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        match s {
+            "lazy" => Ok(Self::Lazy),
+            "precomputed" => Ok(Self::Precomputed),
+            _ => Err(AocError::invalid_args(format!(
+                "blizzard strategy must be lazy or precomputed, not {s}"
+            ))),
         }
-        &MOVES
-    }
-
-    fn next_output(&self) -> Option<Point> {
-        Self::moves()
-            .get(self.i)
-            .and_then(|delta| Some(*self.location + *delta))
-    }
-
-    fn advance_state(&mut self) {
-        self.i += 1;
-    }
-}
-
-impl<'a> Iterator for Neighbors<'a> {
-    type Item = Point;
-    fn next(&mut self) -> Option<Self::Item> {
-        let output = self.next_output()?;
-        self.advance_state();
-        Some(output)
     }
 }
 
-trait ExploreNeighbors {
-    fn explore_neighbors<'a>(&'a self) -> Neighbors<'a>;
+// How the valley finds the quickest way through the blizzards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TravelStrategy {
+    // Explores every reachable state minute by minute. Slower, but useful for
+    // checking the A* search.
+    Bfs,
+    // Explores states closest to the target first, guided by the Manhattan
+    // distance to the target.
+    #[default]
+    AStar,
 }
 
-impl ExploreNeighbors for Point {
-    fn explore_neighbors<'a>(&'a self) -> Neighbors<'a> {
-        Neighbors::new(self)
+impl FromStr for TravelStrategy {
+    type Err = AocError;
+    fn from_str(s: &str) -> AocResult<Self> {
+        match s {
+            "bfs" => Ok(Self::Bfs),
+            "astar" => Ok(Self::AStar),
+            _ => Err(AocError::invalid_args(format!(
+                "travel strategy must be bfs or astar, not {s}"
+            ))),
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Blizzard {
-    negate: bool,
-    start: i64,
+// The most memory, in bytes, that precomputed blizzard occupancy may use.
+const OCCUPANCY_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+// Which tiles of the valley are occupied by a blizzard at each minute of the
+// blizzard cycle, packed into one bit per tile.
+#[derive(Debug)]
+struct Occupancy {
+    tiles: usize,
+    width: usize,
+    bits: Vec<u64>,
 }
 
-impl Blizzard {
-    pub fn position_at(&self, time: i64, bound: i64) -> i64 {
-        (if self.negate {
-            self.start - time
-        } else {
-            self.start + time
-        })
-        .rem_euclid(bound)
+impl Occupancy {
+    pub fn is_occupied(&self, point: &Point, time: i64) -> bool {
+        let bit = time as usize * self.tiles + point.y as usize * self.width + point.x as usize;
+        self.bits[bit / 64] & (1 << (bit % 64)) != 0
     }
 }
 
-// One potential speedup is to save all blizzard positions before starting
-// pathfinding, so that all map operations are O(1). This has a huge memory cost
-// (the same map is saved lcm(width x height) times).
 #[derive(Debug)]
 struct Valley {
     start: Point,
@@ -106,6 +110,8 @@ struct Valley {
     x_blizzards: Vec<Vec<Blizzard>>,
     // Maps y coordinates to blizzards on that row.
     y_blizzards: Vec<Vec<Blizzard>>,
+    occupancy: Option<Occupancy>,
+    travel_strategy: TravelStrategy,
 }
 
 impl FromStr for Valley {
@@ -113,7 +119,7 @@ impl FromStr for Valley {
     fn from_str(s: &str) -> AocResult<Self> {
         let lines = s.lines().collect_vec();
         if lines.len() < 3 {
-            return Err(AocError::new("valley must have at least 3 lines"));
+            return Err(AocError::parse("valley must have at least 3 lines"));
         }
 
         // This code assumes that the input is enclosed by a wall on all sides.
@@ -131,6 +137,8 @@ impl FromStr for Valley {
             size: Point::new(lines[0].len() as i64 - 2, lines.len() as i64 - 2),
             x_blizzards: vec![Vec::new(); lines[0].len() - 2],
             y_blizzards: vec![Vec::new(); lines.len() - 2],
+            occupancy: None,
+            travel_strategy: TravelStrategy::default(),
         };
         for (y, line) in lines[1..(lines.len() - 1)].iter().enumerate() {
             for (x, c) in line[1..(line.len() - 1)].char_indices() {
@@ -152,11 +160,12 @@ impl FromStr for Valley {
                         start: y as i64,
                     }),
                     '.' => (),
-                    _ => return Err(AocError::new("invalid character")),
+                    _ => return Err(AocError::parse("invalid character")),
                 }
             }
         }
 
+        valley.set_strategy(BlizzardStrategy::default());
         Ok(valley)
     }
 }
@@ -168,63 +177,326 @@ impl Valley {
             || point == &self.end
     }
 
-    pub fn open_at(&self, point: &Point, time: i64) -> bool {
-        point == &self.start
-            || point == &self.end
-            || (self.x_blizzards[point.x as usize]
-                .iter()
-                .all(|blizzard| blizzard.position_at(time, self.size.y) != point.y)
-                && self.y_blizzards[point.y as usize]
-                    .iter()
-                    .all(|blizzard| blizzard.position_at(time, self.size.x) != point.x))
+    pub fn set_strategy(&mut self, strategy: BlizzardStrategy) {
+        self.occupancy = match strategy {
+            BlizzardStrategy::Lazy => None,
+            BlizzardStrategy::Precomputed => self.precompute_occupancy(),
+        };
     }
 
-    fn bfs(&self, start_state: (Point, i64), target: Point) -> AocResult<i64> {
-        let mut queue = VecDeque::from([start_state]);
-        let mut seen = FxHashSet::default();
-        let blizzard_cycles_at = self.end.x.lcm(&self.end.y);
-        while let Some((position, time)) = queue.pop_front() {
-            if position == target {
-                return Ok(time);
-            }
+    pub fn set_travel_strategy(&mut self, strategy: TravelStrategy) {
+        self.travel_strategy = strategy;
+    }
 
-            let blizzard_state = time % blizzard_cycles_at;
-            if !seen.insert((position, blizzard_state)) {
-                continue;
-            }
+    // Saves where every blizzard is at every minute of the blizzard cycle, or
+    // returns nothing if that would take too much memory.
+    fn precompute_occupancy(&self) -> Option<Occupancy> {
+        let (width, height) = (self.size.x as usize, self.size.y as usize);
+        let tiles = width * height;
+        let cycle = self.blizzard_cycle() as usize;
+        let words = (tiles * cycle).div_ceil(64);
+        if words * 8 > OCCUPANCY_MEMORY_BUDGET {
+            debug!("blizzard cycle of {cycle} minutes is too long to precompute");
+            return None;
+        }
 
-            let next_time = time + 1;
-            let next_blizzard_state = next_time % blizzard_cycles_at;
-            for neighbor in position.explore_neighbors() {
-                if self.in_valley(&neighbor) && self.open_at(&neighbor, next_blizzard_state) {
-                    queue.push_back((neighbor, next_time));
+        let mut bits = vec![0u64; words];
+        let mut occupy = |time: usize, x: i64, y: i64| {
+            let bit = time * tiles + y as usize * width + x as usize;
+            bits[bit / 64] |= 1 << (bit % 64);
+        };
+        for time in 0..cycle {
+            for (x, blizzards) in self.x_blizzards.iter().enumerate() {
+                for blizzard in blizzards {
+                    occupy(
+                        time,
+                        x as i64,
+                        blizzard.position_at(time as i64, self.size.y),
+                    );
                 }
             }
-
-            if self.open_at(&position, next_time) {
-                queue.push_back((position, next_time));
+            for (y, blizzards) in self.y_blizzards.iter().enumerate() {
+                for blizzard in blizzards {
+                    occupy(
+                        time,
+                        blizzard.position_at(time as i64, self.size.x),
+                        y as i64,
+                    );
+                }
             }
         }
-        Err(AocError::new(&format!("failed to reach end: {target:?}")))
+        debug!(
+            "precomputed a blizzard cycle of {cycle} minutes in {} bytes",
+            words * 8
+        );
+        Some(Occupancy { tiles, width, bits })
     }
 
-    pub fn travel_to_end(&self, time_start: i64) -> AocResult<i64> {
-        self.bfs((self.start, time_start), self.end)
+    // Checks if a point is free of blizzards at the given minute of the blizzard
+    // cycle.
+    pub fn open_at(&self, point: &Point, time: i64) -> bool {
+        if point == &self.start || point == &self.end {
+            return true;
+        }
+        if let Some(occupancy) = &self.occupancy {
+            return !occupancy.is_occupied(point, time);
+        }
+        self.x_blizzards[point.x as usize]
+            .iter()
+            .all(|blizzard| blizzard.position_at(time, self.size.y) != point.y)
+            && self.y_blizzards[point.y as usize]
+                .iter()
+                .all(|blizzard| blizzard.position_at(time, self.size.x) != point.x)
+    }
+
+    // Blizzards return to their starting positions after this many minutes, so
+    // states only need to be distinguished by time modulo this cycle.
+    fn blizzard_cycle(&self) -> i64 {
+        self.size.x.lcm(&self.size.y)
+    }
+
+    fn travel(&self, from: Point, target: Point, time_start: i64) -> AocResult<i64> {
+        let cycle = self.blizzard_cycle();
+        let start = (from, time_start % cycle);
+        let expanded = Cell::new(0u64);
+        let successors = |&(position, time): &(Point, i64)| {
+            expanded.set(expanded.get() + 1);
+            let next_time = (time + 1) % cycle;
+            position
+                .neighbors4()
+                .into_iter()
+                .filter(|neighbor| self.in_valley(neighbor))
+                .chain([position])
+                .filter(move |next| self.open_at(next, next_time))
+                .map(move |next| (next, next_time))
+        };
+        let is_goal = |(position, _): &(Point, i64)| *position == target;
+        let minutes = match self.travel_strategy {
+            TravelStrategy::Bfs => search::bfs([start], successors, is_goal).map(|n| n as i64),
+            TravelStrategy::AStar => search::astar(
+                [start],
+                |state| successors(state).map(|next| (next, 1)),
+                // Every minute moves at most one tile closer to the target.
+                |(position, _)| position.manhattan_distance(&target),
+                is_goal,
+            ),
+        };
+        debug!(
+            "{:?} search from {from:?} to {target:?} at minute {time_start} expanded {} states",
+            self.travel_strategy,
+            expanded.get()
+        );
+        minutes
+            .map(|minutes| time_start + minutes)
+            .ok_or_else(|| AocError::no_solution(format!("failed to reach end: {target:?}")))
     }
 
-    pub fn travel_to_start(&self, time_start: i64) -> AocResult<i64> {
-        self.bfs((self.end, time_start), self.start)
+    pub fn start(&self) -> Point {
+        self.start
+    }
+
+    pub fn end(&self) -> Point {
+        self.end
+    }
+
+    // Travels from the start through each waypoint in order, setting off from
+    // one as soon as the last one is reached, and returns the minute each one
+    // was reached.
+    pub fn plan_trips(&self, waypoints: &[Point], time_start: i64) -> AocResult<Vec<i64>> {
+        if let Some(waypoint) = waypoints.iter().find(|point| !self.in_valley(point)) {
+            return Err(AocError::invalid_args(format!(
+                "waypoint is outside the valley: {waypoint:?}"
+            )));
+        }
+        let mut arrivals = Vec::with_capacity(waypoints.len());
+        let mut from = self.start;
+        let mut time = time_start;
+        for &waypoint in waypoints {
+            time = self.travel(from, waypoint, time)?;
+            arrivals.push(time);
+            from = waypoint;
+        }
+        Ok(arrivals)
+    }
+
+    // The minute the last waypoint is reached.
+    fn finish_trips(&self, waypoints: &[Point]) -> AocResult<u64> {
+        let arrivals = self.plan_trips(waypoints, 0)?;
+        Ok(arrivals.last().copied().unwrap_or_default() as u64)
     }
 }
 
-pub fn solve_a(input: &str) -> AocResult<u64> {
-    let valley = Valley::from_str(input)?;
-    valley.travel_to_end(0).map(|n| n as u64)
+// Both parts search with A* unless given `--travel bfs`, and precompute where
+// the blizzards are unless given `--blizzards lazy`. Precomputing is part of
+// reading the valley, so it counts as parsing.
+fn read_valley(input: &str, day_args: &DayArgs) -> AocResult<Valley> {
+    let blizzards = day_args.get_or("blizzards", BlizzardStrategy::default())?;
+    let mut valley = timing::parse_phase(|| Valley::from_str(input))?;
+    if blizzards != BlizzardStrategy::default() {
+        valley.set_strategy(blizzards);
+    }
+    valley.set_travel_strategy(day_args.get_or("travel", TravelStrategy::default())?);
+    Ok(valley)
 }
 
-pub fn solve_b(input: &str) -> AocResult<u64> {
+pub fn solve_a(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let valley = read_valley(input, day_args)?;
+    valley.finish_trips(&[valley.end()])
+}
+
+pub fn solve_b(input: &str, day_args: &DayArgs) -> AocResult<u64> {
+    let valley = read_valley(input, day_args)?;
+    valley.finish_trips(&[valley.end(), valley.start(), valley.end()])
+}
+
+// The blizzards moving through the valley, along with every tile the expedition
+// could be on by each minute.
+struct ValleyViz {
+    valley: Valley,
+    // Where the expedition goes, in order. Part B goes back for the snacks.
+    legs: Vec<(Point, Point)>,
+}
+
+impl ValleyViz {
+    fn render(&self, time: i64, expedition: &HashSet<Point>) -> String {
+        let mut blizzards = HashMap::<Point, Vec<char>>::new();
+        for (x, column) in self.valley.x_blizzards.iter().enumerate() {
+            for blizzard in column {
+                let y = blizzard.position_at(time, self.valley.size.y);
+                let arrow = if blizzard.negate { '^' } else { 'v' };
+                blizzards
+                    .entry(Point::new(x as i64, y))
+                    .or_default()
+                    .push(arrow);
+            }
+        }
+        for (y, row) in self.valley.y_blizzards.iter().enumerate() {
+            for blizzard in row {
+                let x = blizzard.position_at(time, self.valley.size.x);
+                let arrow = if blizzard.negate { '<' } else { '>' };
+                blizzards
+                    .entry(Point::new(x, y as i64))
+                    .or_default()
+                    .push(arrow);
+            }
+        }
+        viz::render_grid(Point::new(-1, -1), self.valley.size, |point| {
+            if expedition.contains(&point) {
+                'E'
+            } else if !self.valley.in_valley(&point) {
+                '#'
+            } else {
+                match blizzards.get(&point).map(|arrows| arrows.as_slice()) {
+                    None | Some([]) => '.',
+                    Some([arrow]) => *arrow,
+                    Some(arrows) => char::from_digit(arrows.len() as u32, 10).unwrap_or('*'),
+                }
+            }
+        })
+    }
+}
+
+impl Visualize for ValleyViz {
+    fn frames(&self) -> impl Iterator<Item = String> {
+        let mut legs = self.legs.iter().copied();
+        let mut leg = legs.next();
+        let mut expedition = leg.map_or_else(HashSet::new, |(from, _)| HashSet::from([from]));
+        let mut time = 0;
+        iter::from_fn(move || {
+            let (_, target) = leg?;
+            let frame = self.render(time, &expedition);
+            if expedition.contains(&target) {
+                // The next leg sets off from where this one arrived.
+                leg = legs.next();
+                expedition = HashSet::from([target]);
+            } else {
+                time += 1;
+                let cycle_time = time % self.valley.blizzard_cycle();
+                expedition = expedition
+                    .iter()
+                    .flat_map(|position| position.neighbors4().into_iter().chain([*position]))
+                    .filter(|next| {
+                        self.valley.in_valley(next) && self.valley.open_at(next, cycle_time)
+                    })
+                    .collect();
+            }
+            Some(frame)
+        })
+    }
+}
+
+pub fn visualize(input: &str, part: SolutionPart) -> AocResult<impl Visualize> {
     let valley = Valley::from_str(input)?;
-    let first = valley.travel_to_end(0)?;
-    let second = valley.travel_to_start(first)?;
-    valley.travel_to_end(second).map(|n| n as u64)
+    let waypoints = match part {
+        SolutionPart::B => vec![valley.end, valley.start, valley.end],
+        _ => vec![valley.end],
+    };
+    let legs = iter::once(valley.start)
+        .chain(waypoints.iter().copied())
+        .tuple_windows()
+        .collect();
+    Ok(ValleyViz { valley, legs })
+}
+
+register_day!(24, args solve_a, args solve_b);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../examples/24.txt");
+
+    #[test]
+    fn plans_trips_through_waypoints() {
+        let valley = Valley::from_str(EXAMPLE).unwrap();
+        let (start, end) = (valley.start(), valley.end());
+        assert_eq!(
+            valley.plan_trips(&[end, start, end], 0).unwrap(),
+            [18, 41, 54]
+        );
+        // Setting off later meets the blizzards somewhere else.
+        assert_eq!(valley.plan_trips(&[end], 18).unwrap(), [30]);
+        assert!(valley.plan_trips(&[], 5).unwrap().is_empty());
+        // A waypoint in the middle of the valley, then on to the exit.
+        let middle = Point::new(2, 2);
+        let arrivals = valley.plan_trips(&[middle, end], 0).unwrap();
+        assert!(arrivals[0] >= 5 && arrivals[1] >= 18);
+        assert!(valley.plan_trips(&[Point::new(-1, 0)], 0).is_err());
+    }
+
+    #[test]
+    fn blizzard_strategies_agree() {
+        let mut valley = Valley::from_str(EXAMPLE).unwrap();
+        assert!(valley.occupancy.is_some());
+        let cycle = valley.blizzard_cycle();
+        let open_tiles = |valley: &Valley| {
+            (0..cycle)
+                .flat_map(|time| {
+                    (-1..=valley.size.y).flat_map(move |y| {
+                        (0..valley.size.x).map(move |x| (Point::new(x, y), time))
+                    })
+                })
+                .filter(|(point, time)| valley.in_valley(point) && valley.open_at(point, *time))
+                .collect::<Vec<_>>()
+        };
+        let precomputed = open_tiles(&valley);
+        valley.set_strategy(BlizzardStrategy::Lazy);
+        assert!(valley.occupancy.is_none());
+        assert_eq!(open_tiles(&valley), precomputed);
+        let day_args = DayArgs::default().with("blizzards", "lazy");
+        assert_eq!(solve_b(EXAMPLE, &day_args).unwrap(), 54);
+    }
+
+    #[test]
+    fn travel_strategies_agree() {
+        let mut valley = Valley::from_str(EXAMPLE).unwrap();
+        let (start, end) = (valley.start(), valley.end());
+        let itinerary = [end, start, end, Point::new(3, 1), start];
+        let astar = valley.plan_trips(&itinerary, 0).unwrap();
+        valley.set_travel_strategy(TravelStrategy::Bfs);
+        assert_eq!(valley.plan_trips(&itinerary, 0).unwrap(), astar);
+        let day_args = DayArgs::default().with("travel", "bfs");
+        assert_eq!(solve_b(EXAMPLE, &day_args).unwrap(), 54);
+        assert!(solve_a(EXAMPLE, &DayArgs::default().with("travel", "dfs")).is_err());
+    }
 }
@@ -0,0 +1,93 @@
+use super::all::{solve_input, SolveRecord};
+use crate::{
+    common::{constants::Profile, DayArgs, SolutionPart},
+    program::ProgramArgs,
+};
+
+// A puzzle example from the `examples` directory and the answers it should
+// produce for part A and part B.
+pub struct Example {
+    pub day: u8,
+    pub file: &'static str,
+    pub input: &'static str,
+    pub answers: [Option<&'static str>; 2],
+}
+
+// An answer of `_` means the part is not run for the example.
+macro_rules! example_answer {
+    (_) => {
+        None
+    };
+    ($answer:literal) => {
+        Some($answer)
+    };
+}
+
+macro_rules! examples {
+    ($(($day:literal, $file:literal, $a:tt, $b:tt)),* $(,)?) => {
+        pub const EXAMPLES: &[Example] = &[$(
+            Example {
+                day: $day,
+                file: $file,
+                input: include_str!(concat!("../../examples/", $file)),
+                answers: [example_answer!($a), example_answer!($b)],
+            },
+        )*];
+    };
+}
+
+// Day 10 part B draws stripes for its example rather than letters, so there is
+// nothing to read.
+examples!(
+    (1, "1.txt", "24000", "45000"),
+    (2, "2.txt", "15", "12"),
+    (3, "3.txt", "157", "70"),
+    (4, "4.txt", "2", "4"),
+    (5, "5.txt", "CMZ", "MCD"),
+    (6, "6.txt", "7", "19"),
+    (7, "7.txt", "95437", "24933642"),
+    (8, "8.txt", "21", "8"),
+    (8, "8-symmetric.txt", "22", "12"),
+    (9, "9.txt", "13", "1"),
+    (9, "9-larger.txt", "88", "36"),
+    (10, "10.txt", "13140", _),
+    (11, "11.txt", "10605", "2713310158"),
+    (12, "12.txt", "31", "29"),
+    (13, "13.txt", "13", "140"),
+    (14, "14.txt", "24", "93"),
+    (15, "15.txt", "26", "56000011"),
+    (16, "16.txt", "1651", "1707"),
+    (17, "17.txt", "3068", "1514285714288"),
+    (18, "18.txt", "64", "58"),
+    (18, "18-small.txt", "10", "10"),
+    (19, "19.txt", "33", "3472"),
+    (20, "20.txt", "3", "1623178306"),
+    (21, "21.txt", "152", "301"),
+    (22, "22.txt", "6032", "5031"),
+    (23, "23.txt", "110", "20"),
+    (23, "23-small.txt", "25", "4"),
+    (24, "24.txt", "18", "54"),
+    (24, "24-simple.txt", "10", "30"),
+    (25, "25.txt", "2=-1=0", "Start The Blender"),
+);
+
+// Runs both parts of every example and checks them against the expected
+// answers.
+pub fn run_examples() -> Vec<SolveRecord> {
+    let mut results = Vec::new();
+    for example in EXAMPLES {
+        for (part, expected) in [SolutionPart::A, SolutionPart::B]
+            .into_iter()
+            .zip(example.answers)
+            .filter_map(|(part, expected)| Some((part, expected?)))
+        {
+            let args = ProgramArgs::new(example.day, part, Some(example.file.to_owned()))
+                .with_day_args(DayArgs::default().with_profile(Profile::Example));
+            let mut record = solve_input(&args, example.input)
+                .unwrap_or_else(|err| SolveRecord::failed(&args, err));
+            record.verify(Some(expected));
+            results.push(record);
+        }
+    }
+    results
+}
@@ -1,28 +1,19 @@
+#[macro_use]
+mod registry;
 mod all;
-mod day01;
-mod day02;
-mod day03;
-mod day04;
-mod day05;
-mod day06;
-mod day07;
-mod day08;
-mod day09;
-mod day10;
-mod day11;
-mod day12;
-mod day13;
-mod day14;
-mod day15;
-mod day16;
-mod day17;
-mod day18;
-mod day19;
-mod day20;
-mod day21;
-mod day22;
-mod day23;
-mod day24;
-mod day25;
+mod examples;
+mod render;
+mod viz;
 
-pub use all::{solve, solve_all, Solution};
+days!(
+    day01, day02, day03, day04, day05, day06, day07, day08, day09, day10, day11, day12, day13,
+    day14, day15, day16, day17, day18, day19, day20, day21, day22, day23, day24, day25,
+);
+
+pub(crate) use all::{
+    bench, registered_days, run_all_solvers, run_input_sets, solve, solve_all, solve_both,
+    solve_puzzle, AllSolutions, SolveRecord, TimingBreakdown,
+};
+pub(crate) use examples::run_examples;
+pub(crate) use render::render;
+pub(crate) use viz::visualize;
@@ -0,0 +1,136 @@
+use crate::common::{AocError, AocResult, Solver};
+use std::collections::BTreeMap;
+
+pub struct DayRegistration {
+    pub day: u8,
+    pub solvers: [Solver; 2],
+}
+
+// Registers the solvers for a day. Each day module invokes this once with its
+// day number and solver functions. The names below are only for illustration,
+// and `1` stands for the day's own number:
+//
+//     register_day!(1, solve_a, solve_b);
+//
+// Alternate implementations of a part follow its solver, separated by `|`, and
+// are checked against it when running with `--check`:
+//
+//     register_day!(1, solve_a | solve_a_another_way, solve_b);
+//
+// Days whose solvers take a stream of lines rather than the whole input mark
+// both parts with `lines`, and read their input from the file as they go.
+// Their alternates stream their input as well:
+//
+//     register_day!(1, lines solve_a | solve_a_another_way, lines solve_b);
+//
+// Days whose solvers take options from the command line, given after `--`,
+// mark them with `args`, and so do their alternates:
+//
+//     register_day!(1, args solve_a | solve_a_another_way, args solve_b);
+//
+// Only part B may take options on its own, in which case part A and its
+// alternates take none:
+//
+//     register_day!(1, solve_a | solve_a_another_way, args solve_b | solve_b_another_way);
+macro_rules! register_day {
+    ($day:literal, args $solve_a:path $(| $alternate_a:path)*, args $solve_b:path $(| $alternate_b:path)* $(,)?) => {
+        pub fn registration() -> $crate::days::registry::DayRegistration {
+            $crate::days::registry::DayRegistration {
+                day: $day,
+                solvers: [
+                    $crate::common::Solver::with_args($solve_a)
+                        $(.with_alternate_args($alternate_a))*,
+                    $crate::common::Solver::with_args($solve_b)
+                        $(.with_alternate_args($alternate_b))*,
+                ],
+            }
+        }
+    };
+    ($day:literal, $solve_a:path $(| $alternate_a:path)*, args $solve_b:path $(| $alternate_b:path)* $(,)?) => {
+        pub fn registration() -> $crate::days::registry::DayRegistration {
+            $crate::days::registry::DayRegistration {
+                day: $day,
+                solvers: [
+                    $crate::common::Solver::new($solve_a)
+                        $(.with_alternate($alternate_a))*,
+                    $crate::common::Solver::with_args($solve_b)
+                        $(.with_alternate_args($alternate_b))*,
+                ],
+            }
+        }
+    };
+    ($day:literal, lines $solve_a:path $(| $alternate_a:path)*, lines $solve_b:path $(| $alternate_b:path)* $(,)?) => {
+        pub fn registration() -> $crate::days::registry::DayRegistration {
+            $crate::days::registry::DayRegistration {
+                day: $day,
+                solvers: [
+                    $crate::common::Solver::streaming($solve_a)
+                        $(.with_alternate_streaming($alternate_a))*,
+                    $crate::common::Solver::streaming($solve_b)
+                        $(.with_alternate_streaming($alternate_b))*,
+                ],
+            }
+        }
+    };
+    ($day:literal, $solve_a:path $(| $alternate_a:path)*, $solve_b:path $(| $alternate_b:path)* $(,)?) => {
+        pub fn registration() -> $crate::days::registry::DayRegistration {
+            $crate::days::registry::DayRegistration {
+                day: $day,
+                solvers: [
+                    $crate::common::Solver::new($solve_a)
+                        $(.with_alternate($alternate_a))*,
+                    $crate::common::Solver::new($solve_b)
+                        $(.with_alternate($alternate_b))*,
+                ],
+            }
+        }
+    };
+}
+
+// Declares each day module and collects its registration, so adding a module
+// here is all it takes to make a day runnable.
+macro_rules! days {
+    ($($module:ident),* $(,)?) => {
+        $(pub mod $module;)*
+
+        const REGISTRATIONS: &[fn() -> registry::DayRegistration] =
+            &[$($module::registration),*];
+    };
+}
+
+// Solvers for every registered day, in day order.
+pub struct Registry {
+    days: BTreeMap<u8, [Solver; 2]>,
+}
+
+impl Registry {
+    pub fn new(registrations: &[fn() -> DayRegistration]) -> AocResult<Self> {
+        let mut days = BTreeMap::new();
+        for registration in registrations.iter().map(|register| register()) {
+            if registration.day == 0 || registration.day > 25 {
+                return Err(AocError::invalid_args(format!(
+                    "day {} is not between 1 and 25",
+                    registration.day
+                )));
+            }
+            if days
+                .insert(registration.day, registration.solvers)
+                .is_some()
+            {
+                return Err(AocError::invalid_args(format!(
+                    "day {} is registered more than once",
+                    registration.day
+                )));
+            }
+        }
+        Ok(Self { days })
+    }
+
+    pub fn get(&self, day: u8) -> Option<&[Solver; 2]> {
+        self.days.get(&day)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u8, &[Solver; 2])> {
+        self.days.iter().map(|(day, solvers)| (*day, solvers))
+    }
+}
@@ -0,0 +1,54 @@
+use std::{fs, path::Path};
+
+use super::{all::read_input, day10, day14, day16, day17, day18, day22, day23};
+use crate::{
+    common::{AocError, AocResult, IntoAocResult},
+    program::ProgramArgs,
+};
+
+// Saves an image of the final state of a day and part, or a 3D mesh, graph, or
+// drawing of the path taken for days that have one.
+pub fn render(args: &ProgramArgs, path: &str) -> AocResult<()> {
+    let input = read_input(args)?;
+    let part = args.part();
+    let extension = Path::new(path).extension().and_then(|ext| ext.to_str());
+    match (args.day(), extension) {
+        (10, Some("txt")) => {
+            return fs::write(path, day10::draw_text(&input, args.day_args())?)
+                .into_aoc_result_msg(&format!("failed to write {}", path))
+        }
+        (16, Some("dot")) => {
+            return fs::write(path, day16::dot(&input, part)?)
+                .into_aoc_result_msg(&format!("failed to write {}", path))
+        }
+        (16, _) => {
+            return Err(AocError::invalid_args(
+                "day 16 can only be rendered to a .dot file",
+            ))
+        }
+        (22, Some("txt")) => {
+            return fs::write(path, day22::draw_path(&input, part)?)
+                .into_aoc_result_msg(&format!("failed to write {}", path))
+        }
+        (22, _) => {
+            return Err(AocError::invalid_args(
+                "day 22 can only be rendered to a .txt file",
+            ))
+        }
+        (18, Some("obj" | "stl")) => return day18::mesh(&input, part)?.save(path),
+        _ => (),
+    }
+    let image = match args.day() {
+        10 => day10::snapshot(&input, args.day_args())?,
+        14 => day14::snapshot(&input, part)?,
+        17 => day17::snapshot(&input, part)?,
+        18 => day18::snapshot(&input, part)?,
+        23 => day23::snapshot(&input, part)?,
+        day => {
+            return Err(AocError::invalid_args(format!(
+                "day {day} has nothing to render"
+            )))
+        }
+    };
+    image.save(path)
+}
@@ -0,0 +1,32 @@
+use super::{all::read_input, day05, day09, day12, day14, day16, day17, day22, day23, day24};
+use crate::{
+    common::{viz, AocError, AocResult},
+    program::VizArgs,
+};
+
+// Plays the animation for a day and part in the terminal.
+pub fn visualize(args: &VizArgs) -> AocResult<()> {
+    let program_args = args.program_args();
+    let input = read_input(program_args)?;
+    let part = program_args.part();
+    let (delay, every) = (args.delay(), args.every());
+    match program_args.day() {
+        5 => viz::play(&day05::visualize(&input, part)?, delay, every),
+        9 => viz::play(&day09::visualize(&input, part)?, delay, every),
+        12 => viz::play(&day12::visualize(&input, part)?, delay, every),
+        14 => viz::play(&day14::visualize(&input, part)?, delay, every),
+        // The tunnels do not change, so there is nothing to animate. The graph
+        // is printed instead, to be piped into Graphviz.
+        16 => {
+            print!("{}", day16::dot(&input, part)?);
+            Ok(())
+        }
+        17 => viz::play(&day17::visualize(&input, part)?, delay, every),
+        22 => viz::play(&day22::visualize(&input, part)?, delay, every),
+        23 => viz::play(&day23::visualize(&input, part)?, delay, every),
+        24 => viz::play(&day24::visualize(&input, part)?, delay, every),
+        day => Err(AocError::invalid_args(format!(
+            "day {day} has no visualization"
+        ))),
+    }
+}
@@ -0,0 +1,21 @@
+// Solutions to Advent of Code 2022, usable without the command-line program:
+//
+//     let answer = aoc2022::solve(16, aoc2022::SolutionPart::A, &input)?;
+//
+// Each day's solvers can also be called directly, such as
+// `aoc2022::days::day16::solve_a`. The command-line program itself is private,
+// apart from `run`, which the binary hands its arguments to.
+#[macro_use]
+pub mod common;
+pub mod days;
+mod program;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+
+pub use common::{AocError, AocResult, AocSolution, SolutionPart};
+pub use program::run;
+
+// Solves one part of a day's puzzle for the given input.
+pub fn solve(day: u8, part: SolutionPart, input: &str) -> AocResult<AocSolution> {
+    days::solve_puzzle(day, part, input)
+}
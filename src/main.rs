@@ -1,56 +1,9 @@
-#[macro_use]
-extern crate num_derive;
+use std::{env, process::ExitCode};
 
-mod common;
-mod days;
-mod program;
+#[cfg(feature = "memory-stats")]
+#[global_allocator]
+static ALLOCATOR: aoc2022::common::CountingAllocator = aoc2022::common::CountingAllocator;
 
-use std::env;
-
-use days::{solve, solve_all};
-use program::ProgramArgs;
-
-fn run_all() {
-    match solve_all() {
-        Err(err) => eprintln!("{}", err),
-        Ok(total_time) => println!(
-            "All solutions ran in {} seconds ({} us)",
-            total_time.as_secs_f64(),
-            total_time.as_micros()
-        ),
-    }
-}
-
-fn run_part(program_name: &str, args: &mut impl Iterator<Item = String>) {
-    let args = match ProgramArgs::parse_from_args(args) {
-        Err(err) => {
-            eprintln!("{}", err);
-            return eprintln!("{}", ProgramArgs::usage(&program_name));
-        }
-        Ok(args) => args,
-    };
-    let solution = match solve(&args) {
-        Err(err) => {
-            return eprintln!("{}", err);
-        }
-        Ok(solution) => solution,
-    };
-    println!("Day {}, Part {}", args.day(), args.part());
-    println!(
-        "Solution: {} ({} us)",
-        solution.solution,
-        solution.time.as_micros()
-    );
-}
-
-fn main() {
-    let mut args = env::args().peekable();
-    let program_name = match args.next() {
-        None => return eprintln!("args is empty"),
-        Some(name) => name,
-    };
-    match args.peek().and_then(|s| Some(s.as_str())) {
-        Some("all") => run_all(),
-        _ => run_part(&program_name, &mut args),
-    };
+fn main() -> ExitCode {
+    aoc2022::run(env::args())
 }
@@ -1,41 +1,59 @@
-use crate::common::{AocError, AocResult, IntoAocResult};
+use crate::{
+    common::{
+        constants::Profile,
+        log::{self, Level},
+        AocError, AocResult, DayArgs, IntoAocResult, SolutionPart,
+    },
+    program::{config, input_path, input_set_filename, CacheMode},
+};
 use std::{
-    fmt::{Display, Formatter, Result as DisplayResult},
+    env,
+    io::{self, IsTerminal},
+    iter::Peekable,
     str::FromStr,
+    time::Duration,
 };
 
-#[derive(Copy, Clone)]
-pub enum SolutionPart {
-    A,
-    B,
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Markdown,
+    Csv,
 }
 
-impl FromStr for SolutionPart {
+impl FromStr for OutputFormat {
     type Err = AocError;
 
     fn from_str(string: &str) -> AocResult<Self> {
         match string {
-            "A" => Ok(Self::A),
-            "B" => Ok(Self::B),
-            _ => Err(AocError::new("part must be either A or B")),
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "markdown" => Ok(Self::Markdown),
+            "csv" => Ok(Self::Csv),
+            _ => Err(AocError::invalid_args(
+                "format must be text, json, markdown, or csv",
+            )),
         }
     }
 }
 
-impl Display for SolutionPart {
-    fn fmt(&self, f: &mut Formatter) -> DisplayResult {
-        let string = match self {
-            Self::A => "A",
-            Self::B => "B",
-        };
-        write!(f, "{}", string)
-    }
-}
+// Where the examples from the puzzle text are kept.
+const EXAMPLES_DIR: &str = "examples";
 
 pub struct ProgramArgs {
     day: u8,
     part: SolutionPart,
     filename: Option<String>,
+    input_set: Option<String>,
+    input_dir: String,
+    // Whether a missing input may be downloaded, which it is only into the
+    // configured input directory.
+    download: bool,
+    format: OutputFormat,
+    check: bool,
+    render: Option<String>,
+    day_args: DayArgs,
 }
 
 impl ProgramArgs {
@@ -44,6 +62,52 @@ impl ProgramArgs {
             day,
             part,
             filename,
+            input_set: None,
+            input_dir: config().input_dir.clone(),
+            download: true,
+            format: OutputFormat::Text,
+            check: false,
+            render: None,
+            // Only examples are solved with their own constants, which are
+            // asked for with `--example`.
+            day_args: DayArgs::default().with_profile(Profile::Real),
+        }
+    }
+
+    pub fn with_check(mut self, check: bool) -> Self {
+        self.check = check;
+        self
+    }
+
+    // Options given without a profile are for the same input as before.
+    pub fn with_day_args(mut self, day_args: DayArgs) -> Self {
+        self.day_args = match (day_args.profile(), self.day_args.profile()) {
+            (None, Some(profile)) => day_args.with_profile(profile),
+            _ => day_args,
+        };
+        self
+    }
+
+    // Solves one of the day's other inputs instead of its own.
+    pub fn with_input_set(mut self, input_set: &str) -> Self {
+        self.filename = Some(input_set_filename(self.day, input_set));
+        self.input_set = Some(input_set.to_owned());
+        self
+    }
+
+    // The same arguments for a different part.
+    pub fn for_part(&self, part: SolutionPart) -> Self {
+        ProgramArgs {
+            day: self.day,
+            part,
+            filename: self.filename.clone(),
+            input_set: self.input_set.clone(),
+            input_dir: self.input_dir.clone(),
+            download: self.download,
+            format: self.format,
+            check: self.check,
+            render: self.render.clone(),
+            day_args: self.day_args.clone(),
         }
     }
 
@@ -59,13 +123,56 @@ impl ProgramArgs {
         &self.filename
     }
 
+    // The file the input is read from.
+    pub fn input_path(&self) -> String {
+        match &self.filename {
+            None => input_path(&self.input_dir, self.day),
+            Some(filename) => format!("{}/{}", self.input_dir, filename),
+        }
+    }
+
+    // The name of the other input being solved, if it is not the day's own.
+    pub fn input_set(&self) -> Option<&str> {
+        self.input_set.as_deref()
+    }
+
+    // The directory that inputs are read from, which is the configured input
+    // directory unless overridden by `--input-dir`.
+    pub fn input_dir(&self) -> &str {
+        &self.input_dir
+    }
+
+    pub fn download(&self) -> bool {
+        self.download
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    // Whether to also run alternate implementations of the solver and fail if
+    // they find a different answer.
+    pub fn check(&self) -> bool {
+        self.check
+    }
+
+    // Path to save an image of the final state of the puzzle to.
+    pub fn render(&self) -> Option<&str> {
+        self.render.as_deref()
+    }
+
+    // Options for the day's solvers, given after `--`.
+    pub fn day_args(&self) -> &DayArgs {
+        &self.day_args
+    }
+
     fn get_next_string_optional(args: &mut impl Iterator<Item = String>) -> Option<String> {
         args.next()
     }
 
     fn get_next_string(args: &mut impl Iterator<Item = String>, name: &str) -> AocResult<String> {
         match Self::get_next_string_optional(args) {
-            None => Err(AocError::new(format!("missing {}", name))),
+            None => Err(AocError::invalid_args(format!("missing {}", name))),
             Some(parsed) => Ok(parsed),
         }
     }
@@ -78,15 +185,739 @@ impl ProgramArgs {
 
     pub fn parse_from_args(mut args: impl Iterator<Item = String>) -> AocResult<Self> {
         let day = Self::get_next_integer(&mut args, "day")?;
-        if day <= 0 || day > 31 {
-            return Err(AocError::new("day must be between 1 and 31"));
+        if day == 0 || day > 31 {
+            return Err(AocError::invalid_args("day must be between 1 and 31"));
         }
         let part = SolutionPart::from_str(&Self::get_next_string(&mut args, "part")?)?;
-        let filename = Self::get_next_string_optional(&mut args);
-        Ok(ProgramArgs::new(day, part, filename))
+        let mut filename = None;
+        let mut input_dir = config().input_dir.clone();
+        let mut download = true;
+        let mut format = config().format;
+        let mut check = false;
+        let mut render = None;
+        let mut day_args = DayArgs::default();
+        let mut example = false;
+        let mut input_set = None;
+        while let Some(arg) = Self::get_next_string_optional(&mut args) {
+            match arg.as_str() {
+                "--" => {
+                    day_args = DayArgs::parse(&mut args)?;
+                    break;
+                }
+                "--example" => example = true,
+                "--check" => check = true,
+                "--render" => render = Some(Self::get_next_string(&mut args, "render")?),
+                "--format" => format = Self::get_next_string(&mut args, "format")?.parse()?,
+                "--input-dir" => {
+                    input_dir = Self::get_next_string(&mut args, "input directory")?;
+                    download = false;
+                }
+                "--input-set" => {
+                    input_set = Some(parse_input_set(&Self::get_next_string(
+                        &mut args,
+                        "input set",
+                    )?)?)
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(AocError::invalid_args(format!("unknown option: {}", arg)))
+                }
+                _ if filename.is_none() => filename = Some(arg),
+                _ => {
+                    return Err(AocError::invalid_args(format!(
+                        "unexpected argument: {}",
+                        arg
+                    )))
+                }
+            }
+        }
+        // Examples are read from the examples directory, `<day>.txt` unless
+        // another one is named, and solved with the example's constants.
+        if example {
+            input_dir = EXAMPLES_DIR.to_owned();
+            filename = filename.or_else(|| Some(format!("{}.txt", day)));
+            day_args = day_args.with_profile(Profile::Example);
+        } else {
+            day_args = day_args.with_profile(Profile::Real);
+        }
+        // Input sets are other people's inputs, so they name the file to read
+        // themselves.
+        if let Some(input_set) = &input_set {
+            if example || filename.is_some() {
+                return Err(AocError::invalid_args(
+                    "--input-set cannot be used with a filename or --example",
+                ));
+            }
+            filename = Some(input_set_filename(day, input_set));
+        }
+        Ok(ProgramArgs {
+            day,
+            part,
+            filename,
+            input_set,
+            input_dir,
+            download,
+            format,
+            check,
+            render,
+            day_args,
+        })
+    }
+
+    pub fn usage(program_name: &str) -> String {
+        format!(
+            "{} [1-31] [A|B|both] [filename] [--input-dir path] [--input-set name] [--format text|json|markdown|csv] [--example] [--check] [--render file.svg] [-- --key value ...]",
+            program_name
+        )
+    }
+}
+
+pub struct AllArgs {
+    parallel: bool,
+    format: OutputFormat,
+    report: Option<String>,
+    append_csv: Option<String>,
+    check: bool,
+    color: bool,
+    slow_threshold: Duration,
+    timeout: Option<Duration>,
+    cache: CacheMode,
+    progress: bool,
+}
+
+// Input set names are used in paths and in the headers of the answers
+// manifest, so they are kept to letters, digits, `-`, and `_`.
+fn parse_input_set(name: &str) -> AocResult<String> {
+    match !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        true => Ok(name.to_owned()),
+        false => Err(AocError::invalid_args(format!(
+            "invalid input set name: {}",
+            name
+        ))),
+    }
+}
+
+// Reads `--cached` or `--no-cache`, which cannot both be given.
+fn parse_cache_flag(arg: &str, cache: &mut Option<CacheMode>) -> AocResult<bool> {
+    let mode = match arg {
+        "--cached" => CacheMode::Reuse,
+        "--no-cache" => CacheMode::Off,
+        _ => return Ok(false),
+    };
+    match cache.replace(mode) {
+        Some(previous) if previous != mode => Err(AocError::invalid_args(
+            "--cached and --no-cache cannot be used together",
+        )),
+        _ => Ok(true),
+    }
+}
+
+impl AllArgs {
+    pub fn new(parallel: bool, format: OutputFormat, report: Option<String>, check: bool) -> Self {
+        AllArgs {
+            parallel,
+            format,
+            report,
+            append_csv: None,
+            check,
+            color: env::var_os("NO_COLOR").is_none(),
+            slow_threshold: Duration::from_millis(config().slow_threshold_ms),
+            timeout: None,
+            cache: CacheMode::Record,
+            progress: false,
+        }
+    }
+
+    pub fn parallel(&self) -> bool {
+        self.parallel
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    // Path to write a Markdown table of every solution to.
+    pub fn report(&self) -> Option<&str> {
+        self.report.as_deref()
+    }
+
+    // Path to a CSV file to append the time of every solution to.
+    pub fn append_csv(&self) -> Option<&str> {
+        self.append_csv.as_deref()
+    }
+
+    pub fn check(&self) -> bool {
+        self.check
+    }
+
+    // Whether the text table may use ANSI colors.
+    pub fn color(&self) -> bool {
+        self.color
+    }
+
+    // Solvers taking at least this long are highlighted in the text table.
+    pub fn slow_threshold(&self) -> Duration {
+        self.slow_threshold
+    }
+
+    // How long each solver is given before it is reported as timed out.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn cache(&self) -> CacheMode {
+        self.cache
+    }
+
+    // Whether to show live progress bars, which only works in a terminal and
+    // would get in the way of JSON output or logging.
+    pub fn progress(&self) -> bool {
+        self.progress && self.format == OutputFormat::Text && !log::enabled(Level::Debug)
+    }
+
+    pub fn parse_from_args(mut args: impl Iterator<Item = String>) -> AocResult<Self> {
+        let mut parallel = false;
+        let mut format = config().format;
+        let mut report = None;
+        let mut append_csv = None;
+        let mut check = false;
+        let mut no_color = false;
+        let mut no_progress = false;
+        let mut slow_ms = None;
+        let mut timeout = None;
+        let mut cache = None;
+        while let Some(arg) = args.next() {
+            if parse_cache_flag(&arg, &mut cache)? {
+                continue;
+            }
+            match arg.as_str() {
+                "--parallel" => parallel = true,
+                "--check" => check = true,
+                "--no-color" => no_color = true,
+                "--no-progress" => no_progress = true,
+                "--slow-ms" => {
+                    slow_ms = Some(
+                        ProgramArgs::get_next_string(&mut args, "slow-ms")?
+                            .parse::<u64>()
+                            .into_aoc_result_msg("slow-ms must be a non-negative integer")?,
+                    )
+                }
+                "--timeout" => {
+                    timeout = Some(
+                        ProgramArgs::get_next_string(&mut args, "timeout")?
+                            .parse::<f64>()
+                            .ok()
+                            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                            .filter(|timeout| !timeout.is_zero())
+                            .ok_or_else(|| {
+                                AocError::invalid_args(
+                                    "timeout must be a positive number of seconds",
+                                )
+                            })?,
+                    )
+                }
+                "--format" => {
+                    format = ProgramArgs::get_next_string(&mut args, "format")?.parse()?
+                }
+                "--report" => report = Some(ProgramArgs::get_next_string(&mut args, "report")?),
+                "--append-csv" => {
+                    append_csv = Some(ProgramArgs::get_next_string(&mut args, "append-csv")?)
+                }
+                _ => return Err(AocError::invalid_args(format!("unknown option: {}", arg))),
+            }
+        }
+        let mut all_args = AllArgs::new(parallel, format, report, check);
+        all_args.append_csv = append_csv;
+        all_args.color &= !no_color;
+        if let Some(slow_ms) = slow_ms {
+            all_args.slow_threshold = Duration::from_millis(slow_ms);
+        }
+        all_args.timeout = timeout;
+        all_args.cache = cache.unwrap_or(CacheMode::Record);
+        all_args.progress = !no_progress && io::stderr().is_terminal();
+        Ok(all_args)
+    }
+
+    pub fn usage(program_name: &str) -> String {
+        format!(
+            "{} all [--parallel] [--format text|json|markdown|csv] [--report file.md] [--append-csv timings.csv] [--check] [--no-color] [--no-progress] [--slow-ms ms] [--timeout secs] [--cached|--no-cache]",
+            program_name
+        )
+    }
+}
+
+pub struct VerifyArgs {
+    manifest: Option<String>,
+    input_set: Option<String>,
+    cache: CacheMode,
+    format: OutputFormat,
+}
+
+impl VerifyArgs {
+    // The manifest of expected answers to use instead of the default one.
+    pub fn manifest(&self) -> Option<&str> {
+        self.manifest.as_deref()
+    }
+
+    // The only input set to verify, instead of every day's own input and every
+    // input set.
+    pub fn input_set(&self) -> Option<&str> {
+        self.input_set.as_deref()
+    }
+
+    pub fn cache(&self) -> CacheMode {
+        self.cache
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    pub fn parse_from_args(mut args: impl Iterator<Item = String>) -> AocResult<Self> {
+        let mut manifest = None;
+        let mut input_set = None;
+        let mut cache = None;
+        let mut format = config().format;
+        while let Some(arg) = args.next() {
+            if parse_cache_flag(&arg, &mut cache)? {
+                continue;
+            }
+            match arg.as_str() {
+                "--format" => {
+                    format = ProgramArgs::get_next_string(&mut args, "format")?.parse()?
+                }
+                "--input-set" => {
+                    input_set = Some(parse_input_set(&ProgramArgs::get_next_string(
+                        &mut args,
+                        "input set",
+                    )?)?)
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(AocError::invalid_args(format!("unknown option: {}", arg)))
+                }
+                _ if manifest.is_none() => manifest = Some(arg),
+                _ => {
+                    return Err(AocError::invalid_args(format!(
+                        "unexpected argument: {}",
+                        arg
+                    )))
+                }
+            }
+        }
+        Ok(VerifyArgs {
+            manifest,
+            input_set,
+            cache: cache.unwrap_or(CacheMode::Record),
+            format,
+        })
     }
 
     pub fn usage(program_name: &str) -> String {
-        format!("{} [1-31] [A|B]", program_name)
+        format!(
+            "{} verify [answers.toml] [--input-set name] [--format text|json|markdown|csv] [--cached|--no-cache]",
+            program_name
+        )
+    }
+}
+
+pub struct InputArgs {
+    day: u8,
+    force: bool,
+}
+
+impl InputArgs {
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+
+    pub fn force(&self) -> bool {
+        self.force
+    }
+
+    pub fn parse_from_args(mut args: impl Iterator<Item = String>) -> AocResult<Self> {
+        let day = ProgramArgs::get_next_integer(&mut args, "day")?;
+        if day == 0 || day > 25 {
+            return Err(AocError::invalid_args("day must be between 1 and 25"));
+        }
+        let mut force = false;
+        for arg in args {
+            match arg.as_str() {
+                "--force" => force = true,
+                _ => return Err(AocError::invalid_args(format!("unknown option: {}", arg))),
+            }
+        }
+        Ok(InputArgs { day, force })
+    }
+
+    pub fn usage(program_name: &str) -> String {
+        format!("{} input [1-25] [--force]", program_name)
+    }
+}
+
+pub struct BenchArgs {
+    program_args: ProgramArgs,
+    warmup: usize,
+    iterations: usize,
+    alternates: bool,
+    format: OutputFormat,
+}
+
+impl BenchArgs {
+    const DEFAULT_WARMUP: usize = 3;
+    const DEFAULT_ITERATIONS: usize = 20;
+
+    pub fn program_args(&self) -> &ProgramArgs {
+        &self.program_args
+    }
+
+    pub fn warmup(&self) -> usize {
+        self.warmup
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    // Whether to also time every alternate implementation of the solver.
+    pub fn alternates(&self) -> bool {
+        self.alternates
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    fn get_next_count(args: &mut impl Iterator<Item = String>, name: &str) -> AocResult<usize> {
+        ProgramArgs::get_next_string(args, name)?
+            .parse::<usize>()
+            .into_aoc_result_msg(&format!("{} must be a non-negative integer", name))
+    }
+
+    pub fn parse_from_args(mut args: impl Iterator<Item = String>) -> AocResult<Self> {
+        let day = ProgramArgs::get_next_integer(&mut args, "day")?;
+        if day == 0 || day > 31 {
+            return Err(AocError::invalid_args("day must be between 1 and 31"));
+        }
+        let part = SolutionPart::from_str(&ProgramArgs::get_next_string(&mut args, "part")?)?;
+        let mut filename = None;
+        let mut warmup = Self::DEFAULT_WARMUP;
+        let mut iterations = Self::DEFAULT_ITERATIONS;
+        let mut alternates = false;
+        let mut format = config().format;
+        let mut day_args = DayArgs::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    day_args = DayArgs::parse(&mut args)?;
+                    break;
+                }
+                "--warmup" => warmup = Self::get_next_count(&mut args, "warmup")?,
+                "--iterations" => iterations = Self::get_next_count(&mut args, "iterations")?,
+                "--alternates" => alternates = true,
+                "--format" => {
+                    format = ProgramArgs::get_next_string(&mut args, "format")?.parse()?
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(AocError::invalid_args(format!("unknown option: {}", arg)))
+                }
+                _ if filename.is_none() => filename = Some(arg),
+                _ => {
+                    return Err(AocError::invalid_args(format!(
+                        "unexpected argument: {}",
+                        arg
+                    )))
+                }
+            }
+        }
+        if iterations == 0 {
+            return Err(AocError::invalid_args("iterations must be at least 1"));
+        }
+        Ok(BenchArgs {
+            program_args: ProgramArgs::new(day, part, filename).with_day_args(day_args),
+            warmup,
+            iterations,
+            alternates,
+            format,
+        })
+    }
+
+    pub fn usage(program_name: &str) -> String {
+        format!(
+            "{} bench [1-31] [A|B] [filename] [--warmup N] [--iterations N] [--alternates] [--format text|json|markdown|csv] [-- --key value ...]",
+            program_name
+        )
+    }
+}
+
+pub struct VizArgs {
+    program_args: ProgramArgs,
+    delay: Duration,
+    every: usize,
+}
+
+impl VizArgs {
+    const DEFAULT_DELAY_MS: u64 = 50;
+
+    pub fn program_args(&self) -> &ProgramArgs {
+        &self.program_args
+    }
+
+    // How long each frame is shown for.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    // Only every this many frames are drawn.
+    pub fn every(&self) -> usize {
+        self.every
+    }
+
+    pub fn parse_from_args(mut args: impl Iterator<Item = String>) -> AocResult<Self> {
+        let day = ProgramArgs::get_next_integer(&mut args, "day")?;
+        if day == 0 || day > 25 {
+            return Err(AocError::invalid_args("day must be between 1 and 25"));
+        }
+        let part = SolutionPart::from_str(&ProgramArgs::get_next_string(&mut args, "part")?)?;
+        if part == SolutionPart::Both {
+            return Err(AocError::invalid_args(
+                "only one part can be visualized at a time",
+            ));
+        }
+        let mut filename = None;
+        let mut delay = Duration::from_millis(Self::DEFAULT_DELAY_MS);
+        let mut every = 1;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--delay-ms" => {
+                    delay = Duration::from_millis(
+                        BenchArgs::get_next_count(&mut args, "delay-ms")? as u64
+                    )
+                }
+                "--every" => every = BenchArgs::get_next_count(&mut args, "every")?,
+                _ if arg.starts_with("--") => {
+                    return Err(AocError::invalid_args(format!("unknown option: {}", arg)))
+                }
+                _ if filename.is_none() => filename = Some(arg),
+                _ => {
+                    return Err(AocError::invalid_args(format!(
+                        "unexpected argument: {}",
+                        arg
+                    )))
+                }
+            }
+        }
+        if every == 0 {
+            return Err(AocError::invalid_args("every must be at least 1"));
+        }
+        Ok(VizArgs {
+            program_args: ProgramArgs::new(day, part, filename),
+            delay,
+            every,
+        })
+    }
+
+    pub fn usage(program_name: &str) -> String {
+        format!(
+            "{} viz [1-25] [A|B] [filename] [--delay-ms N] [--every N]",
+            program_name
+        )
+    }
+}
+
+pub struct WatchArgs {
+    program_args: ProgramArgs,
+    interval: Duration,
+    cargo: bool,
+    // The arguments the part was parsed from, passed on as they were given
+    // when cargo-watch reruns the program.
+    solve_args: Vec<String>,
+}
+
+impl WatchArgs {
+    const DEFAULT_INTERVAL_MS: u64 = 500;
+
+    pub fn program_args(&self) -> &ProgramArgs {
+        &self.program_args
+    }
+
+    // How often the input is checked for changes.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    // Whether to hand off to cargo-watch, which also rebuilds on source changes.
+    pub fn cargo(&self) -> bool {
+        self.cargo
+    }
+
+    // The arguments to solve the same part again with.
+    pub fn solve_args(&self) -> &[String] {
+        &self.solve_args
+    }
+
+    // Takes out the options only watching understands and leaves the rest to
+    // be parsed like any other solution.
+    pub fn parse_from_args(mut args: impl Iterator<Item = String>) -> AocResult<Self> {
+        let mut interval = Duration::from_millis(Self::DEFAULT_INTERVAL_MS);
+        let mut cargo = false;
+        let mut solve_args = Vec::new();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--" => {
+                    solve_args.push(arg);
+                    solve_args.extend(args.by_ref());
+                }
+                "--interval-ms" => {
+                    interval = Duration::from_millis(BenchArgs::get_next_count(
+                        &mut args,
+                        "interval-ms",
+                    )? as u64)
+                }
+                "--cargo" => cargo = true,
+                _ => solve_args.push(arg),
+            }
+        }
+        if interval.is_zero() {
+            return Err(AocError::invalid_args("interval-ms must be at least 1"));
+        }
+        let program_args = ProgramArgs::parse_from_args(solve_args.iter().cloned())?;
+        if program_args.part() == SolutionPart::Both {
+            return Err(AocError::invalid_args(
+                "only one part can be watched at a time",
+            ));
+        }
+        Ok(WatchArgs {
+            program_args,
+            interval,
+            cargo,
+            solve_args,
+        })
+    }
+
+    pub fn usage(program_name: &str) -> String {
+        format!(
+            "{} watch [1-31] [A|B] [filename] [--input-dir path] [--input-set name] [--example] [--interval-ms N] [--cargo] [-- --key value ...]",
+            program_name
+        )
+    }
+}
+
+// A command given as the first argument. Anything that is not a command is
+// taken as a day to solve.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    Solve,
+    All,
+    Verify,
+    Examples,
+    List,
+    Input,
+    Submit,
+    Bench,
+    Viz,
+    Watch,
+    Help,
+}
+
+impl Command {
+    // Every command with a name, what it is called, and what it does, in the
+    // order they are listed in the help.
+    const NAMED: [(Self, &'static str, &'static str); 10] = [
+        (
+            Self::All,
+            "all",
+            "solve every day and print a table of the answers",
+        ),
+        (
+            Self::Verify,
+            "verify",
+            "check every answer against the recorded ones",
+        ),
+        (
+            Self::Examples,
+            "examples",
+            "check every day against the puzzle examples",
+        ),
+        (
+            Self::List,
+            "list",
+            "list the registered days and their inputs",
+        ),
+        (
+            Self::Input,
+            "input",
+            "download a day's input (also `fetch`)",
+        ),
+        (Self::Submit, "submit", "solve a part and submit the answer"),
+        (Self::Bench, "bench", "time a solver over many runs"),
+        (
+            Self::Viz,
+            "viz",
+            "play an animation of a puzzle in the terminal",
+        ),
+        (
+            Self::Watch,
+            "watch",
+            "solve a part again whenever its input changes",
+        ),
+        (
+            Self::Help,
+            "help",
+            "print this help (also `--help` or `-h`)",
+        ),
+    ];
+
+    // Takes the command off the front of the arguments, if there is one, leaving
+    // only the command's own arguments.
+    pub fn parse(args: &mut Peekable<impl Iterator<Item = String>>) -> Self {
+        let command = match args.peek().map(|arg| arg.as_str()) {
+            Some("fetch") => Self::Input,
+            Some("--help" | "-h") => Self::Help,
+            Some(name) => Self::NAMED
+                .iter()
+                .find(|(_, named, _)| *named == name)
+                .map_or(Self::Solve, |(command, _, _)| *command),
+            None => Self::Solve,
+        };
+        if command != Self::Solve {
+            args.next();
+        }
+        command
+    }
+
+    pub fn usage(self, program_name: &str) -> String {
+        match self {
+            Self::Solve => ProgramArgs::usage(program_name),
+            Self::All => AllArgs::usage(program_name),
+            Self::Verify => VerifyArgs::usage(program_name),
+            Self::Examples => format!("{} examples", program_name),
+            Self::List => format!("{} list", program_name),
+            Self::Input => InputArgs::usage(program_name),
+            Self::Submit => format!("{} submit [1-25] [A|B]", program_name),
+            Self::Bench => BenchArgs::usage(program_name),
+            Self::Viz => VizArgs::usage(program_name),
+            Self::Watch => WatchArgs::usage(program_name),
+            Self::Help => format!("{} help", program_name),
+        }
+    }
+}
+
+// Lists every command, and how to solve a single day.
+pub fn help(program_name: &str) -> String {
+    let mut help = format!(
+        "Usage: {}\n\nCommands:\n",
+        Command::Solve.usage(program_name)
+    );
+    for (_, name, summary) in Command::NAMED {
+        help += &format!("  {:<10} {}\n", name, summary);
     }
+    help += &format!(
+        "\nRun `{} <command> --help` for a command's options. `--trace` turns on trace logging for any command.\n",
+        program_name
+    );
+    help += "\nExit status: 0 on success, 1 if something failed, 2 for bad arguments, 3 for wrong answers.\n";
+    help
 }
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+pub struct BenchStats {
+    pub min: Duration,
+    pub max: Duration,
+    pub mean: Duration,
+    pub median: Duration,
+    pub stddev: Duration,
+}
+
+impl BenchStats {
+    // Returns `None` if there are no samples to summarize.
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let n = sorted.len();
+        let min = *sorted.first()?;
+        let max = *sorted.last()?;
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        } else {
+            sorted[n / 2]
+        };
+        let mean = sorted.iter().sum::<Duration>() / n as u32;
+        // Sample standard deviation, since the runs are a sample of all
+        // possible runs.
+        let variance = if n > 1 {
+            sorted
+                .iter()
+                .map(|sample| (sample.as_secs_f64() - mean.as_secs_f64()).powi(2))
+                .sum::<f64>()
+                / (n - 1) as f64
+        } else {
+            0.0
+        };
+        Some(Self {
+            min,
+            max,
+            mean,
+            median,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+
+    pub fn print_table(&self) {
+        let rows = [
+            ("min", self.min),
+            ("max", self.max),
+            ("mean", self.mean),
+            ("median", self.median),
+            ("stddev", self.stddev),
+        ];
+        println!("{:<8} {:>12}", "Stat", "Time");
+        for (name, time) in rows {
+            println!("{:<8} {:>9} us", name, time.as_micros());
+        }
+    }
+
+    // Prints the stats of several implementations side by side, with how many
+    // times slower each one's median is than the first's.
+    pub fn print_comparison(implementations: &[(&str, BenchStats)]) {
+        println!(
+            "{:<14} {:>12} {:>12} {:>12} {:>12} {:>8}",
+            "Implementation", "Min", "Median", "Mean", "Stddev", "Ratio"
+        );
+        let baseline = implementations
+            .first()
+            .map_or(0.0, |(_, stats)| stats.median.as_secs_f64());
+        for (name, stats) in implementations {
+            println!(
+                "{:<14} {:>9} us {:>9} us {:>9} us {:>9} us {:>7.2}x",
+                name,
+                stats.min.as_micros(),
+                stats.median.as_micros(),
+                stats.mean.as_micros(),
+                stats.stddev.as_micros(),
+                stats.median.as_secs_f64() / baseline
+            );
+        }
+    }
+}
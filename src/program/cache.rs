@@ -0,0 +1,263 @@
+use crate::{
+    common::{AocError, AocResult, AocSolution, IntoAocResult, SolutionPart},
+    days::{SolveRecord, TimingBreakdown},
+};
+use num::BigInt;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CacheMode {
+    // Solve everything, recording the answers for later.
+    Record,
+    // Reuse recorded answers for inputs that have not changed.
+    Reuse,
+    // Neither read nor write the cache.
+    Off,
+}
+
+// Answers from earlier runs, so that tooling built on top of every solver can
+// be iterated on without waiting for the solvers each time.
+//
+// Each answer is a file named after its day, part, and the BLAKE3 hash of its
+// input, holding `key = value` lines:
+//
+//     type = int
+//     answer = 1737
+//     parse_us = 120
+//     solve_us = 1800
+//
+// Only the answer can contain characters that need escaping, and only
+// backslashes and line breaks are escaped.
+pub struct SolutionCache {
+    dir: PathBuf,
+    mode: CacheMode,
+}
+
+impl SolutionCache {
+    pub fn new(dir: impl Into<PathBuf>, mode: CacheMode) -> Self {
+        Self {
+            dir: dir.into(),
+            mode,
+        }
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        self.mode
+    }
+
+    fn path(&self, day: u8, part: SolutionPart, hash: &str) -> PathBuf {
+        self.dir.join(format!("{:02}{}-{}", day, part, hash))
+    }
+
+    // The recorded solution for the input with the given hash, if answers are being reused and
+    // there is one. Entries that cannot be read are treated as missing, so they
+    // are simply solved and recorded again.
+    pub fn get(&self, day: u8, part: SolutionPart, hash: &str) -> Option<SolveRecord> {
+        if self.mode != CacheMode::Reuse {
+            return None;
+        }
+        let entry = fs::read_to_string(self.path(day, part, hash)).ok()?;
+        let (answer, timings, peak_memory) = parse_entry(&entry).ok()?;
+        let mut record = SolveRecord::new(day, part, answer, timings, peak_memory);
+        record.cached = true;
+        Some(record)
+    }
+
+    // Records a solution, unless the cache is off or the solver failed.
+    pub fn put(
+        &self,
+        day: u8,
+        part: SolutionPart,
+        hash: &str,
+        record: &SolveRecord,
+    ) -> AocResult<()> {
+        let answer = match &record.answer {
+            Ok(answer) if self.mode != CacheMode::Off && !record.cached => answer,
+            _ => return Ok(()),
+        };
+        fs::create_dir_all(&self.dir)
+            .into_aoc_result_msg(&format!("failed to create {}", self.dir.display()))?;
+        let path = self.path(day, part, hash);
+        fs::write(&path, format_entry(answer, record))
+            .into_aoc_result_msg(&format!("failed to write {}", path.display()))
+    }
+}
+
+// The BLAKE3 hash of an input, which names its answers in the cache.
+pub fn input_hash(input: &[u8]) -> String {
+    blake3::hash(input).to_hex().to_string()
+}
+
+// The same hash of an input file, read a piece at a time rather than whole.
+pub fn file_hash(path: &Path) -> AocResult<String> {
+    let file =
+        fs::File::open(path).into_aoc_result_msg(&format!("failed to read {}", path.display()))?;
+    Ok(blake3::Hasher::new()
+        .update_reader(file)
+        .into_aoc_result_msg(&format!("failed to read {}", path.display()))?
+        .finalize()
+        .to_hex()
+        .to_string())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn format_entry(answer: &AocSolution, record: &SolveRecord) -> String {
+    let (kind, answer) = match answer {
+        AocSolution::Int(n) => ("int", n.to_string()),
+        AocSolution::SInt(n) => ("sint", n.to_string()),
+        AocSolution::BigInt(n) => ("bigint", n.to_string()),
+        AocSolution::Str(s) => ("str", escape(s)),
+    };
+    let mut entry = format!("type = {kind}\nanswer = {answer}\n");
+    let timings = &record.timings;
+    for (key, time) in [("io_us", timings.io), ("parse_us", timings.parse)] {
+        if let Some(time) = time {
+            entry += &format!("{key} = {}\n", time.as_micros());
+        }
+    }
+    entry += &format!("solve_us = {}\n", timings.solve.as_micros());
+    if let Some(bytes) = record.peak_memory {
+        entry += &format!("peak_bytes = {bytes}\n");
+    }
+    entry
+}
+
+// Reads an entry's answer, its timings, and its peak heap usage.
+fn parse_entry(entry: &str) -> AocResult<(AocSolution, TimingBreakdown, Option<usize>)> {
+    let (mut kind, mut answer) = (None, None);
+    let mut timing = TimingBreakdown::default();
+    let mut peak_memory = None;
+    for line in entry.lines() {
+        let (key, value) = line
+            .split_once(" = ")
+            .into_aoc_result_msg("expected key = value")?;
+        let micros = || {
+            value
+                .parse::<u64>()
+                .map(Duration::from_micros)
+                .into_aoc_result_msg("invalid time")
+        };
+        match key {
+            "type" => kind = Some(value),
+            "answer" => answer = Some(value),
+            "io_us" => timing.io = Some(micros()?),
+            "parse_us" => timing.parse = Some(micros()?),
+            "solve_us" => timing.solve = micros()?,
+            "peak_bytes" => {
+                peak_memory = Some(value.parse().into_aoc_result_msg("invalid peak bytes")?)
+            }
+            _ => return Err(AocError::parse(format!("unknown cache key: {key}"))),
+        }
+    }
+    let answer = answer.into_aoc_result_msg("cache entry has no answer")?;
+    let solution = match kind.into_aoc_result_msg("cache entry has no type")? {
+        "int" => AocSolution::Int(answer.parse().into_aoc_result()?),
+        "sint" => AocSolution::SInt(answer.parse().into_aoc_result()?),
+        "bigint" => AocSolution::BigInt(answer.parse::<BigInt>().into_aoc_result()?),
+        "str" => AocSolution::Str(unescape(answer)),
+        kind => return Err(AocError::parse(format!("unknown answer type: {kind}"))),
+    };
+    Ok((solution, timing, peak_memory))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_entries() {
+        let timing = TimingBreakdown {
+            io: Some(Duration::from_micros(31)),
+            parse: None,
+            solve: Duration::from_micros(688),
+        };
+        for answer in [
+            AocSolution::Int(27551),
+            AocSolution::SInt(-3),
+            AocSolution::BigInt(BigInt::from(u64::MAX) * 7),
+            AocSolution::Str("a\\b\nc = d".to_owned()),
+        ] {
+            let record = SolveRecord::new(1, SolutionPart::A, answer.clone(), timing, Some(2048));
+            let (parsed, timings, peak_memory) =
+                parse_entry(&format_entry(&answer, &record)).unwrap();
+            assert_eq!(parsed.to_string(), answer.to_string());
+            assert_eq!(timings.solver_time(), timing.solver_time());
+            assert_eq!(timings.io, timing.io);
+            assert_eq!(peak_memory, Some(2048));
+        }
+    }
+
+    #[test]
+    fn only_reuses_answers_when_asked() {
+        let dir = std::env::temp_dir().join(format!("aoc-cache-test-{}", std::process::id()));
+        let solution = SolveRecord::new(
+            1,
+            SolutionPart::A,
+            AocSolution::Int(7),
+            TimingBreakdown::default(),
+            None,
+        );
+        let cache = |mode| SolutionCache::new(&dir, mode);
+        cache(CacheMode::Off)
+            .put(1, SolutionPart::A, &input_hash(b"1"), &solution)
+            .unwrap();
+        assert!(cache(CacheMode::Reuse)
+            .get(1, SolutionPart::A, &input_hash(b"1"))
+            .is_none());
+        cache(CacheMode::Record)
+            .put(1, SolutionPart::A, &input_hash(b"1"), &solution)
+            .unwrap();
+        assert!(cache(CacheMode::Record)
+            .get(1, SolutionPart::A, &input_hash(b"1"))
+            .is_none());
+        let cached = cache(CacheMode::Reuse)
+            .get(1, SolutionPart::A, &input_hash(b"1"))
+            .unwrap();
+        assert!(cached.cached);
+        assert_eq!(cached.answer.unwrap(), AocSolution::Int(7));
+        assert!(cache(CacheMode::Reuse)
+            .get(1, SolutionPart::A, &input_hash(b"2"))
+            .is_none());
+        assert!(cache(CacheMode::Reuse)
+            .get(1, SolutionPart::B, &input_hash(b"1"))
+            .is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hashes_files_like_their_contents() {
+        let path = std::env::temp_dir().join(format!("aoc-hash-test-{}.txt", std::process::id()));
+        let input = "1\n2\n3\n".repeat(10000);
+        fs::write(&path, &input).unwrap();
+        assert_eq!(file_hash(&path).unwrap(), input_hash(input.as_bytes()));
+        fs::remove_file(&path).unwrap();
+        assert!(file_hash(&path).is_err());
+    }
+}
@@ -0,0 +1,440 @@
+use crate::common::{log, AocError, SolutionPart};
+use crate::days::{bench, render, solve, solve_all, solve_both, visualize, SolveRecord};
+use crate::program::{
+    append_timings_csv, cargo_watch, config, download_input, format_records, help, input_path,
+    list_days, load_config, record_json, solutions_markdown, submit_answer, verify_all,
+    verify_examples, verify_table, write_markdown_report, AllArgs, BenchArgs, BenchStats, Command,
+    ExitStatus, ExpectedAnswers, FileWatcher, InputArgs, OutputFormat, ProgramArgs, RunHistory,
+    SubmitOutcome, SubmitResponse, TableReport, VerifyArgs, VerifySummary, VizArgs, WatchArgs,
+};
+use std::{fmt::Display, path::Path, process::ExitCode};
+
+// Prints an error, returning the status to exit with because of it.
+fn fail(err: impl Display, status: ExitStatus) -> ExitStatus {
+    eprintln!("{}", err);
+    status
+}
+
+// Prints an error from solving, which may be in the options given to the day.
+fn solver_failed(err: AocError) -> ExitStatus {
+    let status = if err.is_invalid_args() {
+        ExitStatus::BadArgs
+    } else {
+        ExitStatus::Failure
+    };
+    fail(err, status)
+}
+
+// Prints an error in the arguments along with how the command is used.
+fn bad_args(err: impl Display, usage: String) -> ExitStatus {
+    eprintln!("{}", err);
+    eprintln!("{}", usage);
+    ExitStatus::BadArgs
+}
+
+fn run_all(program_name: &str, args: &mut impl Iterator<Item = String>) -> ExitStatus {
+    let args = match AllArgs::parse_from_args(args) {
+        Err(err) => return bad_args(err, AllArgs::usage(program_name)),
+        Ok(args) => args,
+    };
+    let solutions = match solve_all(&args) {
+        Err(err) => return fail(err, ExitStatus::Failure),
+        Ok(solutions) => solutions,
+    };
+    match args.format() {
+        OutputFormat::Text => {
+            let report = TableReport::new(args.color(), args.slow_threshold());
+            print!("{}", report.render(&solutions));
+        }
+        // The Markdown table of every solver ends with the totals.
+        OutputFormat::Markdown => print!("{}", solutions_markdown(&solutions)),
+        format => print_records(format, &solutions.records),
+    }
+    if let Some(path) = args.report() {
+        if let Err(err) = write_markdown_report(path, &solutions) {
+            return fail(err, ExitStatus::Failure);
+        }
+    }
+    if let Some(path) = args.append_csv() {
+        if let Err(err) = append_timings_csv(path, &solutions.records) {
+            return fail(err, ExitStatus::Failure);
+        }
+    }
+    // Every result is reported before failing, so one missing input does not
+    // hide the rest.
+    if solutions.failures() > 0 {
+        ExitStatus::Failure
+    } else {
+        ExitStatus::Success
+    }
+}
+
+fn run_verify(program_name: &str, args: &mut impl Iterator<Item = String>) -> ExitStatus {
+    let args = match VerifyArgs::parse_from_args(args) {
+        Err(err) => return bad_args(err, VerifyArgs::usage(program_name)),
+        Ok(args) => args,
+    };
+    let answers = match ExpectedAnswers::load_with_config(args.manifest()) {
+        Err(err) => return fail(err, ExitStatus::Failure),
+        Ok(answers) => answers,
+    };
+    let records = match verify_all(&answers, args.cache(), args.input_set()) {
+        Err(err) => return fail(err, ExitStatus::Failure),
+        Ok(records) => records,
+    };
+    report_verified(args.format(), &records)
+}
+
+// Prints checked records, failing if any answer was wrong or missing.
+fn report_verified(format: OutputFormat, records: &[SolveRecord]) -> ExitStatus {
+    let summary = VerifySummary::from_records(records);
+    match format {
+        OutputFormat::Text => {
+            print!("{}", verify_table(records));
+            println!(
+                "{} passed, {} failed, {} unchecked",
+                summary.passed, summary.failed, summary.unchecked
+            );
+        }
+        format => print_records(format, records),
+    }
+    if summary.failed > 0 {
+        ExitStatus::WrongAnswer
+    } else {
+        ExitStatus::Success
+    }
+}
+
+// Fails if any example fails, so that the examples can be checked by scripts
+// and tests.
+fn run_examples() -> ExitStatus {
+    report_verified(OutputFormat::Text, &verify_examples())
+}
+
+fn run_list() -> ExitStatus {
+    match list_days() {
+        Err(err) => fail(err, ExitStatus::Failure),
+        Ok(()) => ExitStatus::Success,
+    }
+}
+
+fn run_input(program_name: &str, args: &mut impl Iterator<Item = String>) -> ExitStatus {
+    let args = match InputArgs::parse_from_args(args) {
+        Err(err) => return bad_args(err, InputArgs::usage(program_name)),
+        Ok(args) => args,
+    };
+    let input_dir = &config().input_dir;
+    let path = input_path(input_dir, args.day());
+    if !args.force() && Path::new(&path).exists() {
+        println!("{} already exists, pass --force to download it again", path);
+        return ExitStatus::Success;
+    }
+    match download_input(input_dir, args.day()) {
+        Err(err) => fail(err, ExitStatus::Failure),
+        Ok(input) => {
+            println!("Saved {} ({} bytes)", path, input.len());
+            ExitStatus::Success
+        }
+    }
+}
+
+fn run_submit(program_name: &str, args: &mut impl Iterator<Item = String>) -> ExitStatus {
+    let args = match ProgramArgs::parse_from_args(args) {
+        Err(err) => return bad_args(err, Command::Submit.usage(program_name)),
+        Ok(args) => args,
+    };
+    if args.filename().is_some() || args.input_dir() != config().input_dir {
+        return bad_args(
+            "answers can only be submitted for the puzzle input",
+            Command::Submit.usage(program_name),
+        );
+    }
+    if !args.day_args().is_empty() {
+        return bad_args(
+            "answers can only be submitted with the puzzle's own constants",
+            Command::Submit.usage(program_name),
+        );
+    }
+    let answer = match solve(&args).and_then(|record| record.answer) {
+        Err(err) => return solver_failed(err),
+        Ok(answer) => answer,
+    };
+    println!(
+        "Submitting {} for day {}, part {}",
+        answer,
+        args.day(),
+        args.part()
+    );
+    let response = match submit_answer(args.day(), args.part(), &answer) {
+        Err(err) => return fail(err, ExitStatus::Failure),
+        Ok(response) => response,
+    };
+    match response {
+        SubmitResponse::Recorded(SubmitOutcome::Right) => println!("That's the right answer!"),
+        SubmitResponse::Recorded(SubmitOutcome::Wrong) => println!("That's not the right answer."),
+        SubmitResponse::Recorded(SubmitOutcome::TooHigh) => println!("Your answer is too high."),
+        SubmitResponse::Recorded(SubmitOutcome::TooLow) => println!("Your answer is too low."),
+        SubmitResponse::Wait(ref message) => println!("{}", message),
+        SubmitResponse::AlreadySolved => println!("This part has already been solved."),
+    }
+    match response {
+        SubmitResponse::Recorded(
+            SubmitOutcome::Wrong | SubmitOutcome::TooHigh | SubmitOutcome::TooLow,
+        ) => ExitStatus::WrongAnswer,
+        // Submitting too soon did not submit anything.
+        SubmitResponse::Wait(_) => ExitStatus::Failure,
+        _ => ExitStatus::Success,
+    }
+}
+
+fn run_bench(program_name: &str, args: &mut impl Iterator<Item = String>) -> ExitStatus {
+    let args = match BenchArgs::parse_from_args(args) {
+        Err(err) => return bad_args(err, BenchArgs::usage(program_name)),
+        Ok(args) => args,
+    };
+    let benchmarks = match bench(&args) {
+        Err(err) => return solver_failed(err),
+        Ok(benchmarks) => benchmarks,
+    };
+    let stats = match benchmarks
+        .iter()
+        .map(|benchmark| {
+            BenchStats::from_samples(&benchmark.samples)
+                .map(|stats| (benchmark.implementation.as_str(), stats))
+        })
+        .collect::<Option<Vec<_>>>()
+    {
+        None => return fail("no samples were collected", ExitStatus::Failure),
+        Some(stats) => stats,
+    };
+    if args.format() != OutputFormat::Text {
+        let records = benchmarks
+            .into_iter()
+            .map(|benchmark| benchmark.record)
+            .collect::<Vec<_>>();
+        print_records(args.format(), &records);
+        return ExitStatus::Success;
+    }
+    println!(
+        "Day {}, Part {}",
+        args.program_args().day(),
+        args.program_args().part()
+    );
+    if let Ok(answer) = &benchmarks[0].record.answer {
+        println!("Solution: {}", answer);
+    }
+    println!(
+        "{} iterations after {} warm-up iterations",
+        args.iterations(),
+        args.warmup()
+    );
+    match stats.as_slice() {
+        [(_, stats)] => stats.print_table(),
+        _ => BenchStats::print_comparison(&stats),
+    }
+    ExitStatus::Success
+}
+
+fn run_viz(program_name: &str, args: &mut impl Iterator<Item = String>) -> ExitStatus {
+    let args = match VizArgs::parse_from_args(args) {
+        Err(err) => return bad_args(err, VizArgs::usage(program_name)),
+        Ok(args) => args,
+    };
+    match visualize(&args) {
+        Err(err) => fail(err, ExitStatus::Failure),
+        Ok(()) => ExitStatus::Success,
+    }
+}
+
+// Solves a part, then again every time its input is written, comparing each
+// run with the one before it. Runs until interrupted.
+fn run_watch(program_name: &str, args: &mut impl Iterator<Item = String>) -> ExitStatus {
+    let args = match WatchArgs::parse_from_args(args) {
+        Err(err) => return bad_args(err, WatchArgs::usage(program_name)),
+        Ok(args) => args,
+    };
+    let input = args.program_args().input_path();
+    if args.cargo() {
+        return match cargo_watch(&input, args.solve_args()) {
+            Err(err) => fail(err, ExitStatus::Failure),
+            Ok(status) => status,
+        };
+    }
+    let mut watcher = FileWatcher::new([&input]);
+    let mut history = RunHistory::default();
+    loop {
+        let run = match solve(args.program_args()) {
+            Err(err) if err.is_invalid_args() => return solver_failed(err),
+            run => run,
+        };
+        match &run {
+            Err(err) => eprintln!("{}", err),
+            Ok(record) => print_solution(record),
+        }
+        if let Some(change) = history.record(&run) {
+            println!("{}", change);
+        }
+        println!("Watching {} for changes", input);
+        watcher.wait(args.interval());
+    }
+}
+
+// Prints records in any format but plain text, which each command lays out
+// itself.
+fn print_records(format: OutputFormat, records: &[SolveRecord]) {
+    if let Some(output) = format_records(format, records) {
+        println!("{}", output.trim_end());
+    }
+}
+
+// Prints a single solution for the terminal.
+fn print_solution(record: &SolveRecord) {
+    println!("Day {}, Part {}", record.day, record.part);
+    match &record.answer {
+        Ok(answer) => println!("Solution: {} ({})", answer, record.usage()),
+        Err(err) => println!("Error: {}", err),
+    }
+    println!("Timing: {}", record.timings);
+}
+
+fn run_both(args: &ProgramArgs) -> ExitStatus {
+    let records = match solve_both(args) {
+        Err(err) => return solver_failed(err),
+        Ok(records) => records,
+    };
+    match args.format() {
+        OutputFormat::Text => {
+            records.iter().for_each(print_solution);
+            println!(
+                "Both parts ran in {} us",
+                records
+                    .iter()
+                    .map(|record| record.timings.solver_time())
+                    .sum::<std::time::Duration>()
+                    .as_micros()
+            );
+        }
+        format => print_records(format, &records),
+    }
+    ExitStatus::Success
+}
+
+fn run_part(program_name: &str, args: &mut impl Iterator<Item = String>) -> ExitStatus {
+    let args = match ProgramArgs::parse_from_args(args) {
+        Err(err) => return bad_args(err, ProgramArgs::usage(program_name)),
+        Ok(args) => args,
+    };
+    if args.part() == SolutionPart::Both {
+        if args.render().is_some() {
+            return bad_args(
+                "only one part can be rendered at a time",
+                ProgramArgs::usage(program_name),
+            );
+        }
+        return run_both(&args);
+    }
+    let record = match solve(&args) {
+        Err(err) => return solver_failed(err),
+        Ok(record) => record,
+    };
+    match args.format() {
+        OutputFormat::Text => print_solution(&record),
+        // A single solution is a single object rather than an array of one.
+        OutputFormat::Json => println!("{}", record_json(&record)),
+        format => print_records(format, &[record]),
+    }
+    if let Some(path) = args.render() {
+        if let Err(err) = render(&args, path) {
+            return fail(err, ExitStatus::Failure);
+        }
+    }
+    ExitStatus::Success
+}
+
+// Removes `--trace` from anywhere in the arguments before `--`, so that it can
+// be given to any command without taking it from the day.
+fn take_trace_flag(args: &mut Vec<String>) -> bool {
+    let end = args
+        .iter()
+        .position(|arg| arg == "--")
+        .unwrap_or(args.len());
+    let day_args = args.split_off(end);
+    let len = args.len();
+    args.retain(|arg| arg != "--trace");
+    let traced = args.len() != len;
+    args.extend(day_args);
+    traced
+}
+
+// Whether to print a command's usage instead of running it. Everything after
+// `--` belongs to the day, not the command.
+fn wants_help(args: &[String]) -> bool {
+    args.iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--help" || arg == "-h")
+}
+
+// Runs the command-line program with the arguments it was started with,
+// including its own name.
+pub fn run(args: impl IntoIterator<Item = String>) -> ExitCode {
+    let mut args = args.into_iter().collect::<Vec<_>>();
+    if let Err(err) = log::init_from_env() {
+        return fail(err, ExitStatus::BadArgs).into();
+    }
+    if take_trace_flag(&mut args) {
+        log::set_level(log::Level::Trace);
+    }
+    let mut args = args.into_iter().peekable();
+    let program_name = match args.next() {
+        None => return fail("args is empty", ExitStatus::BadArgs).into(),
+        Some(name) => name,
+    };
+    let command = Command::parse(&mut args);
+    let args = args.collect::<Vec<_>>();
+    if command != Command::Help && wants_help(&args) {
+        println!("{}", command.usage(&program_name));
+        return ExitStatus::Success.into();
+    }
+    if let Err(err) = load_config() {
+        return fail(err, ExitStatus::Failure).into();
+    }
+    let args = &mut args.into_iter();
+    match command {
+        Command::Solve => run_part(&program_name, args),
+        Command::All => run_all(&program_name, args),
+        Command::Verify => run_verify(&program_name, args),
+        Command::Examples => run_examples(),
+        Command::List => run_list(),
+        Command::Input => run_input(&program_name, args),
+        Command::Submit => run_submit(&program_name, args),
+        Command::Bench => run_bench(&program_name, args),
+        Command::Viz => run_viz(&program_name, args),
+        Command::Watch => run_watch(&program_name, args),
+        Command::Help => {
+            print!("{}", help(&program_name));
+            ExitStatus::Success
+        }
+    }
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(str::to_owned).collect()
+    }
+
+    #[test]
+    fn leaves_day_arguments_alone() {
+        let mut traced = args("aoc --trace 16 A -- --trace");
+        assert!(take_trace_flag(&mut traced));
+        assert_eq!(traced, args("aoc 16 A -- --trace"));
+        let mut untraced = args("aoc 16 A -- --trace --help");
+        assert!(!take_trace_flag(&mut untraced));
+        assert_eq!(untraced, args("aoc 16 A -- --trace --help"));
+        assert!(!wants_help(&untraced));
+        assert!(wants_help(&args("aoc 16 A --help")));
+    }
+}
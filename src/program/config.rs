@@ -0,0 +1,220 @@
+use crate::{
+    common::{AocError, AocResult, IntoAocResult},
+    program::{ExpectedAnswers, OutputFormat},
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+const CONFIG_ENV_VAR: &str = "AOC_CONFIG";
+const CONFIG_FILE: &str = "aoc.toml";
+
+// Settings that apply to every command, read from an optional config file in
+// the same subset of TOML as the expected answers:
+//
+//     input_dir = "input"
+//     session_file = ".aoc-session"
+//     format = "json"
+//     year = 2022
+//     slow_threshold_ms = 500
+//     cache_dir = ".aoc-cache"
+//     answers_file = "answers/answers.toml"
+//
+//     [answers.16]
+//     A = 1737
+//
+//     [answers.friend1.16]
+//     A = 1651
+//
+// Relative paths are resolved from the directory of the config file, so the
+// program can be run from anywhere once the config file is found.
+#[derive(Clone)]
+pub struct Config {
+    pub input_dir: String,
+    pub session_file: String,
+    // Used when no `--format` flag is given.
+    pub format: OutputFormat,
+    pub year: u16,
+    // Solvers at least this slow are highlighted in the table printed by `all`.
+    pub slow_threshold_ms: u64,
+    // Where answers are recorded for `--cached` to reuse.
+    pub cache_dir: String,
+    // The manifest `verify` checks when none is passed on the command line.
+    pub answers_file: String,
+    pub answers: ExpectedAnswers,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            input_dir: "input".to_owned(),
+            session_file: ".aoc-session".to_owned(),
+            format: OutputFormat::Text,
+            year: 2022,
+            slow_threshold_ms: 500,
+            cache_dir: ".aoc-cache".to_owned(),
+            answers_file: "answers/answers.toml".to_owned(),
+            answers: ExpectedAnswers::default(),
+        }
+    }
+}
+
+// Which table of the config file the following keys belong to.
+enum Table {
+    Settings,
+    Answers(Option<String>, u8),
+}
+
+impl Config {
+    pub fn load(path: &Path) -> AocResult<Self> {
+        let mut config = fs::read_to_string(path)
+            .into_aoc_result_msg(&format!("failed to read {}", path.display()))?
+            .parse::<Self>()
+            .map_err(|err| AocError::parse(format!("{}: {}", path.display(), err.message())))?;
+        if let Some(dir) = path.parent() {
+            config.input_dir = Self::resolve(dir, &config.input_dir);
+            config.session_file = Self::resolve(dir, &config.session_file);
+            config.cache_dir = Self::resolve(dir, &config.cache_dir);
+            config.answers_file = Self::resolve(dir, &config.answers_file);
+        }
+        Ok(config)
+    }
+
+    fn resolve(dir: &Path, path: &str) -> String {
+        dir.join(path).to_string_lossy().into_owned()
+    }
+
+    // The config file named by the environment, or else the first one found in
+    // the current directory or any of its parents.
+    fn find() -> Option<PathBuf> {
+        if let Some(path) = env::var_os(CONFIG_ENV_VAR) {
+            return Some(PathBuf::from(path));
+        }
+        let current_dir = env::current_dir().ok()?;
+        current_dir
+            .ancestors()
+            .map(|dir| dir.join(CONFIG_FILE))
+            .find(|path| path.is_file())
+    }
+
+    fn parse_string(value: &str) -> AocResult<String> {
+        if !value.starts_with('"') {
+            return Err(AocError::parse("expected a string"));
+        }
+        ExpectedAnswers::parse_value(value)
+    }
+
+    fn parse_table(header: &str) -> AocResult<Table> {
+        match header.trim().split_once('.') {
+            Some((table, header)) if table.trim() == "answers" => {
+                let (input_set, day) = ExpectedAnswers::parse_header(header)?;
+                Ok(Table::Answers(input_set, day))
+            }
+            _ => Err(AocError::parse(format!("unknown table: {}", header))),
+        }
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> AocResult<()> {
+        match key {
+            "input_dir" => self.input_dir = Self::parse_string(value)?,
+            "session_file" => self.session_file = Self::parse_string(value)?,
+            "cache_dir" => self.cache_dir = Self::parse_string(value)?,
+            "answers_file" => self.answers_file = Self::parse_string(value)?,
+            "format" => self.format = Self::parse_string(value)?.parse()?,
+            "year" => {
+                self.year = ExpectedAnswers::parse_value(value)?
+                    .parse()
+                    .into_aoc_result_msg("year must be a positive integer")?
+            }
+            "slow_threshold_ms" => {
+                self.slow_threshold_ms = ExpectedAnswers::parse_value(value)?
+                    .parse()
+                    .into_aoc_result_msg("slow_threshold_ms must be a positive integer")?
+            }
+            _ => return Err(AocError::parse(format!("unknown setting: {}", key))),
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Config {
+    type Err = AocError;
+
+    fn from_str(s: &str) -> AocResult<Self> {
+        let mut config = Self::default();
+        let mut table = Table::Settings;
+        for (i, line) in s.lines().enumerate() {
+            let line_error =
+                |err: AocError| AocError::parse(format!("line {}: {}", i + 1, err.message()));
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .split_once(']')
+                    .into_aoc_result_msg("unterminated table header")
+                    .map_err(line_error)?
+                    .0;
+                table = Self::parse_table(header).map_err(line_error)?;
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .into_aoc_result_msg("expected key = value")
+                .map_err(line_error)?;
+            match &table {
+                Table::Settings => config.set(key.trim(), value.trim()),
+                Table::Answers(input_set, day) => {
+                    config
+                        .answers
+                        .insert(input_set.as_deref(), *day, key, value)
+                }
+            }
+            .map_err(line_error)?;
+        }
+        Ok(config)
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+// Reads the config file, if there is one. Must be called before any command
+// runs, or else the defaults are used.
+pub fn load_config() -> AocResult<()> {
+    let config = match Config::find() {
+        None => Config::default(),
+        Some(path) => Config::load(&path)?,
+    };
+    CONFIG.get_or_init(|| config);
+    Ok(())
+}
+
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_paths_from_config_directory() {
+        let dir = env::temp_dir().join(format!("aoc-config-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CONFIG_FILE);
+        fs::write(&path, "cache_dir = \"cache\"\n").unwrap();
+        let config = Config::load(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        let config = config.unwrap();
+        assert_eq!(Path::new(&config.input_dir), dir.join("input"));
+        assert_eq!(Path::new(&config.cache_dir), dir.join("cache"));
+        assert_eq!(
+            Path::new(&config.answers_file),
+            dir.join("answers/answers.toml")
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use crate::days::SolveRecord;
+use std::time::Duration;
+
+// Quotes a field if it holds anything that would end it early, doubling any
+// quotes inside of it.
+fn escape_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+fn micros(time: Option<Duration>) -> String {
+    time.map(|time| time.as_micros().to_string())
+        .unwrap_or_default()
+}
+
+// A header and one row for every record. Fields that do not apply to a record,
+// like the timings of a solver that failed, are left empty.
+pub fn records_csv(records: &[SolveRecord]) -> String {
+    let mut csv = String::from(
+        "day,part,filename,input_set,answer,error,io_us,parse_us,solve_us,peak_bytes,input_hash,verified,expected,cached\n",
+    );
+    for record in records {
+        let (answer, error, timings) = match &record.answer {
+            Ok(answer) => (answer.to_string(), String::new(), Some(record.timings)),
+            Err(err) => (String::new(), err.message(), None),
+        };
+        let fields = [
+            record.day.to_string(),
+            record.part.to_string(),
+            record.filename.clone().unwrap_or_default(),
+            record.input_set.clone().unwrap_or_default(),
+            answer,
+            error,
+            micros(timings.and_then(|timings| timings.io)),
+            micros(timings.and_then(|timings| timings.parse)),
+            micros(timings.map(|timings| timings.solve)),
+            record
+                .peak_memory
+                .map(|bytes| bytes.to_string())
+                .unwrap_or_default(),
+            record.input_hash.clone().unwrap_or_default(),
+            record
+                .verified
+                .map(|verified| verified.to_string())
+                .unwrap_or_default(),
+            record.expected.clone().unwrap_or_default(),
+            record.cached.to_string(),
+        ];
+        csv += &fields.map(|field| escape_field(&field)).join(",");
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{AocError, AocSolution, SolutionPart},
+        days::TimingBreakdown,
+        program::ProgramArgs,
+    };
+
+    #[test]
+    fn writes_a_row_for_every_record() {
+        let timings = TimingBreakdown {
+            io: None,
+            parse: Some(Duration::from_micros(12)),
+            solve: Duration::from_micros(340),
+        };
+        let mut solved = SolveRecord::new(
+            10,
+            SolutionPart::B,
+            AocSolution::Str("A,\"B\"".to_owned()),
+            timings,
+            None,
+        );
+        solved.verify(Some("AB"));
+        let failed = SolveRecord::failed(
+            &ProgramArgs::new(3, SolutionPart::A, None).with_input_set("friend1"),
+            AocError::no_solution("none"),
+        );
+        let csv = records_csv(&[solved, failed]);
+        let rows = csv.lines().collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1], "10,B,,,\"A,\"\"B\"\"\",,,12,340,,,false,AB,false");
+        assert_eq!(
+            rows[2],
+            "3,A,3/friend1.txt,friend1,,no solution: none,,,,,,,,false"
+        );
+    }
+}
@@ -0,0 +1,19 @@
+use std::process::ExitCode;
+
+// What the program exits with, so that scripts can tell why a command failed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExitStatus {
+    Success = 0,
+    // A solver failed, or something else the command needed, like an input.
+    Failure = 1,
+    // The arguments could not be understood.
+    BadArgs = 2,
+    // Answers were found, but not the expected ones.
+    WrongAnswer = 3,
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status as u8)
+    }
+}
@@ -0,0 +1,172 @@
+use crate::{
+    common::{AocError, AocResult, IntoAocResult},
+    program::config,
+};
+use std::{
+    env, fs,
+    io::{ErrorKind, Write},
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const SESSION_ENV_VAR: &str = "AOC_SESSION";
+const USER_AGENT: &str = "github.com/jackson-nestelroad/advent-of-code-2022";
+
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_secs(5);
+
+pub fn input_path(input_dir: &str, day: u8) -> String {
+    format!("{}/{}.txt", input_dir, day)
+}
+
+// Other inputs for a day, like a friend's, are kept next to its own input as
+// `<day>/<name>.txt`.
+pub fn input_set_filename(day: u8, input_set: &str) -> String {
+    format!("{}/{}.txt", day, input_set)
+}
+
+// The names of every input set for a day, in order.
+pub fn input_sets(input_dir: &str, day: u8) -> Vec<String> {
+    let mut input_sets = fs::read_dir(format!("{}/{}", input_dir, day))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "txt"))
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_owned()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    input_sets.sort();
+    input_sets
+}
+
+// Requests are spaced out across program runs by remembering when the last one
+// was made.
+fn last_request_path() -> String {
+    format!("{}/.last-request", config().input_dir)
+}
+
+pub fn session_token() -> AocResult<String> {
+    if let Ok(token) = env::var(SESSION_ENV_VAR) {
+        if !token.trim().is_empty() {
+            return Ok(token.trim().to_owned());
+        }
+    }
+    let session_file = &config().session_file;
+    match fs::read_to_string(session_file) {
+        Ok(token) if !token.trim().is_empty() => Ok(token.trim().to_owned()),
+        _ => Err(AocError::invalid_args(format!(
+            "no session token, set {} or write it to {}",
+            SESSION_ENV_VAR, session_file
+        ))),
+    }
+}
+
+fn now() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+fn wait_for_rate_limit() {
+    let last_request = fs::read_to_string(last_request_path())
+        .ok()
+        .and_then(|s| s.trim().parse::<u64>().ok())
+        .map(Duration::from_millis);
+    if let Some(last_request) = last_request {
+        let next_allowed = last_request + MIN_REQUEST_INTERVAL;
+        let now = now();
+        if next_allowed > now {
+            thread::sleep(next_allowed - now);
+        }
+    }
+    // Failing to record the request only loosens the rate limit, so it is not
+    // worth failing the request over.
+    fs::write(last_request_path(), now().as_millis().to_string()).ok();
+}
+
+// Performs a request with curl. Options are passed through a config file on
+// stdin so that the session token does not show up in the process list. Form
+// fields are URL-encoded by curl, and their presence makes the request a POST.
+pub fn request(path: &str, session: &str, form: &[(&str, &str)]) -> AocResult<String> {
+    wait_for_rate_limit();
+    let year = config().year;
+    let mut curl_config = format!(
+        "url = \"https://adventofcode.com/{}/{}\"\nuser-agent = \"{}\"\ncookie = \"session={}\"\n",
+        year, path, USER_AGENT, session
+    );
+    for (name, value) in form {
+        curl_config += &format!(
+            "data-urlencode = \"{}={}\"\n",
+            name,
+            value.replace('\\', "\\\\").replace('"', "\\\"")
+        );
+    }
+    let mut curl = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .into_aoc_result_msg("failed to run curl")?;
+    curl.stdin
+        .take()
+        .into_aoc_result_msg("failed to open curl stdin")?
+        .write_all(curl_config.as_bytes())
+        .into_aoc_result_msg("failed to configure curl")?;
+    let output = curl
+        .wait_with_output()
+        .into_aoc_result_msg("failed to run curl")?;
+    if !output.status.success() {
+        return Err(AocError::io(format!(
+            "request to /{}/{} failed: {}",
+            year,
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    String::from_utf8(output.stdout).into_aoc_result()
+}
+
+pub fn download_input(input_dir: &str, day: u8) -> AocResult<String> {
+    let session = session_token()?;
+    let input = request(&format!("day/{}/input", day), &session, &[])?;
+    let path = input_path(input_dir, day);
+    if let Some(dir) = Path::new(&path).parent() {
+        fs::create_dir_all(dir).into_aoc_result()?;
+    }
+    fs::write(&path, &input).into_aoc_result_msg(&format!("failed to write {}", path))?;
+    Ok(input)
+}
+
+// Reads the cached input for the day, without downloading it.
+pub fn read_local_input(input_dir: &str, day: u8) -> AocResult<String> {
+    let path = input_path(input_dir, day);
+    fs::read_to_string(&path)
+        .map_err(|err| AocError::from_source(format!("failed to read {}", path), err))
+}
+
+// Reads the cached input for the day, downloading it first if it does not
+// exist yet.
+pub fn read_or_download_input(input_dir: &str, day: u8) -> AocResult<String> {
+    let path = input_path(input_dir, day);
+    match fs::read_to_string(&path) {
+        Ok(input) => Ok(input),
+        Err(err) if err.kind() == ErrorKind::NotFound => match session_token() {
+            Ok(_) => download_input(input_dir, day),
+            Err(_) => Err(AocError::from_source(
+                format!(
+                    "{} not found, set {} to download it automatically",
+                    path, SESSION_ENV_VAR
+                ),
+                err,
+            )),
+        },
+        Err(err) => Err(AocError::from_source(
+            format!("failed to read {}", path),
+            err,
+        )),
+    }
+}
@@ -0,0 +1,61 @@
+use crate::days::SolveRecord;
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Formats a single record as a JSON object. The solution is always a string
+// so that consumers do not need to care about which days have string answers.
+// Solvers that failed are reported with their error instead of a solution.
+pub fn record_json(record: &SolveRecord) -> String {
+    let mut object = format!("{{\"day\":{},\"part\":\"{}\"", record.day, record.part);
+    if let Some(filename) = &record.filename {
+        object += &format!(",\"filename\":\"{}\"", escape(filename));
+    }
+    if let Some(input_set) = &record.input_set {
+        object += &format!(",\"input_set\":\"{}\"", escape(input_set));
+    }
+    match &record.answer {
+        Ok(answer) => {
+            object += &format!(
+                ",\"solution\":\"{}\",\"micros\":{}",
+                escape(&answer.to_string()),
+                record.timings.solver_time().as_micros()
+            );
+        }
+        Err(err) => object += &format!(",\"error\":\"{}\"", escape(&err.message())),
+    }
+    if let Some(bytes) = record.peak_memory {
+        object += &format!(",\"peak_bytes\":{}", bytes);
+    }
+    if let Some(hash) = &record.input_hash {
+        object += &format!(",\"input_hash\":\"{}\"", hash);
+    }
+    if let Some(verified) = record.verified {
+        object += &format!(",\"verified\":{}", verified);
+    }
+    if let Some(expected) = &record.expected {
+        object += &format!(",\"expected\":\"{}\"", escape(expected));
+    }
+    if record.cached {
+        object += ",\"cached\":true";
+    }
+    object + "}"
+}
+
+pub fn records_json<'a>(records: impl IntoIterator<Item = &'a SolveRecord>) -> String {
+    let objects = records.into_iter().map(record_json).collect::<Vec<_>>();
+    format!("[{}]", objects.join(","))
+}
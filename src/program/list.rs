@@ -0,0 +1,42 @@
+use crate::{
+    common::{AocResult, SolutionPart},
+    days::registered_days,
+    program::{config, input_path, ExpectedAnswers},
+};
+use std::path::Path;
+
+// Prints every day of the puzzle, which of its parts are implemented and with
+// what answer type, and whether it has an input and expected answers to check.
+pub fn list_days() -> AocResult<()> {
+    let registered = registered_days()?;
+    let answers = ExpectedAnswers::load_with_config(None)?;
+    println!("{:>3} {:<6} {:<6} {:<5} Answers", "Day", "A", "B", "Input");
+    for day in 1..=25 {
+        let solvers = registered
+            .iter()
+            .find(|(registered_day, _)| *registered_day == day)
+            .map(|(_, solvers)| solvers);
+        let [a, b] = match solvers {
+            None => ["-", "-"],
+            Some(solvers) => solvers.each_ref().map(|solver| solver.output_type()),
+        };
+        let input = match Path::new(&input_path(&config().input_dir, day)).exists() {
+            true => "yes",
+            false => "no",
+        };
+        let parts_with_answers = [SolutionPart::A, SolutionPart::B]
+            .into_iter()
+            .filter(|part| answers.get(day, *part).is_some())
+            .map(|part| part.to_string())
+            .collect::<Vec<_>>();
+        let parts_with_answers = match parts_with_answers.is_empty() {
+            true => "-".to_owned(),
+            false => parts_with_answers.join(" "),
+        };
+        println!(
+            "{:>3} {:<6} {:<6} {:<5} {}",
+            day, a, b, input, parts_with_answers
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::{
+    common::{AocResult, IntoAocResult},
+    days::{AllSolutions, SolveRecord},
+};
+use std::fs;
+
+// Answers are placed in code spans, so only characters that would break the
+// table itself need escaping.
+fn escape_cell(s: &str) -> String {
+    s.replace('|', "\\|").replace('\n', " ")
+}
+
+// A table of every record with its answer and run time. Records that were
+// checked against an expected answer also say whether they matched it.
+pub fn records_markdown(records: &[SolveRecord]) -> String {
+    let checked = records.iter().any(|record| record.verified.is_some());
+    let mut markdown = String::from("| Day | Part | Answer | Time (us) |");
+    if checked {
+        markdown += " Verified |";
+    }
+    markdown += "\n| --: | :--: | :-- | --: |";
+    if checked {
+        markdown += " :--: |";
+    }
+    markdown.push('\n');
+    for record in records {
+        let (answer, time) = match &record.answer {
+            Ok(answer) => (
+                format!("`{}`", escape_cell(&answer.to_string())),
+                record.timings.solver_time().as_micros().to_string(),
+            ),
+            Err(err) => (format!("*{}*", escape_cell(&err.message())), "-".to_owned()),
+        };
+        let day = match &record.input_set {
+            None => record.day.to_string(),
+            Some(input_set) => format!("{} ({})", record.day, escape_cell(input_set)),
+        };
+        markdown += &format!("| {day} | {} | {answer} | {time} |", record.part);
+        if checked {
+            let verified = match (record.verified, &record.expected) {
+                (Some(true), _) => "yes".to_owned(),
+                (Some(false), Some(expected)) => {
+                    format!("no, expected `{}`", escape_cell(expected))
+                }
+                _ => "-".to_owned(),
+            };
+            markdown += &format!(" {verified} |");
+        }
+        markdown.push('\n');
+    }
+    markdown
+}
+
+pub fn solutions_markdown(solutions: &AllSolutions) -> String {
+    let mut markdown = records_markdown(&solutions.records);
+    markdown += &format!(
+        "| **Total** | | | **{}** |\n\nWall-clock time: {} us\n",
+        solutions.total_time.as_micros(),
+        solutions.wall_time.as_micros()
+    );
+    let failures = solutions.failures();
+    if failures > 0 {
+        markdown += &format!(
+            "\n{} of {} solvers failed\n",
+            failures,
+            solutions.records.len()
+        );
+    }
+    markdown
+}
+
+pub fn write_markdown_report(path: &str, solutions: &AllSolutions) -> AocResult<()> {
+    fs::write(path, solutions_markdown(solutions))
+        .into_aoc_result_msg(&format!("failed to write {}", path))
+}
@@ -1,3 +1,41 @@
 mod args;
+mod bench;
+mod cache;
+mod cli;
+mod config;
+mod csv;
+mod exit;
+mod fetch;
+mod json;
+mod list;
+mod markdown;
+mod output;
+mod progress;
+mod report;
+mod submit;
+mod verify;
+mod watch;
 
-pub use args::{ProgramArgs, SolutionPart};
+pub use args::{
+    help, AllArgs, BenchArgs, Command, InputArgs, OutputFormat, ProgramArgs, VerifyArgs, VizArgs,
+    WatchArgs,
+};
+pub use bench::BenchStats;
+pub use cache::{file_hash, input_hash, CacheMode, SolutionCache};
+pub use cli::run;
+pub use config::{config, load_config};
+pub use csv::records_csv;
+pub use exit::ExitStatus;
+pub use fetch::{
+    download_input, input_path, input_set_filename, input_sets, read_local_input,
+    read_or_download_input,
+};
+pub use json::{record_json, records_json};
+pub use list::list_days;
+pub use markdown::{records_markdown, solutions_markdown, write_markdown_report};
+pub use output::format_records;
+pub use progress::ProgressView;
+pub use report::{append_timings_csv, TableReport};
+pub use submit::{submit_answer, SubmitOutcome, SubmitResponse};
+pub use verify::{verify_all, verify_examples, verify_table, ExpectedAnswers, VerifySummary};
+pub use watch::{cargo_watch, FileWatcher, RunHistory};
@@ -0,0 +1,13 @@
+use super::{records_csv, records_json, records_markdown, OutputFormat};
+use crate::days::SolveRecord;
+
+// Writes records in one of the formats every command shares. Plain text is
+// left to each command, since each one lays out its results differently.
+pub fn format_records(format: OutputFormat, records: &[SolveRecord]) -> Option<String> {
+    match format {
+        OutputFormat::Text => None,
+        OutputFormat::Json => Some(records_json(records)),
+        OutputFormat::Markdown => Some(records_markdown(records)),
+        OutputFormat::Csv => Some(records_csv(records)),
+    }
+}
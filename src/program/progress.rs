@@ -0,0 +1,163 @@
+use crate::common::progress::{Progress, Tracker};
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+const BAR_WIDTH: usize = 24;
+
+// The progress of one running solver.
+struct Bar {
+    label: String,
+    started: Instant,
+    // How many steps there are and what they are called, once the solver says.
+    work: Mutex<Option<(Option<u64>, &'static str)>>,
+    done: AtomicU64,
+}
+
+impl Progress for Bar {
+    fn start(&self, total: Option<u64>, unit: &'static str) {
+        *self.work.lock().unwrap() = Some((total, unit));
+        self.done.store(0, Ordering::Relaxed);
+    }
+
+    fn advance(&self, steps: u64) {
+        self.done.fetch_add(steps, Ordering::Relaxed);
+    }
+}
+
+impl Bar {
+    fn render(&self) -> String {
+        let elapsed = format!("{:.1} s", self.started.elapsed().as_secs_f64());
+        let done = self.done.load(Ordering::Relaxed);
+        match *self.work.lock().unwrap() {
+            None => format!("{:<10} {}", self.label, elapsed),
+            Some((None, unit)) => format!("{:<10} {} {}  {}", self.label, done, unit, elapsed),
+            Some((Some(total), unit)) => {
+                let filled = (done.min(total) as usize * BAR_WIDTH)
+                    .checked_div(total as usize)
+                    .unwrap_or(BAR_WIDTH);
+                format!(
+                    "{:<10} [{}{}] {}/{} {}  {}",
+                    self.label,
+                    "#".repeat(filled),
+                    "-".repeat(BAR_WIDTH - filled),
+                    done.min(total),
+                    total,
+                    unit,
+                    elapsed
+                )
+            }
+        }
+    }
+}
+
+// A live view on stderr of every solver that is running, with a bar for each
+// one and a count of how many jobs are done.
+pub struct ProgressView {
+    jobs: usize,
+    finished: AtomicUsize,
+    bars: Mutex<Vec<Arc<Bar>>>,
+}
+
+// Keeps a solver's bar in the view until it is dropped.
+pub struct BarGuard<'a> {
+    view: &'a ProgressView,
+    bar: Arc<Bar>,
+}
+
+impl BarGuard<'_> {
+    pub fn tracker(&self) -> Tracker {
+        Tracker::new(self.bar.clone())
+    }
+}
+
+impl Drop for BarGuard<'_> {
+    fn drop(&mut self) {
+        self.view
+            .bars
+            .lock()
+            .unwrap()
+            .retain(|bar| !Arc::ptr_eq(bar, &self.bar));
+    }
+}
+
+impl ProgressView {
+    pub fn new(jobs: usize) -> Self {
+        Self {
+            jobs,
+            finished: AtomicUsize::new(0),
+            bars: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Adds a bar for a solver that is about to run.
+    pub fn bar(&self, label: String) -> BarGuard<'_> {
+        let bar = Arc::new(Bar {
+            label,
+            started: Instant::now(),
+            work: Mutex::new(None),
+            done: AtomicU64::new(0),
+        });
+        self.bars.lock().unwrap().push(bar.clone());
+        BarGuard { view: self, bar }
+    }
+
+    // Counts a job as done, whether or not it had to be solved.
+    pub fn finish_job(&self) {
+        self.finished.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn render(&self) -> Vec<String> {
+        let mut lines = vec![format!(
+            "solved {} of {}",
+            self.finished.load(Ordering::Relaxed),
+            self.jobs
+        )];
+        lines.extend(self.bars.lock().unwrap().iter().map(|bar| bar.render()));
+        lines
+    }
+
+    // Redraws the view while running `f`, and clears it once `f` returns.
+    pub fn show<T>(&self, f: impl FnOnce() -> T) -> T {
+        let stop = AtomicBool::new(false);
+        thread::scope(|scope| {
+            let drawer = scope.spawn(|| {
+                let mut drawn = 0;
+                while !stop.load(Ordering::Relaxed) {
+                    let lines = self.render();
+                    redraw(drawn, &lines);
+                    drawn = lines.len();
+                    thread::park_timeout(REDRAW_INTERVAL);
+                }
+                redraw(drawn, &[]);
+            });
+            let result = f();
+            stop.store(true, Ordering::Relaxed);
+            drawer.thread().unpark();
+            result
+        })
+    }
+}
+
+// Replaces the last `drawn` lines written to stderr.
+fn redraw(drawn: usize, lines: &[String]) {
+    let mut out = String::new();
+    if drawn > 0 {
+        out.push_str(&format!("\x1b[{}A", drawn));
+    }
+    out.push_str("\r\x1b[J");
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    let mut stderr = io::stderr().lock();
+    let _ = stderr.write_all(out.as_bytes());
+    let _ = stderr.flush();
+}
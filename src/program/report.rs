@@ -0,0 +1,338 @@
+use crate::{
+    common::{format_bytes, AocResult, IntoAocResult},
+    days::{AllSolutions, SolveRecord},
+};
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RED: &str = "\x1b[31m";
+
+// The cells of one row of the table, before they are padded and colored.
+struct Row {
+    day: String,
+    part: String,
+    answer: String,
+    read: String,
+    parse: String,
+    solve: String,
+    time: Option<(Duration, bool)>,
+    peak: Option<String>,
+}
+
+// Renders the results of `all` as an aligned table for the terminal. Times at
+// or above the slow threshold are red, times above half of it are yellow, and
+// the rest are green.
+pub struct TableReport {
+    color: bool,
+    slow_threshold: Duration,
+}
+
+impl TableReport {
+    pub fn new(color: bool, slow_threshold: Duration) -> Self {
+        Self {
+            color,
+            slow_threshold,
+        }
+    }
+
+    fn paint(&self, code: &str, cell: String) -> String {
+        if self.color {
+            format!("{code}{cell}{RESET}")
+        } else {
+            cell
+        }
+    }
+
+    fn time_color(&self, time: Duration) -> &'static str {
+        if time >= self.slow_threshold {
+            RED
+        } else if time >= self.slow_threshold / 2 {
+            YELLOW
+        } else {
+            GREEN
+        }
+    }
+
+    pub fn render(&self, solutions: &AllSolutions) -> String {
+        let micros = |time: Option<Duration>| {
+            time.map_or("-".to_owned(), |time| format!("{} us", time.as_micros()))
+        };
+        let rows = solutions
+            .records
+            .iter()
+            .map(|record| {
+                let (answer, time) = match &record.answer {
+                    Ok(answer) => (
+                        answer.to_string(),
+                        Some((record.timings.solver_time(), record.cached)),
+                    ),
+                    Err(err) => (err.message(), None),
+                };
+                // The breakdown is only known for solvers that finished.
+                let timings = time.map(|_| &record.timings);
+                Row {
+                    day: record.day.to_string(),
+                    part: record.part.to_string(),
+                    answer,
+                    read: micros(timings.and_then(|timings| timings.io)),
+                    parse: micros(timings.and_then(|timings| timings.parse)),
+                    solve: micros(timings.map(|timings| timings.solve)),
+                    time,
+                    peak: record.peak_memory.map(format_bytes),
+                }
+            })
+            .collect::<Vec<_>>();
+        let total = format!("{} us", solutions.total_time.as_micros());
+        // Cached times are from when the answer was solved, not from this run.
+        let time_text = |(time, cached): (Duration, bool)| {
+            format!("{} us{}", time.as_micros(), if cached { "*" } else { "" })
+        };
+        // Peak memory is only measured with the `memory-stats` feature.
+        let show_peak = rows.iter().any(|row| row.peak.is_some());
+
+        // Widths are measured before coloring, since escape codes take up no
+        // space on the terminal.
+        let width = |cell: fn(&Row) -> &str, header: &str| {
+            rows.iter()
+                .map(|row| cell(row).len())
+                .fold(header.len(), usize::max)
+        };
+        let day_width = width(|row| &row.day, "Day");
+        let part_width = width(|row| &row.part, "Part");
+        let answer_width = width(|row| &row.answer, "Answer");
+        let read_width = width(|row| &row.read, "Read");
+        let parse_width = width(|row| &row.parse, "Parse");
+        let solve_width = width(|row| &row.solve, "Solve");
+        let peak_width = rows
+            .iter()
+            .filter_map(|row| row.peak.as_ref())
+            .map(|peak| peak.len())
+            .fold(4, usize::max);
+        let time_width = rows
+            .iter()
+            .filter_map(|row| row.time)
+            .map(|time| time_text(time).len())
+            .fold(total.len().max(4), usize::max);
+
+        let mut header = format!(
+            "{:>day_width$}  {:<part_width$}  {:<answer_width$}  {:>read_width$}  {:>parse_width$}  {:>solve_width$}  {:>time_width$}",
+            "Day", "Part", "Answer", "Read", "Parse", "Solve", "Time"
+        );
+        if show_peak {
+            header += &format!("  {:>peak_width$}", "Peak");
+        }
+        let mut table = self.paint(BOLD, header);
+        table.push('\n');
+        for row in &rows {
+            // Solvers that did not finish have no time, and are as slow as
+            // solvers can be.
+            let (time_cell, color) = match row.time {
+                Some(time) => (time_text(time), self.time_color(time.0)),
+                None => ("-".to_owned(), RED),
+            };
+            table += &format!(
+                "{:>day_width$}  {:<part_width$}  {:<answer_width$}  {:>read_width$}  {:>parse_width$}  {:>solve_width$}  {}",
+                row.day,
+                row.part,
+                row.answer,
+                row.read,
+                row.parse,
+                row.solve,
+                self.paint(color, format!("{time_cell:>time_width$}"))
+            );
+            if show_peak {
+                table += &format!("  {:>peak_width$}", row.peak.as_deref().unwrap_or("-"));
+            }
+            table.push('\n');
+        }
+        table += &self.paint(
+            BOLD,
+            format!(
+                "{:<width$}  {total:>time_width$}",
+                "Total",
+                width = day_width
+                    + part_width
+                    + answer_width
+                    + read_width
+                    + parse_width
+                    + solve_width
+                    + 10
+            ),
+        );
+        table += &format!(
+            "\nWall-clock time: {} seconds ({} us)\n",
+            solutions.wall_time.as_secs_f64(),
+            solutions.wall_time.as_micros()
+        );
+        if rows
+            .iter()
+            .any(|row| row.time.is_some_and(|(_, cached)| cached))
+        {
+            table += "* Reused from the cache, timed when first solved\n";
+        }
+        let failures = solutions.failures();
+        if failures > 0 {
+            table += &self.paint(
+                RED,
+                format!("{} of {} solvers failed", failures, rows.len()),
+            );
+            table.push('\n');
+        }
+        table
+    }
+}
+
+const TIMINGS_HEADER: &str = "timestamp,revision,day,part,answer_hash,micros\n";
+
+// The commit being run, if this is a git checkout with git installed.
+fn git_revision() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+        .filter(|revision| !revision.is_empty())
+}
+
+// A row for the time of every solver that ran. Failed solvers have no time, and
+// cached ones were timed by an earlier run, so neither gets a row. Answers are
+// hashed so that a changed answer stands out without the file holding them.
+fn timing_rows(records: &[SolveRecord], timestamp: u64, revision: Option<&str>) -> String {
+    records
+        .iter()
+        .filter(|record| !record.cached)
+        .filter_map(|record| {
+            let answer = record.answer.as_ref().ok()?;
+            Some(format!(
+                "{},{},{},{},{},{}\n",
+                timestamp,
+                revision.unwrap_or_default(),
+                record.day,
+                record.part,
+                blake3::hash(answer.to_string().as_bytes()).to_hex(),
+                record.timings.solver_time().as_micros()
+            ))
+        })
+        .collect()
+}
+
+// Appends the time of every solution to a CSV file, so that times can be
+// charted across runs. The header is only written to a new or empty file.
+pub fn append_timings_csv(path: &str, records: &[SolveRecord]) -> AocResult<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .into_aoc_result_msg(&format!("failed to open {}", path))?;
+    let is_empty = file
+        .metadata()
+        .into_aoc_result_msg(&format!("failed to read {}", path))?
+        .len()
+        == 0;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let mut contents = timing_rows(records, timestamp, git_revision().as_deref());
+    if is_empty {
+        contents.insert_str(0, TIMINGS_HEADER);
+    }
+    file.write_all(contents.as_bytes())
+        .into_aoc_result_msg(&format!("failed to write {}", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{AocError, AocSolution, SolutionPart},
+        days::TimingBreakdown,
+        program::ProgramArgs,
+    };
+    use std::fs;
+
+    fn record(day: u8, answer: i64, micros: u64) -> SolveRecord {
+        let timings = TimingBreakdown {
+            solve: Duration::from_micros(micros),
+            ..Default::default()
+        };
+        SolveRecord::new(
+            day,
+            SolutionPart::A,
+            AocSolution::SInt(answer),
+            timings,
+            None,
+        )
+    }
+
+    #[test]
+    fn appends_rows_for_solvers_that_ran() {
+        let mut cached = record(2, 15, 9);
+        cached.cached = true;
+        let failed = SolveRecord::failed(
+            &ProgramArgs::new(3, SolutionPart::B, None),
+            AocError::no_solution("none"),
+        );
+        let records = [record(1, 24000, 120), cached, failed];
+        let hash = blake3::hash(b"24000").to_hex();
+        assert_eq!(
+            timing_rows(&records, 1670000000, Some("abc1234")),
+            format!("1670000000,abc1234,1,A,{hash},120\n")
+        );
+        assert_eq!(
+            timing_rows(&records, 1670000000, None),
+            format!("1670000000,,1,A,{hash},120\n")
+        );
+
+        let path = std::env::temp_dir().join(format!("aoc-timings-{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        append_timings_csv(path, &records).unwrap();
+        append_timings_csv(path, &records).unwrap();
+        let contents = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], TIMINGS_HEADER.trim_end());
+        assert!(lines[1..]
+            .iter()
+            .all(|line| line.ends_with(&format!("{hash},120"))));
+    }
+
+    #[test]
+    fn renders_time_breakdown_and_peak_memory() {
+        let mut parsed = record(1, 24000, 120);
+        parsed.timings.io = Some(Duration::from_micros(7));
+        parsed.timings.parse = Some(Duration::from_micros(30));
+        let solutions = AllSolutions {
+            records: vec![parsed, record(2, 15, 9)],
+            total_time: Duration::from_micros(159),
+            wall_time: Duration::from_micros(200),
+        };
+        let report = TableReport::new(false, Duration::from_millis(500));
+        let table = report.render(&solutions);
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "Day  Part  Answer  Read  Parse   Solve    Time");
+        assert_eq!(lines[1], "  1  A     24000   7 us  30 us  120 us  150 us");
+        assert_eq!(lines[2], "  2  A     15         -      -    9 us    9 us");
+        assert_eq!(lines[3], "Total                                   159 us");
+        assert!(!table.contains("Peak"));
+
+        let mut solutions = solutions;
+        solutions.records[0].peak_memory = Some(2048);
+        let table = report.render(&solutions);
+        assert!(table.lines().next().unwrap().ends_with("  Peak"));
+        assert!(table.lines().nth(1).unwrap().ends_with("2.0 KiB"));
+        assert!(table.lines().nth(2).unwrap().ends_with("-"));
+    }
+}
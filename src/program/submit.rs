@@ -0,0 +1,221 @@
+use crate::{
+    common::{AocError, AocResult, AocSolution, IntoAocResult, SolutionPart},
+    program::fetch,
+};
+use num::BigInt;
+use std::{
+    fmt::{Display, Formatter, Result as DisplayResult},
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    str::FromStr,
+};
+
+const SUBMISSIONS_PATH: &str = "answers/submissions.log";
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SubmitOutcome {
+    Right,
+    Wrong,
+    TooHigh,
+    TooLow,
+}
+
+impl Display for SubmitOutcome {
+    fn fmt(&self, f: &mut Formatter) -> DisplayResult {
+        let string = match self {
+            Self::Right => "right",
+            Self::Wrong => "wrong",
+            Self::TooHigh => "too-high",
+            Self::TooLow => "too-low",
+        };
+        write!(f, "{}", string)
+    }
+}
+
+impl FromStr for SubmitOutcome {
+    type Err = AocError;
+
+    fn from_str(s: &str) -> AocResult<Self> {
+        match s {
+            "right" => Ok(Self::Right),
+            "wrong" => Ok(Self::Wrong),
+            "too-high" => Ok(Self::TooHigh),
+            "too-low" => Ok(Self::TooLow),
+            _ => Err(AocError::parse(format!(
+                "unknown submission outcome: {}",
+                s
+            ))),
+        }
+    }
+}
+
+pub enum SubmitResponse {
+    Recorded(SubmitOutcome),
+    // The server asked us to wait before submitting again.
+    Wait(String),
+    AlreadySolved,
+}
+
+struct Submission {
+    day: u8,
+    part: SolutionPart,
+    outcome: SubmitOutcome,
+    answer: String,
+}
+
+impl FromStr for Submission {
+    type Err = AocError;
+
+    fn from_str(s: &str) -> AocResult<Self> {
+        let mut fields = s.splitn(4, '\t');
+        let mut next = |name: &str| {
+            fields
+                .next()
+                .into_aoc_result_msg(&format!("missing {} in submission record", name))
+        };
+        Ok(Self {
+            day: next("day")?.parse().into_aoc_result()?,
+            part: next("part")?.parse()?,
+            outcome: next("outcome")?.parse()?,
+            answer: next("answer")?.to_owned(),
+        })
+    }
+}
+
+impl Display for Submission {
+    fn fmt(&self, f: &mut Formatter) -> DisplayResult {
+        write!(
+            f,
+            "{}\t{}\t{}\t{}",
+            self.day, self.part, self.outcome, self.answer
+        )
+    }
+}
+
+fn read_submissions() -> AocResult<Vec<Submission>> {
+    match fs::read_to_string(SUBMISSIONS_PATH) {
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(AocError::from_source(
+            format!("failed to read {}", SUBMISSIONS_PATH),
+            err,
+        )),
+        Ok(log) => log
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(Submission::from_str)
+            .collect(),
+    }
+}
+
+fn record_submission(submission: &Submission) -> AocResult<()> {
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(SUBMISSIONS_PATH)
+        .into_aoc_result_msg(&format!("failed to open {}", SUBMISSIONS_PATH))?;
+    writeln!(log, "{}", submission).into_aoc_result()
+}
+
+// Refuses answers that previous submissions have already ruled out.
+fn check_previous_submissions(day: u8, part: SolutionPart, answer: &AocSolution) -> AocResult<()> {
+    let answer_string = answer.to_string();
+    let answer_number = answer.as_integer();
+    for previous in read_submissions()?
+        .into_iter()
+        .filter(|s| s.day == day && s.part == part)
+    {
+        let previous_number = previous.answer.parse::<BigInt>().ok();
+        let ruled_out = match (previous.outcome, answer_number.as_ref(), previous_number) {
+            (SubmitOutcome::Right, _, _) => {
+                return Err(AocError::invalid_args(format!(
+                    "day {} part {} was already solved with {}",
+                    day, part, previous.answer
+                )))
+            }
+            (_, _, _) if previous.answer == answer_string => true,
+            (SubmitOutcome::TooHigh, Some(answer), Some(previous)) => answer >= &previous,
+            (SubmitOutcome::TooLow, Some(answer), Some(previous)) => answer <= &previous,
+            _ => false,
+        };
+        if ruled_out {
+            return Err(AocError::invalid_args(format!(
+                "{} is ruled out by a previous submission of {} ({})",
+                answer_string, previous.answer, previous.outcome
+            )));
+        }
+    }
+    Ok(())
+}
+
+// Extracts the human-readable message from the response page.
+fn response_message(html: &str) -> String {
+    let article = match (html.find("<article"), html.find("</article>")) {
+        (Some(start), Some(end)) if start < end => &html[start..end],
+        _ => html,
+    };
+    let mut message = String::new();
+    let mut in_tag = false;
+    for c in article.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => message.push(c),
+            _ => (),
+        }
+    }
+    message.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn parse_response(message: &str) -> AocResult<SubmitResponse> {
+    if message.contains("That's the right answer") {
+        Ok(SubmitResponse::Recorded(SubmitOutcome::Right))
+    } else if message.contains("your answer is too high") {
+        Ok(SubmitResponse::Recorded(SubmitOutcome::TooHigh))
+    } else if message.contains("your answer is too low") {
+        Ok(SubmitResponse::Recorded(SubmitOutcome::TooLow))
+    } else if message.contains("That's not the right answer") {
+        Ok(SubmitResponse::Recorded(SubmitOutcome::Wrong))
+    } else if message.contains("You gave an answer too recently") {
+        Ok(SubmitResponse::Wait(message.to_owned()))
+    } else if message.contains("You don't seem to be solving the right level") {
+        Ok(SubmitResponse::AlreadySolved)
+    } else {
+        Err(AocError::parse(format!(
+            "unrecognized response: {}",
+            message
+        )))
+    }
+}
+
+pub fn submit_answer(
+    day: u8,
+    part: SolutionPart,
+    answer: &AocSolution,
+) -> AocResult<SubmitResponse> {
+    check_previous_submissions(day, part, answer)?;
+    let session = fetch::session_token()?;
+    let level = match part {
+        SolutionPart::A => "1",
+        SolutionPart::B => "2",
+        SolutionPart::Both => {
+            return Err(AocError::invalid_args(
+                "both parts cannot be submitted at once",
+            ))
+        }
+    };
+    let html = fetch::request(
+        &format!("day/{}/answer", day),
+        &session,
+        &[("level", level), ("answer", &answer.to_string())],
+    )?;
+    let response = parse_response(&response_message(&html))?;
+    if let SubmitResponse::Recorded(outcome) = response {
+        record_submission(&Submission {
+            day,
+            part,
+            outcome,
+            answer: answer.to_string(),
+        })?;
+    }
+    Ok(response)
+}
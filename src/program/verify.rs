@@ -0,0 +1,293 @@
+use crate::{
+    common::{AocError, AocResult, IntoAocResult, SolutionPart},
+    days::{run_all_solvers, run_examples, run_input_sets, SolveRecord},
+    program::{config, CacheMode},
+};
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+// Expected answers for each day and part, read from a small subset of TOML:
+//
+//     [16]
+//     A = 1737
+//     B = "2216"
+//
+// Answers for an input set are in tables named after it, like `[friend1.16]`.
+#[derive(Clone, Default)]
+pub struct ExpectedAnswers {
+    answers: HashMap<(Option<String>, u8, SolutionPart), String>,
+}
+
+impl ExpectedAnswers {
+    pub fn load(path: &str) -> AocResult<Self> {
+        fs::read_to_string(path)
+            .into_aoc_result_msg(&format!("failed to read {}", path))?
+            .parse()
+    }
+
+    // Like `load`, but a missing file has no answers.
+    pub fn load_or_default(path: &str) -> AocResult<Self> {
+        match Path::new(path).exists() {
+            true => Self::load(path),
+            false => Ok(Self::default()),
+        }
+    }
+
+    // Loads the given manifest or the default one, along with any answers in the
+    // config file. Answers in the config file replace those in the default
+    // manifest, but not those in a manifest passed on the command line.
+    pub fn load_with_config(path: Option<&str>) -> AocResult<Self> {
+        match path {
+            Some(path) => Ok(config().answers.clone().merge(Self::load(path)?)),
+            None => {
+                Ok(Self::load_or_default(&config().answers_file)?.merge(config().answers.clone()))
+            }
+        }
+    }
+
+    pub fn get(&self, day: u8, part: SolutionPart) -> Option<&str> {
+        self.get_in(None, day, part)
+    }
+
+    // The expected answer for an input set, or for the day's own input if there
+    // is no set.
+    pub fn get_in(&self, input_set: Option<&str>, day: u8, part: SolutionPart) -> Option<&str> {
+        self.answers
+            .get(&(input_set.map(str::to_owned), day, part))
+            .map(|s| s.as_str())
+    }
+
+    // Reads a table header, which is a day number, optionally after the name of
+    // an input set and a dot.
+    pub(super) fn parse_header(header: &str) -> AocResult<(Option<String>, u8)> {
+        let header = header.trim();
+        let (input_set, day) = match header.rsplit_once('.') {
+            Some((input_set, day)) => (Some(input_set.trim().trim_matches('"')), day),
+            None => (None, header),
+        };
+        if input_set.is_some_and(str::is_empty) {
+            return Err(AocError::parse("input set name cannot be empty"));
+        }
+        let day = day
+            .trim()
+            .trim_matches('"')
+            .parse::<u8>()
+            .into_aoc_result_msg("table header must be a day number")?;
+        Ok((input_set.map(str::to_owned), day))
+    }
+
+    // Adds an answer from a `key = value` line, where the key is the part.
+    pub(super) fn insert(
+        &mut self,
+        input_set: Option<&str>,
+        day: u8,
+        key: &str,
+        value: &str,
+    ) -> AocResult<()> {
+        let part = match SolutionPart::from_str(key.trim().trim_matches('"')) {
+            Ok(SolutionPart::Both) => Err(AocError::parse("expected part A or B")),
+            part => part,
+        }?;
+        let value = Self::parse_value(value.trim())?;
+        match self
+            .answers
+            .insert((input_set.map(str::to_owned), day, part), value)
+        {
+            None => Ok(()),
+            Some(_) => Err(AocError::parse(match input_set {
+                None => format!("duplicate answer for day {} part {}", day, part),
+                Some(input_set) => format!(
+                    "duplicate answer for day {} part {} of {}",
+                    day, part, input_set
+                ),
+            })),
+        }
+    }
+
+    // Combines two sets of answers, preferring the other answers where both have
+    // one for the same day and part.
+    pub fn merge(mut self, other: Self) -> Self {
+        self.answers.extend(other.answers);
+        self
+    }
+
+    fn parse_string(value: &str) -> AocResult<String> {
+        let mut result = String::new();
+        let mut chars = value.chars();
+        loop {
+            match chars.next() {
+                None => return Err(AocError::parse("unterminated string")),
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some('"') => result.push('"'),
+                    Some('\\') => result.push('\\'),
+                    Some(c) => return Err(AocError::parse(format!("invalid escape: \\{}", c))),
+                    None => return Err(AocError::parse("unterminated string")),
+                },
+                Some(c) => result.push(c),
+            }
+        }
+        match chars.as_str().trim() {
+            "" => Ok(result),
+            rest if rest.starts_with('#') => Ok(result),
+            rest => Err(AocError::parse(format!("unexpected characters: {}", rest))),
+        }
+    }
+
+    pub(super) fn parse_value(value: &str) -> AocResult<String> {
+        match value.strip_prefix('"') {
+            Some(value) => Self::parse_string(value),
+            None => {
+                let value = value.split('#').next().unwrap_or_default().trim();
+                value
+                    .parse::<i64>()
+                    .into_aoc_result_msg("expected a string or integer")
+                    .map(|n| n.to_string())
+            }
+        }
+    }
+}
+
+impl FromStr for ExpectedAnswers {
+    type Err = AocError;
+
+    fn from_str(s: &str) -> AocResult<Self> {
+        let mut answers = Self::default();
+        let mut table = None;
+        for (i, line) in s.lines().enumerate() {
+            let line_error =
+                |err: AocError| AocError::parse(format!("line {}: {}", i + 1, err.message()));
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header
+                    .split_once(']')
+                    .into_aoc_result_msg("unterminated table header")
+                    .map_err(line_error)?
+                    .0;
+                table = Some(Self::parse_header(header).map_err(line_error)?);
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .into_aoc_result_msg("expected key = value")
+                .map_err(line_error)?;
+            let (input_set, day) = table
+                .as_ref()
+                .into_aoc_result_msg("answer outside of a day table")
+                .map_err(line_error)?;
+            answers
+                .insert(input_set.as_deref(), *day, key, value)
+                .map_err(line_error)?;
+        }
+        Ok(answers)
+    }
+}
+
+#[derive(Default)]
+pub struct VerifySummary {
+    pub passed: usize,
+    pub failed: usize,
+    pub unchecked: usize,
+}
+
+impl VerifySummary {
+    // Counts solvers that failed along with wrong answers.
+    pub fn from_records(records: &[SolveRecord]) -> Self {
+        let mut summary = Self::default();
+        for record in records {
+            match (&record.answer, record.verified) {
+                (Err(_), _) | (_, Some(false)) => summary.failed += 1,
+                (_, Some(true)) => summary.passed += 1,
+                (_, None) => summary.unchecked += 1,
+            }
+        }
+        summary
+    }
+}
+
+// Lays out checked records as a table for the terminal, with a row for each.
+pub fn verify_table(records: &[SolveRecord]) -> String {
+    let mut table = format!(
+        "{:>3} {:<4} {:<6} {:>10}  Answer\n",
+        "Day", "Part", "Status", "Time"
+    );
+    for record in records {
+        let (status, time, mut details) = match &record.answer {
+            Err(err) => ("ERROR", String::new(), err.to_string()),
+            Ok(answer) => {
+                let time = format!("{} us", record.timings.solver_time().as_micros());
+                match (record.verified, &record.expected) {
+                    (Some(true), _) => ("PASS", time, answer.to_string()),
+                    (Some(false), Some(expected)) => {
+                        ("FAIL", time, format!("{} (expected {})", answer, expected))
+                    }
+                    _ => ("-", time, format!("{} (no expected answer)", answer)),
+                }
+            }
+        };
+        match (&record.input_set, &record.filename) {
+            (Some(input_set), _) => details += &format!(" [{}]", input_set),
+            (None, Some(filename)) => details += &format!(" [{}]", filename),
+            (None, None) => (),
+        }
+        table += &format!(
+            "{:>3} {:<4} {:<6} {:>10}  {}\n",
+            record.day, record.part, status, time, details
+        );
+    }
+    table
+}
+
+// Solves every day and part, checking each answer against the expected one.
+// Every input set is solved after the days' own inputs, unless one is named, in
+// which case only it is.
+pub fn verify_all(
+    answers: &ExpectedAnswers,
+    cache: CacheMode,
+    input_set: Option<&str>,
+) -> AocResult<Vec<SolveRecord>> {
+    let mut records = match input_set {
+        None => run_all_solvers(cache)?,
+        Some(_) => Vec::new(),
+    };
+    records.extend(run_input_sets(cache, input_set)?);
+    for record in &mut records {
+        record.verify(answers.get_in(record.input_set.as_deref(), record.day, record.part));
+    }
+    Ok(records)
+}
+
+// Checks every solver against the puzzle examples in the `examples`
+// directory.
+pub fn verify_examples() -> Vec<SolveRecord> {
+    run_examples()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_answers_by_input_set() {
+        let answers = "[16]\nA = 1737\n\n[friend1.16]\nA = 1651\nB = \"1707\"\n"
+            .parse::<ExpectedAnswers>()
+            .unwrap();
+        assert_eq!(answers.get(16, SolutionPart::A), Some("1737"));
+        assert_eq!(answers.get(16, SolutionPart::B), None);
+        assert_eq!(
+            answers.get_in(Some("friend1"), 16, SolutionPart::A),
+            Some("1651")
+        );
+        assert_eq!(
+            answers.get_in(Some("friend1"), 16, SolutionPart::B),
+            Some("1707")
+        );
+        assert_eq!(answers.get_in(Some("friend2"), 16, SolutionPart::A), None);
+        assert!("[.16]\nA = 1\n".parse::<ExpectedAnswers>().is_err());
+        assert!("[friend1.x]\nA = 1\n".parse::<ExpectedAnswers>().is_err());
+    }
+}
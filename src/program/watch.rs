@@ -0,0 +1,247 @@
+use crate::{
+    common::{AocError, AocResult, IntoAocResult},
+    days::SolveRecord,
+    program::ExitStatus,
+};
+use std::{
+    fs,
+    path::PathBuf,
+    process::Command,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+// Notices when files are written by polling their modification times and
+// sizes, which is all it takes to catch an input being saved.
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    seen: Vec<Option<(SystemTime, u64)>>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        let paths = paths.into_iter().map(Into::into).collect::<Vec<_>>();
+        let seen = paths.iter().map(Self::stamp).collect();
+        Self { paths, seen }
+    }
+
+    // A missing file has no stamp, so creating or removing one is a change too.
+    fn stamp(path: &PathBuf) -> Option<(SystemTime, u64)> {
+        let metadata = fs::metadata(path).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    }
+
+    // Whether any file has changed since the last check.
+    pub fn changed(&mut self) -> bool {
+        let seen = self.paths.iter().map(Self::stamp).collect::<Vec<_>>();
+        let changed = seen != self.seen;
+        self.seen = seen;
+        changed
+    }
+
+    // Blocks until a file changes.
+    pub fn wait(&mut self, interval: Duration) {
+        while !self.changed() {
+            thread::sleep(interval);
+        }
+    }
+}
+
+// What a run found, kept to compare the next run against.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunSummary {
+    answer: Result<String, String>,
+    time: Duration,
+}
+
+impl RunSummary {
+    pub fn new(answer: Result<String, String>, time: Duration) -> Self {
+        Self { answer, time }
+    }
+}
+
+impl From<&SolveRecord> for RunSummary {
+    fn from(record: &SolveRecord) -> Self {
+        Self::new(
+            record
+                .answer
+                .as_ref()
+                .map(|answer| answer.to_string())
+                .map_err(|err| err.to_string()),
+            record.timings.solver_time(),
+        )
+    }
+}
+
+impl RunSummary {
+    // Summarizes a run that may have failed before solving anything, which
+    // took no time worth comparing.
+    pub fn from_run(run: &AocResult<SolveRecord>) -> Self {
+        match run {
+            Ok(record) => Self::from(record),
+            Err(err) => Self::new(Err(err.message()), Duration::ZERO),
+        }
+    }
+}
+
+// Every run is compared with the one right before it, whether either failed
+// or not.
+#[derive(Debug, Default)]
+pub struct RunHistory {
+    previous: Option<RunSummary>,
+}
+
+impl RunHistory {
+    // Remembers a run, returning how it differs from the last one, if any.
+    pub fn record(&mut self, run: &AocResult<SolveRecord>) -> Option<String> {
+        let current = RunSummary::from_run(run);
+        let change = self
+            .previous
+            .as_ref()
+            .map(|previous| describe_change(previous, &current));
+        self.previous = Some(current);
+        change
+    }
+}
+
+// A line on how a run differs from the one before it.
+pub fn describe_change(previous: &RunSummary, current: &RunSummary) -> String {
+    let answer = match (&previous.answer, &current.answer) {
+        (Ok(before), Ok(after)) if before == after => "same answer".to_owned(),
+        (Ok(before), Ok(after)) => format!("answer changed from {} to {}", before, after),
+        (Err(_), Ok(_)) => "no longer fails".to_owned(),
+        (Ok(_), Err(_)) => "now fails".to_owned(),
+        (Err(_), Err(_)) => "still fails".to_owned(),
+    };
+    let before = previous.time.as_micros();
+    let after = current.time.as_micros();
+    let time = match before {
+        0 => format!("{} us -> {} us", before, after),
+        _ => format!(
+            "{} us -> {} us ({:+.1}%)",
+            before,
+            after,
+            (after as f64 - before as f64) / before as f64 * 100.0
+        ),
+    };
+    format!("Since the last run: {}, {}", answer, time)
+}
+
+// Quotes an argument for a POSIX shell, closing the quotes around any quote
+// inside it.
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+// Hands off to cargo-watch, which rebuilds the program and solves again whenever
+// the source or the input changes.
+pub fn cargo_watch(input: &str, solve_args: &[String]) -> AocResult<ExitStatus> {
+    let installed = Command::new("cargo")
+        .args(["watch", "--version"])
+        .output()
+        .is_ok_and(|output| output.status.success());
+    if !installed {
+        return Err(AocError::io(
+            "cargo-watch is not installed, install it with `cargo install cargo-watch`",
+        ));
+    }
+    // cargo-watch runs the command through a shell, so every argument is
+    // quoted to reach the program as it was given.
+    let run = format!(
+        "run --release -- {}",
+        solve_args
+            .iter()
+            .map(|arg| shell_quote(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let status = Command::new("cargo")
+        .args(["watch", "--watch", "src", "--watch", input, "--exec", &run])
+        .status()
+        .into_aoc_result_msg("failed to run cargo-watch")?;
+    Ok(match status.success() {
+        true => ExitStatus::Success,
+        false => ExitStatus::Failure,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::{AocSolution, SolutionPart},
+        days::TimingBreakdown,
+    };
+
+    #[test]
+    fn notices_written_files() {
+        let path = std::env::temp_dir().join(format!("aoc-watch-test-{}.txt", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let mut watcher = FileWatcher::new([&path]);
+        assert!(!watcher.changed());
+        fs::write(&path, "1").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+        fs::write(&path, "12").unwrap();
+        assert!(watcher.changed());
+        fs::remove_file(&path).unwrap();
+        assert!(watcher.changed());
+    }
+
+    #[test]
+    fn quotes_arguments_for_the_shell() {
+        assert_eq!(shell_quote("15"), "'15'");
+        assert_eq!(shell_quote("my input.txt"), "'my input.txt'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+
+    #[test]
+    fn compares_with_failed_runs() {
+        let solved = |answer| {
+            Ok(SolveRecord::new(
+                1,
+                SolutionPart::A,
+                AocSolution::Int(answer),
+                TimingBreakdown::default(),
+                None,
+            ))
+        };
+        let mut history = RunHistory::default();
+        assert_eq!(history.record(&solved(13)), None);
+        assert_eq!(
+            history.record(&Err(AocError::parse("bad input"))),
+            Some("Since the last run: now fails, 0 us -> 0 us".to_owned())
+        );
+        assert_eq!(
+            history.record(&Err(AocError::parse("still bad"))),
+            Some("Since the last run: still fails, 0 us -> 0 us".to_owned())
+        );
+        assert_eq!(
+            history.record(&solved(140)),
+            Some("Since the last run: no longer fails, 0 us -> 0 us".to_owned())
+        );
+    }
+
+    #[test]
+    fn describes_changes_between_runs() {
+        let run = |answer: Result<&str, &str>, micros| {
+            RunSummary::new(
+                answer.map(str::to_owned).map_err(str::to_owned),
+                Duration::from_micros(micros),
+            )
+        };
+        assert_eq!(
+            describe_change(&run(Ok("13"), 200), &run(Ok("13"), 150)),
+            "Since the last run: same answer, 200 us -> 150 us (-25.0%)"
+        );
+        assert_eq!(
+            describe_change(&run(Ok("13"), 100), &run(Ok("140"), 110)),
+            "Since the last run: answer changed from 13 to 140, 100 us -> 110 us (+10.0%)"
+        );
+        assert_eq!(
+            describe_change(&run(Err("no input"), 0), &run(Ok("140"), 110)),
+            "Since the last run: no longer fails, 0 us -> 110 us"
+        );
+    }
+}
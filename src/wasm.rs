@@ -0,0 +1,58 @@
+use crate::{common::SolutionPart, days::solve_puzzle};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    // Milliseconds from the browser's high-resolution clock, since `Instant` is
+    // not available in WebAssembly.
+    #[wasm_bindgen(js_namespace = performance)]
+    fn now() -> f64;
+}
+
+fn parse_part(part: char) -> Result<SolutionPart, JsError> {
+    match part {
+        'A' | 'a' => Ok(SolutionPart::A),
+        'B' | 'b' => Ok(SolutionPart::B),
+        _ => Err(JsError::new("part must be A or B")),
+    }
+}
+
+// Solves one part of a day's puzzle, throwing the error message if the input
+// cannot be solved.
+#[wasm_bindgen]
+pub fn solve(day: u8, part: char, input: &str) -> Result<String, JsError> {
+    solve_puzzle(day, parse_part(part)?, input)
+        .map(|solution| solution.to_string())
+        .map_err(|err| JsError::new(&err.to_string()))
+}
+
+// An answer along with how long the solver took to find it.
+#[wasm_bindgen]
+pub struct TimedSolution {
+    answer: String,
+    millis: f64,
+}
+
+#[wasm_bindgen]
+impl TimedSolution {
+    #[wasm_bindgen(getter)]
+    pub fn answer(&self) -> String {
+        self.answer.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn millis(&self) -> f64 {
+        self.millis
+    }
+}
+
+// Like `solve`, but also measures the time taken by the solver.
+#[wasm_bindgen]
+pub fn solve_timed(day: u8, part: char, input: &str) -> Result<TimedSolution, JsError> {
+    let start = now();
+    let answer = solve(day, part, input)?;
+    Ok(TimedSolution {
+        answer,
+        millis: now() - start,
+    })
+}
@@ -0,0 +1,60 @@
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_advent-of-code-2022"))
+        .args(args)
+        .output()
+        .expect("failed to run the program")
+}
+
+#[test]
+fn help_lists_every_command() {
+    for flag in ["help", "--help", "-h"] {
+        let output = run(&[flag]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert_eq!(output.status.code(), Some(0));
+        for command in [
+            "all", "bench", "verify", "viz", "watch", "input", "fetch", "submit", "list",
+        ] {
+            assert!(
+                stdout.contains(command),
+                "{} is missing from\n{}",
+                command,
+                stdout
+            );
+        }
+    }
+    let output = run(&["bench", "--help"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("--iterations"));
+}
+
+#[test]
+fn exits_with_status_for_bad_args() {
+    assert_eq!(run(&["99", "A"]).status.code(), Some(2));
+    assert_eq!(run(&["bench", "1", "A", "--bogus"]).status.code(), Some(2));
+    assert_eq!(
+        run(&["15", "A", "--example", "--", "--row", "x"])
+            .status
+            .code(),
+        Some(2)
+    );
+    assert_eq!(run(&["15", "A", "--example"]).status.code(), Some(0));
+    assert_eq!(
+        run(&["15", "A", "--example", "--input-set", "friend1"])
+            .status
+            .code(),
+        Some(2)
+    );
+    assert_eq!(
+        run(&["verify", "--input-set", "../friend1"]).status.code(),
+        Some(2)
+    );
+    assert_eq!(run(&["watch", "1", "both"]).status.code(), Some(2));
+    assert_eq!(
+        run(&["watch", "1", "A", "--interval-ms", "0"])
+            .status
+            .code(),
+        Some(2)
+    );
+}
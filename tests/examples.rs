@@ -0,0 +1,15 @@
+use std::process::Command;
+
+// Runs every solver against the puzzle examples through the `examples`
+// subcommand, which fails if any answer is wrong.
+#[test]
+fn all_examples_pass() {
+    let output = Command::new(env!("CARGO_BIN_EXE_advent-of-code-2022"))
+        .arg("examples")
+        .output()
+        .expect("failed to run examples");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{}\n{}", stdout, stderr);
+    assert!(stdout.contains(" 0 failed"), "{}", stdout);
+}
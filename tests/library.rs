@@ -0,0 +1,27 @@
+use aoc2022::{
+    days::{day01, day19},
+    AocSolution, SolutionPart,
+};
+
+// Solvers can be called through the library without any files or arguments.
+#[test]
+fn solves_through_library() {
+    let input = include_str!("../examples/1.txt");
+    assert_eq!(
+        aoc2022::solve(1, SolutionPart::A, input).unwrap(),
+        AocSolution::Int(24000)
+    );
+    assert_eq!(day01::solve_b(input.into()).unwrap(), 45000);
+    assert!(aoc2022::solve(1, SolutionPart::Both, input).is_err());
+}
+
+// Day 19's search reports how the greedy rollout compared with the optimum.
+#[test]
+fn reports_search_stats_through_library() {
+    let input = include_str!("../examples/19.txt");
+    let materials = day19::Materials::from_input(input).unwrap();
+    let blueprints = day19::parse_blueprints::<4>(input, &materials).unwrap();
+    let stats = blueprints[0].search(materials.get("geode").unwrap(), 24);
+    assert_eq!(stats.optimal, 9);
+    assert!(stats.greedy <= stats.optimal);
+}